@@ -0,0 +1,118 @@
+use crate::runner::format_utc;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Creates the file receiving the output of a run in the directory, named after the tick and time,
+// and returns its path.
+// If `keep_last` is set, the oldest run files beyond this number are removed.
+pub fn create_run_file(
+    dir: &Path,
+    tick_number: u64,
+    time: SystemTime,
+    keep_last: Option<usize>,
+) -> io::Result<(PathBuf, File)> {
+    let path = dir.join(file_name(tick_number, time));
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+    if let Some(keep_last) = keep_last {
+        remove_old_files(dir, keep_last)?;
+    }
+
+    Ok((path, file))
+}
+
+// The file name of a run, such as run-3-20241231T235959Z.log.
+fn file_name(tick_number: u64, time: SystemTime) -> String {
+    let timestamp = format_utc(time)
+        .replace(['-', ':'], "")
+        .replace(" UTC", "Z")
+        .replace(' ', "T");
+
+    format!("run-{tick_number}-{timestamp}.log")
+}
+
+// Returns the timestamp and tick number of a run file name, that sort in chronological order.
+fn parse_file_name(name: &str) -> Option<(&str, u64)> {
+    let (tick_number, timestamp) = name
+        .strip_prefix("run-")?
+        .strip_suffix(".log")?
+        .split_once('-')?;
+
+    Some((timestamp, tick_number.parse().ok()?))
+}
+
+fn remove_old_files(dir: &Path, keep_last: usize) -> io::Result<()> {
+    let mut names = vec![];
+
+    for entry in fs::read_dir(dir)? {
+        if let Ok(name) = entry?.file_name().into_string() {
+            if parse_file_name(&name).is_some() {
+                names.push(name);
+            }
+        }
+    }
+
+    names.sort_by_cached_key(|name| parse_file_name(name).map(|(t, n)| (t.to_string(), n)));
+
+    for name in &names[..names.len().saturating_sub(keep_last)] {
+        fs::remove_file(dir.join(name))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_file_name() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_735_689_599);
+
+        assert_eq!(file_name(3, time), "run-3-20241231T235959Z.log");
+        assert_eq!(
+            parse_file_name("run-3-20241231T235959Z.log"),
+            Some(("20241231T235959Z", 3))
+        );
+        assert_eq!(parse_file_name("run-x-20241231T235959Z.log"), None);
+        assert_eq!(parse_file_name("notes.txt"), None);
+    }
+
+    #[test]
+    fn test_create_run_file() {
+        let dir = env::temp_dir().join(format!("every-test-{}-archive", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let start = UNIX_EPOCH + Duration::from_secs(1_735_689_599);
+
+        // tick 10 sorts after tick 9, and the next day after both
+        for (tick_number, seconds) in [(9, 0), (10, 0), (1, 86_400)] {
+            let time = start + Duration::from_secs(seconds);
+            create_run_file(&dir, tick_number, time, Some(2)).unwrap();
+        }
+
+        let mut names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+
+        assert_eq!(
+            names,
+            [
+                "notes.txt",
+                "run-1-20250101T235959Z.log",
+                "run-10-20241231T235959Z.log"
+            ]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
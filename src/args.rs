@@ -1,30 +1,256 @@
-use regex::{Match, Regex};
+use crate::calendar::parse_dates;
+use crate::clock::{validate_time_zone, Date, LocalTime, TimeWindow, Weekdays};
+use crate::instances::validate_name;
+use crate::interval::Interval;
+use crate::jobfile::{parse_concurrency, read_jobs_file, MAX_CONCURRENCY};
+use crate::logger::LogFormat;
+use crate::rate::RateLimit;
+use crate::runner::{
+    ArgsExhausted, Config, Cooldown, ErrorOutput, FreeSpace, Input, IntervalWindow, Job,
+    NetworkCheck, OnBusy, OnSpawnError, OutputMode, Report, ReportFormat, UntilMatch,
+};
+use crate::scheduler::{Clock, OnResume};
+use crate::style::ColorChoice;
+use crate::suggest::suggest;
+use nix::sys::signal::Signal;
+use regex::Regex;
+use std::cmp::Ordering;
 use std::env::Args;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::iter;
 use std::num::IntErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-const MAX_CONCURRENCY: u16 = 1000;
+const MAX_QUIET: u8 = 2;
+const MAX_VERBOSE: u8 = 2;
 
+// The options following the first argument, for the suggestions.
+const OPTIONS: [&str; 98] = [
+    "--preview",
+    "--supervise",
+    "-c",
+    "--concurrency",
+    "--detach",
+    "--batch",
+    "--on-busy",
+    "--max-per",
+    "--kill-signal",
+    "--kill-after",
+    "--stall-timeout",
+    "--max-output",
+    "--max-output-kill",
+    "--catch-up",
+    "--clock",
+    "--on-resume",
+    "--at",
+    "-n",
+    "--on-failure",
+    "--on-success",
+    "--on-skip",
+    "--notify-url",
+    "--notify-recovery",
+    "--notify-desktop",
+    "--bell",
+    "--flash",
+    "--otel-endpoint",
+    "--ok-codes",
+    "--max-failures",
+    "--cooldown",
+    "--cooldown-after",
+    "--on-spawn-error",
+    "--no-keys",
+    "--countdown",
+    "--pty",
+    "--set-title",
+    "--pause-file",
+    "--max-load",
+    "--only-on-ac",
+    "--min-battery",
+    "--min-free-space",
+    "--require-network",
+    "--if-healthy",
+    "--if",
+    "--until-success",
+    "--until-match",
+    "--until-no-match",
+    "--until",
+    "--between",
+    "--interval-between",
+    "--interval-default",
+    "--days",
+    "--skip-dates",
+    "--tz",
+    "-q",
+    "--quiet",
+    "-qq",
+    "--verbose",
+    "-vv",
+    "--log-format",
+    "--color",
+    "--summary",
+    "--stats-every",
+    "--on-failure-output",
+    "--changes",
+    "--watch",
+    "--diff",
+    "--output-dir",
+    "--keep-last",
+    "--heartbeat-file",
+    "--lock-file",
+    "--history",
+    "--report",
+    "--name",
+    "--single-instance",
+    "--control-socket",
+    "--log",
+    "--daemon",
+    "--systemd",
+    "--log-syslog",
+    "--pid-file",
+    "--tui",
+    "-e",
+    "--env-file",
+    "--nice",
+    "--limit-mem",
+    "--limit-cpu",
+    "--clean-env",
+    "--cwd",
+    "--jobs-file",
+    "--from-stdin",
+    "--args-from",
+    "--args-exhausted",
+    "--cmd",
+    "--stderr",
+    "--stdin",
+    "--stdin-data",
+    "--stdin-file",
+];
+
+// The short options taking a value, that may be attached to them, as in -c5 or -c=5.
+const SHORT_OPTIONS_WITH_VALUE: [&str; 3] = ["-c", "-n", "-e"];
+
+// only built once, so the size of the Run variant does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, PartialEq)]
 pub enum Action {
     Run(Config),
+    /// Print the times of this number of next runs.
+    Preview(Config, usize),
+    Status(Option<String>),
+    Stop(String),
+    History(PathBuf),
     Help,
+    /// Print the help as a man page.
+    Man,
     Version,
 }
 
+/// The reason why the command line cannot be parsed.
 #[derive(Debug, PartialEq)]
-pub struct Config {
-    pub interval_ms: u64,
-    pub concurrency: u16,
-    pub command: String,
-    pub args: Vec<String>,
+pub enum ParseError {
+    /// An option that does not exist, with the closest existing one.
+    InvalidOption {
+        option: String,
+        after_interval: bool,
+        suggestion: Option<&'static str>,
+    },
+    /// An argument after the ones of status, stop or history.
+    UnexpectedArgument(String),
+    /// The value of an option is missing, described as in "Missing <what>!".
+    MissingValue(&'static str),
+    /// The interval is missing, or the restart delay with --supervise.
+    MissingInterval {
+        supervise: bool,
+    },
+    MissingCommand,
+    /// An option expecting a number got another argument, usually the command.
+    ExpectedNumber {
+        option: String,
+        value: String,
+    },
+    /// A value is attached to an option that does not take any.
+    UnexpectedValue {
+        option: String,
+        value: String,
+    },
+    /// The interval, or the interval range, cannot be parsed; the reason is the whole message.
+    InvalidInterval {
+        input: String,
+        reason: String,
+    },
+    /// The concurrency level cannot be parsed, or is out of range.
+    InvalidConcurrency {
+        input: String,
+        reason: String,
+    },
+    /// The value of another option cannot be parsed, or its file cannot be read.
+    InvalidValue {
+        option: String,
+        value: String,
+        reason: String,
+    },
+    /// Options that cannot be used together, or one used without another that it requires.
+    InvalidUsage(&'static str),
+    /// An option of a feature that is not included in this build.
+    #[cfg_attr(
+        all(feature = "otel", feature = "tui", feature = "webhook"),
+        allow(dead_code)
+    )]
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::InvalidOption {
+                option,
+                after_interval,
+                suggestion,
+            } => {
+                match after_interval {
+                    true => write!(f, "Invalid option after interval: {option}")?,
+                    false => write!(f, "Invalid option: {option}")?,
+                }
+
+                match suggestion {
+                    Some(suggestion) => write!(f, ", did you mean '{suggestion}'?"),
+                    None => Ok(()),
+                }
+            }
+            ParseError::UnexpectedArgument(arg) => write!(f, "Unexpected argument: {arg}"),
+            ParseError::MissingValue(what) => write!(f, "Missing {what}!"),
+            ParseError::MissingInterval { supervise: true } => write!(f, "Missing restart delay!"),
+            ParseError::MissingInterval { supervise: false } => write!(f, "Missing interval!"),
+            ParseError::MissingCommand => write!(f, "Missing command name!"),
+            ParseError::ExpectedNumber { option, value } => {
+                write!(f, "Option {option} expects a number, got '{value}'")
+            }
+            ParseError::UnexpectedValue { option, value } => {
+                write!(f, "Option {option} does not take a value, got '{value}'")
+            }
+            ParseError::InvalidInterval { reason, .. }
+            | ParseError::InvalidConcurrency { reason, .. }
+            | ParseError::InvalidValue { reason, .. } => write!(f, "{reason}"),
+            ParseError::InvalidUsage(message) => write!(f, "{message}"),
+            ParseError::Unsupported(option) => {
+                write!(f, "This build of every does not support {option}!")
+            }
+        }
+    }
 }
 
+impl Error for ParseError {}
+
 impl Action {
-    pub fn parse(args: Args) -> Result<Action, String> {
+    pub fn parse(args: Args) -> Result<Action, ParseError> {
         Self::parse_iter(args.skip(1))
     }
 
-    fn parse_iter<T>(mut args: T) -> Result<Action, String>
+    fn parse_iter<T>(mut args: T) -> Result<Action, ParseError>
     where
         T: Iterator<Item = String>,
     {
@@ -33,161 +259,1141 @@ impl Action {
             None => return Ok(Action::Help),
         };
 
-        if arg.starts_with("-") {
-            if arg == "-h" {
-                return Ok(Action::Help);
+        match arg.as_str() {
+            "-h" => return Ok(Action::Help),
+            "-v" => return Ok(Action::Version),
+            "--man" => return Ok(Action::Man),
+            "status" => {
+                let instance = args.next();
+
+                if let Some(arg) = args.next() {
+                    return Err(ParseError::UnexpectedArgument(arg));
+                }
+
+                return Ok(Action::Status(instance));
             }
+            "stop" => {
+                let instance = next_arg(&mut args, "instance name or PID")?;
+
+                if let Some(arg) = args.next() {
+                    return Err(ParseError::UnexpectedArgument(arg));
+                }
 
-            if arg == "-v" {
-                return Ok(Action::Version);
+                return Ok(Action::Stop(instance));
             }
+            "history" => {
+                let path = next_arg(&mut args, "history file path")?;
 
-            return Err(format!("Invalid option: {arg}"));
-        }
+                if let Some(arg) = args.next() {
+                    return Err(ParseError::UnexpectedArgument(arg));
+                }
 
-        let interval_ms = parse_interval_as_ms(&arg)?;
+                return Ok(Action::History(PathBuf::from(path)));
+            }
+            _ => {}
+        }
 
-        let arg = match args.next() {
-            Some(arg) => arg,
-            None => return Err(String::from("Missing command name!")),
+        let mut args = Arguments {
+            args: iter::once(arg).chain(args),
+            attached: None,
         };
 
-        let mut concurrency = 1;
-        let command;
+        let mut config = Config::default();
 
-        if arg.starts_with("-") {
-            if arg == "-c" {
-                concurrency = match args.next() {
-                    Some(arg) => parse_concurrency(&arg)?,
-                    None => return Err(String::from("Missing concurrency value!")),
+        // the options may precede the interval, and the first argument after it is the command
+        let mut has_interval = false;
+        let mut preview = None;
+        let mut cooldown = None;
+        let mut args_exhausted = None;
+        let mut interval_default = None;
+        let mut cooldown_after = None;
+
+        let command = loop {
+            let Some(arg) = args.next() else {
+                break None;
+            };
+
+            if !arg.starts_with("-") {
+                if has_interval {
+                    break Some(arg);
+                }
+
+                // every --supervise <min-delay> <command>
+                let interval = match config.supervise {
+                    true => parse_duration(&arg).map(|interval| (interval, None)),
+                    false => parse_interval_range(&arg),
                 };
-            } else {
-                return Err(format!("Invalid option after interval: {arg}"));
+
+                (config.interval, config.max_interval) = interval
+                    .map_err(|reason| ParseError::InvalidInterval { input: arg, reason })?;
+
+                has_interval = true;
+                continue;
             }
 
-            command = match args.next() {
-                Some(arg) => arg,
-                None => return Err(String::from("Missing command name!")),
+            // -c5, -c=5 or --concurrency=5: the value is returned first by next_arg()
+            let arg = match split_attached_value(&arg) {
+                Some((option, value)) => {
+                    args.attached = Some(value);
+                    option
+                }
+                None => arg,
             };
-        } else {
-            command = arg;
+
+            match arg.as_str() {
+                // the end of the options, for a command that starts with a dash
+                "--" if has_interval => break args.next(),
+                // every --preview <count> <interval> [options] [<command>]
+                "--preview" => {
+                    preview = Some(args.number(&arg, "preview count", parse_preview_count)?)
+                }
+                "--supervise" if has_interval => {
+                    return Err(ParseError::InvalidUsage(
+                        "--supervise must precede the restart delay!",
+                    ))
+                }
+                "--supervise" => config.supervise = true,
+                "-c" | "--concurrency" => {
+                    let concurrency = next_number(&mut args, &arg, "concurrency value")?;
+
+                    config.concurrency = parse_concurrency(&concurrency).map_err(|reason| {
+                        ParseError::InvalidConcurrency {
+                            input: concurrency,
+                            reason,
+                        }
+                    })?;
+                }
+                "--detach" => config.detach = true,
+                "--batch" => config.batch = Some(args.number(&arg, "batch size", parse_batch)?),
+                "--on-busy" => {
+                    config.on_busy = args.value(&arg, "on-busy policy", parse_on_busy)?
+                }
+                "--max-per" => {
+                    config.max_per = Some(args.value(&arg, "rate limit", parse_rate_limit)?)
+                }
+                "--kill-signal" => {
+                    config.kill_signal = args.value(&arg, "kill signal", parse_signal)?
+                }
+                "--kill-after" => {
+                    let kill_after = args.value(&arg, "kill delay", str::parse::<Interval>)?;
+                    config.kill_after = Some(kill_after.as_duration());
+                }
+                "--stall-timeout" => {
+                    let timeout = args.value(&arg, "stall timeout", str::parse::<Interval>)?;
+                    config.stall_timeout = Some(timeout.as_duration());
+                }
+                "--max-output" => {
+                    config.max_output = Some(args.value(&arg, "output size", parse_size)?)
+                }
+                "--max-output-kill" => {
+                    config.max_output_kill = Some(args.value(&arg, "output size", parse_size)?)
+                }
+                "--catch-up" => {
+                    config.catch_up = match args.attached {
+                        Some(_) => args.value(&arg, "catch-up value", parse_catch_up)?,
+                        None => u64::MAX,
+                    }
+                }
+                "--clock" => config.clock = args.value(&arg, "clock", parse_clock)?,
+                "--on-resume" => {
+                    config.on_resume = args.value(&arg, "on-resume policy", parse_on_resume)?
+                }
+                "--at" => config.at = Some(args.value(&arg, "time", str::parse::<LocalTime>)?),
+                "-n" => config.max_runs = Some(args.number(&arg, "run count", parse_max_runs)?),
+                "--on-failure" => config.on_failure = Some(next_arg(&mut args, "hook command")?),
+                "--on-success" => config.on_success = Some(next_arg(&mut args, "hook command")?),
+                "--on-skip" => config.on_skip = Some(next_arg(&mut args, "hook command")?),
+                #[cfg(feature = "webhook")]
+                "--notify-url" => config.notify_url = Some(args.value(&arg, "URL", parse_url)?),
+                #[cfg(feature = "webhook")]
+                "--notify-recovery" => config.notify_recovery = true,
+                #[cfg(not(feature = "webhook"))]
+                "--notify-url" | "--notify-recovery" => {
+                    return Err(ParseError::Unsupported(if arg == "--notify-url" {
+                        "--notify-url"
+                    } else {
+                        "--notify-recovery"
+                    }))
+                }
+                "--notify-desktop" => config.notify_desktop = true,
+                "--bell" => config.bell = true,
+                "--flash" => config.flash = true,
+                #[cfg(feature = "otel")]
+                "--otel-endpoint" => {
+                    config.otel_endpoint = Some(args.value(&arg, "URL", parse_url)?)
+                }
+                #[cfg(not(feature = "otel"))]
+                "--otel-endpoint" => return Err(ParseError::Unsupported("--otel-endpoint")),
+                "--ok-codes" => config.ok_codes = args.value(&arg, "exit codes", parse_ok_codes)?,
+                "--max-failures" => {
+                    config.max_failures =
+                        Some(args.number(&arg, "failure count", parse_max_failures)?);
+                }
+                "--cooldown" => cooldown = Some(args.value(&arg, "cooldown", parse_duration)?),
+                "--cooldown-after" => {
+                    cooldown_after =
+                        Some(args.number(&arg, "failure count", parse_max_failures)?);
+                }
+                "--on-spawn-error" => {
+                    config.on_spawn_error =
+                        args.value(&arg, "on-spawn-error policy", parse_on_spawn_error)?
+                }
+                "--no-keys" => config.keys = false,
+                "--countdown" => config.countdown = true,
+                "--pty" => config.pty = true,
+                "--set-title" => config.set_title = true,
+                "--pause-file" => {
+                    config.pause_file = Some(PathBuf::from(next_arg(&mut args, "pause file path")?))
+                }
+                "--max-load" => {
+                    config.max_load = Some(args.value(&arg, "load average", parse_max_load)?)
+                }
+                "--only-on-ac" => config.only_on_ac = true,
+                "--min-battery" => {
+                    config.min_battery =
+                        Some(args.value(&arg, "battery level", parse_battery_level)?)
+                }
+                "--min-free-space" => {
+                    config.min_free_space =
+                        Some(args.value(&arg, "free space", parse_free_space)?)
+                }
+                "--require-network" => {
+                    config.require_network = match args.attached {
+                        Some(_) => Some(NetworkCheck::Connect(args.value(
+                            &arg,
+                            "address",
+                            parse_address,
+                        )?)),
+                        None => Some(NetworkCheck::DefaultRoute),
+                    }
+                }
+                "--if-healthy" => {
+                    config.if_healthy = Some(args.value(&arg, "health check URL", parse_url)?)
+                }
+                "--if" => config.condition = Some(next_arg(&mut args, "condition command")?),
+                "--until-success" => config.until_success = true,
+                "--until-match" | "--until-no-match" => {
+                    let pattern = args.value(&arg, "pattern", parse_pattern)?;
+
+                    config.until_match = Some(UntilMatch {
+                        pattern,
+                        matches: arg == "--until-match",
+                    });
+                }
+                "--until" => {
+                    config.until = Some(args.value(&arg, "time", str::parse::<LocalTime>)?)
+                }
+                "--between" => {
+                    config.between =
+                        Some(args.value(&arg, "time window", str::parse::<TimeWindow>)?)
+                }
+                "--interval-between" => config.interval_windows.push(args.value(
+                    &arg,
+                    "interval window",
+                    parse_interval_window,
+                )?),
+                "--interval-default" => {
+                    interval_default = Some(args.value(&arg, "interval", parse_duration)?);
+                }
+                "--days" => config.days = Some(args.value(&arg, "days", str::parse::<Weekdays>)?),
+                "--skip-dates" => {
+                    config.skip_dates = args.value(&arg, "skip dates path", read_skip_dates)?
+                }
+                "--tz" => {
+                    config.time_zone = Some(args.value(&arg, "time zone", |time_zone| {
+                        validate_time_zone(time_zone).map(|_| time_zone.to_string())
+                    })?);
+                }
+                "-q" | "--quiet" => config.quiet = MAX_QUIET.min(config.quiet + 1),
+                "-qq" => config.quiet = MAX_QUIET,
+                "--verbose" => config.verbose = MAX_VERBOSE.min(config.verbose + 1),
+                "-vv" => config.verbose = MAX_VERBOSE,
+                "--log-format" => {
+                    config.log_format = args.value(&arg, "log format", parse_log_format)?
+                }
+                "--color" => config.color = args.value(&arg, "color choice", parse_color)?,
+                "--summary" => config.summary = true,
+                "--stats-every" => {
+                    let stats_every = args.value(&arg, "stats interval", str::parse::<Interval>)?;
+                    config.stats_every = Some(stats_every.as_duration());
+                }
+                "--on-failure-output" => set_output_mode(&mut config, OutputMode::OnFailure)?,
+                "--changes" => set_output_mode(&mut config, OutputMode::Changes)?,
+                "--watch" => set_output_mode(&mut config, OutputMode::Watch)?,
+                "--diff" => config.diff = true,
+                "--output-dir" => {
+                    config.output_dir = Some(args.value(&arg, "output directory", |dir| {
+                        parse_directory(dir, "output directory")
+                    })?);
+                }
+                "--keep-last" => {
+                    config.keep_last =
+                        Some(args.number(&arg, "keep-last value", parse_keep_last)?)
+                }
+                "--heartbeat-file" => {
+                    config.heartbeat_file =
+                        Some(PathBuf::from(next_arg(&mut args, "heartbeat file path")?));
+                }
+                "--lock-file" => {
+                    config.lock_file = Some(PathBuf::from(next_arg(&mut args, "lock file path")?));
+                }
+                "--history" => {
+                    config.history = Some(PathBuf::from(next_arg(&mut args, "history file path")?));
+                }
+                "--report" => {
+                    config.report = Some(args.value(&arg, "report file path", parse_report)?)
+                }
+                "--name" => {
+                    config.name = Some(args.value(&arg, "name", |name| {
+                        validate_name(name).map(|_| name.to_string())
+                    })?);
+                }
+                "--single-instance" => config.single_instance = true,
+                "--control-socket" => {
+                    config.control_socket =
+                        Some(PathBuf::from(next_arg(&mut args, "control socket path")?));
+                }
+                "--log" => config.log = Some(PathBuf::from(next_arg(&mut args, "log file path")?)),
+                "--daemon" => config.daemon = true,
+                "--systemd" => config.systemd = true,
+                "--log-syslog" => config.syslog = true,
+                "--pid-file" => {
+                    config.pid_file = Some(PathBuf::from(next_arg(&mut args, "PID file path")?));
+                }
+                #[cfg(feature = "tui")]
+                "--tui" => config.tui = true,
+                #[cfg(not(feature = "tui"))]
+                "--tui" => return Err(ParseError::Unsupported("--tui")),
+                "-e" => {
+                    let (key, value) = args.value(&arg, "environment variable", parse_env_var)?;
+                    config.env.insert(key, value);
+                }
+                "--env-file" => {
+                    config
+                        .env
+                        .extend(args.value(&arg, "env file path", read_env_file)?);
+                }
+                "--nice" => config.nice = Some(args.value(&arg, "niceness", parse_nice)?),
+                "--limit-mem" => {
+                    config.limits.memory = Some(args.value(&arg, "memory limit", parse_size)?)
+                }
+                "--limit-cpu" => {
+                    config.limits.cpu = Some(args.value(&arg, "CPU time limit", parse_duration)?)
+                }
+                "--clean-env" => config.clean_env = true,
+                "--cwd" => {
+                    config.cwd = Some(args.value(&arg, "working directory", |cwd| {
+                        parse_directory(cwd, "working directory")
+                    })?);
+                }
+                "--jobs-file" => {
+                    let path = next_arg(&mut args, "jobs file path")?;
+                    let jobs = read_jobs_file(Path::new(&path));
+                    let jobs = jobs.map_err(invalid_value(&arg, &path))?;
+
+                    // re-read on SIGHUP, when it is the only source of the jobs
+                    config.jobs_file =
+                        (config.jobs.is_empty() && path != "-").then(|| PathBuf::from(path));
+                    config.jobs.extend(jobs);
+                }
+                "--from-stdin" => {
+                    let jobs = read_jobs_file(Path::new("-"));
+                    config.jobs.extend(jobs.map_err(invalid_value(&arg, "-"))?);
+                    config.jobs_file = None;
+                }
+                "--args-from" => {
+                    config.arg_lines = Some(args.value(&arg, "args file path", read_args_file)?)
+                }
+                "--args-exhausted" => {
+                    let policy = args.value(&arg, "args-exhausted policy", parse_args_exhausted)?;
+                    args_exhausted = Some(policy);
+                }
+                "--cmd" => {
+                    config.jobs.push(Job {
+                        command: next_arg(&mut args, "command")?,
+                        interval: None,
+                        concurrency: None,
+                        rotate: true,
+                    });
+                    config.jobs_file = None;
+                }
+                "--stderr" => config.stderr = args.value(&arg, "stderr mode", parse_stderr)?,
+                "--stdin" => config.stdin = args.value(&arg, "stdin mode", parse_stdin)?,
+                "--stdin-data" => config.stdin = Input::Data(next_arg(&mut args, "stdin data")?),
+                "--stdin-file" => {
+                    config.stdin =
+                        Input::File(PathBuf::from(next_arg(&mut args, "stdin file path")?))
+                }
+                _ => return Err(invalid_option(arg, has_interval)),
+            }
+
+            if let Some(value) = args.attached.take() {
+                return Err(ParseError::UnexpectedValue { option: arg, value });
+            }
+        };
+
+        if !has_interval {
+            return Err(ParseError::MissingInterval {
+                supervise: config.supervise,
+            });
+        }
+
+        match command {
+            Some(_) if !config.jobs.is_empty() => {
+                return Err(ParseError::InvalidUsage(
+                    "--jobs-file and --cmd cannot be used with a command!",
+                ))
+            }
+            Some(command) => config.command = command,
+            None if config.jobs.is_empty() && preview.is_none() => {
+                return Err(ParseError::MissingCommand)
+            }
+            None => {}
+        }
+
+        if !config.jobs.is_empty()
+            && (config.output == OutputMode::Changes
+                || config.output == OutputMode::Watch
+                || config.diff)
+        {
+            return Err(ParseError::InvalidUsage(
+                "--jobs-file and --cmd cannot be used with --changes, --watch and --diff!",
+            ));
+        }
+
+        if config.output_dir.is_some() && (config.output != OutputMode::Inherit || config.diff) {
+            return Err(ParseError::InvalidUsage(
+                "--output-dir cannot be used with --on-failure-output, --changes, --watch and --diff!",
+            ));
+        }
+
+        if config.until_match.is_some() && config.output_dir.is_some() {
+            return Err(ParseError::InvalidUsage(
+                "--until-match and --until-no-match cannot be used with --output-dir!",
+            ));
+        }
+
+        config.cooldown = match (cooldown, cooldown_after) {
+            (Some(duration), Some(after)) => Some(Cooldown { duration, after }),
+            (None, None) => None,
+            _ => {
+                return Err(ParseError::InvalidUsage(
+                    "--cooldown and --cooldown-after must be used together!",
+                ))
+            }
+        };
+
+        if config.pty && config.quiet > 0 {
+            return Err(ParseError::InvalidUsage(
+                "--pty cannot be used with --quiet!",
+            ));
+        }
+
+        if config.pty && config.output_dir.is_some() {
+            return Err(ParseError::InvalidUsage(
+                "--pty cannot be used with --output-dir!",
+            ));
+        }
+
+        if config.stall_timeout.is_some() && config.quiet > 0 {
+            return Err(ParseError::InvalidUsage(
+                "--stall-timeout cannot be used with --quiet!",
+            ));
+        }
+
+        if config.stall_timeout.is_some() && config.output_dir.is_some() {
+            return Err(ParseError::InvalidUsage(
+                "--stall-timeout cannot be used with --output-dir!",
+            ));
+        }
+
+        let caps_output = config.max_output.is_some() || config.max_output_kill.is_some();
+
+        if caps_output && config.output_dir.is_some() {
+            return Err(ParseError::InvalidUsage(
+                "--max-output and --max-output-kill cannot be used with --output-dir!",
+            ));
+        }
+
+        if let (Some(max_output), Some(kill)) = (config.max_output, config.max_output_kill) {
+            if kill <= max_output {
+                return Err(ParseError::InvalidUsage(
+                    "--max-output-kill must be larger than --max-output!",
+                ));
+            }
+        }
+
+        if let Some(interval) = interval_default {
+            if config.interval_windows.is_empty() {
+                return Err(ParseError::InvalidUsage(
+                    "--interval-default requires --interval-between!",
+                ));
+            }
+
+            config.interval = interval;
+            config.max_interval = None;
+        }
+
+        if !config.interval_windows.is_empty() {
+            if config.supervise {
+                return Err(ParseError::InvalidUsage(
+                    "--interval-between cannot be used with --supervise!",
+                ));
+            }
+
+            if config.max_interval.is_some() {
+                return Err(ParseError::InvalidUsage(
+                    "--interval-between cannot be used with an interval range!",
+                ));
+            }
+        }
+
+        if config.supervise {
+            let schedules_runs = config.concurrency != 1
+                || config.on_busy != OnBusy::Skip
+                || config.batch.is_some()
+                || config.detach
+                || !config.jobs.is_empty();
+
+            if schedules_runs {
+                return Err(ParseError::InvalidUsage(
+                    "--supervise cannot be used with -c, --on-busy, --batch, --detach, --jobs-file and --cmd!",
+                ));
+            }
+        }
+
+        if config.detach {
+            if config.concurrency != 1 || config.on_busy != OnBusy::Skip {
+                return Err(ParseError::InvalidUsage(
+                    "--detach cannot be used with -c and --on-busy!",
+                ));
+            }
+
+            let reads_output = config.output != OutputMode::Inherit
+                || config.diff
+                || config.until_match.is_some()
+                || config.output_dir.is_some()
+                || config.pty
+                || config.stall_timeout.is_some()
+                || config.max_output.is_some()
+                || config.max_output_kill.is_some();
+
+            if reads_output {
+                return Err(ParseError::InvalidUsage(
+                    "--detach cannot be used with the options that read the output of the runs!",
+                ));
+            }
+
+            #[cfg(feature = "webhook")]
+            let notifies = config.notify_url.is_some();
+            #[cfg(not(feature = "webhook"))]
+            let notifies = false;
+
+            let waits = config.lock_file.is_some()
+                || config.until_success
+                || config.max_failures.is_some()
+                || config.cooldown.is_some()
+                || config.on_failure.is_some()
+                || config.on_success.is_some()
+                || notifies
+                || config.notify_desktop
+                || config.bell
+                || config.history.is_some()
+                || config.report.is_some();
+
+            if waits {
+                return Err(ParseError::InvalidUsage(
+                    "--detach cannot be used with the options that wait for the runs to complete!",
+                ));
+            }
+        }
+
+        if config.batch.is_some() && config.lock_file.is_some() {
+            return Err(ParseError::InvalidUsage(
+                "--batch cannot be used with --lock-file!",
+            ));
+        }
+
+        if config.stderr != ErrorOutput::Separate && config.pty {
+            return Err(ParseError::InvalidUsage(
+                "--stderr cannot be used with --pty!",
+            ));
+        }
+
+        if config.stderr != ErrorOutput::Separate && config.output_dir.is_some() {
+            return Err(ParseError::InvalidUsage(
+                "--stderr cannot be used with --output-dir!",
+            ));
+        }
+
+        match args_exhausted {
+            Some(_) if config.arg_lines.is_none() => {
+                return Err(ParseError::InvalidUsage(
+                    "--args-exhausted requires --args-from!",
+                ))
+            }
+            Some(policy) => config.args_exhausted = policy,
+            None => {}
+        }
+
+        if config.single_instance && config.name.is_none() {
+            return Err(ParseError::InvalidUsage(
+                "--single-instance requires --name!",
+            ));
+        }
+
+        if config.flash && !config.bell {
+            return Err(ParseError::InvalidUsage("--flash requires --bell!"));
+        }
+
+        #[cfg(feature = "tui")]
+        let draws_screen = config.output == OutputMode::Watch || config.tui;
+        #[cfg(not(feature = "tui"))]
+        let draws_screen = config.output == OutputMode::Watch;
+
+        if config.flash && !draws_screen {
+            return Err(ParseError::InvalidUsage(
+                "--flash requires --watch or --tui!",
+            ));
+        }
+
+        #[cfg(feature = "webhook")]
+        if config.notify_recovery && config.notify_url.is_none() {
+            return Err(ParseError::InvalidUsage(
+                "--notify-recovery requires --notify-url!",
+            ));
+        }
+
+        if config.keep_last.is_some() && config.output_dir.is_none() {
+            return Err(ParseError::InvalidUsage(
+                "--keep-last requires --output-dir!",
+            ));
+        }
+
+        if config.daemon && config.log.is_none() {
+            return Err(ParseError::InvalidUsage("--daemon requires --log!"));
+        }
+
+        if config.daemon && config.stdin == Input::Inherit {
+            return Err(ParseError::InvalidUsage(
+                "--daemon cannot be used with --stdin inherit!",
+            ));
         }
 
-        let args = args.collect();
+        // the dashboard reads its keys from stdin
+        #[cfg(feature = "tui")]
+        if config.tui && config.stdin == Input::Inherit {
+            return Err(ParseError::InvalidUsage(
+                "--tui cannot be used with --stdin inherit!",
+            ));
+        }
+
+        #[cfg(feature = "tui")]
+        if config.tui && !config.jobs.is_empty() {
+            return Err(ParseError::InvalidUsage(
+                "--tui cannot be used with --jobs-file and --cmd!",
+            ));
+        }
+
+        config.args = args.collect();
+
+        if let Some(count) = preview {
+            return Ok(Action::Preview(config, count));
+        }
 
-        Ok(Action::Run(Config {
-            interval_ms,
-            concurrency,
-            command,
-            args,
-        }))
+        Ok(Action::Run(config))
     }
 }
 
-fn parse_interval_as_ms(interval: &str) -> Result<u64, String> {
-    if interval.is_empty() {
-        return Err(String::from("Interval cannot be empty"));
+// The arguments, preceded by the value attached to the last option, if any.
+struct Arguments<T> {
+    args: T,
+    attached: Option<String>,
+}
+
+impl<T: Iterator<Item = String>> Iterator for Arguments<T> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.attached.take().or_else(|| self.args.next())
     }
+}
 
-    let re = Regex::new(concat!(
-        "^",
-        "(?:([0-9]+)d)?",
-        "(?:([0-9]+)h)?",
-        "(?:([0-9]+)m)?",
-        "(?:([0-9]+)(?:\\.([0-9]+))?s)?",
-        "$"
+impl<T: Iterator<Item = String>> Arguments<T> {
+    // Parses the next argument, as the value of the option.
+    fn value<V>(
+        &mut self,
+        option: &str,
+        what: &'static str,
+        parse: impl FnOnce(&str) -> Result<V, String>,
+    ) -> Result<V, ParseError> {
+        let value = next_arg(self, what)?;
+
+        parse(&value).map_err(invalid_value(option, &value))
+    }
+
+    // Parses the next argument, as the value of an option expecting a number.
+    fn number<V>(
+        &mut self,
+        option: &str,
+        what: &'static str,
+        parse: impl FnOnce(&str) -> Result<V, String>,
+    ) -> Result<V, ParseError> {
+        let value = next_number(self, option, what)?;
+
+        parse(&value).map_err(invalid_value(option, &value))
+    }
+}
+
+// Splits an option from its attached value: --concurrency=5 into --concurrency and 5, -c5 or -c=5 into -c and 5.
+fn split_attached_value(arg: &str) -> Option<(String, String)> {
+    if let Some(option) = arg.strip_prefix("--") {
+        let (name, value) = option.split_once('=')?;
+
+        return match name.is_empty() {
+            true => None,
+            false => Some((format!("--{name}"), value.to_string())),
+        };
+    }
+
+    let option = SHORT_OPTIONS_WITH_VALUE
+        .into_iter()
+        .find(|option| arg.len() > option.len() && arg.starts_with(option))?;
+    let value = &arg[option.len()..];
+
+    Some((
+        option.to_string(),
+        value.strip_prefix('=').unwrap_or(value).to_string(),
     ))
-    .unwrap();
+}
 
-    if let Some(caps) = re.captures(interval) {
-        let d = caps.get(1);
-        let h = caps.get(2);
-        let m = caps.get(3);
-        let s = caps.get(4);
-        let f = caps.get(5);
+// Turns the reason why the value of an option is invalid into an error.
+fn invalid_value(option: &str, value: &str) -> impl FnOnce(String) -> ParseError {
+    let (option, value) = (option.to_string(), value.to_string());
 
-        let d = convert_match_to_u64(d);
-        let h = convert_match_to_u64(h);
-        let m = convert_match_to_u64(m);
-        let s = convert_match_to_u64(s);
+    move |reason| ParseError::InvalidValue {
+        option,
+        value,
+        reason,
+    }
+}
 
-        let ms = convert_fraction_match_to_ms_u64(f).ok_or_else(|| {
-            format!("Invalid interval '{interval}': maximum precision is millisecond")
-        })?;
+// The error for an unknown option, suggesting the closest known one, compared without their dashes.
+fn invalid_option(arg: String, after_interval: bool) -> ParseError {
+    let options = OPTIONS.map(|option| (option.trim_start_matches('-'), option));
 
-        let total_ms = calculate_total_ms(d, h, m, s, ms);
+    ParseError::InvalidOption {
+        suggestion: suggest(arg.trim_start_matches('-'), options),
+        option: arg,
+        after_interval,
+    }
+}
 
-        match total_ms {
-            Some(0) => Err(format!(
-                "Invalid interval '{interval}': interval cannot be zero"
-            )),
-            Some(ms) => Ok(ms),
-            None => Err(format!(
-                "Invalid interval '{interval}': interval is too large"
-            )),
-        }
+// Returns the next argument, or an error mentioning what is missing.
+fn next_arg<T>(args: &mut T, what: &'static str) -> Result<String, ParseError>
+where
+    T: Iterator<Item = String>,
+{
+    args.next().ok_or(ParseError::MissingValue(what))
+}
+
+// Returns the next argument of an option expecting a number,
+// with an explicit error when it is missing and the command follows instead.
+fn next_number<T>(args: &mut T, option: &str, what: &'static str) -> Result<String, ParseError>
+where
+    T: Iterator<Item = String>,
+{
+    let value = next_arg(args, what)?;
+
+    match value.starts_with(|c: char| c.is_ascii_digit()) {
+        true => Ok(value),
+        false => Err(ParseError::ExpectedNumber {
+            option: option.to_string(),
+            value,
+        }),
+    }
+}
+
+// The non-empty lines of the file, or of stdin for -.
+fn read_args_file(path: &str) -> Result<Vec<String>, String> {
+    let contents = if path == "-" {
+        io::read_to_string(io::stdin())
+            .map_err(|e| format!("Failed to read args from stdin: {e}"))?
     } else {
-        Err(format!(
-            "Invalid interval '{interval}': unrecognized format"
-        ))
-    }
-}
-
-// Optional arguments, and return value, are None iff the number is too large.
-fn calculate_total_ms(
-    d: Option<u64>,
-    h: Option<u64>,
-    m: Option<u64>,
-    s: Option<u64>,
-    ms: u64,
-) -> Option<u64> {
-    d?.checked_mul(86_400_000)?
-        .checked_add(h?.checked_mul(3_600_000)?)?
-        .checked_add(m?.checked_mul(60_000)?)?
-        .checked_add(s?.checked_mul(1_000)?)?
-        .checked_add(ms)
-}
-
-// Used with [0-9]+ matches.
-// Returns 0 if there is no match, and None if the number does not fit in u64.
-fn convert_match_to_u64(m: Option<Match>) -> Option<u64> {
-    match m {
-        Some(m) => m.as_str().parse().ok(),
-        None => Some(0),
-    }
-}
-
-// Used with [0-9]+ matches.
-// Returns 0 if there is no match, and None if the fraction is > 3 digits.
-// Examples:
-//  - "1" => 100,
-//  - "12" => 120
-//  - "123" => 123
-//  - "1234" => None
-fn convert_fraction_match_to_ms_u64(f: Option<Match>) -> Option<u64> {
-    match f {
-        Some(f) => {
-            let f = f.as_str();
-
-            match f.len() {
-                len if len <= 3 => format!("{f:0<3}").parse().ok(),
-                _ => None,
-            }
+        fs::read_to_string(path).map_err(|e| format!("Failed to read args file '{path}': {e}"))?
+    };
+
+    let lines: Vec<String> = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(String::from)
+        .collect();
+
+    if lines.is_empty() {
+        return Err(String::from("No lines in args file!"));
+    }
+
+    Ok(lines)
+}
+
+fn read_env_file(path: &str) -> Result<Vec<(String, String)>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read env file '{path}': {e}"))?;
+
+    parse_env_file(&contents)
+}
+
+fn read_skip_dates(path: &str) -> Result<Vec<Date>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read skip dates file '{path}': {e}"))?;
+
+    parse_dates(&contents).map_err(|e| format!("{e} in '{path}'"))
+}
+
+fn set_output_mode(config: &mut Config, mode: OutputMode) -> Result<(), ParseError> {
+    if config.output != OutputMode::Inherit && config.output != mode {
+        return Err(ParseError::InvalidUsage(
+            "Only one of --on-failure-output, --changes and --watch can be used!",
+        ));
+    }
+
+    config.output = mode;
+
+    Ok(())
+}
+
+fn parse_on_busy(policy: &str) -> Result<OnBusy, String> {
+    match policy.split_once(':') {
+        None if policy == "skip" => Ok(OnBusy::Skip),
+        None if policy == "queue" => Ok(OnBusy::Queue(1)),
+        None if policy == "kill" => Ok(OnBusy::Kill),
+        Some(("queue", max_queued)) => match max_queued.parse() {
+            Ok(max_queued) if max_queued > 0 => Ok(OnBusy::Queue(max_queued)),
+            _ => Err(format!("Invalid queue size: '{max_queued}'")),
+        },
+        _ => Err(format!("Invalid on-busy policy: '{policy}'")),
+    }
+}
+
+fn parse_duration(duration: &str) -> Result<Duration, String> {
+    Ok(duration.parse::<Interval>()?.as_duration())
+}
+
+// Parses an interval, or an interval range such as 30s-2m.
+fn parse_interval_range(range: &str) -> Result<(Duration, Option<Duration>), String> {
+    let Some((min, max)) = range.split_once('-') else {
+        return Ok((range.parse::<Interval>()?.as_duration(), None));
+    };
+
+    let min = min.parse::<Interval>()?.as_duration();
+    let max = max.parse::<Interval>()?.as_duration();
+
+    match min.cmp(&max) {
+        Ordering::Less => Ok((min, Some(max))),
+        Ordering::Equal => Ok((min, None)),
+        Ordering::Greater => Err(format!(
+            "Invalid interval range '{range}': minimum is greater than maximum"
+        )),
+    }
+}
+
+fn parse_rate_limit(limit: &str) -> Result<RateLimit, String> {
+    let invalid = || format!("Invalid rate limit: '{limit}', expected <runs>/<interval>");
+
+    let (runs, per) = limit.split_once('/').ok_or_else(invalid)?;
+
+    match runs.parse() {
+        Ok(runs) if runs > 0 => Ok(RateLimit {
+            runs,
+            per: per.parse::<Interval>()?.as_duration(),
+        }),
+        _ => Err(invalid()),
+    }
+}
+
+// Parses a signal name such as TERM or SIGTERM, or number.
+fn parse_signal(signal: &str) -> Result<Signal, String> {
+    let parsed = match signal.parse::<i32>() {
+        Ok(number) => Signal::try_from(number),
+        Err(_) if signal.starts_with("SIG") => signal.parse(),
+        Err(_) => format!("SIG{signal}").parse(),
+    };
+
+    parsed.map_err(|_| format!("Invalid signal: '{signal}'"))
+}
+
+fn parse_report(path: &str) -> Result<Report, String> {
+    let format = match path.rsplit_once('.') {
+        Some((_, "csv")) => ReportFormat::Csv,
+        Some((_, "json" | "jsonl")) => ReportFormat::Json,
+        _ => {
+            return Err(format!(
+                "Invalid report file: '{path}', expected a .csv or .json file"
+            ))
         }
-        None => Some(0),
+    };
+
+    Ok(Report {
+        path: PathBuf::from(path),
+        format,
+    })
+}
+
+fn parse_url(url: &str) -> Result<String, String> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(format!(
+            "Invalid URL: '{url}', expected http:// or https://"
+        ));
+    }
+
+    Ok(String::from(url))
+}
+
+fn parse_pattern(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|_| format!("Invalid pattern: '{pattern}'"))
+}
+
+fn parse_max_runs(count: &str) -> Result<u64, String> {
+    match count.parse() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!("Invalid run count: '{count}'")),
+    }
+}
+
+fn parse_ok_codes(codes: &str) -> Result<Vec<i32>, String> {
+    codes
+        .split(',')
+        .map(|code| match code.parse::<u8>() {
+            Ok(code) => Ok(i32::from(code)),
+            Err(_) => Err(format!("Invalid exit code: '{code}', expected 0–255")),
+        })
+        .collect()
+}
+
+fn parse_max_load(load: &str) -> Result<f64, String> {
+    match load.parse::<f64>() {
+        Ok(load) if load > 0.0 && load.is_finite() => Ok(load),
+        _ => Err(format!("Invalid load average: '{load}'")),
+    }
+}
+
+fn parse_battery_level(level: &str) -> Result<u8, String> {
+    match level.strip_suffix('%').unwrap_or(level).parse::<u8>() {
+        Ok(percent @ 1..=100) => Ok(percent),
+        _ => Err(format!("Invalid battery level: '{level}', expected 1–100%")),
+    }
+}
+
+// <start>-<end>=<interval>, such as 09:00-18:00=30s.
+fn parse_interval_window(window: &str) -> Result<IntervalWindow, String> {
+    let Some((time_window, interval)) = window.split_once('=') else {
+        return Err(format!(
+            "Invalid interval window: '{window}', expected <start>-<end>=<interval>, e.g. 09:00-18:00=30s"
+        ));
+    };
+
+    Ok(IntervalWindow {
+        window: time_window.parse::<TimeWindow>()?,
+        interval: interval.parse::<Interval>()?.as_duration(),
+    })
+}
+
+// <size>@<path>, such as 10G@/var/lib/downloads.
+fn parse_free_space(free_space: &str) -> Result<FreeSpace, String> {
+    match free_space.split_once('@') {
+        Some((size, path)) if !path.is_empty() => Ok(FreeSpace {
+            bytes: parse_size(size)?,
+            path: PathBuf::from(path),
+        }),
+        _ => Err(format!(
+            "Invalid free space: '{free_space}', expected <size>@<path>, e.g. 10G@/var/lib"
+        )),
+    }
+}
+
+fn parse_address(address: &str) -> Result<String, String> {
+    match address.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {
+            Ok(String::from(address))
+        }
+        _ => Err(format!("Invalid address: '{address}', expected host:port")),
+    }
+}
+
+fn parse_max_failures(count: &str) -> Result<u64, String> {
+    match count.parse() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!("Invalid failure count: '{count}'")),
+    }
+}
+
+fn parse_keep_last(count: &str) -> Result<usize, String> {
+    match count.parse() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!("Invalid keep-last value: '{count}'")),
+    }
+}
+
+fn parse_catch_up(max: &str) -> Result<u64, String> {
+    match max.parse() {
+        Ok(max) if max > 0 => Ok(max),
+        _ => Err(format!("Invalid catch-up value: '{max}'")),
+    }
+}
+
+fn parse_clock(clock: &str) -> Result<Clock, String> {
+    match clock {
+        "monotonic" => Ok(Clock::Monotonic),
+        "wall" => Ok(Clock::Wall),
+        _ => Err(format!("Invalid clock: '{clock}'")),
+    }
+}
+
+fn parse_preview_count(count: &str) -> Result<usize, String> {
+    match count.parse() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!("Invalid preview count: '{count}'")),
+    }
+}
+
+fn parse_args_exhausted(policy: &str) -> Result<ArgsExhausted, String> {
+    match policy {
+        "wrap" => Ok(ArgsExhausted::Wrap),
+        "stop" => Ok(ArgsExhausted::Stop),
+        _ => Err(format!(
+            "Invalid args-exhausted policy: '{policy}', expected wrap or stop"
+        )),
+    }
+}
+
+fn parse_on_spawn_error(policy: &str) -> Result<OnSpawnError, String> {
+    match policy {
+        "exit" => Ok(OnSpawnError::Exit),
+        "retry" => Ok(OnSpawnError::Retry),
+        "ignore" => Ok(OnSpawnError::Ignore),
+        _ => Err(format!(
+            "Invalid on-spawn-error policy: '{policy}', expected exit, retry or ignore"
+        )),
+    }
+}
+
+fn parse_on_resume(policy: &str) -> Result<OnResume, String> {
+    match policy {
+        "warn" => Ok(OnResume::Warn),
+        "run-once" => Ok(OnResume::RunOnce),
+        "catch-up" => Ok(OnResume::CatchUp),
+        _ => Err(format!("Invalid on-resume policy: '{policy}'")),
     }
 }
 
-fn parse_concurrency(concurrency: &str) -> Result<u16, String> {
-    let invalid_range = || {
-        format!("Invalid concurrency: value {concurrency} is not in the range 1–{MAX_CONCURRENCY}")
+fn parse_log_format(format: &str) -> Result<LogFormat, String> {
+    match format {
+        "text" => Ok(LogFormat::Text),
+        "json" => Ok(LogFormat::Json),
+        _ => Err(format!(
+            "Invalid log format: '{format}', expected text or json"
+        )),
+    }
+}
+
+fn parse_color(color: &str) -> Result<ColorChoice, String> {
+    match color {
+        "auto" => Ok(ColorChoice::Auto),
+        "always" => Ok(ColorChoice::Always),
+        "never" => Ok(ColorChoice::Never),
+        _ => Err(format!(
+            "Invalid color choice: '{color}', expected auto, always or never"
+        )),
+    }
+}
+
+fn parse_env_var(var: &str) -> Result<(String, String), String> {
+    match var.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!(
+            "Invalid environment variable: '{var}', expected KEY=VALUE"
+        )),
+    }
+}
+
+// Parses one KEY=VALUE per line, ignoring empty lines and comments.
+fn parse_env_file(contents: &str) -> Result<Vec<(String, String)>, String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(parse_env_var)
+        .collect()
+}
+
+fn parse_nice(nice: &str) -> Result<i32, String> {
+    match nice.parse() {
+        Ok(nice) if (-20..=19).contains(&nice) => Ok(nice),
+        _ => Err(format!("Invalid niceness: '{nice}', expected -20 to 19")),
+    }
+}
+
+// Parses a number of bytes, with an optional binary suffix such as 512M.
+fn parse_size(size: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid size: '{size}', expected e.g. 512M or 2G");
+
+    let (number, multiplier) = match size.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&size[..i], 1 << 10),
+        Some((i, 'M' | 'm')) => (&size[..i], 1 << 20),
+        Some((i, 'G' | 'g')) => (&size[..i], 1 << 30),
+        Some((i, 'T' | 't')) => (&size[..i], 1 << 40),
+        _ => (size, 1),
     };
 
-    match concurrency.parse() {
-        Ok(concurrency) if (1..=MAX_CONCURRENCY).contains(&concurrency) => Ok(concurrency),
+    match number.parse::<u64>() {
+        Ok(number) if number > 0 => number.checked_mul(multiplier).ok_or_else(invalid),
+        _ => Err(invalid()),
+    }
+}
+
+// Returns the path of the directory, that must exist.
+fn parse_directory(dir: &str, what: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(dir);
+
+    if !path.is_dir() {
+        return Err(format!("Invalid {what}: '{dir}'"));
+    }
+
+    Ok(path)
+}
+
+fn parse_stdin(mode: &str) -> Result<Input, String> {
+    match mode {
+        "null" => Ok(Input::Null),
+        "inherit" => Ok(Input::Inherit),
+        _ => Err(format!("Invalid stdin mode: '{mode}'")),
+    }
+}
+
+fn parse_stderr(mode: &str) -> Result<ErrorOutput, String> {
+    match mode {
+        "separate" => Ok(ErrorOutput::Separate),
+        "merge" => Ok(ErrorOutput::Merge),
+        "discard" => Ok(ErrorOutput::Discard),
+        _ => match mode.strip_prefix("file:") {
+            Some(path) if !path.is_empty() => Ok(ErrorOutput::File(PathBuf::from(path))),
+            _ => Err(format!(
+                "Invalid stderr mode: '{mode}', expected separate, merge, discard or file:<path>"
+            )),
+        },
+    }
+}
+
+// The batch is bounded like the concurrency, beyond which its instances would be skipped anyway.
+fn parse_batch(batch: &str) -> Result<u16, String> {
+    let invalid_range =
+        || format!("Invalid batch size: value {batch} is not in the range 1–{MAX_CONCURRENCY}");
+
+    match batch.parse() {
+        Ok(batch) if (1..=MAX_CONCURRENCY).contains(&batch) => Ok(batch),
         Ok(_) => Err(invalid_range()),
         Err(err) if *err.kind() == IntErrorKind::PosOverflow => Err(invalid_range()),
-        Err(_) => Err(format!("Invalid concurrency value: '{concurrency}'")),
+        Err(_) => Err(format!("Invalid batch size: '{batch}'")),
     }
 }
 
@@ -195,283 +1401,647 @@ fn parse_concurrency(concurrency: &str) -> Result<u16, String> {
 #[rustfmt::skip]
 mod tests {
     use super::*;
+    use crate::limits::Limits;
+
+    fn config(interval_ms: u64, command: &str, args: &[&str]) -> Config {
+        Config {
+            interval: Duration::from_millis(interval_ms),
+            command: String::from(command),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            ..Config::default()
+        }
+    }
 
     #[test]
     fn test_parse_args() {
-        let test_cases = [
+        let test_cases = vec![
             // empty
             (vec![], Ok(Action::Help)),
             // help
             (vec!["-h"], Ok(Action::Help)),
             // version
             (vec!["-v"], Ok(Action::Version)),
+            // man page
+            (vec!["--man"], Ok(Action::Man)),
             // invalid option
             (vec!["-x"], Err("Invalid option: -x")),
+            // status
+            (vec!["status"], Ok(Action::Status(None))),
+            (vec!["status", "1234"], Ok(Action::Status(Some(String::from("1234"))))),
+            (vec!["status", "1234", "5678"], Err("Unexpected argument: 5678")),
+            // stop
+            (vec!["stop"], Err("Missing instance name or PID!")),
+            (vec!["stop", "1234"], Ok(Action::Stop(String::from("1234")))),
+            (vec!["stop", "1234", "5678"], Err("Unexpected argument: 5678")),
+            // history
+            (vec!["history"], Err("Missing history file path!")),
+            (vec!["history", "runs.sqlite"], Ok(Action::History(PathBuf::from("runs.sqlite")))),
+            (vec!["history", "runs.sqlite", "-n"], Err("Unexpected argument: -n")),
             // empty interval
             (vec![""], Err("Interval cannot be empty")),
             // invalid interval
-            (vec!["1"], Err("Invalid interval '1': unrecognized format")),
+            (vec!["1x"], Err("Invalid interval '1x': unrecognized format")),
             // missing command
             (vec!["1s"], Err("Missing command name!")),
             // invalid option after interval
             (vec!["1s", "-x"], Err("Invalid option after interval: -x")),
+            // suggestions
+            (vec!["--verbos", "1s", "date"], Err("Invalid option: --verbos, did you mean '--verbose'?")),
+            (vec!["1s", "-verbose", "date"], Err("Invalid option after interval: -verbose, did you mean '--verbose'?")),
+            (vec!["1s", "--concurency=2", "date"], Err("Invalid option after interval: --concurency, did you mean '--concurrency'?")),
+            (vec!["1s", "--c", "2", "date"], Err("Invalid option after interval: --c, did you mean '-c'?")),
+            (vec!["2sec", "date"], Err("Invalid interval '2sec': unrecognized format, did you mean '2s'?")),
+            // options before the interval
+            (vec!["-c", "10", "2.5s", "curl", "-s"], Ok(Action::Run(Config { concurrency: 10, ..config(2_500, "curl", &["-s"]) }))),
+            (vec!["-c", "10", "--verbose", "2.5s", "-q", "curl"], Ok(Action::Run(Config { concurrency: 10, verbose: 1, quiet: 1, ..config(2_500, "curl", &[]) }))),
+            (vec!["-c", "10"], Err("Missing interval!")),
+            (vec!["-c", "10", "-x", "1s", "date"], Err("Invalid option: -x")),
+            (vec!["-c", "10", "--", "1s", "date"], Err("Invalid option: --")),
+            (vec!["--jobs-file", "/dev/null", "1s"], Err("No commands in jobs file!")),
+            (vec!["--interval-default", "5m", "--interval-between", "09:00-18:00=30s", "1m", "./poll.sh"], Ok(Action::Run(Config {
+                interval_windows: vec![IntervalWindow { window: "09:00-18:00".parse().unwrap(), interval: Duration::from_secs(30) }],
+                ..config(300_000, "./poll.sh", &[])
+            }))),
+            (vec!["--verbose", "--preview", "3", "5m"], Ok(Action::Preview(Config { verbose: 1, ..config(300_000, "", &[]) }, 3))),
             // missing concurrency value
             (vec!["1s", "-c"], Err("Missing concurrency value!")),
             // invalid concurrency value
-            (vec!["1s", "-c", "x"], Err("Invalid concurrency value: 'x'")),
+            (vec!["1s", "-c", "x"], Err("Option -c expects a number, got 'x'")),
+            (vec!["1s", "-c", "echo", "hello"], Err("Option -c expects a number, got 'echo'")),
+            // attached values
+            (vec!["1s", "-c5", "date"], Ok(Action::Run(Config { concurrency: 5, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "-c=5", "date"], Ok(Action::Run(Config { concurrency: 5, ..config(1_000, "date", &[]) }))),
+            (vec!["--concurrency=5", "1s", "date"], Ok(Action::Run(Config { concurrency: 5, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--concurrency", "5", "date"], Ok(Action::Run(Config { concurrency: 5, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "-n3", "--name=backup", "date"], Ok(Action::Run(Config { max_runs: Some(3), name: Some(String::from("backup")), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "-c=", "date"], Err("Option -c expects a number, got ''")),
+            (vec!["1s", "-cx", "date"], Err("Option -c expects a number, got 'x'")),
+            (vec!["1s", "--verbose=1", "date"], Err("Option --verbose does not take a value, got '1'")),
+            (vec!["1s", "--foo=bar", "date"], Err("Invalid option after interval: --foo")),
+            (vec!["1s", "-c2", "--", "rm", "-c5", "--force=yes"], Ok(Action::Run(Config { concurrency: 2, ..config(1_000, "rm", &["-c5", "--force=yes"]) }))),
             // missing command after options
             (vec!["1s", "-c", "1"], Err("Missing command name!")),
+            // end of options
+            (vec!["1s", "-c", "2", "--", "rm", "-c", "file"], Ok(Action::Run(Config { concurrency: 2, ..config(1_000, "rm", &["-c", "file"]) }))),
+            (vec!["1s", "--", "-weird-command", "-x"], Ok(Action::Run(config(1_000, "-weird-command", &["-x"])))),
+            (vec!["1s", "--", "--", "x"], Ok(Action::Run(config(1_000, "--", &["x"])))),
+            (vec!["1s", "-c", "2", "--"], Err("Missing command name!")),
             // valid
-            (vec!["1s", "date"], Ok(Action::Run(Config {
-                interval_ms: 1_000,
-                concurrency: 1,
-                command: String::from("date"),
-                args: vec![],
+            (vec!["1s", "date"], Ok(Action::Run(config(1_000, "date", &[])))),
+            // valid, bare number of seconds
+            (vec!["5", "date"], Ok(Action::Run(config(5_000, "date", &[])))),
+            (vec!["0.5", "date"], Ok(Action::Run(config(500, "date", &[])))),
+            // valid, sub-millisecond
+            (vec!["250us", "date"], Ok(Action::Run(Config {
+                interval: Duration::from_micros(250),
+                ..config(1_000, "date", &[])
+            }))),
+            // interval range
+            (vec!["30s-2m", "date"], Ok(Action::Run(Config {
+                max_interval: Some(Duration::from_secs(120)),
+                ..config(30_000, "date", &[])
             }))),
+            (vec!["1s-1s", "date"], Ok(Action::Run(config(1_000, "date", &[])))),
+            (vec!["2m-30s", "date"], Err("Invalid interval range '2m-30s': minimum is greater than maximum")),
+            (vec!["1s-2x", "date"], Err("Invalid interval '2x': unrecognized format")),
+            (vec!["1s-", "date"], Err("Interval cannot be empty")),
+            (vec!["1s-2s-3s", "date"], Err("Invalid interval '2s-3s': unrecognized format")),
             // valid with concurrency and args
             (vec!["1m5.5s", "-c", "10", "echo", "hello", "world"], Ok(Action::Run(Config {
-                interval_ms: 65500,
                 concurrency: 10,
-                command: String::from("echo"),
-                args: vec![
-                    String::from("hello"),
-                    String::from("world"),
+                ..config(65_500, "echo", &["hello", "world"])
+            }))),
+            // quiet
+            (vec!["1s", "-q", "date"], Ok(Action::Run(Config { quiet: 1, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--quiet", "date"], Ok(Action::Run(Config { quiet: 1, ..config(1_000, "date", &[]) }))),
+            // very quiet
+            (vec!["1s", "-qq", "date"], Ok(Action::Run(Config { quiet: 2, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "-q", "-c", "2", "-q", "-q", "date"], Ok(Action::Run(Config {
+                concurrency: 2,
+                quiet: 2,
+                ..config(1_000, "date", &[])
+            }))),
+            // verbose
+            (vec!["1s", "--verbose", "date"], Ok(Action::Run(Config { verbose: 1, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--verbose", "--verbose", "date"], Ok(Action::Run(Config { verbose: 2, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "-vv", "date"], Ok(Action::Run(Config { verbose: 2, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "-vv", "--verbose", "date"], Ok(Action::Run(Config { verbose: 2, ..config(1_000, "date", &[]) }))),
+            // log format
+            (vec!["1s", "--log-format", "text", "date"], Ok(Action::Run(config(1_000, "date", &[])))),
+            (vec!["1s", "--log-format", "json", "date"], Ok(Action::Run(Config { log_format: LogFormat::Json, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--color", "always", "date"], Ok(Action::Run(Config { color: ColorChoice::Always, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--color", "never", "date"], Ok(Action::Run(Config { color: ColorChoice::Never, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--color", "yes", "date"], Err("Invalid color choice: 'yes', expected auto, always or never")),
+            (vec!["1s", "--color"], Err("Missing color choice!")),
+            (vec!["1s", "--log-format", "xml", "date"], Err("Invalid log format: 'xml', expected text or json")),
+            (vec!["1s", "--log-format"], Err("Missing log format!")),
+            // summary
+            (vec!["1s", "--summary", "date"], Ok(Action::Run(Config { summary: true, ..config(1_000, "date", &[]) }))),
+            // on busy
+            (vec!["1s", "--on-busy"], Err("Missing on-busy policy!")),
+            (vec!["1s", "--on-busy", "skip", "date"], Ok(Action::Run(config(1_000, "date", &[])))),
+            (vec!["1s", "--on-busy", "queue", "date"], Ok(Action::Run(Config { on_busy: OnBusy::Queue(1), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--on-busy", "queue:10", "date"], Ok(Action::Run(Config { on_busy: OnBusy::Queue(10), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--on-busy", "kill", "date"], Ok(Action::Run(Config { on_busy: OnBusy::Kill, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--on-busy", "queue:0", "date"], Err("Invalid queue size: '0'")),
+            (vec!["1s", "--on-busy", "queue:x", "date"], Err("Invalid queue size: 'x'")),
+            (vec!["1s", "--on-busy", "wait", "date"], Err("Invalid on-busy policy: 'wait'")),
+            (vec!["1s", "--on-busy", "skip:1", "date"], Err("Invalid on-busy policy: 'skip:1'")),
+            // max per
+            (vec!["1s", "--max-per"], Err("Missing rate limit!")),
+            (vec!["1s", "--max-per", "100/1m", "date"], Ok(Action::Run(Config { max_per: Some(RateLimit { runs: 100, per: Duration::from_secs(60) }), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--max-per", "100", "date"], Err("Invalid rate limit: '100', expected <runs>/<interval>")),
+            (vec!["1s", "--max-per", "0/1m", "date"], Err("Invalid rate limit: '0/1m', expected <runs>/<interval>")),
+            (vec!["1s", "--max-per", "x/1m", "date"], Err("Invalid rate limit: 'x/1m', expected <runs>/<interval>")),
+            (vec!["1s", "--max-per", "1/1x", "date"], Err("Invalid interval '1x': unrecognized format")),
+            // kill signal & delay
+            (vec!["1s", "--on-busy", "kill", "--kill-signal", "TERM", "--kill-after", "5s", "date"], Ok(Action::Run(Config {
+                on_busy: OnBusy::Kill,
+                kill_signal: Signal::SIGTERM,
+                kill_after: Some(Duration::from_secs(5)),
+                ..config(1_000, "date", &[])
+            }))),
+            (vec!["1s", "--kill-signal", "SIGINT", "date"], Ok(Action::Run(Config { kill_signal: Signal::SIGINT, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--kill-signal", "15", "date"], Ok(Action::Run(Config { kill_signal: Signal::SIGTERM, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--kill-signal"], Err("Missing kill signal!")),
+            (vec!["1s", "--kill-signal", "term", "date"], Err("Invalid signal: 'term'")),
+            (vec!["1s", "--kill-signal", "0", "date"], Err("Invalid signal: '0'")),
+            (vec!["1s", "--kill-after"], Err("Missing kill delay!")),
+            (vec!["1s", "--stats-every", "1h", "date"], Ok(Action::Run(Config { stats_every: Some(Duration::from_secs(3600)), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--stats-every"], Err("Missing stats interval!")),
+            (vec!["1s", "--kill-after", "1x", "date"], Err("Invalid interval '1x': unrecognized format")),
+            (vec!["1m", "--stall-timeout", "30s", "./poller.sh"], Ok(Action::Run(Config { stall_timeout: Some(Duration::from_secs(30)), ..config(60_000, "./poller.sh", &[]) }))),
+            (vec!["1s", "--stall-timeout"], Err("Missing stall timeout!")),
+            (vec!["1s", "--max-output", "1M", "--max-output-kill", "1G", "date"], Ok(Action::Run(Config { max_output: Some(1 << 20), max_output_kill: Some(1 << 30), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--max-output-kill", "100M", "date"], Ok(Action::Run(Config { max_output_kill: Some(100 << 20), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--max-output"], Err("Missing output size!")),
+            (vec!["1s", "--max-output", "0", "date"], Err("Invalid size: '0', expected e.g. 512M or 2G")),
+            (vec!["1s", "--max-output", "1M", "--max-output-kill", "1M", "date"], Err("--max-output-kill must be larger than --max-output!")),
+            (vec!["1s", "--max-output", "1M", "--output-dir", "/tmp", "date"], Err("--max-output and --max-output-kill cannot be used with --output-dir!")),
+            (vec!["1s", "--stall-timeout", "10s", "-q", "date"], Err("--stall-timeout cannot be used with --quiet!")),
+            (vec!["1s", "--stall-timeout", "10s", "--output-dir", "/tmp", "date"], Err("--stall-timeout cannot be used with --output-dir!")),
+            // catch up
+            (vec!["10s", "--cmd", "dig example.com", "--cmd", "curl -s https://example.com"], Ok(Action::Run(Config {
+                jobs: vec![
+                    Job { command: String::from("dig example.com"), interval: None, concurrency: None, rotate: true },
+                    Job { command: String::from("curl -s https://example.com"), interval: None, concurrency: None, rotate: true },
                 ],
+                ..config(10_000, "", &[])
             }))),
+            (vec!["1s", "--args-exhausted", "wrap", "curl", "{arg}"], Err("--args-exhausted requires --args-from!")),
+            (vec!["1s", "--args-from", "/nonexistent/urls.txt", "curl", "{arg}"], Err("Failed to read args file '/nonexistent/urls.txt': No such file or directory (os error 2)")),
+            (vec!["1s", "--args-from"], Err("Missing args file path!")),
+            (vec!["1s", "--args-exhausted"], Err("Missing args-exhausted policy!")),
+            (vec!["1s", "--args-exhausted", "loop"], Err("Invalid args-exhausted policy: 'loop', expected wrap or stop")),
+            (vec!["10s", "--cmd", "dig example.com", "date"], Err("--jobs-file and --cmd cannot be used with a command!")),
+            (vec!["10s", "--cmd", "dig example.com", "--changes"], Err("--jobs-file and --cmd cannot be used with --changes, --watch and --diff!")),
+            (vec!["10s", "--cmd"], Err("Missing command!")),
+            (vec!["--preview", "10", "1h", "--between", "09:00-17:00"], Ok(Action::Preview(Config { between: Some("09:00-17:00".parse().unwrap()), ..config(3_600_000, "", &[]) }, 10))),
+            (vec!["--preview", "3", "5m", "./backup.sh"], Ok(Action::Preview(config(300_000, "./backup.sh", &[]), 3))),
+            (vec!["--preview", "0", "5m"], Err("Invalid preview count: '0'")),
+            (vec!["--preview", "ten", "5m"], Err("Option --preview expects a number, got 'ten'")),
+            (vec!["--preview"], Err("Missing preview count!")),
+            (vec!["--preview", "10"], Err("Missing interval!")),
+            (vec!["--supervise", "5s", "./server.sh"], Ok(Action::Run(Config { supervise: true, ..config(5_000, "./server.sh", &[]) }))),
+            (vec!["--supervise", "5s", "--max-failures", "3", "./server.sh"], Ok(Action::Run(Config { supervise: true, max_failures: Some(3), ..config(5_000, "./server.sh", &[]) }))),
+            (vec!["--supervise"], Err("Missing restart delay!")),
+            (vec!["--supervise", "-c"], Err("Missing concurrency value!")),
+            (vec!["--supervise", "--verbose"], Err("Missing restart delay!")),
+            (vec!["--verbose", "--supervise", "5s", "./server.sh"], Ok(Action::Run(Config { supervise: true, verbose: 1, ..config(5_000, "./server.sh", &[]) }))),
+            (vec!["5s", "--supervise", "./server.sh"], Err("--supervise must precede the restart delay!")),
+            (vec!["--supervise", "5s-10s", "./server.sh"], Err("Invalid interval '5s-10s': unrecognized format")),
+            (vec!["--supervise", "5s", "-c", "2", "./server.sh"], Err("--supervise cannot be used with -c, --on-busy, --batch, --detach, --jobs-file and --cmd!")),
+            (vec!["--supervise", "5s", "--detach", "./server.sh"], Err("--supervise cannot be used with -c, --on-busy, --batch, --detach, --jobs-file and --cmd!")),
+            (vec!["10s", "--detach", "./start-worker.sh"], Ok(Action::Run(Config { detach: true, ..config(10_000, "./start-worker.sh", &[]) }))),
+            (vec!["10s", "--detach", "-c", "2", "./start-worker.sh"], Err("--detach cannot be used with -c and --on-busy!")),
+            (vec!["10s", "--detach", "--on-busy", "queue", "./start-worker.sh"], Err("--detach cannot be used with -c and --on-busy!")),
+            (vec!["10s", "--detach", "--changes", "./start-worker.sh"], Err("--detach cannot be used with the options that read the output of the runs!")),
+            (vec!["10s", "--detach", "--output-dir", "/tmp", "./start-worker.sh"], Err("--detach cannot be used with the options that read the output of the runs!")),
+            (vec!["10s", "--detach", "--until-success", "./start-worker.sh"], Err("--detach cannot be used with the options that wait for the runs to complete!")),
+            (vec!["10s", "--detach", "--on-failure", "echo", "./start-worker.sh"], Err("--detach cannot be used with the options that wait for the runs to complete!")),
+            (vec!["1s", "--batch", "20", "-c", "20", "./request.sh"], Ok(Action::Run(Config { batch: Some(20), concurrency: 20, ..config(1_000, "./request.sh", &[]) }))),
+            (vec!["1s", "--batch", "0", "./request.sh"], Err("Invalid batch size: value 0 is not in the range 1–1000")),
+            (vec!["1s", "--batch", "1001", "./request.sh"], Err("Invalid batch size: value 1001 is not in the range 1–1000")),
+            (vec!["1s", "--batch", "x", "./request.sh"], Err("Option --batch expects a number, got 'x'")),
+            (vec!["1s", "--batch"], Err("Missing batch size!")),
+            (vec!["1s", "--batch", "2", "--lock-file", "/tmp/every.lock", "date"], Err("--batch cannot be used with --lock-file!")),
+            (vec!["1s", "--catch-up", "date"], Ok(Action::Run(Config { catch_up: u64::MAX, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--catch-up=10", "date"], Ok(Action::Run(Config { catch_up: 10, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--catch-up=0", "date"], Err("Invalid catch-up value: '0'")),
+            (vec!["1s", "--catch-up=", "date"], Err("Invalid catch-up value: ''")),
+            (vec!["1s", "--catch-up=x", "date"], Err("Invalid catch-up value: 'x'")),
+            // clock
+            (vec!["1s", "--clock", "monotonic", "date"], Ok(Action::Run(config(1_000, "date", &[])))),
+            (vec!["1s", "--clock", "wall", "date"], Ok(Action::Run(Config { clock: Clock::Wall, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--clock"], Err("Missing clock!")),
+            (vec!["1s", "--clock", "cpu", "date"], Err("Invalid clock: 'cpu'")),
+            // on resume
+            (vec!["1s", "--on-resume", "warn", "date"], Ok(Action::Run(config(1_000, "date", &[])))),
+            (vec!["1s", "--on-resume", "run-once", "date"], Ok(Action::Run(Config { on_resume: OnResume::RunOnce, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--on-resume", "catch-up", "date"], Ok(Action::Run(Config { on_resume: OnResume::CatchUp, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--on-resume"], Err("Missing on-resume policy!")),
+            (vec!["1s", "--on-resume", "ignore", "date"], Err("Invalid on-resume policy: 'ignore'")),
+            // at
+            (vec!["1s", "--at", "03:00", "date"], Ok(Action::Run(Config { at: Some("03:00".parse().unwrap()), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--at"], Err("Missing time!")),
+            (vec!["1s", "--at", "3am", "date"], Err("Invalid time: '3am', expected HH:MM[:SS] or YYYY-MM-DDTHH:MM[:SS]")),
+            // until
+            (vec!["1s", "--until", "18:30", "date"], Ok(Action::Run(Config { until: Some("18:30".parse().unwrap()), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--until", "2024-12-31T23:59:59", "date"], Ok(Action::Run(Config { until: Some("2024-12-31T23:59:59".parse().unwrap()), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--until"], Err("Missing time!")),
+            (vec!["1s", "-n", "3", "date"], Ok(Action::Run(Config { max_runs: Some(3), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "-n"], Err("Missing run count!")),
+            (vec!["1s", "--on-failure", "notify-send \"$EVERY_EXIT_CODE\"", "date"], Ok(Action::Run(Config { on_failure: Some(String::from("notify-send \"$EVERY_EXIT_CODE\"")), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--on-failure"], Err("Missing hook command!")),
+            (vec!["1s", "--on-success", "touch /tmp/ok", "--on-skip", "echo $EVERY_SKIP_REASON", "date"], Ok(Action::Run(Config {
+                on_success: Some(String::from("touch /tmp/ok")),
+                on_skip: Some(String::from("echo $EVERY_SKIP_REASON")),
+                ..config(1_000, "date", &[])
+            }))),
+            (vec!["1s", "--on-skip"], Err("Missing hook command!")),
+            #[cfg(not(feature = "webhook"))]
+            (vec!["1s", "--notify-url", "https://hooks.example.com/every", "date"], Err("This build of every does not support --notify-url!")),
+            #[cfg(not(feature = "webhook"))]
+            (vec!["1s", "--notify-recovery", "date"], Err("This build of every does not support --notify-recovery!")),
+            #[cfg(feature = "webhook")]
+            (vec!["1s", "--notify-url", "https://hooks.example.com/every", "--notify-recovery", "date"], Ok(Action::Run(Config {
+                notify_url: Some(String::from("https://hooks.example.com/every")),
+                notify_recovery: true,
+                ..config(1_000, "date", &[])
+            }))),
+            #[cfg(feature = "webhook")]
+            (vec!["1s", "--notify-url"], Err("Missing URL!")),
+            #[cfg(feature = "webhook")]
+            (vec!["1s", "--notify-url", "hooks.example.com", "date"], Err("Invalid URL: 'hooks.example.com', expected http:// or https://")),
+            #[cfg(feature = "webhook")]
+            (vec!["1s", "--notify-recovery", "date"], Err("--notify-recovery requires --notify-url!")),
+            (vec!["1s", "--no-keys", "date"], Ok(Action::Run(Config { keys: false, ..config(1_000, "date", &[]) }))),
+            // countdown
+            (vec!["1s", "--countdown", "date"], Ok(Action::Run(Config { countdown: true, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--pty", "ls", "--color=auto"], Ok(Action::Run(Config { pty: true, ..config(1_000, "ls", &["--color=auto"]) }))),
+            (vec!["1s", "--pty", "-q", "date"], Err("--pty cannot be used with --quiet!")),
+            (vec!["1s", "--pty", "--output-dir", "/tmp", "date"], Err("--pty cannot be used with --output-dir!")),
+            // set title
+            (vec!["1s", "--set-title", "date"], Ok(Action::Run(Config { set_title: true, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--notify-desktop", "date"], Ok(Action::Run(Config { notify_desktop: true, ..config(1_000, "date", &[]) }))),
+            // bell
+            (vec!["1s", "--bell", "date"], Ok(Action::Run(Config { bell: true, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--bell", "--flash", "--watch", "date"], Ok(Action::Run(Config { bell: true, flash: true, output: OutputMode::Watch, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--flash", "--watch", "date"], Err("--flash requires --bell!")),
+            (vec!["1s", "--bell", "--flash", "date"], Err("--flash requires --watch or --tui!")),
+            #[cfg(not(feature = "otel"))]
+            (vec!["1s", "--otel-endpoint", "http://localhost:4318", "date"], Err("This build of every does not support --otel-endpoint!")),
+            #[cfg(feature = "otel")]
+            (vec!["1s", "--otel-endpoint", "http://localhost:4318", "date"], Ok(Action::Run(Config { otel_endpoint: Some(String::from("http://localhost:4318")), ..config(1_000, "date", &[]) }))),
+            #[cfg(feature = "otel")]
+            (vec!["1s", "--otel-endpoint", "localhost:4318", "date"], Err("Invalid URL: 'localhost:4318', expected http:// or https://")),
+            (vec!["1s", "-n", "0", "date"], Err("Invalid run count: '0'")),
+            (vec!["1s", "-n", "x", "date"], Err("Option -n expects a number, got 'x'")),
+            (vec!["1s", "--max-failures", "3", "date"], Ok(Action::Run(Config { max_failures: Some(3), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--max-failures"], Err("Missing failure count!")),
+            (vec!["1s", "--max-failures", "0", "date"], Err("Invalid failure count: '0'")),
+            (vec!["1s", "--cooldown", "5m", "--cooldown-after", "3", "date"], Ok(Action::Run(Config {
+                cooldown: Some(Cooldown { duration: Duration::from_secs(300), after: 3 }),
+                ..config(1_000, "date", &[])
+            }))),
+            (vec!["1s", "--cooldown"], Err("Missing cooldown!")),
+            (vec!["1s", "--cooldown-after", "0", "date"], Err("Invalid failure count: '0'")),
+            (vec!["1s", "--cooldown", "5m", "date"], Err("--cooldown and --cooldown-after must be used together!")),
+            (vec!["1s", "--cooldown-after", "3", "date"], Err("--cooldown and --cooldown-after must be used together!")),
+            (vec!["1s", "--on-spawn-error", "retry", "date"], Ok(Action::Run(Config { on_spawn_error: OnSpawnError::Retry, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--on-spawn-error=ignore", "date"], Ok(Action::Run(Config { on_spawn_error: OnSpawnError::Ignore, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--on-spawn-error", "exit", "date"], Ok(Action::Run(config(1_000, "date", &[])))),
+            (vec!["1s", "--on-spawn-error", "fail", "date"], Err("Invalid on-spawn-error policy: 'fail', expected exit, retry or ignore")),
+            (vec!["1s", "--on-spawn-error"], Err("Missing on-spawn-error policy!")),
+            (vec!["1s", "--until-match", "^ready$", "date"], Ok(Action::Run(Config { until_match: Some(UntilMatch { pattern: Regex::new("^ready$").unwrap(), matches: true }), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--until-no-match", "pending", "date"], Ok(Action::Run(Config { until_match: Some(UntilMatch { pattern: Regex::new("pending").unwrap(), matches: false }), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--until-match"], Err("Missing pattern!")),
+            (vec!["1s", "--until-match", "(", "date"], Err("Invalid pattern: '('")),
+            (vec!["1s", "--until-match", "x", "--output-dir", "/tmp", "date"], Err("--until-match and --until-no-match cannot be used with --output-dir!")),
+            (vec!["1s", "--ok-codes"], Err("Missing exit codes!")),
+            (vec!["1s", "--ok-codes", "0,24", "rsync", "-a", "src/", "dst/"], Ok(Action::Run(Config { ok_codes: vec![0, 24], ..config(1_000, "rsync", &["-a", "src/", "dst/"]) }))),
+            (vec!["1s", "--ok-codes", "1", "grep", "-q", "error", "log"], Ok(Action::Run(Config { ok_codes: vec![1], ..config(1_000, "grep", &["-q", "error", "log"]) }))),
+            (vec!["1s", "--ok-codes", "0,256", "date"], Err("Invalid exit code: '256', expected 0–255")),
+            (vec!["1s", "--ok-codes", "0,", "date"], Err("Invalid exit code: '', expected 0–255")),
+            (vec!["5m", "--if", "on_ac_power", "./index.sh"], Ok(Action::Run(Config { condition: Some(String::from("on_ac_power")), ..config(300_000, "./index.sh", &[]) }))),
+            (vec!["5m", "--if"], Err("Missing condition command!")),
+            (vec!["1h", "--pause-file", "/tmp/pause-backups", "./backup.sh"], Ok(Action::Run(Config { pause_file: Some(PathBuf::from("/tmp/pause-backups")), ..config(3_600_000, "./backup.sh", &[]) }))),
+            (vec!["1h", "--pause-file"], Err("Missing pause file path!")),
+            (vec!["1h", "--max-load", "1.5", "./index.sh"], Ok(Action::Run(Config { max_load: Some(1.5), ..config(3_600_000, "./index.sh", &[]) }))),
+            (vec!["1h", "--max-load"], Err("Missing load average!")),
+            (vec!["1h", "--only-on-ac", "./backup.sh"], Ok(Action::Run(Config { only_on_ac: true, ..config(3_600_000, "./backup.sh", &[]) }))),
+            (vec!["1h", "--min-battery", "20", "./backup.sh"], Ok(Action::Run(Config { min_battery: Some(20), ..config(3_600_000, "./backup.sh", &[]) }))),
+            (vec!["1h", "--min-battery", "50%", "./backup.sh"], Ok(Action::Run(Config { min_battery: Some(50), ..config(3_600_000, "./backup.sh", &[]) }))),
+            (vec!["1h", "--min-battery"], Err("Missing battery level!")),
+            (vec!["1h", "--min-free-space", "10G@/var/lib/downloads", "./download.sh"], Ok(Action::Run(Config { min_free_space: Some(FreeSpace { bytes: 10 << 30, path: PathBuf::from("/var/lib/downloads") }), ..config(3_600_000, "./download.sh", &[]) }))),
+            (vec!["1h", "--min-free-space", "10G", "./download.sh"], Err("Invalid free space: '10G', expected <size>@<path>, e.g. 10G@/var/lib")),
+            (vec!["1h", "--min-free-space", "10G@", "./download.sh"], Err("Invalid free space: '10G@', expected <size>@<path>, e.g. 10G@/var/lib")),
+            (vec!["1h", "--min-free-space", "ten@/var/lib", "./download.sh"], Err("Invalid size: 'ten', expected e.g. 512M or 2G")),
+            (vec!["1h", "--min-free-space"], Err("Missing free space!")),
+            (vec!["10s", "--if-healthy", "https://api.example.com/health", "./poll.sh"], Ok(Action::Run(Config { if_healthy: Some(String::from("https://api.example.com/health")), ..config(10_000, "./poll.sh", &[]) }))),
+            (vec!["10s", "--if-healthy", "api.example.com", "./poll.sh"], Err("Invalid URL: 'api.example.com', expected http:// or https://")),
+            (vec!["10s", "--if-healthy"], Err("Missing health check URL!")),
+            (vec!["5m", "--require-network", "./sync.sh"], Ok(Action::Run(Config { require_network: Some(NetworkCheck::DefaultRoute), ..config(300_000, "./sync.sh", &[]) }))),
+            (vec!["5m", "--require-network=example.com:443", "./sync.sh"], Ok(Action::Run(Config { require_network: Some(NetworkCheck::Connect(String::from("example.com:443"))), ..config(300_000, "./sync.sh", &[]) }))),
+            (vec!["5m", "--require-network=[::1]:22", "./sync.sh"], Ok(Action::Run(Config { require_network: Some(NetworkCheck::Connect(String::from("[::1]:22"))), ..config(300_000, "./sync.sh", &[]) }))),
+            (vec!["5m", "--require-network=example.com", "./sync.sh"], Err("Invalid address: 'example.com', expected host:port")),
+            (vec!["5m", "--require-network=:443", "./sync.sh"], Err("Invalid address: ':443', expected host:port")),
+            (vec!["1h", "--min-battery", "0", "./backup.sh"], Err("Invalid battery level: '0', expected 1–100%")),
+            (vec!["1h", "--min-battery", "101%", "./backup.sh"], Err("Invalid battery level: '101%', expected 1–100%")),
+            (vec!["1h", "--max-load", "0", "./index.sh"], Err("Invalid load average: '0'")),
+            (vec!["1h", "--max-load", "inf", "./index.sh"], Err("Invalid load average: 'inf'")),
+            (vec!["2s", "--until-success", "-n", "10", "curl", "--fail", "localhost"], Ok(Action::Run(Config { until_success: true, max_runs: Some(10), ..config(2_000, "curl", &["--fail", "localhost"]) }))),
+            (vec!["1s", "--until", "6pm", "date"], Err("Invalid time: '6pm', expected HH:MM[:SS] or YYYY-MM-DDTHH:MM[:SS]")),
+            // between & days
+            (vec!["1s", "--between", "09:00-17:30", "--days", "mon-fri", "date"], Ok(Action::Run(Config {
+                between: Some("09:00-17:30".parse().unwrap()),
+                days: Some("mon-fri".parse().unwrap()),
+                ..config(1_000, "date", &[])
+            }))),
+            (vec!["1s", "--between"], Err("Missing time window!")),
+            (vec!["1s", "--between", "9-5", "date"], Err("Invalid time window: '9-5', expected HH:MM-HH:MM")),
+            (vec!["1s", "--days"], Err("Missing days!")),
+            // interval between
+            (vec!["1m", "--interval-between", "09:00-18:00=30s", "--interval-between", "18:00-20:00=1m30s", "--interval-default", "5m", "./poll.sh"], Ok(Action::Run(Config {
+                interval_windows: vec![
+                    IntervalWindow { window: "09:00-18:00".parse().unwrap(), interval: Duration::from_secs(30) },
+                    IntervalWindow { window: "18:00-20:00".parse().unwrap(), interval: Duration::from_secs(90) },
+                ],
+                ..config(300_000, "./poll.sh", &[])
+            }))),
+            (vec!["1m-2m", "--interval-default", "5m", "--interval-between", "09:00-18:00=30s", "./poll.sh"], Ok(Action::Run(Config {
+                interval_windows: vec![IntervalWindow { window: "09:00-18:00".parse().unwrap(), interval: Duration::from_secs(30) }],
+                ..config(300_000, "./poll.sh", &[])
+            }))),
+            (vec!["1m", "--interval-between"], Err("Missing interval window!")),
+            (vec!["1m", "--interval-between", "09:00-18:00", "./poll.sh"], Err("Invalid interval window: '09:00-18:00', expected <start>-<end>=<interval>, e.g. 09:00-18:00=30s")),
+            (vec!["1m", "--interval-between", "09:00=30s", "./poll.sh"], Err("Invalid time window: '09:00', expected HH:MM-HH:MM")),
+            (vec!["1m", "--interval-between", "09:00-18:00=fast", "./poll.sh"], Err("Invalid interval 'fast': unrecognized format")),
+            (vec!["1m", "--interval-default"], Err("Missing interval!")),
+            (vec!["1m", "--interval-default", "5m", "./poll.sh"], Err("--interval-default requires --interval-between!")),
+            (vec!["1m-2m", "--interval-between", "09:00-18:00=30s", "./poll.sh"], Err("--interval-between cannot be used with an interval range!")),
+            (vec!["--supervise", "1m", "--interval-between", "09:00-18:00=30s", "./server.sh"], Err("--interval-between cannot be used with --supervise!")),
+            (vec!["1s", "--skip-dates"], Err("Missing skip dates path!")),
+            (vec!["1s", "--skip-dates", "/nonexistent/holidays.txt", "date"], Err("Failed to read skip dates file '/nonexistent/holidays.txt': No such file or directory (os error 2)")),
+            (vec!["1s", "--days", "weekends", "date"], Err("Invalid days: 'weekends', expected e.g. mon-fri or sat,sun")),
+            // time zone
+            (vec!["1s", "--tz", "Europe/Paris", "date"], Ok(Action::Run(Config { time_zone: Some(String::from("Europe/Paris")), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--tz"], Err("Missing time zone!")),
+            (vec!["1s", "--tz", "Mars/Olympus_Mons", "date"], Err("Invalid time zone: 'Mars/Olympus_Mons'")),
+            // on failure output
+            (vec!["1s", "--on-failure-output", "date"], Ok(Action::Run(Config { output: OutputMode::OnFailure, ..config(1_000, "date", &[]) }))),
+            // changes
+            (vec!["1s", "--changes", "date"], Ok(Action::Run(Config { output: OutputMode::Changes, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--changes", "--on-failure-output", "date"], Err("Only one of --on-failure-output, --changes and --watch can be used!")),
+            // watch
+            (vec!["1s", "--watch", "date"], Ok(Action::Run(Config { output: OutputMode::Watch, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--watch", "--watch", "date"], Ok(Action::Run(Config { output: OutputMode::Watch, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--watch", "--changes", "date"], Err("Only one of --on-failure-output, --changes and --watch can be used!")),
+            // diff
+            (vec!["1s", "--diff", "date"], Ok(Action::Run(Config { diff: true, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--watch", "--diff", "date"], Ok(Action::Run(Config { output: OutputMode::Watch, diff: true, ..config(1_000, "date", &[]) }))),
+            // output dir
+            (vec!["1s", "--output-dir"], Err("Missing output directory!")),
+            (vec!["1s", "--output-dir", "/", "--keep-last", "100", "date"], Ok(Action::Run(Config {
+                output_dir: Some(PathBuf::from("/")),
+                keep_last: Some(100),
+                ..config(1_000, "date", &[])
+            }))),
+            (vec!["1s", "--output-dir", "/nonexistent", "date"], Err("Invalid output directory: '/nonexistent'")),
+            (vec!["1s", "--output-dir", "/", "--changes", "date"], Err("--output-dir cannot be used with --on-failure-output, --changes, --watch and --diff!")),
+            (vec!["1s", "--keep-last"], Err("Missing keep-last value!")),
+            (vec!["1s", "--keep-last", "0", "date"], Err("Invalid keep-last value: '0'")),
+            (vec!["1s", "--keep-last", "10", "date"], Err("--keep-last requires --output-dir!")),
+            // tui
+            #[cfg(not(feature = "tui"))]
+            (vec!["1s", "--tui", "date"], Err("This build of every does not support --tui!")),
+            #[cfg(feature = "tui")]
+            (vec!["1s", "--tui", "date"], Ok(Action::Run(Config { tui: true, ..config(1_000, "date", &[]) }))),
+            #[cfg(feature = "tui")]
+            (vec!["1s", "--tui", "--stdin", "inherit", "date"], Err("--tui cannot be used with --stdin inherit!")),
+            // heartbeat file
+            (vec!["1s", "--heartbeat-file"], Err("Missing heartbeat file path!")),
+            (vec!["1s", "--heartbeat-file", "/tmp/hb", "date"], Ok(Action::Run(Config {
+                heartbeat_file: Some(PathBuf::from("/tmp/hb")),
+                ..config(1_000, "date", &[])
+            }))),
+            // control socket
+            (vec!["1s", "--lock-file"], Err("Missing lock file path!")),
+            (vec!["1s", "--lock-file", "/tmp/backup.lock", "date"], Ok(Action::Run(Config {
+                lock_file: Some(PathBuf::from("/tmp/backup.lock")),
+                ..config(1_000, "date", &[])
+            }))),
+            (vec!["1s", "--history"], Err("Missing history file path!")),
+            (vec!["1s", "--history", "runs.sqlite", "date"], Ok(Action::Run(Config { history: Some(PathBuf::from("runs.sqlite")), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--report"], Err("Missing report file path!")),
+            (vec!["1s", "--report", "runs.csv", "date"], Ok(Action::Run(Config {
+                report: Some(Report { path: PathBuf::from("runs.csv"), format: ReportFormat::Csv }),
+                ..config(1_000, "date", &[])
+            }))),
+            (vec!["1s", "--report", "/var/log/runs.jsonl", "date"], Ok(Action::Run(Config {
+                report: Some(Report { path: PathBuf::from("/var/log/runs.jsonl"), format: ReportFormat::Json }),
+                ..config(1_000, "date", &[])
+            }))),
+            (vec!["1s", "--report", "runs.txt", "date"], Err("Invalid report file: 'runs.txt', expected a .csv or .json file")),
+            (vec!["1s", "--name"], Err("Missing name!")),
+            (vec!["1s", "--name", "1234", "date"], Err("Invalid name: '1234', expected letters, digits, '.', '_' or '-'")),
+            (vec!["1s", "--name", "backup", "--single-instance", "date"], Ok(Action::Run(Config {
+                name: Some(String::from("backup")),
+                single_instance: true,
+                ..config(1_000, "date", &[])
+            }))),
+            (vec!["1s", "--single-instance", "date"], Err("--single-instance requires --name!")),
+            (vec!["1s", "--control-socket"], Err("Missing control socket path!")),
+            (vec!["1s", "--control-socket", "/tmp/every.sock", "date"], Ok(Action::Run(Config {
+                control_socket: Some(PathBuf::from("/tmp/every.sock")),
+                ..config(1_000, "date", &[])
+            }))),
+            // environment variables
+            (vec!["1s", "-e", "FOO=bar", "-e", "BAZ=", "env"], Ok(Action::Run(Config {
+                env: [("FOO", "bar"), ("BAZ", "")].map(|(k, v)| (k.to_string(), v.to_string())).into(),
+                ..config(1_000, "env", &[])
+            }))),
+            (vec!["1s", "-e", "FOO=bar", "-e", "FOO=a=b", "env"], Ok(Action::Run(Config {
+                env: [(String::from("FOO"), String::from("a=b"))].into(),
+                ..config(1_000, "env", &[])
+            }))),
+            (vec!["1s", "-eFOO=bar", "-e=BAZ=", "env"], Ok(Action::Run(Config {
+                env: [("FOO", "bar"), ("BAZ", "")].map(|(k, v)| (k.to_string(), v.to_string())).into(),
+                ..config(1_000, "env", &[])
+            }))),
+            (vec!["1s", "-e"], Err("Missing environment variable!")),
+            (vec!["1s", "-e", "FOO", "env"], Err("Invalid environment variable: 'FOO', expected KEY=VALUE")),
+            (vec!["1s", "-e", "=bar", "env"], Err("Invalid environment variable: '=bar', expected KEY=VALUE")),
+            (vec!["1s", "--env-file"], Err("Missing env file path!")),
+            (vec!["1s", "--env-file", "/nonexistent", "env"], Err("Failed to read env file '/nonexistent': No such file or directory (os error 2)")),
+            (vec!["1s", "--jobs-file"], Err("Missing jobs file path!")),
+            (vec!["1s", "--jobs-file", "/nonexistent"], Err("Failed to read jobs file '/nonexistent': No such file or directory (os error 2)")),
+            (vec!["1s", "--jobs-file", "/dev/null"], Err("No commands in jobs file!")),
+            (vec!["1s", "--clean-env", "-e", "FOO=bar", "env"], Ok(Action::Run(Config {
+                clean_env: true,
+                env: [(String::from("FOO"), String::from("bar"))].into(),
+                ..config(1_000, "env", &[])
+            }))),
+            // niceness
+            (vec!["1s", "--nice", "10", "date"], Ok(Action::Run(Config { nice: Some(10), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--nice", "-20", "date"], Ok(Action::Run(Config { nice: Some(-20), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--nice"], Err("Missing niceness!")),
+            (vec!["1s", "--nice", "20", "date"], Err("Invalid niceness: '20', expected -20 to 19")),
+            (vec!["1s", "--nice", "low", "date"], Err("Invalid niceness: 'low', expected -20 to 19")),
+            // resource limits
+            (vec!["1s", "--limit-mem", "512M", "--limit-cpu", "30s", "date"], Ok(Action::Run(Config {
+                limits: Limits { memory: Some(512 << 20), cpu: Some(Duration::from_secs(30)) },
+                ..config(1_000, "date", &[])
+            }))),
+            (vec!["1s", "--limit-mem"], Err("Missing memory limit!")),
+            (vec!["1s", "--limit-mem", "lots", "date"], Err("Invalid size: 'lots', expected e.g. 512M or 2G")),
+            (vec!["1s", "--limit-cpu"], Err("Missing CPU time limit!")),
+            (vec!["1s", "--limit-cpu", "0s", "date"], Err("Invalid interval '0s': interval cannot be zero")),
+            (vec!["1s", "--limit-cpu", "1x", "date"], Err("Invalid interval '1x': unrecognized format")),
+            // working directory
+            (vec!["1s", "--cwd"], Err("Missing working directory!")),
+            (vec!["1s", "--cwd", "/", "ls"], Ok(Action::Run(Config { cwd: Some(PathBuf::from("/")), ..config(1_000, "ls", &[]) }))),
+            (vec!["1s", "--cwd", "/nonexistent", "ls"], Err("Invalid working directory: '/nonexistent'")),
+            (vec!["1s", "--cwd", "/dev/null", "ls"], Err("Invalid working directory: '/dev/null'")),
+            // log & daemon
+            (vec!["1s", "--log"], Err("Missing log file path!")),
+            (vec!["1s", "--log", "/var/log/every.log", "date"], Ok(Action::Run(Config {
+                log: Some(PathBuf::from("/var/log/every.log")),
+                ..config(1_000, "date", &[])
+            }))),
+            (vec!["1s", "--daemon", "--log", "/var/log/every.log", "date"], Ok(Action::Run(Config {
+                log: Some(PathBuf::from("/var/log/every.log")),
+                daemon: true,
+                ..config(1_000, "date", &[])
+            }))),
+            (vec!["1s", "--daemon", "date"], Err("--daemon requires --log!")),
+            (vec!["1s", "--daemon", "--log", "/var/log/every.log", "--stdin", "inherit", "date"], Err("--daemon cannot be used with --stdin inherit!")),
+            // systemd
+            (vec!["1s", "--systemd", "date"], Ok(Action::Run(Config { systemd: true, ..config(1_000, "date", &[]) }))),
+            // syslog
+            (vec!["1s", "--log-syslog", "date"], Ok(Action::Run(Config { syslog: true, ..config(1_000, "date", &[]) }))),
+            // pid file
+            (vec!["1s", "--pid-file"], Err("Missing PID file path!")),
+            (vec!["1s", "--pid-file", "/run/every.pid", "date"], Ok(Action::Run(Config {
+                pid_file: Some(PathBuf::from("/run/every.pid")),
+                ..config(1_000, "date", &[])
+            }))),
+            // stdin
+            (vec!["1s", "--stdin"], Err("Missing stdin mode!")),
+            (vec!["1s", "--stdin", "x"], Err("Invalid stdin mode: 'x'")),
+            (vec!["1s", "--stdin", "null", "cat"], Ok(Action::Run(config(1_000, "cat", &[])))),
+            (vec!["1s", "--stdin", "inherit", "cat"], Ok(Action::Run(Config { stdin: Input::Inherit, ..config(1_000, "cat", &[]) }))),
+            (vec!["1s", "--stderr"], Err("Missing stderr mode!")),
+            (vec!["1s", "--stderr", "separate", "date"], Ok(Action::Run(config(1_000, "date", &[])))),
+            (vec!["1s", "--stderr", "merge", "date"], Ok(Action::Run(Config { stderr: ErrorOutput::Merge, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--stderr", "discard", "date"], Ok(Action::Run(Config { stderr: ErrorOutput::Discard, ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--stderr", "file:/tmp/errors.log", "date"], Ok(Action::Run(Config { stderr: ErrorOutput::File(PathBuf::from("/tmp/errors.log")), ..config(1_000, "date", &[]) }))),
+            (vec!["1s", "--stderr", "file:", "date"], Err("Invalid stderr mode: 'file:', expected separate, merge, discard or file:<path>")),
+            (vec!["1s", "--stderr", "inherit", "date"], Err("Invalid stderr mode: 'inherit', expected separate, merge, discard or file:<path>")),
+            (vec!["1s", "--stderr", "merge", "--pty", "date"], Err("--stderr cannot be used with --pty!")),
+            (vec!["1s", "--stderr", "discard", "--output-dir", "/tmp", "date"], Err("--stderr cannot be used with --output-dir!")),
+            (vec!["1s", "--stdin-data"], Err("Missing stdin data!")),
+            (vec!["1s", "--stdin-data", "hello", "cat"], Ok(Action::Run(Config {
+                stdin: Input::Data(String::from("hello")),
+                ..config(1_000, "cat", &[])
+            }))),
+            (vec!["1s", "--stdin-file"], Err("Missing stdin file path!")),
+            (vec!["1s", "--stdin-file", "/tmp/input", "cat"], Ok(Action::Run(Config {
+                stdin: Input::File(PathBuf::from("/tmp/input")),
+                ..config(1_000, "cat", &[])
+            }))),
+            // options after the command belong to the command
+            (vec!["1s", "ls", "-q"], Ok(Action::Run(config(1_000, "ls", &["-q"])))),
         ];
 
         for (args, expected) in test_cases {
             let expected = expected.map_err(|e| e.to_string());
             let args = args.into_iter().map(|s| s.to_string());
-            let actual = Action::parse_iter(args.clone());
+            let actual = Action::parse_iter(args.clone()).map_err(|e| e.to_string());
 
             assert_eq!(actual, expected, "args: {:?}", args.collect::<Vec<_>>());
         }
     }
 
     #[test]
-    fn test_parse_interval_as_ms() {
-        let tests_cases = [
-            // empty
-            ("", Err("Interval cannot be empty")),
-            // unparsable
-            (" ", Err("Invalid interval ' ': unrecognized format")),
-            ("d", Err("Invalid interval 'd': unrecognized format")),
-            ("h", Err("Invalid interval 'h': unrecognized format")),
-            ("m", Err("Invalid interval 'm': unrecognized format")),
-            ("s", Err("Invalid interval 's': unrecognized format")),
-            (".s", Err("Invalid interval '.s': unrecognized format")),
-            (".1s", Err("Invalid interval '.1s': unrecognized format")),
-            ("1m.0s", Err("Invalid interval '1m.0s': unrecognized format")),
-            ("0.1d", Err("Invalid interval '0.1d': unrecognized format")),
-            ("0.1h", Err("Invalid interval '0.1h': unrecognized format")),
-            ("0.1m", Err("Invalid interval '0.1m': unrecognized format")),
-            ("-1s", Err("Invalid interval '-1s': unrecognized format")),
-            (" 1s", Err("Invalid interval ' 1s': unrecognized format")),
-            ("1s-", Err("Invalid interval '1s-': unrecognized format")),
-            ("1s ", Err("Invalid interval '1s ': unrecognized format")),
-            ("1s 1s", Err("Invalid interval '1s 1s': unrecognized format")),
-            ("1.1.1s", Err("Invalid interval '1.1.1s': unrecognized format")),
-            // seconds
-            ("0s", Err("Invalid interval '0s': interval cannot be zero")),
-            ("0.0s", Err("Invalid interval '0.0s': interval cannot be zero")),
-            ("0.00s", Err("Invalid interval '0.00s': interval cannot be zero")),
-            ("0.000s", Err("Invalid interval '0.000s': interval cannot be zero")),
-            ("0.0000s", Err("Invalid interval '0.0000s': maximum precision is millisecond")),
-            ("1s", Ok(1_000)),
-            ("23s", Ok(23_000)),
-            ("0.1s", Ok(100)),
-            ("0.01s", Ok(10)),
-            ("0.12s", Ok(120)),
-            ("0.001s", Ok(1)),
-            ("0.012s", Ok(12)),
-            ("0.123s", Ok(123)),
-            ("0.1234s", Err("Invalid interval '0.1234s': maximum precision is millisecond")),
-            ("1234.5s", Ok(1234_500)),
-            ("1234.56s", Ok(1234_560)),
-            ("1234.567s", Ok(1234_567)),
-            ("1234.5678s", Err("Invalid interval '1234.5678s': maximum precision is millisecond")),
-            // minutes
-            ("0m", Err("Invalid interval '0m': interval cannot be zero")),
-            ("1m", Ok(60 * 1000)),
-            ("123m", Ok(123 * 60 * 1000)),
-            // minutes + seconds
-            ("0m0s", Err("Invalid interval '0m0s': interval cannot be zero")),
-            ("0m0.0s", Err("Invalid interval '0m0.0s': interval cannot be zero")),
-            ("0m0.00s", Err("Invalid interval '0m0.00s': interval cannot be zero")),
-            ("0m0.000s", Err("Invalid interval '0m0.000s': interval cannot be zero")),
-            ("0m0.0000s", Err("Invalid interval '0m0.0000s': maximum precision is millisecond")),
-            ("1m0s", Ok(60 * 1000)),
-            ("1m0.0s", Ok(60 * 1000)),
-            ("1m0.01s", Ok(60 * 1000 + 10)),
-            ("1m0.001s", Ok(60 * 1000 + 1)),
-            ("1m0.0010s", Err("Invalid interval '1m0.0010s': maximum precision is millisecond")),
-            ("1m0.12s", Ok(60 * 1000 + 120)),
-            ("1m0.123s", Ok(60 * 1000 + 123)),
-            ("1m0.1234s", Err("Invalid interval '1m0.1234s': maximum precision is millisecond")),
-            ("2m3.4s", Ok((2 * 60 + 3) * 1_000 + 400)),
-            ("34m56.78s", Ok((34 * 60 + 56) * 1_000 + 780)),
-            ("456m7.890s", Ok((456 * 60 + 7) * 1_000 + 890)),
-            ("456m7.891s", Ok((456 * 60 + 7) * 1_000 + 891)),
-            // hours
-            ("0h", Err("Invalid interval '0h': interval cannot be zero")),
-            ("1h", Ok(3600 * 1000)),
-            ("123h", Ok(123 * 3600 * 1000)),
-            // hours + seconds
-            ("0h0s", Err("Invalid interval '0h0s': interval cannot be zero")),
-            ("0h1s", Ok(1000)),
-            ("1h0s", Ok(3600 * 1000)),
-            ("1h1s", Ok((3600 + 1) * 1000)),
-            ("1h2s", Ok((3600 + 2) * 1000)),
-            ("1h2.3s", Ok((3600 + 2) * 1000 + 300)),
-            ("1h2.34s", Ok((3600 + 2) * 1000 + 340)),
-            ("1h2.345s", Ok((3600 + 2) * 1000 + 345)),
-            ("1h2.3456s", Err("Invalid interval '1h2.3456s': maximum precision is millisecond")),
-            // hours + minutes
-            ("0h0m", Err("Invalid interval '0h0m': interval cannot be zero")),
-            ("0h1m", Ok(60 * 1000)),
-            ("1h0m", Ok(3600 * 1000)),
-            ("1h1m", Ok((3600 + 60) * 1000)),
-            ("1h2m", Ok((3600 + 2 * 60) * 1000)),
-            // hours + minutes + seconds
-            ("1h2m3s", Ok((3600 + 2 * 60 + 3) * 1000)),
-            ("1h2m3.4s", Ok((3600 + 2 * 60 + 3) * 1000 + 400)),
-            ("1h2m3.45s", Ok((3600 + 2 * 60 + 3) * 1000 + 450)),
-            ("1h2m3.456s", Ok((3600 + 2 * 60 + 3) * 1000 + 456)),
-            ("1h2m3.4567s", Err("Invalid interval '1h2m3.4567s': maximum precision is millisecond")),
-            // days
-            ("0d", Err("Invalid interval '0d': interval cannot be zero")),
-            ("1d", Ok(86400 * 1000)),
-            ("123d", Ok(123 * 86400 * 1000)),
-            // days + seconds
-            ("0d0s", Err("Invalid interval '0d0s': interval cannot be zero")),
-            ("0d1s", Ok(1000)),
-            ("1d0s", Ok(86400 * 1000)),
-            ("1d1s", Ok((86400 + 1) * 1000)),
-            ("2d3s", Ok((2 * 86400 + 3) * 1000)),
-            ("2d3.4s", Ok((2 * 86400 + 3) * 1000 + 400)),
-            ("2d3.45s", Ok((2 * 86400 + 3) * 1000 + 450)),
-            ("2d3.456s", Ok((2 * 86400 + 3) * 1000 + 456)),
-            ("2d3.4567s", Err("Invalid interval '2d3.4567s': maximum precision is millisecond")),
-            // days + minutes
-            ("0d0m", Err("Invalid interval '0d0m': interval cannot be zero")),
-            ("0d1m", Ok(60 * 1000)),
-            ("1d0m", Ok(86400 * 1000)),
-            ("1d1m", Ok((86400 + 60) * 1000)),
-            ("2d3m", Ok((2 * 86400 + 3 * 60) * 1000)),
-            // days + minutes + seconds
-            ("0d0m0s", Err("Invalid interval '0d0m0s': interval cannot be zero")),
-            ("0d0m0.0s", Err("Invalid interval '0d0m0.0s': interval cannot be zero")),
-            ("0d0m1s", Ok(1000)),
-            ("0d1m0s", Ok(60 * 1000)),
-            ("1d0m0s", Ok(86400 * 1000)),
-            ("1d1m0s", Ok((86400 + 60) * 1000)),
-            ("2d3m4s", Ok((2 * 86400 + 3 * 60 + 4) * 1000)),
-            ("2d3m4.5s", Ok((2 * 86400 + 3 * 60 + 4) * 1000 + 500)),
-            ("2d3m4.56s", Ok((2 * 86400 + 3 * 60 + 4) * 1000 + 560)),
-            ("2d3m4.567s", Ok((2 * 86400 + 3 * 60 + 4) * 1000 + 567)),
-            ("2d3m4.5678s", Err("Invalid interval '2d3m4.5678s': maximum precision is millisecond")),
-            // days + hours
-            ("0d0h", Err("Invalid interval '0d0h': interval cannot be zero")),
-            ("0d1h", Ok(3600 * 1000)),
-            ("1d0h", Ok(86400 * 1000)),
-            ("1d1h", Ok((86400 + 3600) * 1000)),
-            ("2d3h", Ok((2 * 86400 + 3 * 3600) * 1000)),
-            // days + hours + seconds
-            ("0d0h0s", Err("Invalid interval '0d0h0s': interval cannot be zero")),
-            ("0d0h0.0s", Err("Invalid interval '0d0h0.0s': interval cannot be zero")),
-            ("0d0h1s", Ok(1000)),
-            ("0d1h0s", Ok(3600 * 1000)),
-            ("1d0h0s", Ok(86400 * 1000)),
-            ("1d1h0s", Ok((86400 + 3600) * 1000)),
-            ("2d3h4s", Ok((2 * 86400 + 3 * 3600 + 4) * 1000)),
-            ("2d3h4.5s", Ok((2 * 86400 + 3 * 3600 + 4) * 1000 + 500)),
-            ("2d3h4.56s", Ok((2 * 86400 + 3 * 3600 + 4) * 1000 + 560)),
-            ("2d3h4.567s", Ok((2 * 86400 + 3 * 3600 + 4) * 1000 + 567)),
-            ("2d3h4.5678s", Err("Invalid interval '2d3h4.5678s': maximum precision is millisecond")),
-            // days + hours + minutes
-            ("0d0h0m", Err("Invalid interval '0d0h0m': interval cannot be zero")),
-            ("0d0h1m", Ok(60 * 1000)),
-            ("0d1h0m", Ok(3600 * 1000)),
-            ("1d0h0m", Ok(86400 * 1000)),
-            ("2d3h4m", Ok((2 * 86400 + 3 * 3600 + 4 * 60) * 1000)),
-            // days + hours + minutes + seconds
-            ("0d0h0m0s", Err("Invalid interval '0d0h0m0s': interval cannot be zero")),
-            ("0d0h0m0.0s", Err("Invalid interval '0d0h0m0.0s': interval cannot be zero")),
-            ("0d0h0m0.00s", Err("Invalid interval '0d0h0m0.00s': interval cannot be zero")),
-            ("0d0h0m0.000s", Err("Invalid interval '0d0h0m0.000s': interval cannot be zero")),
-            ("0d0h0m0.0000s", Err("Invalid interval '0d0h0m0.0000s': maximum precision is millisecond")),
-            ("0d0h0m1s", Ok(1000)),
-            ("0d0h1m0s", Ok(60 * 1000)),
-            ("0d1h0m0s", Ok(3600 * 1000)),
-            ("1d0h0m0s", Ok(86400 * 1000)),
-            ("1d2h3m4s", Ok((86400 + 2 * 3600 + 3 * 60 + 4) * 1000)),
-            ("1d2h3m4.5s", Ok((86400 + 2 * 3600 + 3 * 60 + 4) * 1000 + 500)),
-            ("1d2h3m4.56s", Ok((86400 + 2 * 3600 + 3 * 60 + 4) * 1000 + 560)),
-            ("1d2h3m4.567s", Ok((86400 + 2 * 3600 + 3 * 60 + 4) * 1000 + 567)),
-            ("1d2h3m4.5678s", Err("Invalid interval '1d2h3m4.5678s': maximum precision is millisecond")),
-            ("2d3s", Ok((2 * 86400 + 3) * 1000)),
-            ("2d3.4s", Ok((2 * 86400 + 3) * 1000 + 400)),
-            ("2d3h4.56s", Ok((2 * 86400 + 3 * 3600 + 4) * 1000 + 560)),
-            ("1d1.123s", Ok((86400 + 1) * 1000 + 123)),
-            ("1d1.1234s", Err("Invalid interval '1d1.1234s': maximum precision is millisecond")),
-            // out of order units
-            ("1h1d", Err("Invalid interval '1h1d': unrecognized format")),
-            ("1m1d", Err("Invalid interval '1m1d': unrecognized format")),
-            ("1m1h", Err("Invalid interval '1m1h': unrecognized format")),
-            ("1s1d", Err("Invalid interval '1s1d': unrecognized format")),
-            ("1s1h", Err("Invalid interval '1s1h': unrecognized format")),
-            ("1s1m", Err("Invalid interval '1s1m': unrecognized format")),
-            // integer overflow
-            ("18446744073709551s", Ok(18446744073709551000)),
-            ("18446744073709551.999s", Err("Invalid interval '18446744073709551.999s': interval is too large")),
-            ("18446744073709552s", Err("Invalid interval '18446744073709552s': interval is too large")),
-            ("307445734561825m51s", Ok(18446744073709551000)),
-            ("307445734561825m52s", Err("Invalid interval '307445734561825m52s': interval is too large")),
-            ("307445734561825m", Ok(18446744073709500000)),
-            ("307445734561826m", Err("Invalid interval '307445734561826m': interval is too large")),
-            ("5124095576030h25m", Ok(18446744073709500000)),
-            ("5124095576030h26m", Err("Invalid interval '5124095576030h26m': interval is too large")),
-            ("5124095576030h", Ok(18446744073708000000)),
-            ("5124095576031h", Err("Invalid interval '5124095576031h': interval is too large")),
-            ("213503982334d14h", Ok(18446744073708000000)),
-            ("213503982334d15h", Err("Invalid interval '213503982334d15h': interval is too large")),
-            ("213503982334d", Ok(18446744073657600000)),
-            ("213503982335d", Err("Invalid interval '213503982335d': interval is too large")),
-        ];
-
-        for (input, expected) in tests_cases {
-            let actual = parse_interval_as_ms(input);
-            let expected = expected.map_err(|e| e.to_string());
+    fn test_options() {
+        // the suggestions only name existing options, but --from-stdin, that would read the jobs from stdin
+        for option in OPTIONS.into_iter().filter(|option| *option != "--from-stdin") {
+            let args = ["1s", option].map(String::from).into_iter();
 
-            assert_eq!(actual, expected, "input: {input}");
+            if let Err(e) = Action::parse_iter(args) {
+                assert!(!matches!(e, ParseError::InvalidOption { .. }), "{option}: {e}");
+            }
         }
     }
 
     #[test]
-    fn test_parse_concurrency() {
+    fn test_parse_error() {
+        let parse = |args: &[&str]| Action::parse_iter(args.iter().map(|arg| arg.to_string())).unwrap_err();
+
+        assert_eq!(parse(&["1s"]), ParseError::MissingCommand);
+        assert_eq!(parse(&["--supervise"]), ParseError::MissingInterval { supervise: true });
+        assert_eq!(parse(&["1s", "--name"]), ParseError::MissingValue("name"));
+        assert_eq!(parse(&["2sec", "date"]), ParseError::InvalidInterval {
+            input: String::from("2sec"),
+            reason: String::from("Invalid interval '2sec': unrecognized format, did you mean '2s'?"),
+        });
+        assert_eq!(parse(&["1s", "-c", "0", "date"]), ParseError::InvalidConcurrency {
+            input: String::from("0"),
+            reason: String::from("Invalid concurrency: value 0 is not in the range 1–1000"),
+        });
+        assert_eq!(parse(&["1s", "-c", "date"]), ParseError::ExpectedNumber {
+            option: String::from("-c"),
+            value: String::from("date"),
+        });
+        assert_eq!(parse(&["1s", "--kill-after=1x", "date"]), ParseError::InvalidValue {
+            option: String::from("--kill-after"),
+            value: String::from("1x"),
+            reason: String::from("Invalid interval '1x': unrecognized format"),
+        });
+        assert_eq!(parse(&["1s", "--verbos", "date"]), ParseError::InvalidOption {
+            option: String::from("--verbos"),
+            after_interval: true,
+            suggestion: Some("--verbose"),
+        });
+        assert_eq!(parse(&["1s", "--flash", "date"]), ParseError::InvalidUsage("--flash requires --bell!"));
+    }
+
+    #[test]
+    fn test_parse_env_file() {
+        let contents = "# database\nDB_HOST=localhost\n\n  DB_PORT=5432  \nDB_URL=postgres://u:p@h/db?a=b\n";
+
+        assert_eq!(parse_env_file(contents), Ok(vec![
+            (String::from("DB_HOST"), String::from("localhost")),
+            (String::from("DB_PORT"), String::from("5432")),
+            (String::from("DB_URL"), String::from("postgres://u:p@h/db?a=b")),
+        ]));
+
+        assert_eq!(parse_env_file(""), Ok(vec![]));
+        assert_eq!(parse_env_file("A=1\nB\n"), Err(String::from("Invalid environment variable: 'B', expected KEY=VALUE")));
+    }
+
+    #[test]
+    fn test_parse_size() {
         let test_cases = [
-            ("", Err("Invalid concurrency value: ''")),
-            ("-1", Err("Invalid concurrency value: '-1'")),
-            (" 1", Err("Invalid concurrency value: ' 1'")),
-            ("1 ", Err("Invalid concurrency value: '1 '")),
-            ("1 1", Err("Invalid concurrency value: '1 1'")),
-            ("1.", Err("Invalid concurrency value: '1.'")),
-            ("1.0", Err("Invalid concurrency value: '1.0'")),
-            ("0", Err("Invalid concurrency: value 0 is not in the range 1–1000")),
             ("1", Ok(1)),
-            ("2", Ok(2)),
-            ("3", Ok(3)),
-            ("10", Ok(10)),
-            ("100", Ok(100)),
-            ("1000", Ok(1000)),
-            ("1001", Err("Invalid concurrency: value 1001 is not in the range 1–1000")),
-            ("9999999999", Err("Invalid concurrency: value 9999999999 is not in the range 1–1000")),
-            ("abc", Err("Invalid concurrency value: 'abc'")),
+            ("1048576", Ok(1_048_576)),
+            ("64K", Ok(65_536)),
+            ("64k", Ok(65_536)),
+            ("512M", Ok(536_870_912)),
+            ("2G", Ok(2_147_483_648)),
+            ("1T", Ok(1_099_511_627_776)),
+            ("", Err("Invalid size: '', expected e.g. 512M or 2G")),
+            ("0", Err("Invalid size: '0', expected e.g. 512M or 2G")),
+            ("M", Err("Invalid size: 'M', expected e.g. 512M or 2G")),
+            ("1.5G", Err("Invalid size: '1.5G', expected e.g. 512M or 2G")),
+            ("1MB", Err("Invalid size: '1MB', expected e.g. 512M or 2G")),
+            ("-1M", Err("Invalid size: '-1M', expected e.g. 512M or 2G")),
+            ("99999999T", Err("Invalid size: '99999999T', expected e.g. 512M or 2G")),
         ];
 
         for (input, expected) in test_cases {
-            let actual = parse_concurrency(input);
+            let actual = parse_size(input);
             let expected = expected.map_err(|e| e.to_string());
 
             assert_eq!(actual, expected, "input: {input}");
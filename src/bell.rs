@@ -0,0 +1,49 @@
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+const BELL: &str = "\x07";
+
+// The DEC sequences turning the reverse video of the screen on and off.
+const REVERSE_VIDEO: &str = "\x1b[?5h";
+const NORMAL_VIDEO: &str = "\x1b[?5l";
+
+// How long the screen is inverted by a flash.
+const FLASH_DURATION: Duration = Duration::from_millis(100);
+
+// Whether the screen may be left inverted, if we exit during a flash.
+static FLASHED: AtomicBool = AtomicBool::new(false);
+
+// Rings the bell of the terminal on stderr, and with flash, briefly inverts the screen, in the background.
+// Nothing is written if stderr is not a terminal, such as a log file.
+pub fn ring(flash: bool) {
+    if !io::stderr().is_terminal() {
+        return;
+    }
+
+    ring_on(io::stderr(), flash);
+}
+
+// Rings the bell on the given terminal.
+pub fn ring_on<W: Write + Send + 'static>(mut terminal: W, flash: bool) {
+    if !flash {
+        let _ = terminal.write_all(BELL.as_bytes());
+        return;
+    }
+
+    FLASHED.store(true, Ordering::SeqCst);
+    let _ = terminal.write_all(format!("{BELL}{REVERSE_VIDEO}").as_bytes());
+
+    thread::spawn(move || {
+        thread::sleep(FLASH_DURATION);
+        let _ = terminal.write_all(NORMAL_VIDEO.as_bytes());
+    });
+}
+
+// Turns the reverse video off on stderr, if the screen has been flashed.
+pub fn restore() {
+    if FLASHED.load(Ordering::SeqCst) {
+        eprint!("{NORMAL_VIDEO}");
+    }
+}
@@ -0,0 +1,162 @@
+use crate::clock::Date;
+
+/// Parses the dates of `--skip-dates`: an iCalendar file, or one `YYYY-MM-DD` date per line,
+/// ignoring empty lines and `#` comments. Returns the dates sorted, for a binary search.
+pub(crate) fn parse_dates(contents: &str) -> Result<Vec<Date>, String> {
+    let mut dates = if contents.trim_start().starts_with("BEGIN:VCALENDAR") {
+        parse_ical(contents)?
+    } else {
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::parse)
+            .collect::<Result<Vec<Date>, _>>()?
+    };
+
+    dates.sort();
+    dates.dedup();
+
+    Ok(dates)
+}
+
+// The days of the events, from DTSTART to DTEND excluded for the events that last several days.
+// The time of the events is ignored, and recurring events are not expanded.
+fn parse_ical(contents: &str) -> Result<Vec<Date>, String> {
+    let mut dates = vec![];
+    let mut event: Option<(Option<Date>, Option<Date>)> = None;
+
+    for line in unfold(contents) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+
+        // the parameters of the property, such as DTSTART;VALUE=DATE, are ignored
+        let name = name.split(';').next().unwrap_or_default();
+
+        match (name, &mut event) {
+            ("BEGIN", None) if value == "VEVENT" => event = Some((None, None)),
+            ("DTSTART", Some((start, _))) => *start = Some(parse_ical_date(value)?),
+            ("DTEND", Some((_, end))) => *end = Some(parse_ical_date(value)?),
+            ("END", Some((start, end))) if value == "VEVENT" => {
+                let Some(mut date) = *start else {
+                    return Err(String::from("Invalid calendar: event without DTSTART"));
+                };
+
+                dates.push(date);
+
+                while let Some(end) = *end {
+                    date = date.next();
+
+                    if date >= end {
+                        break;
+                    }
+
+                    dates.push(date);
+                }
+
+                event = None;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(dates)
+}
+
+// The lines of an iCalendar file, with the long lines folded on several ones joined back.
+fn unfold(contents: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+
+    for line in contents.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(String::from(line)),
+        }
+    }
+
+    lines
+}
+
+// Parses the date of DTSTART or DTEND: YYYYMMDD, followed by THHMMSS[Z] for a date and time.
+fn parse_ical_date(value: &str) -> Result<Date, String> {
+    let invalid = || format!("Invalid calendar date: '{value}', expected YYYYMMDD");
+
+    let date = value
+        .get(..8)
+        .filter(|date| date.bytes().all(|b| b.is_ascii_digit()));
+    let date = date.ok_or_else(invalid)?;
+
+    format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..])
+        .parse()
+        .map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u8, day: u8) -> Date {
+        Date { year, month, day }
+    }
+
+    #[test]
+    fn test_parse_dates() {
+        assert_eq!(
+            parse_dates("# public holidays\n2024-12-25\n\n2024-01-01\n  2024-12-25  \n"),
+            Ok(vec![date(2024, 1, 1), date(2024, 12, 25)])
+        );
+        assert_eq!(
+            parse_dates("2024-12-25\nchristmas\n"),
+            Err(String::from(
+                "Invalid date: 'christmas', expected YYYY-MM-DD"
+            ))
+        );
+        assert_eq!(parse_dates(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_parse_ical() {
+        let calendar = concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "VERSION:2.0\r\n",
+            "BEGIN:VEVENT\r\n",
+            "DTSTART;VALUE=DATE:20241225\r\n",
+            "DTEND;VALUE=DATE:20241226\r\n",
+            "SUMMARY:Christmas\r\n",
+            "END:VEVENT\r\n",
+            "BEGIN:VEVENT\r\n",
+            "SUMMARY:Company\r\n",
+            "  retreat\r\n",
+            "DTSTART;VALUE=DATE:20241230\r\n",
+            "DTEND;VALUE=DATE:20250102\r\n",
+            "END:VEVENT\r\n",
+            "BEGIN:VEVENT\r\n",
+            "DTSTART:20240501T080000Z\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n",
+        );
+
+        assert_eq!(
+            parse_dates(calendar),
+            Ok(vec![
+                date(2024, 5, 1),
+                date(2024, 12, 25),
+                date(2024, 12, 30),
+                date(2024, 12, 31),
+                date(2025, 1, 1),
+            ])
+        );
+
+        assert_eq!(
+            parse_dates("BEGIN:VCALENDAR\nBEGIN:VEVENT\nDTSTART:2024\nEND:VEVENT\nEND:VCALENDAR\n"),
+            Err(String::from(
+                "Invalid calendar date: '2024', expected YYYYMMDD"
+            ))
+        );
+        assert_eq!(
+            parse_dates("BEGIN:VCALENDAR\nBEGIN:VEVENT\nSUMMARY:x\nEND:VEVENT\nEND:VCALENDAR\n"),
+            Err(String::from("Invalid calendar: event without DTSTART"))
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use crate::usage::format_size;
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+// The size limits of the output of a run, shared by its stdout & stderr.
+pub struct OutputCap {
+    // the bytes read from the command so far, including the ones dropped
+    read: AtomicU64,
+    truncate_at: Option<u64>,
+    kill_at: Option<u64>,
+    truncated: AtomicBool,
+    killed: AtomicBool,
+    // called once the output goes beyond kill_at
+    on_kill: Box<dyn Fn() + Send + Sync>,
+}
+
+// A reader of the output of a command, that drops what goes beyond the cap.
+// The output is still read to the end, so that the command does not block writing it.
+pub struct Capped<R> {
+    reader: R,
+    cap: Arc<OutputCap>,
+    // the truncation marker, and the number of its bytes returned so far
+    marker: Option<(String, usize)>,
+}
+
+impl OutputCap {
+    pub fn new(
+        truncate_at: Option<u64>,
+        kill_at: Option<u64>,
+        on_kill: impl Fn() + Send + Sync + 'static,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            read: AtomicU64::new(0),
+            truncate_at,
+            kill_at,
+            truncated: AtomicBool::new(false),
+            killed: AtomicBool::new(false),
+            on_kill: Box::new(on_kill),
+        })
+    }
+
+    pub fn wrap<R: Read>(self: &Arc<Self>, reader: R) -> Capped<R> {
+        Capped {
+            reader,
+            cap: Arc::clone(self),
+            marker: None,
+        }
+    }
+}
+
+impl<R: Read> Read for Capped<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some((marker, position)) = &mut self.marker {
+                if *position < marker.len() {
+                    let length = buffer.len().min(marker.len() - *position);
+                    buffer[..length].copy_from_slice(&marker.as_bytes()[*position..][..length]);
+                    *position += length;
+
+                    return Ok(length);
+                }
+            }
+
+            let length = self.reader.read(buffer)?;
+
+            if length == 0 {
+                return Ok(0);
+            }
+
+            let before = self.cap.read.fetch_add(length as u64, Ordering::SeqCst);
+            let after = before + length as u64;
+
+            if self.cap.kill_at.is_some_and(|kill_at| after > kill_at)
+                && !self.cap.killed.swap(true, Ordering::SeqCst)
+            {
+                (self.cap.on_kill)();
+            }
+
+            let Some(truncate_at) = self.cap.truncate_at else {
+                return Ok(length);
+            };
+
+            if after <= truncate_at {
+                return Ok(length);
+            }
+
+            // once, on whichever stream goes beyond the cap first
+            if !self.cap.truncated.swap(true, Ordering::SeqCst) {
+                let marker = format!("\n[output truncated after {}]\n", format_size(truncate_at));
+                self.marker = Some((marker, 0));
+            }
+
+            let allowed = truncate_at.saturating_sub(before) as usize;
+
+            if allowed > 0 {
+                return Ok(allowed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_truncate() {
+        let cap = OutputCap::new(Some(1024), None, || {});
+
+        let mut output = String::new();
+        cap.wrap(&[b'a'; 1000][..])
+            .read_to_string(&mut output)
+            .unwrap();
+        cap.wrap(&[b'b'; 5000][..])
+            .read_to_string(&mut output)
+            .unwrap();
+
+        assert_eq!(
+            output,
+            format!(
+                "{}{}\n[output truncated after 1K]\n",
+                "a".repeat(1000),
+                "b".repeat(24)
+            )
+        );
+    }
+
+    #[test]
+    fn test_kill() {
+        let kills = Arc::new(AtomicUsize::new(0));
+        let cap = {
+            let kills = Arc::clone(&kills);
+            OutputCap::new(None, Some(10), move || {
+                kills.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+
+        let mut output = Vec::new();
+        cap.wrap(&[0; 10][..]).read_to_end(&mut output).unwrap();
+        assert_eq!(kills.load(Ordering::SeqCst), 0);
+
+        cap.wrap(&[0; 20][..]).read_to_end(&mut output).unwrap();
+        cap.wrap(&[0; 20][..]).read_to_end(&mut output).unwrap();
+        assert_eq!(kills.load(Ordering::SeqCst), 1);
+        assert_eq!(output.len(), 50);
+    }
+}
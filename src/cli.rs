@@ -0,0 +1,29 @@
+use crate::args::Action;
+use crate::exit::ExitReason;
+use crate::help;
+use crate::history;
+use crate::instances;
+use crate::runner;
+use std::env::Args;
+
+/// Runs the `every` command line tool with the arguments of the process,
+/// and returns the reason to exit with.
+pub fn main(args: Args) -> ExitReason {
+    match Action::parse(args) {
+        Ok(Action::Help) => help::print_help(),
+        Ok(Action::Man) => help::print_man(),
+        Ok(Action::Version) => help::print_version(),
+        // already logged
+        Ok(Action::Run(config)) => return runner::run(config).unwrap_or(ExitReason::Error),
+        Ok(Action::Preview(config, count)) => runner::preview(config, count),
+        Ok(Action::Status(instance)) => return instances::status(instance),
+        Ok(Action::Stop(instance)) => return instances::stop(instance),
+        Ok(Action::History(path)) => return history::show(&path),
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitReason::Usage;
+        }
+    }
+
+    ExitReason::Success
+}
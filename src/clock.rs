@@ -0,0 +1,681 @@
+use nix::libc;
+use regex::Regex;
+use std::env;
+use std::ffi::{CString, OsString};
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::path::Path;
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_ZONEINFO_DIR: &str = "/usr/share/zoneinfo";
+
+// The TZ variable of our environment before set_time_zone(), None if the time zone is not set.
+static INHERITED_TZ: OnceLock<Option<OsString>> = OnceLock::new();
+
+extern "C" {
+    fn tzset();
+}
+
+/// A point in local time, as a time of day (`18:30`) or a date and time (`2024-12-31T23:59:59`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LocalTime {
+    /// The next occurrence of this time of day.
+    TimeOfDay(TimeOfDay),
+    DateTime {
+        year: i32,
+        month: u8,
+        day: u8,
+        time: TimeOfDay,
+    },
+}
+
+/// A local time of day, parsed from `HH:MM` or `HH:MM:SS` in 24-hour format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeOfDay {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl LocalTime {
+    /// Returns the system time of this local time, or of its next occurrence after `now`
+    /// for a time of day.
+    pub fn resolve(&self, now: SystemTime) -> SystemTime {
+        match *self {
+            LocalTime::TimeOfDay(time) => {
+                let mut tm = local_tm(now);
+                set_time(&mut tm, time);
+
+                let mut resolved = make_time(&mut tm);
+
+                if resolved <= now {
+                    // tomorrow, the day is normalized by mktime()
+                    let mut tm = local_tm(now);
+                    tm.tm_mday += 1;
+                    set_time(&mut tm, time);
+
+                    resolved = make_time(&mut tm);
+                }
+
+                resolved
+            }
+            LocalTime::DateTime {
+                year,
+                month,
+                day,
+                time,
+            } => {
+                let mut tm = local_tm(now);
+                tm.tm_year = year - 1900;
+                tm.tm_mon = i32::from(month) - 1;
+                tm.tm_mday = i32::from(day);
+                set_time(&mut tm, time);
+
+                make_time(&mut tm)
+            }
+        }
+    }
+}
+
+impl FromStr for LocalTime {
+    type Err = String;
+
+    fn from_str(time: &str) -> Result<Self, Self::Err> {
+        let re = Regex::new(concat!(
+            "^",
+            "(?:([0-9]{4})-([0-9]{2})-([0-9]{2})[T ])?",
+            "([0-9:]+)",
+            "$"
+        ))
+        .unwrap();
+
+        let invalid =
+            || format!("Invalid time: '{time}', expected HH:MM[:SS] or YYYY-MM-DDTHH:MM[:SS]");
+
+        let caps = re.captures(time).ok_or_else(invalid)?;
+        let time_of_day = caps[4].parse::<TimeOfDay>().map_err(|_| invalid())?;
+
+        let (Some(year), Some(month), Some(day)) = (caps.get(1), caps.get(2), caps.get(3)) else {
+            return Ok(LocalTime::TimeOfDay(time_of_day));
+        };
+
+        // the regex guarantees that these are numbers
+        let year = year.as_str().parse().unwrap();
+        let month = month.as_str().parse().unwrap();
+        let day = day.as_str().parse().unwrap();
+
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return Err(invalid());
+        }
+
+        Ok(LocalTime::DateTime {
+            year,
+            month,
+            day,
+            time: time_of_day,
+        })
+    }
+}
+
+impl fmt::Display for LocalTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LocalTime::TimeOfDay(time) => write!(f, "{time}"),
+            LocalTime::DateTime {
+                year,
+                month,
+                day,
+                time,
+            } => write!(f, "{year:04}-{month:02}-{day:02}T{time}"),
+        }
+    }
+}
+
+impl TimeOfDay {
+    /// The time of day of the system time, in local time, and the day of the week (0 is Sunday).
+    pub fn local(now: SystemTime) -> (TimeOfDay, u8) {
+        let tm = local_tm(now);
+
+        let time = TimeOfDay {
+            hour: tm.tm_hour as u8,
+            minute: tm.tm_min as u8,
+            // leap seconds are counted as the last second of the minute
+            second: tm.tm_sec.min(59) as u8,
+        };
+
+        (time, tm.tm_wday as u8)
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = String;
+
+    fn from_str(time: &str) -> Result<Self, Self::Err> {
+        let re = Regex::new("^([0-9]{2}):([0-9]{2})(?::([0-9]{2}))?$").unwrap();
+
+        let invalid = || format!("Invalid time of day: '{time}', expected HH:MM[:SS]");
+
+        let caps = re.captures(time).ok_or_else(invalid)?;
+
+        // the regex guarantees that these are numbers
+        let hour = caps[1].parse().unwrap();
+        let minute = caps[2].parse().unwrap();
+        let second = caps.get(3).map_or(0, |s| s.as_str().parse().unwrap());
+
+        if hour > 23 || minute > 59 || second > 59 {
+            return Err(invalid());
+        }
+
+        Ok(TimeOfDay {
+            hour,
+            minute,
+            second,
+        })
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)?;
+
+        if self.second != 0 {
+            write!(f, ":{:02}", self.second)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A daily window of local time, that may span midnight: `09:00-17:30`, `22:00-06:00`.
+/// The start is included, the end is excluded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, time: TimeOfDay) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+impl FromStr for TimeWindow {
+    type Err = String;
+
+    fn from_str(window: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid time window: '{window}', expected HH:MM-HH:MM");
+
+        let (start, end) = window.split_once('-').ok_or_else(invalid)?;
+        let start = start.parse().map_err(|_| invalid())?;
+        let end = end.parse().map_err(|_| invalid())?;
+
+        if start == end {
+            return Err(format!(
+                "Invalid time window: '{window}', start and end are equal"
+            ));
+        }
+
+        Ok(TimeWindow { start, end })
+    }
+}
+
+const DAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A set of days of the week, parsed from a list of days and ranges of days: `mon-fri`, `sat,sun`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Weekdays {
+    // bit 0 is Sunday
+    days: u8,
+}
+
+impl Weekdays {
+    /// Whether the set contains the day of the week, 0 being Sunday.
+    pub fn contains(&self, weekday: u8) -> bool {
+        self.days & (1 << weekday) != 0
+    }
+}
+
+impl FromStr for Weekdays {
+    type Err = String;
+
+    fn from_str(days: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid days: '{days}', expected e.g. mon-fri or sat,sun");
+
+        let day = |name: &str| {
+            DAY_NAMES
+                .iter()
+                .position(|day| *day == name.to_ascii_lowercase())
+                .ok_or_else(invalid)
+        };
+
+        let mut weekdays = Weekdays { days: 0 };
+
+        for part in days.split(',') {
+            let (first, last) = match part.split_once('-') {
+                Some((first, last)) => (day(first)?, day(last)?),
+                None => (day(part)?, day(part)?),
+            };
+
+            // ranges may wrap around the end of the week: fri-mon
+            let mut day = first;
+
+            loop {
+                weekdays.days |= 1 << day;
+
+                if day == last {
+                    break;
+                }
+
+                day = (day + 1) % 7;
+            }
+        }
+
+        Ok(weekdays)
+    }
+}
+
+/// A local date, parsed from `YYYY-MM-DD`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Date {
+    /// The date of the system time, in local time.
+    pub fn local(now: SystemTime) -> Date {
+        let tm = local_tm(now);
+
+        Date {
+            year: tm.tm_year + 1900,
+            month: (tm.tm_mon + 1) as u8,
+            day: tm.tm_mday as u8,
+        }
+    }
+
+    /// Returns the day after this date.
+    pub fn next(self) -> Date {
+        match self {
+            Date {
+                year,
+                month: 12,
+                day: 31,
+            } => Date {
+                year: year + 1,
+                month: 1,
+                day: 1,
+            },
+            Date { year, month, day } if day >= days_in_month(year, month) => Date {
+                year,
+                month: month + 1,
+                day: 1,
+            },
+            Date { year, month, day } => Date {
+                year,
+                month,
+                day: day + 1,
+            },
+        }
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    fn from_str(date: &str) -> Result<Self, Self::Err> {
+        let re = Regex::new("^([0-9]{4})-([0-9]{2})-([0-9]{2})$").unwrap();
+
+        let invalid = || format!("Invalid date: '{date}', expected YYYY-MM-DD");
+
+        let caps = re.captures(date).ok_or_else(invalid)?;
+
+        // the regex guarantees that these are numbers
+        let year = caps[1].parse().unwrap();
+        let month = caps[2].parse().unwrap();
+        let day = caps[3].parse().unwrap();
+
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return Err(invalid());
+        }
+
+        Ok(Date { year, month, day })
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Checks that the IANA time zone, such as `Europe/Paris`, exists in the time zone database.
+pub(crate) fn validate_time_zone(zone: &str) -> Result<(), String> {
+    let zoneinfo_dir = env::var_os("TZDIR").unwrap_or_else(|| OsString::from(DEFAULT_ZONEINFO_DIR));
+
+    let valid = !zone.is_empty()
+        && !zone.starts_with('/')
+        && !zone.split('/').any(|part| part == "..")
+        && Path::new(&zoneinfo_dir).join(zone).is_file();
+
+    match valid {
+        true => Ok(()),
+        false => Err(format!("Invalid time zone: '{zone}'")),
+    }
+}
+
+// Interprets local times in the IANA time zone, instead of the system one.
+// Must be called before starting any thread, as it changes our environment.
+pub fn set_time_zone(zone: &str) {
+    INHERITED_TZ.get_or_init(|| env::var_os("TZ"));
+    env::set_var("TZ", format!(":{zone}"));
+
+    // SAFETY: no other thread is running, that could read the environment or the time zone
+    unsafe { tzset() };
+}
+
+// Gives the command the TZ variable of our original environment, if set_time_zone() replaced it.
+pub fn inherit_time_zone(command: &mut Command) {
+    match INHERITED_TZ.get() {
+        Some(Some(tz)) => command.env("TZ", tz),
+        Some(None) => command.env_remove("TZ"),
+        None => command,
+    };
+}
+
+// Formats the local time with a strftime() format, such as %Y-%m-%d.
+pub fn format_local(time: SystemTime, format: &str) -> String {
+    let tm = local_tm(time);
+
+    // command line arguments cannot contain a NUL byte
+    let Ok(format) = CString::new(format) else {
+        return String::new();
+    };
+
+    let mut buffer = [0u8; 256];
+
+    // SAFETY: the buffer length is passed, and the format is NUL-terminated
+    let length = unsafe {
+        libc::strftime(
+            buffer.as_mut_ptr().cast(),
+            buffer.len(),
+            format.as_ptr(),
+            &tm,
+        )
+    };
+
+    String::from_utf8_lossy(&buffer[..length]).into_owned()
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn set_time(tm: &mut libc::tm, time: TimeOfDay) {
+    tm.tm_hour = i32::from(time.hour);
+    tm.tm_min = i32::from(time.minute);
+    tm.tm_sec = i32::from(time.second);
+    // let mktime() determine whether DST is in effect
+    tm.tm_isdst = -1;
+}
+
+fn local_tm(time: SystemTime) -> libc::tm {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as libc::time_t;
+
+    let mut tm = MaybeUninit::uninit();
+
+    // SAFETY: localtime_r() is thread-safe, and initializes tm on success
+    unsafe {
+        if libc::localtime_r(&seconds, tm.as_mut_ptr()).is_null() {
+            panic!("Failed to convert the time to local time");
+        }
+
+        tm.assume_init()
+    }
+}
+
+fn make_time(tm: &mut libc::tm) -> SystemTime {
+    // SAFETY: tm is a valid, initialized struct
+    let seconds = unsafe { libc::mktime(tm) };
+
+    UNIX_EPOCH + Duration::from_secs(seconds.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u8, minute: u8, second: u8) -> TimeOfDay {
+        TimeOfDay {
+            hour,
+            minute,
+            second,
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_parse_local_time() {
+        let expected_error = |input: &str| {
+            Err(format!("Invalid time: '{input}', expected HH:MM[:SS] or YYYY-MM-DDTHH:MM[:SS]"))
+        };
+
+        let test_cases = [
+            ("18:30", Ok(LocalTime::TimeOfDay(time(18, 30, 0)))),
+            ("00:00", Ok(LocalTime::TimeOfDay(time(0, 0, 0)))),
+            ("23:59:59", Ok(LocalTime::TimeOfDay(time(23, 59, 59)))),
+            ("2024-12-31T23:59:59", Ok(LocalTime::DateTime { year: 2024, month: 12, day: 31, time: time(23, 59, 59) })),
+            ("2024-02-29 08:00", Ok(LocalTime::DateTime { year: 2024, month: 2, day: 29, time: time(8, 0, 0) })),
+            ("", expected_error("")),
+            ("18", expected_error("18")),
+            ("8:30", expected_error("8:30")),
+            ("24:00", expected_error("24:00")),
+            ("18:60", expected_error("18:60")),
+            ("18:30:60", expected_error("18:30:60")),
+            ("18:30:00:00", expected_error("18:30:00:00")),
+            ("2024-12-31", expected_error("2024-12-31")),
+            ("2024-13-01T00:00", expected_error("2024-13-01T00:00")),
+            ("2024-00-01T00:00", expected_error("2024-00-01T00:00")),
+            ("2024-04-31T00:00", expected_error("2024-04-31T00:00")),
+            ("2023-02-29T00:00", expected_error("2023-02-29T00:00")),
+            ("2024-12-00T00:00", expected_error("2024-12-00T00:00")),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(input.parse::<LocalTime>(), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        for input in [
+            "18:30",
+            "23:59:59",
+            "2024-12-31T23:59:59",
+            "2024-02-29T08:00",
+        ] {
+            assert_eq!(input.parse::<LocalTime>().unwrap().to_string(), input);
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_time_window() {
+        let test_cases = [
+            ("09:00-17:30", time(8, 59, 59), false),
+            ("09:00-17:30", time(9, 0, 0), true),
+            ("09:00-17:30", time(12, 0, 0), true),
+            ("09:00-17:30", time(17, 29, 59), true),
+            ("09:00-17:30", time(17, 30, 0), false),
+            ("22:00-06:00", time(21, 59, 59), false),
+            ("22:00-06:00", time(22, 0, 0), true),
+            ("22:00-06:00", time(0, 0, 0), true),
+            ("22:00-06:00", time(5, 59, 59), true),
+            ("22:00-06:00", time(6, 0, 0), false),
+            ("22:00-06:00", time(12, 0, 0), false),
+        ];
+
+        for (window, time, expected) in test_cases {
+            let actual = window.parse::<TimeWindow>().unwrap().contains(time);
+
+            assert_eq!(actual, expected, "window: {window}, time: {time}");
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_parse_time_window() {
+        let test_cases = [
+            ("09:00-17:30:15", Ok(TimeWindow { start: time(9, 0, 0), end: time(17, 30, 15) })),
+            ("09:00", Err("Invalid time window: '09:00', expected HH:MM-HH:MM")),
+            ("09:00-", Err("Invalid time window: '09:00-', expected HH:MM-HH:MM")),
+            ("9-17", Err("Invalid time window: '9-17', expected HH:MM-HH:MM")),
+            ("09:00-17:30-18:00", Err("Invalid time window: '09:00-17:30-18:00', expected HH:MM-HH:MM")),
+            ("09:00-09:00", Err("Invalid time window: '09:00-09:00', start and end are equal")),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(input.parse::<TimeWindow>(), expected.map_err(String::from), "input: {input}");
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_parse_weekdays() {
+        let test_cases = [
+            ("mon-fri", Ok([false, true, true, true, true, true, false])),
+            ("sat,sun", Ok([true, false, false, false, false, false, true])),
+            ("Mon,wed-thu", Ok([false, true, false, true, true, false, false])),
+            ("fri-mon", Ok([true, true, false, false, false, true, true])),
+            ("sun-sat", Ok([true; 7])),
+            ("wed", Ok([false, false, false, true, false, false, false])),
+            ("", Err("Invalid days: '', expected e.g. mon-fri or sat,sun")),
+            ("monday", Err("Invalid days: 'monday', expected e.g. mon-fri or sat,sun")),
+            ("mon-", Err("Invalid days: 'mon-', expected e.g. mon-fri or sat,sun")),
+            ("mon,,fri", Err("Invalid days: 'mon,,fri', expected e.g. mon-fri or sat,sun")),
+        ];
+
+        for (input, expected) in test_cases {
+            let actual = input
+                .parse::<Weekdays>()
+                .map(|weekdays| std::array::from_fn(|day| weekdays.contains(day as u8)));
+
+            assert_eq!(actual, expected.map_err(String::from), "input: {input}");
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_parse_date() {
+        let date = |year, month, day| Date { year, month, day };
+
+        let test_cases: Vec<(&str, Result<Date, &str>)> = vec![
+            ("2024-12-25", Ok(date(2024, 12, 25))),
+            ("2024-02-29", Ok(date(2024, 2, 29))),
+            ("2023-02-29", Err("Invalid date: '2023-02-29', expected YYYY-MM-DD")),
+            ("2024-13-01", Err("Invalid date: '2024-13-01', expected YYYY-MM-DD")),
+            ("2024-12-00", Err("Invalid date: '2024-12-00', expected YYYY-MM-DD")),
+            ("20241225", Err("Invalid date: '20241225', expected YYYY-MM-DD")),
+            ("", Err("Invalid date: '', expected YYYY-MM-DD")),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(input.parse::<Date>(), expected.map_err(String::from), "input: {input}");
+        }
+
+        assert_eq!(date(2024, 2, 28).next(), date(2024, 2, 29));
+        assert_eq!(date(2023, 2, 28).next(), date(2023, 3, 1));
+        assert_eq!(date(2024, 12, 31).next(), date(2025, 1, 1));
+        assert_eq!(date(2024, 12, 31).to_string(), "2024-12-31");
+    }
+
+    #[test]
+    fn test_validate_time_zone() {
+        assert_eq!(validate_time_zone("UTC"), Ok(()));
+        assert_eq!(validate_time_zone("Europe/Paris"), Ok(()));
+
+        for zone in [
+            "",
+            "Europe/Nowhere",
+            "Europe",
+            "/etc/passwd",
+            "../zoneinfo/UTC",
+        ] {
+            assert_eq!(
+                validate_time_zone(zone),
+                Err(format!("Invalid time zone: '{zone}'")),
+                "zone: {zone}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_time_of_day() {
+        let now = SystemTime::now();
+
+        for target in [time(0, 0, 0), time(12, 30, 15), time(23, 59, 59)] {
+            let resolved = LocalTime::TimeOfDay(target).resolve(now);
+
+            // the next occurrence, at most a day later, accounting for DST changes
+            assert!(resolved > now, "target: {target}");
+            assert!(
+                resolved <= now + Duration::from_secs(25 * 3600),
+                "target: {target}"
+            );
+            assert_eq!(TimeOfDay::local(resolved).0, target);
+        }
+    }
+
+    #[test]
+    fn test_format_local() {
+        let time = LocalTime::DateTime {
+            year: 2024,
+            month: 12,
+            day: 31,
+            time: time(23, 59, 58),
+        }
+        .resolve(SystemTime::now());
+
+        assert_eq!(format_local(time, "%Y%m%d-%H%M%S"), "20241231-235958");
+        assert_eq!(
+            format_local(time, "%d/%m at %H:%M, 100%%"),
+            "31/12 at 23:59, 100%"
+        );
+        assert_eq!(format_local(time, ""), "");
+    }
+
+    #[test]
+    fn test_resolve_date_time() {
+        let now = SystemTime::now();
+
+        let target = LocalTime::DateTime {
+            year: 2024,
+            month: 12,
+            day: 31,
+            time: time(23, 59, 59),
+        };
+
+        let resolved = target.resolve(now);
+
+        assert!(resolved < now);
+        assert_eq!(TimeOfDay::local(resolved).0, time(23, 59, 59));
+        // 2024-12-31 is a Tuesday
+        assert_eq!(TimeOfDay::local(resolved).1, 2);
+    }
+}
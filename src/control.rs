@@ -0,0 +1,204 @@
+use crate::interval::{format_duration, format_interval_range, Interval};
+use crate::logger;
+use crate::scheduler::Control;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+
+// Listens on a Unix socket for control commands, one per line, and forwards them to the scheduler.
+// Each command is answered with zero or more lines, followed by "ok" or "error: <message>".
+// The status function returns the lines to append to the scheduler status.
+pub fn listen<F>(path: &Path, controls: Sender<Control>, status: F) -> io::Result<()>
+where
+    F: Fn() -> Vec<String> + Send + Sync + 'static,
+{
+    let listener = UnixListener::bind(path)?;
+    let status = Arc::new(status);
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    logger::error(&format!("Failed to accept control connection: {e}"), &[]);
+                    continue;
+                }
+            };
+
+            let controls = controls.clone();
+            let status = Arc::clone(&status);
+
+            thread::spawn(move || {
+                if let Err(e) = handle_connection(stream, &controls, &*status) {
+                    logger::error(&format!("Control connection error: {e}"), &[]);
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+fn handle_connection(
+    stream: UnixStream,
+    controls: &Sender<Control>,
+    status: &dyn Fn() -> Vec<String>,
+) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+
+        if line.trim() == "stop" {
+            // respond first, as the process may exit as soon as the scheduler is stopped
+            writeln!(writer, "ok")?;
+            let _ = controls.send(Control::Stop);
+            continue;
+        }
+
+        let response = match handle_command(line.trim(), controls, status) {
+            Ok(mut lines) => {
+                lines.push(String::from("ok"));
+                lines
+            }
+            Err(e) => vec![format!("error: {e}")],
+        };
+
+        for line in response {
+            writeln!(writer, "{line}")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_command(
+    command: &str,
+    controls: &Sender<Control>,
+    status: &dyn Fn() -> Vec<String>,
+) -> Result<Vec<String>, String> {
+    let (name, arg) = match command.split_once(' ') {
+        Some((name, arg)) => (name, Some(arg.trim())),
+        None => (command, None),
+    };
+
+    let control = match (name, arg) {
+        ("status", None) => return get_status(controls, status),
+        ("pause", None) => Control::Pause,
+        ("resume", None) => Control::Resume,
+        ("run-now", None) => Control::RunNow,
+        ("set-interval", Some(interval)) => {
+            Control::SetInterval(interval.parse::<Interval>()?.as_duration())
+        }
+        ("set-interval", None) => return Err(String::from("Missing interval!")),
+        ("stop", None) => Control::Stop,
+        ("status" | "pause" | "resume" | "run-now" | "stop", Some(_)) => {
+            return Err(format!("Command '{name}' does not take an argument"))
+        }
+        _ => return Err(format!("Unknown command: '{command}'")),
+    };
+
+    send(controls, control)?;
+
+    Ok(vec![])
+}
+
+fn get_status(
+    controls: &Sender<Control>,
+    status: &dyn Fn() -> Vec<String>,
+) -> Result<Vec<String>, String> {
+    let (reply, response) = mpsc::channel();
+
+    send(controls, Control::Status(reply))?;
+
+    let scheduler_status = response
+        .recv()
+        .map_err(|_| String::from("The scheduler is stopped"))?;
+
+    let mut lines = vec![
+        format!(
+            "State:         {}",
+            if scheduler_status.paused {
+                "paused"
+            } else {
+                "running"
+            }
+        ),
+        format!(
+            "Interval:      {}",
+            format_interval_range(scheduler_status.interval, scheduler_status.max_interval)
+        ),
+    ];
+
+    if !scheduler_status.paused {
+        lines.push(format!(
+            "Next tick in:  {}",
+            format_duration(scheduler_status.next_tick_in)
+        ));
+    }
+
+    lines.extend(status());
+
+    Ok(lines)
+}
+
+fn send(controls: &Sender<Control>, control: Control) -> Result<(), String> {
+    controls
+        .send(control)
+        .map_err(|_| String::from("The scheduler is stopped"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handle_command() {
+        let test_cases = [
+            ("pause", Ok(vec![])),
+            ("resume", Ok(vec![])),
+            ("run-now", Ok(vec![])),
+            ("stop", Ok(vec![])),
+            ("set-interval 10s", Ok(vec![])),
+            ("set-interval 10", Ok(vec![])),
+            ("set-interval", Err("Missing interval!")),
+            (
+                "set-interval 10x",
+                Err("Invalid interval '10x': unrecognized format"),
+            ),
+            (
+                "pause now",
+                Err("Command 'pause' does not take an argument"),
+            ),
+            ("", Err("Unknown command: ''")),
+            ("foo", Err("Unknown command: 'foo'")),
+        ];
+
+        for (command, expected) in test_cases {
+            let (controls, _receiver) = mpsc::channel();
+            let actual = handle_command(command, &controls, &Vec::new);
+            let expected = expected.map_err(|e| e.to_string());
+
+            assert_eq!(actual, expected, "command: {command}");
+        }
+    }
+
+    #[test]
+    fn test_handle_command_when_scheduler_is_stopped() {
+        let (controls, receiver) = mpsc::channel();
+        drop(receiver);
+
+        for command in ["status", "pause", "set-interval 1s"] {
+            let actual = handle_command(command, &controls, &Vec::new);
+
+            assert_eq!(
+                actual,
+                Err(String::from("The scheduler is stopped")),
+                "command: {command}"
+            );
+        }
+    }
+}
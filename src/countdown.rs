@@ -0,0 +1,172 @@
+use crate::interval::format_duration;
+use crate::jobs::Jobs;
+use crate::keys::get_status;
+use crate::scheduler::{Control, Status};
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+// How often the status line is updated.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+const CLEAR_LINE: &str = "\r\x1b[K";
+
+// Whether the status line has been started, and the output of the commands must be relayed.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+// Held while writing to the terminal, so that the status line is never mixed with other output.
+static TERMINAL: Mutex<Line> = Mutex::new(Line {
+    shown: false,
+    at_line_start: true,
+    stopped: false,
+});
+
+pub struct Line {
+    // whether the status line is currently drawn on the terminal
+    shown: bool,
+    // whether the last output written ended with a newline, so that the status line can be drawn below it
+    at_line_start: bool,
+    // set on exit, once the status line has been cleared for good
+    stopped: bool,
+}
+
+// Shows a status line on stderr with the time until the next run and the number of running commands,
+// updated in the background until the scheduler stops.
+pub fn start(controls: Sender<Control>, jobs: Arc<Jobs>) {
+    STARTED.store(true, Ordering::SeqCst);
+
+    thread::spawn(move || {
+        while let Some(status) = get_status(&controls) {
+            draw(&text(&status, jobs.pids().len()));
+            thread::sleep(REFRESH_INTERVAL);
+        }
+    });
+}
+
+// Whether the output of the commands must be relayed through relay().
+pub fn is_started() -> bool {
+    STARTED.load(Ordering::SeqCst)
+}
+
+// Clears the status line, for the caller to write to the terminal while holding the returned guard.
+// The status line is drawn again on the next update.
+pub fn hide() -> MutexGuard<'static, Line> {
+    let mut line = TERMINAL.lock().unwrap();
+
+    if line.shown {
+        eprint!("{CLEAR_LINE}");
+        line.shown = false;
+    }
+
+    line
+}
+
+// Clears the status line for good, on exit.
+pub fn stop() {
+    hide().stopped = true;
+}
+
+// Copies the piped stdout & stderr of a child process to our own, clearing the status line first.
+// Returns the number of bytes copied.
+pub fn relay<O: Read + Send, E: Read + Send>(
+    stdout: Option<O>,
+    stderr: Option<E>,
+) -> io::Result<u64> {
+    thread::scope(|scope| {
+        // both pipes must be drained at the same time, or the child may block writing to one
+        let stderr = scope.spawn(|| copy(stderr, io::stderr()));
+        let stdout = copy(stdout, io::stdout())?;
+
+        Ok(stdout + stderr.join().expect("stderr relay thread panicked")?)
+    })
+}
+
+fn copy<R: Read, W: Write>(reader: Option<R>, mut writer: W) -> io::Result<u64> {
+    let Some(mut reader) = reader else {
+        return Ok(0);
+    };
+
+    let mut buffer = [0; 8192];
+    let mut copied = 0;
+
+    loop {
+        let length = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(copied),
+            Ok(length) => length,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        let mut line = hide();
+
+        writer.write_all(&buffer[..length])?;
+        writer.flush()?;
+        copied += length as u64;
+
+        line.at_line_start = buffer[length - 1] == b'\n';
+    }
+}
+
+fn draw(text: &str) {
+    let mut line = TERMINAL.lock().unwrap();
+
+    // not drawn over a partial line of output, that it would erase
+    if line.stopped || !line.at_line_start {
+        return;
+    }
+
+    eprint!("{CLEAR_LINE}{text}");
+    line.shown = true;
+}
+
+// The time until the next tick, in whole seconds rounded up, so that the countdown ends on 1s.
+pub(crate) fn time_left(status: &Status) -> String {
+    format_duration(Duration::from_secs(
+        status.next_tick_in.as_secs_f64().ceil() as u64,
+    ))
+}
+
+fn text(status: &Status, running: usize) -> String {
+    let state = match status.paused {
+        true => String::from("Paused"),
+        false => format!("Next run in {}", time_left(status)),
+    };
+
+    match running {
+        0 => state,
+        running => format!("{state} ({running} running)"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(paused: bool, next_tick_in: Duration) -> Status {
+        Status {
+            paused,
+            interval: Duration::from_secs(60),
+            max_interval: None,
+            next_tick_in,
+        }
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(
+            text(&status(false, Duration::from_millis(11_200)), 0),
+            "Next run in 12s"
+        );
+        assert_eq!(
+            text(&status(false, Duration::from_millis(300)), 3),
+            "Next run in 1s (3 running)"
+        );
+        assert_eq!(
+            text(&status(true, Duration::from_secs(5)), 1),
+            "Paused (1 running)"
+        );
+    }
+}
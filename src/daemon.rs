@@ -0,0 +1,42 @@
+use nix::unistd::{dup2, fork, setsid, ForkResult};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::process;
+
+// Opens the log file for appending, creating it if needed.
+pub fn open_log(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+// Redirects our standard output and error to the log file.
+// The commands inherit them, so that their output is interleaved with ours in order.
+pub fn redirect_output(log: &File) -> io::Result<()> {
+    dup2(log.as_raw_fd(), io::stdout().as_raw_fd())?;
+    dup2(log.as_raw_fd(), io::stderr().as_raw_fd())?;
+
+    Ok(())
+}
+
+// Forks into the background, detached from the terminal, with an empty standard input.
+// The original process exits. Must be called before spawning any thread.
+pub fn daemonize() -> io::Result<()> {
+    // SAFETY: no other thread is running
+    if let ForkResult::Parent { .. } = unsafe { fork() }? {
+        process::exit(0);
+    }
+
+    setsid()?;
+
+    // a second fork, so that the daemon is not a session leader, and can never acquire a terminal
+    // SAFETY: no other thread is running
+    if let ForkResult::Parent { .. } = unsafe { fork() }? {
+        process::exit(0);
+    }
+
+    let null = File::open("/dev/null")?;
+    dup2(null.as_raw_fd(), io::stdin().as_raw_fd())?;
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+const HIGHLIGHT: &str = "\x1b[7m";
+const RESET: &str = "\x1b[0m";
+
+// Highlights the characters of each line that differ from the same position in the previous text,
+// like watch --differences does.
+pub fn highlight_changes(previous: &str, current: &str) -> String {
+    let mut previous_lines = previous.split('\n');
+    let mut result = String::with_capacity(current.len());
+
+    for (index, line) in current.split('\n').enumerate() {
+        if index > 0 {
+            result.push('\n');
+        }
+
+        let previous_line: Vec<char> = previous_lines.next().unwrap_or("").chars().collect();
+        let mut highlighted = false;
+
+        for (position, char) in line.chars().enumerate() {
+            let changed = previous_line.get(position) != Some(&char);
+
+            if changed != highlighted {
+                result.push_str(if changed { HIGHLIGHT } else { RESET });
+                highlighted = changed;
+            }
+
+            result.push(char);
+        }
+
+        if highlighted {
+            result.push_str(RESET);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_changes() {
+        let test_cases = [
+            ("", "", ""),
+            ("abc\n", "abc\n", "abc\n"),
+            ("value 1\n", "value 2\n", "value \x1b[7m2\x1b[0m\n"),
+            ("abcdef", "aXcdYf", "a\x1b[7mX\x1b[0mcd\x1b[7mY\x1b[0mf"),
+            ("abc", "abcdef", "abc\x1b[7mdef\x1b[0m"),
+            ("abcdef", "abc", "abc"),
+            (
+                "one\ntwo\n",
+                "one\nTwo\nthree\n",
+                "one\n\x1b[7mT\x1b[0mwo\n\x1b[7mthree\x1b[0m\n",
+            ),
+            ("é1", "é2", "é\x1b[7m2\x1b[0m"),
+        ];
+
+        for (previous, current, expected) in test_cases {
+            assert_eq!(
+                highlight_changes(previous, current),
+                expected,
+                "previous: {previous:?}, current: {current:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use nix::sys::statvfs::statvfs;
+use std::io;
+use std::path::Path;
+
+// The space available to unprivileged users on the filesystem containing the path, in bytes.
+pub fn free_space(path: &Path) -> io::Result<u64> {
+    let stats = statvfs(path)?;
+
+    #[allow(clippy::useless_conversion)] // the types differ from one platform to another
+    Ok(u64::from(stats.blocks_available()).saturating_mul(u64::from(stats.fragment_size())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_free_space() {
+        assert!(free_space(Path::new("/")).is_ok());
+        assert!(free_space(Path::new("/nonexistent/path")).is_err());
+    }
+}
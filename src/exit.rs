@@ -0,0 +1,44 @@
+use std::process;
+
+/// Why `every` exits, each reason with its own exit code,
+/// so that scripts can tell apart invalid arguments, errors of `every`, and failures of the command.
+///
+/// When terminated by SIGINT or SIGTERM, `every` is terminated by the same signal once stopped,
+/// that shells report as 130 or 143.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExitReason {
+    /// Stopped as requested, or once the condition of `--until-success` or `--until-match` was met: 0.
+    Success,
+    /// An error of `every` itself, such as a PID file that cannot be written: 1.
+    Error,
+    /// Invalid arguments: 2.
+    Usage,
+    /// The last run failed to start: 3.
+    SpawnFailed,
+    /// The last run was killed by `every`, with `--on-busy kill`, `--stall-timeout` or `--max-output-kill`: 4.
+    Killed,
+    /// Stopped before the condition of `--until-match` was met, after a successful run: 5.
+    ConditionNotMet,
+    /// The last run failed with this exit code, or with 128 + the signal that killed it,
+    /// the one that reached `--max-failures`, or the last one of `-n` to complete.
+    Command(i32),
+}
+
+impl ExitReason {
+    pub fn code(self) -> i32 {
+        match self {
+            ExitReason::Success => 0,
+            ExitReason::Error => 1,
+            ExitReason::Usage => 2,
+            ExitReason::SpawnFailed => 3,
+            ExitReason::Killed => 4,
+            ExitReason::ConditionNotMet => 5,
+            ExitReason::Command(code) => code,
+        }
+    }
+
+    /// Exits the process with the code of this reason.
+    pub fn exit(self) -> ! {
+        process::exit(self.code())
+    }
+}
@@ -0,0 +1,141 @@
+use crate::signals;
+use crate::systemd;
+use std::io;
+use std::process::{Command, Stdio};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// How long to wait for the response of the health check URL, in seconds.
+const TIMEOUT_SECS: &str = "5";
+
+// The last health check, run on its own thread so that a slow URL does not hold up the ticks.
+static CHECK: (Mutex<Check>, Condvar) = (
+    Mutex::new(Check {
+        running: false,
+        result: None,
+    }),
+    Condvar::new(),
+);
+
+struct Check {
+    running: bool,
+    // the status code or the error of the last completed check, None before the first one
+    result: Option<Result<u16, String>>,
+}
+
+// Starts a check of the URL unless one is still running, and waits for it for at most the given time.
+// Returns the result of the last completed check: this one if it was fast enough, or else a previous one.
+pub fn check(url: &str, wait: Duration) -> Option<Result<u16, String>> {
+    let (check, done) = &CHECK;
+    let mut guard = check.lock().unwrap();
+
+    if !guard.running {
+        guard.running = true;
+
+        let url = String::from(url);
+
+        thread::spawn(move || {
+            let result = status_code(&url).map_err(|e| e.to_string());
+            let mut guard = check.lock().unwrap();
+
+            guard.running = false;
+            guard.result = Some(result);
+            done.notify_all();
+        });
+    }
+
+    let (guard, _) = done
+        .wait_timeout_while(guard, wait, |check| check.running)
+        .unwrap();
+
+    guard.result.clone()
+}
+
+// GETs the URL with curl, and returns the HTTP status code of the response.
+pub fn status_code(url: &str) -> io::Result<u16> {
+    let mut curl = Command::new("curl");
+
+    curl.args(["--silent", "--show-error", "--max-time", TIMEOUT_SECS])
+        .args(["--output", "/dev/null", "--write-out", "%{http_code}", url])
+        .stdin(Stdio::null());
+
+    signals::unblock_termination(&mut curl);
+    systemd::hide_socket(&mut curl);
+
+    let output = curl.output()?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        let error = error.trim().trim_start_matches("curl: ");
+
+        return Err(io::Error::other(match error {
+            "" => format!("curl exited with {}", output.status),
+            error => String::from(error),
+        }));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .map_err(|_| io::Error::other("curl returned no HTTP status code"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    #[test]
+    fn test_status_code() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/health", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            for status in ["200 OK", "503 Service Unavailable"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let _ = stream.read(&mut [0; 1024]).unwrap();
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+                .unwrap();
+            }
+        });
+
+        assert_eq!(status_code(&url).unwrap(), 200);
+        assert_eq!(status_code(&url).unwrap(), 503);
+        server.join().unwrap();
+
+        // nothing listening anymore
+        assert!(status_code(&url).is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/health", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]).unwrap();
+            thread::sleep(Duration::from_millis(300));
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            )
+            .unwrap();
+        });
+
+        // too slow for the first tick, which has no previous result
+        let start = Instant::now();
+        assert_eq!(check(&url, Duration::from_millis(50)), None);
+        assert!(start.elapsed() < Duration::from_millis(250));
+
+        // the same check, still running
+        assert_eq!(check(&url, Duration::from_secs(2)), Some(Ok(200)));
+        server.join().unwrap();
+    }
+}
@@ -1,27 +1,54 @@
-use std::env;
+use crate::style::{ColorChoice, Style};
 use std::io;
 use std::io::IsTerminal;
 
-const BOLD: &str = "\x1b[1m";
-const DIM: &str = "\x1b[2m";
-const UNDERLINE: &str = "\x1b[4m";
-const RESET: &str = "\x1b[0m";
+// The styles of the man page, in roff.
+const ROFF_STYLE: Style = Style {
+    bold: "\\fB",
+    dim: "\\fI",
+    underline: "",
+    red: "",
+    yellow: "",
+    reset: "\\fR",
+};
 
 pub fn print_help() {
-    let environment = Environment::get_current();
+    let colored = ColorChoice::Auto.enabled(io::stdout().is_terminal());
 
-    let (b, d, u, r) = if environment.supports_color() {
-        (BOLD, DIM, UNDERLINE, RESET)
-    } else {
-        ("", "", "", "")
-    };
+    println!("{}", help(Style::new(colored)));
+}
 
-    println!("Run a command every N seconds.
+// Prints the help as a man page, in roff.
+pub fn print_man() {
+    print!("{}", to_roff(&help(ROFF_STYLE)));
+}
+
+pub fn print_version() {
+    println!("every {}", env!("CARGO_PKG_VERSION"));
+}
+
+fn help(style: Style) -> String {
+    let Style {
+        bold: b,
+        dim: d,
+        underline: u,
+        reset: r,
+        ..
+    } = style;
+
+    format!("Run a command every N seconds.
 
 {u}Usage:{r}
 
-  every -h | -v
-  every <interval> [-c <n>] <command> [args...]
+  every -h | -v | --man
+  every [options] <interval> [options] <command> [args...]
+  every <interval> [options] --jobs-file <path>
+  every <interval> [options] --cmd <command> [--cmd <command>...]
+  every --supervise <min-delay> [options] <command> [args...]
+  every status [<name|pid>]
+  every stop <name|pid>
+  every history <path>
+  every --preview <count> <interval> [options] [<command> [args...]]
 
 {u}Examples:{r}
 
@@ -32,67 +59,347 @@ pub fn print_help() {
   {d}# with up to 10 commands running concurrently:{r}
   every 2.5s -c 10 curl https://...
 
+  {d}# Check a service every minute, only reporting failures:{r}
+  every 1m -q curl --fail https://...
+
 {u}Arguments:{r}
 
   <interval>  The time between each command execution.
-              Examples: {b}1s{r}, {b}0.75s{r}, {b}1m30s{r}, {b}1h2m3s{r}.
-              Available units: {b}s{r} (seconds), {b}m{r} (minutes), {b}h{r} (hours), {b}d{r} (days).
+              Examples: {b}1s{r}, {b}0.75s{r}, {b}500ms{r}, {b}1m30s{r}, {b}1h2m3s{r}.
+              Available units: {b}us{r} (microseconds), {b}ms{r} (milliseconds), {b}s{r} (seconds), {b}m{r} (minutes),
+              {b}h{r} (hours), {b}d{r} (days), {b}w{r} (weeks).
+              ISO 8601 durations are accepted too: {b}PT1H30M{r}, {b}P1DT6H{r}, {b}P2W{r}.
+              A bare number is a number of seconds: {b}5{r}, {b}0.5{r}.
+              A frequency gives the number of ticks per second or per unit: {b}2hz{r}, {b}10/m{r}.
+              A range such as {b}30s-2m{r} draws each interval at random within the range.
   <command>   The command to run, followed by its arguments.
+              In the arguments, {b}{{i}}{r} is replaced with the tick number, {b}{{ts}}{r} with the scheduled time,
+              or {b}{{ts:<format>}}{r} with a strftime format such as {b}%H%M{r}, {b}{{uuid}}{r} with a random UUID,
+              and {b}{{arg}}{r} with the line of {b}--args-from{r}.
+              Use {b}{{{{{r} and {b}}}}}{r} for literal braces.
+              In the commands of {b}--cmd{r} and of a jobs file, the values are shell-quoted.
+  <min-delay> With {b}--supervise{r}, the minimum time between two starts of the command,
+              that is restarted whenever it exits.
 
 {u}Standalone Options:{r}
 
   -h      Show this help message and exit.
   -v      Show version information and exit.
+  --man   Print this help as a man page, in roff, and exit.
+
+{u}Commands:{r}
+
+  status [<name|pid>]
+          Show the state and run statistics of the running instance with this name or PID,
+          or of all the running instances of {b}every{r}.
+  stop <name|pid>
+          Stop the running instance with this name or PID, and wait for its running commands to complete.
+  history <path>
+          Show the last 20 runs recorded in this SQLite database with {b}--history{r}.
+  --preview <count> <interval> [options]
+          Show the local times of this number of next runs, within {b}--between{r} and {b}--days{r}, and exit.
 
 {u}Interval Options:{r}
 
-  -c <n>  Set the concurrency level (default: 1).
-          This option must follow the interval."
-    );
-}
+  These options must precede the command, and may precede the interval too: {b}every -c 2 1s date{r},
+  except {b}--jobs-file{r} and {b}--from-stdin{r}, that must follow it.
+  Use {b}--{r} after the interval to end them, for a command that starts with a dash: {b}every 1s -c 2 -- -command{r}.
+  Their values may be attached to them, as in {b}-c2{r}, {b}-c=2{r} or {b}--concurrency=2{r}, the long form of {b}-c{r}.
 
-pub fn print_version() {
-    println!("every {}", env!("CARGO_PKG_VERSION"));
-}
+  -c <n>         Set the concurrency level (default: 1).
+                 At most 256 commands run at once, the runs beyond wait for one of them to complete.
+  --detach       Start the commands without waiting for them, nor counting them against the concurrency level,
+                 e.g. for commands that daemonize themselves. Their exit is only logged.
+  --batch <n>    Start this number of instances of the command on each tick, within the concurrency level,
+                 with {b}EVERY_BATCH_INDEX{r} set from {b}0{r}.
+  --on-busy <policy>
+                 What to do on a tick when the concurrency limit is reached:
+                 {b}skip{r} (default), {b}queue[:<max>]{r} to run it as soon as a command completes,
+                 with up to {b}max{r} ticks queued (default: 1),
+                 or {b}kill{r} to kill the oldest running command and start a fresh run.
+  --kill-signal <signal>
+                 The signal sent to kill a command, e.g. {b}TERM{r} (default: {b}KILL{r}),
+                 including the commands still running when every is interrupted.
+  --kill-after <interval>
+                 Send {b}KILL{r} to a command still running this long after the kill signal.
+  --stall-timeout <interval>
+                 Kill a command that has not written to its standard output or error for this long,
+                 with the kill signal.
+  --max-output <size>
+                 Truncate the output of each run beyond this size, e.g. {b}10M{r}, stdout and stderr together.
+  --max-output-kill <size>
+                 Kill a command once its output goes beyond this size, with the kill signal.
+  --max-per <runs>/<interval>
+                 Never start more than this number of runs per interval, e.g. {b}100/1m{r}.
+  --lock-file <path>
+                 Take an exclusive lock on this file for each run, and skip the tick if it is already locked,
+                 by another run or by another process.
+  --catch-up[=<max>]
+                 When behind schedule, run up to {b}max{r} missed ticks back-to-back
+                 instead of skipping them (default: all of them).
+  --on-resume <policy>
+                 What to do when the machine resumes from a suspend: {b}warn{r} (default) to report it,
+                 {b}run-once{r} to run immediately, or {b}catch-up{r} to run the ticks missed during the suspend.
+  --interval-between <start>-<end>=<interval>
+                 Use this interval within this daily window of local time, e.g. {b}09:00-18:00=30s{r}.
+                 Can be repeated; the first matching window applies.
+  --interval-default <interval>
+                 The interval outside of the windows of {b}--interval-between{r}, instead of {b}<interval>{r}.
+  --at <time>    Wait until this local time before the first run: {b}03:00{r}, or {b}2024-12-31T23:59:59{r}.
+  --clock <clock>
+                 The clock that the schedule follows: {b}monotonic{r} (default), immune to clock changes,
+                 or {b}wall{r} to follow the system time across suspends and clock changes.
+  --until <time> Stop once this local time is reached: {b}18:30{r}, or {b}2024-12-31T23:59:59{r}.
+  -n <count>     Stop after this number of runs, with the exit code of the last one.
+  --ok-codes <codes>
+                 Count these comma-separated exit codes as successes, e.g. {b}0,24{r} (default: {b}0{r}).
+  --max-failures <n>
+                 Stop and exit with an error once this number of runs in a row have failed.
+  --cooldown <interval> --cooldown-after <n>
+                 Pause for the cooldown once {b}n{r} runs in a row have failed, then again after each failure,
+                 until a run succeeds.
+  --on-spawn-error <policy>
+                 When the command cannot be started, e.g. when it does not exist: {b}exit{r} with an error
+                 (default), {b}retry{r} on the next ticks, or {b}ignore{r} it to retry without counting it as a failure.
+  --until-success
+                 Stop as soon as a run succeeds, and exit with an error if none did.
+  --until-match <regex>
+                 Stop as soon as a line of the output of a run matches this regular expression,
+                 and exit with an error if none did.
+  --until-no-match <regex>
+                 Stop as soon as no line of the output of a run matches this regular expression.
+  --between <start>-<end>
+                 Skip the ticks outside of this daily window of local time, e.g. {b}09:00-17:30{r}.
+  --days <days>  Skip the ticks on other days of the week, e.g. {b}mon-fri{r} or {b}sat,sun{r}.
+  --skip-dates <path>
+                 Skip the ticks on the local dates listed in this file, one {b}YYYY-MM-DD{r} per line,
+                 or on the days of the events of this iCalendar file, e.g. public holidays.
+  --pause-file <path>
+                 Skip the ticks while this file exists.
+  --max-load <n> Skip the ticks while the 1-minute load average of the system is above this value.
+  --only-on-ac   Skip the ticks while the machine is on battery power.
+  --min-battery <percent>
+                 Skip the ticks while on battery power, and the battery is below this level, e.g. {b}20%{r}.
+  --min-free-space <size>@<path>
+                 Skip the ticks while the filesystem containing the path has less free space than this,
+                 e.g. {b}10G@/var/lib/downloads{r}.
+  --require-network[=<host:port>]
+                 Skip the ticks while the machine has no default route, or else while a TCP connection
+                 to this address fails, e.g. {b}--require-network=example.com:443{r}.
+  --if-healthy <url>
+                 GET this URL first on each tick, and skip the tick unless the response is a {b}2xx{r},
+                 using {b}curl{r}.
+  --if <command> Run this shell command first on each tick, and skip the tick unless it exits with {b}0{r},
+                 within half the interval (or a second).
+  --tz <zone>    Interpret the times of {b}--at{r}, {b}--until{r} and {b}--between{r} in this IANA time zone,
+                 e.g. {b}Europe/Paris{r} (default: the system time zone).
+  -q, --quiet    Discard the command's standard output, and only print errors,
+                 without warning about runs that took longer than the interval.
+  -qq            Discard the command's standard output and standard error.
+                 Failures are still reported.
+  --verbose      Report scheduler events on standard error:
+                 ticks fired or skipped, commands started and exited, with their CPU time and max RSS.
+  -vv            Also report the command line and the size of the output of each run.
+  --log-format <format>
+                 Print the messages of every as {b}text{r} (default) or as {b}json{r}, one object per line,
+                 with their time, level, and the run ID, PID and exit code of the run.
+  --color <when> Color the messages of every: {b}auto{r} (default) when standard error is a terminal,
+                 unless {b}NO_COLOR{r} is set or {b}TERM=dumb{r}, {b}always{r}, or {b}never{r}.
+                 {b}always{r} also sets {b}CLICOLOR_FORCE{r} and {b}FORCE_COLOR{r} for the command, and {b}never{r} sets {b}NO_COLOR{r}.
+  --summary      Print a summary of the runs on exit, including their CPU time and max RSS.
+  --stats-every <interval>
+                 Print a line of statistics at this interval: runs, failures, skipped ticks, running commands,
+                 and the median and 95th percentile of the last 1000 run durations.
+  --on-failure-output
+                 Only print the output of the runs that fail.
+  --changes      Only print the output of a run if it differs from the last successful run.
+  --watch        Clear the screen before printing the output of each run,
+                 below a header showing the command, the time of the run and its exit status.
+  --heartbeat-file <path>
+                 Write the Unix timestamp of the last successful run to this file.
+  --history <path>
+                 Record each completed run into this SQLite database, created if needed, using {b}sqlite3{r}.
+                 The end of the output is recorded too if it is captured, e.g. with {b}--on-failure-output{r}.
+  --report <path>
+                 Append a record of each completed run to this {b}.csv{r} or {b}.json{r} file, created if needed:
+                 tick number, command, scheduled, start and end times, duration, exit code or signal,
+                 user and system CPU time, and max RSS in bytes.
+  --on-failure <command>
+                 Run this shell command whenever a run fails, with the variables {b}EVERY_RUN_ID{r},
+                 {b}EVERY_EXIT_CODE{r} or {b}EVERY_EXIT_SIGNAL{r}, {b}EVERY_DURATION{r} and {b}EVERY_OUTPUT_FILE{r}.
+  --on-success <command>
+                 Run this shell command whenever a run succeeds, with the same variables.
+  --on-skip <command>
+                 Run this shell command whenever a tick is skipped,
+                 with the variables {b}EVERY_RUN_ID{r} and {b}EVERY_SKIP_REASON{r}.
+  --notify-url <url>
+                 POST a JSON description of each failed run to this URL, using {b}curl{r}
+                 (requires the {b}webhook{r} feature).
+  --notify-recovery
+                 Also POST to the URL when a run succeeds after a failure.
+  --notify-desktop
+                 Raise a desktop notification when a run fails, or when the {b}--until-match{r} condition is met,
+                 using {b}notify-send{r}, or {b}osascript{r} on macOS.
+  --bell         Ring the terminal bell when a run fails.
+  --flash        Also invert the screen briefly, with {b}--bell{r} and {b}--watch{r} or {b}--tui{r}.
+  --otel-endpoint <url>
+                 Export a span per run to this OpenTelemetry collector, over OTLP/HTTP with {b}curl{r},
+                 passing it to the command in {b}TRACEPARENT{r} (requires the {b}otel{r} feature).
+  --name <name>  Register the instance under this name, for {b}every status{r} and {b}every stop{r}.
+                 Letters, digits, {b}.{r}, {b}_{r} and {b}-{r} are allowed.
+  --single-instance
+                 Refuse to start if an instance with the same name is already running (requires {b}--name{r}).
+  --control-socket <path>
+                 Listen for commands on this Unix socket, one per line:
+                 {b}status{r}, {b}pause{r}, {b}resume{r}, {b}run-now{r}, {b}set-interval <interval>{r}, {b}stop{r}.
+  --log <path>   Append the output of {b}every{r} and of the commands to this file.
+  --daemon       Run in the background, detached from the terminal (requires {b}--log{r}).
+  --systemd      Notify systemd of the startup, of each successful run and of the shutdown,
+                 to run as a {b}Type=notify{r} service with a {b}WatchdogSec{r}.
+  --log-syslog   Record the runs, failures and skipped ticks in the system log,
+                 with structured fields when it is the journal.
+  --pid-file <path>
+                 Write the PID of {b}every{r} to this file, removed on exit.
+  --diff         Highlight the characters that changed since the last successful run.
+  --output-dir <dir>
+                 Write the output of each run to its own file in this directory,
+                 named after the tick number and time: {b}run-<n>-<timestamp>.log{r}.
+  --keep-last <n>
+                 Only keep the files of the last {b}n{r} runs in the output directory.
+  --no-keys      Do not read single-key commands from the terminal: {b}space{r} to run now, {b}p{r} to pause/resume,
+                 {b}+{r} and {b}-{r} to double or halve the interval, {b}s{r} to print the stats and {b}q{r} to quit.
+  --countdown    Show the time until the next run and the number of running commands on a status line,
+                 when standard error is a terminal. It is cleared before the output of the commands.
+  --set-title    Show the command, the exit status of the last run and the time until the next one
+                 in the title of the terminal, when standard error is a terminal.
+  --tui          Show a live dashboard of the runs instead of their output,
+                 with keys to {b}p{r}ause/resume, {b}r{r}un now and {b}q{r}uit (requires the {b}tui{r} feature).
+  -e <key>=<value>
+                 Set this environment variable on the command. Can be repeated.
+  --env-file <path>
+                 Set the environment variables of this file on the command, one {b}KEY=VALUE{r} per line.
+                 Empty lines and lines starting with {b}#{r} are ignored.
+  --clean-env    Start the command with an empty environment, except for the variables set above.
+  --nice <n>     Run the command with this niceness, from {b}-20{r} (highest priority) to {b}19{r} (lowest).
+  --limit-mem <size>
+                 Limit the address space of the command to this size, e.g. {b}512M{r} or {b}2G{r}.
+  --limit-cpu <interval>
+                 Limit the CPU time of the command, e.g. {b}30s{r}: it is then terminated.
+  --cwd <dir>    Run the command in this working directory.
+  --stderr <mode>
+                 Standard error of the command: {b}separate{r} (default), {b}merge{r} into the standard output,
+                 {b}discard{r}, or {b}file:<path>{r} to append it to a file, apart from the messages of {b}every{r}.
+  --pty          Run the command on a pseudo-terminal, so that it keeps its colors and progress bars.
+                 Its standard output and standard error are both written to it, and relayed to our output.
+  --stdin <mode> Standard input of the command: {b}null{r} (default) or {b}inherit{r}.
+  --stdin-data <data>
+                 Feed this string to the standard input of each command.
+  --stdin-file <path>
+                 Feed this file to the standard input of each command.
+  --jobs-file <path>
+                 Instead of a command, run each line of this file through {b}sh -c{r} on every tick,
+                 sharing the concurrency and rate limits. Use {b}-{r} to read the lines from standard input.
+                 Empty lines and lines starting with {b}#{r} are ignored.
+                 A line may start with {b}@<interval>{r} to run on its own schedule, instead of the global interval,
+                 and {b}-c <n>{r} to limit its own concurrency: {b}@5m -c 1 ./sync.sh{r}.
+                 A line starting with {b}@rotate{r} takes turns with the other such lines instead, one per tick.
+                 The file is read again on {b}SIGHUP{r}, where only the intervals of the startup can be used.
+  --from-stdin   The same as {b}--jobs-file -{r}.
+  --args-from <path>
+                 Take the next line of this file on each run, as {b}{{arg}}{r} in the arguments,
+                 skipping empty lines. Use {b}-{r} to read the lines from standard input.
+  --args-exhausted <policy>
+                 What to do once the last line of {b}--args-from{r} is taken: {b}stop{r} (default),
+                 or {b}wrap{r} to start over from the first line.
+  --cmd <command>
+                 Instead of a command, run this shell command in turn with those of the other {b}--cmd{r} options,
+                 one per tick: {b}--cmd 'dig example.com' --cmd 'curl -s https://example.com'{r}.
 
-struct Environment {
-    is_terminal: bool,
-    env_no_color: Option<String>,
-    env_term: Option<String>,
+{u}Exit Codes:{r}
+
+  0       Stopped as requested, or once the {b}--until-success{r} or {b}--until-match{r} condition was met.
+  1       An error of {b}every{r} itself.
+  2       Invalid arguments.
+  3       The last run, that reached {b}--max-failures{r} or ended without meeting the condition, failed to start,
+          or a run failed to start with {b}--on-spawn-error exit{r}.
+  4       The last run was killed by {b}--on-busy kill{r}, {b}--stall-timeout{r} or {b}--max-output-kill{r}.
+  5       Stopped before the {b}--until-match{r} condition was met, after a successful run.
+  <code>  The exit code of the last run, or 128 + the signal that killed it."
+    )
 }
 
-impl Environment {
-    fn get_current() -> Self {
-        Self {
-            is_terminal: io::stdout().is_terminal(),
-            env_no_color: env::var("NO_COLOR").ok(),
-            env_term: env::var("TERM").ok(),
+// Converts the help to a man page: the first line is the description, the lines ending with a colon
+// are the headings of the sections, and the entries of the lists are separated from their description
+// by at least two spaces, or followed by more indented lines. Other lines are kept as they are.
+fn to_roff(help: &str) -> String {
+    let mut lines = help.lines().map(escape_roff).peekable();
+
+    let description = lines.next().unwrap_or_default();
+
+    let mut roff = format!(
+        ".TH EVERY 1 \"\" \"every {}\"\n.SH NAME\nevery \\- {description}\n",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    // whether an empty line separates the next line from the previous paragraph of the section
+    let mut paragraph = false;
+    let mut section_start = true;
+
+    while let Some(line) = lines.next() {
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim();
+
+        if line.is_empty() {
+            paragraph = !section_start;
+            continue;
         }
-    }
 
-    #[cfg(test)]
-    fn mock(is_terminal: bool, env_vars: &Vec<(&str, &str)>) -> Self {
-        let get_env_var = |key| {
-            env_vars
-                .iter()
-                .find(|(k, _)| *k == key)
-                .map(|(_, v)| v.to_string())
-        };
-
-        Self {
-            is_terminal,
-            env_no_color: get_env_var("NO_COLOR"),
-            env_term: get_env_var("TERM"),
+        if indent == 0 {
+            // followed by the reset of the underline of the help
+            let heading = match line
+                .trim_end_matches(ROFF_STYLE.reset)
+                .trim_end_matches(':')
+            {
+                "Usage" => "Synopsis",
+                heading => heading,
+            };
+
+            roff.push_str(&format!(".SH {}\n", heading.to_uppercase()));
+            paragraph = false;
+            section_start = true;
+            continue;
+        }
+
+        section_start = false;
+
+        if paragraph {
+            roff.push_str(".PP\n");
+            paragraph = false;
+        }
+
+        if indent > 2 {
+            roff.push_str(&format!("{line}\n"));
+        } else if let Some((entry, description)) = line.split_once("  ") {
+            roff.push_str(&format!(".TP\n{entry}\n{}\n", description.trim_start()));
+        } else if lines.peek().is_some_and(|next| next.starts_with("   ")) {
+            roff.push_str(&format!(".TP\n{line}\n"));
+        } else {
+            roff.push_str(&format!("{line}\n.br\n"));
         }
     }
 
-    fn supports_color(&self) -> bool {
-        self.is_terminal
-            // any non-empty value for NO_COLOR should disable colors
-            // https://no-color.org/
-            && self.env_no_color.as_ref().map_or(true, |v| v.is_empty())
-            // TERM=dumb should also disable colors
-            && self.env_term.as_ref().map_or(true, |v| v != "dumb")
+    roff.replace(".br\n.PP\n", ".PP\n")
+        .replace(".br\n.SH ", ".SH ")
+}
+
+// Escapes the dashes, that are hyphens in roff, and the lines that would start with a control character.
+fn escape_roff(line: &str) -> String {
+    let line = line.replace('-', "\\-");
+    let content = line.trim_start();
+
+    match content.starts_with(['.', '\'']) {
+        true => format!("{}\\&{content}", &line[..line.len() - content.len()]),
+        false => line,
     }
 }
 
@@ -101,29 +408,49 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_environment_supports_color() {
-        let test_cases = [
-            (false, vec![], false),
-            (true, vec![], true),
-            (true, vec![("NO_COLOR", "")], true),
-            (true, vec![("NO_COLOR", "0")], false),
-            (true, vec![("NO_COLOR", "1")], false),
-            (true, vec![("TERM", "xterm")], true),
-            (true, vec![("TERM", "dumb")], false),
-            (true, vec![("NO_COLOR", ""), ("TERM", "dumb")], false),
-            (true, vec![("NO_COLOR", "1"), ("TERM", "xterm")], false),
-            (true, vec![("NO_COLOR", ""), ("TERM", "xterm")], true),
-        ];
-
-        for (is_terminal, env_vars, expected_supports_color) in test_cases {
-            let environment = Environment::mock(is_terminal, &env_vars);
-
-            assert_eq!(
-                environment.supports_color(),
-                expected_supports_color,
-                "{:?}",
-                (is_terminal, &env_vars, expected_supports_color)
-            );
-        }
+    fn test_to_roff() {
+        let help = concat!(
+            "Run a command.\n",
+            "\n",
+            "Usage:\\fR\n",
+            "\n",
+            "  every <interval> <command>\n",
+            "  every -h\n",
+            "\n",
+            "Options:\n",
+            "\n",
+            "  -c <n>     Set the \\fBconcurrency\\fR,\n",
+            "             up to 100.\n",
+            "  --on-busy <policy>\n",
+            "             What to do.\n",
+            "  .x  Starts with a dot.",
+        );
+
+        assert_eq!(
+            to_roff(help),
+            format!(
+                concat!(
+                    ".TH EVERY 1 \"\" \"every {}\"\n",
+                    ".SH NAME\n",
+                    "every \\- Run a command.\n",
+                    ".SH SYNOPSIS\n",
+                    "every <interval> <command>\n",
+                    ".br\n",
+                    "every \\-h\n",
+                    ".SH OPTIONS\n",
+                    ".TP\n",
+                    "\\-c <n>\n",
+                    "Set the \\fBconcurrency\\fR,\n",
+                    "up to 100.\n",
+                    ".TP\n",
+                    "\\-\\-on\\-busy <policy>\n",
+                    "What to do.\n",
+                    ".TP\n",
+                    "\\&.x\n",
+                    "Starts with a dot.\n",
+                ),
+                env!("CARGO_PKG_VERSION")
+            )
+        );
     }
 }
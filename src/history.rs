@@ -0,0 +1,210 @@
+use crate::exit::ExitReason;
+use crate::hooks;
+use crate::logger;
+use crate::notify;
+use crate::usage::Usage;
+use std::fmt::{Display, Write};
+use std::io::ErrorKind;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Creates the table of the runs, if needed.
+// The times are Unix timestamps, with a millisecond precision.
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    run_id INTEGER NOT NULL,
+    command TEXT NOT NULL,
+    scheduled_at REAL NOT NULL,
+    started_at REAL NOT NULL,
+    ended_at REAL NOT NULL,
+    exit_code INTEGER,
+    exit_signal INTEGER,
+    user_time REAL NOT NULL,
+    system_time REAL NOT NULL,
+    max_rss INTEGER NOT NULL,
+    output_bytes INTEGER,
+    output TEXT
+);";
+
+// The number of runs shown by `every history`.
+const SHOWN_RUNS: u32 = 20;
+
+// A completed run, recorded in the history.
+pub struct Record<'a> {
+    pub run_id: u64,
+    pub command: &'a str,
+    pub scheduled_at: SystemTime,
+    pub started_at: SystemTime,
+    pub duration: Duration,
+    // None if the command was killed by a signal
+    pub exit_code: Option<i32>,
+    pub exit_signal: Option<i32>,
+    pub usage: Usage,
+    // None if the output could not be read
+    pub output_bytes: Option<u64>,
+    // None if the output was not captured
+    pub output: Option<&'a str>,
+}
+
+impl Record<'_> {
+    fn to_sql(&self) -> String {
+        let ended_at = self.started_at + self.duration;
+
+        let mut sql = String::from(
+            "INSERT INTO runs (run_id, command, scheduled_at, started_at, ended_at, \
+             exit_code, exit_signal, user_time, system_time, max_rss, output_bytes, output) VALUES (",
+        );
+
+        write!(
+            sql,
+            "{}, {}, {}, {}, {}, {}, {}, {:.3}, {:.3}, {}, {}, ",
+            self.run_id,
+            sql_string(self.command),
+            timestamp(self.scheduled_at),
+            timestamp(self.started_at),
+            timestamp(ended_at),
+            sql_integer(self.exit_code),
+            sql_integer(self.exit_signal),
+            self.usage.user_time.as_secs_f64(),
+            self.usage.system_time.as_secs_f64(),
+            self.usage.max_rss,
+            sql_integer(self.output_bytes)
+        )
+        .unwrap();
+
+        match self.output {
+            Some(output) => sql.push_str(&sql_string(notify::tail(output))),
+            None => sql.push_str("NULL"),
+        }
+
+        sql.push_str(");");
+
+        sql
+    }
+}
+
+// Inserts the record into the SQLite database at the path, in the background, with sqlite3.
+// The database and its table are created if needed.
+pub fn record(path: &Path, record: &Record) {
+    let mut sqlite = Command::new("sqlite3");
+    sqlite.arg("-bail").arg(path);
+
+    // concurrent runs wait for each other's writes
+    let script = format!(".timeout 5000\n{SCHEMA}\n{}\n", record.to_sql());
+
+    hooks::spawn_command("history", sqlite, Some(script));
+}
+
+/// Prints the most recent runs recorded in the SQLite database at the path, with sqlite3,
+/// that takes over our process, so that this only returns on errors.
+pub(crate) fn show(path: &Path) -> ExitReason {
+    if !path.is_file() {
+        logger::error(&format!("History file not found: {}", path.display()), &[]);
+        return ExitReason::Error;
+    }
+
+    let query = format!(
+        "SELECT run_id AS run, \
+         datetime(started_at, 'unixepoch') AS started_at, \
+         printf('%.3fs', ended_at - started_at) AS duration, \
+         coalesce(exit_code, 'signal ' || exit_signal) AS exit, \
+         printf('%.3fs', user_time + system_time) AS cpu_time, \
+         max_rss, \
+         output_bytes, \
+         command \
+         FROM runs ORDER BY id DESC LIMIT {SHOWN_RUNS};"
+    );
+
+    let e = Command::new("sqlite3")
+        .args(["-readonly", "-header", "-column"])
+        .arg(path)
+        .arg(query)
+        .exec();
+
+    match e.kind() {
+        ErrorKind::NotFound => logger::error("Failed to run sqlite3: it is not installed", &[]),
+        _ => logger::error(&format!("Failed to run sqlite3: {e}"), &[]),
+    }
+
+    ExitReason::Error
+}
+
+// The time as a Unix timestamp, with a millisecond precision.
+pub(crate) fn timestamp(time: SystemTime) -> String {
+    let millis = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+
+    format!("{}.{:03}", millis / 1000, millis % 1000)
+}
+
+fn sql_integer(value: Option<impl Display>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => String::from("NULL"),
+    }
+}
+
+// SQLite string literals have no escape sequences, only doubled quotes.
+// NUL characters would end the statement, and are removed.
+fn sql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''").replace('\0', ""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_sql() {
+        let scheduled_at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_000);
+
+        let record = Record {
+            run_id: 3,
+            command: "echo 'hello'",
+            scheduled_at,
+            started_at: scheduled_at + Duration::from_millis(2),
+            duration: Duration::from_millis(1500),
+            exit_code: Some(1),
+            exit_signal: None,
+            usage: Usage {
+                user_time: Duration::from_millis(1200),
+                system_time: Duration::from_millis(30),
+                max_rss: 4096,
+            },
+            output_bytes: Some(12),
+            output: Some("it's\nfailed\n"),
+        };
+
+        assert_eq!(
+            record.to_sql(),
+            "INSERT INTO runs (run_id, command, scheduled_at, started_at, ended_at, \
+             exit_code, exit_signal, user_time, system_time, max_rss, output_bytes, output) \
+             VALUES (3, 'echo ''hello''', 1700000000.000, 1700000000.002, 1700000001.502, \
+             1, NULL, 1.200, 0.030, 4096, 12, 'it''s\nfailed\n');"
+        );
+
+        let record = Record {
+            exit_code: None,
+            exit_signal: Some(9),
+            // relayed rather than captured
+            output_bytes: Some(20),
+            output: None,
+            ..record
+        };
+
+        assert!(record
+            .to_sql()
+            .ends_with("1700000001.502, NULL, 9, 1.200, 0.030, 4096, 20, NULL);"));
+
+        let record = Record {
+            output_bytes: None,
+            ..record
+        };
+
+        assert!(record.to_sql().ends_with(", 4096, NULL, NULL);"));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::logger;
+use crate::signals;
+use crate::systemd;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+// The maximum number of hooks waiting for the previous ones to exit.
+const MAX_QUEUED: usize = 64;
+
+// The number of hooks queued or running, and not yet exited.
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+// The hooks waiting to run, on the thread started with the first one.
+static QUEUE: OnceLock<SyncSender<Hook>> = OnceLock::new();
+
+struct Hook {
+    name: &'static str,
+    command: Command,
+    input: Option<String>,
+}
+
+// Runs the hook command through `sh -c` in the background, with these environment variables.
+// Hooks do not take a slot of the commands, so that they never delay a run.
+pub fn spawn(name: &'static str, command: &str, env: Vec<(&'static str, String)>) {
+    let mut hook = Command::new("sh");
+    hook.arg("-c").arg(command).envs(env);
+
+    spawn_command(name, hook, None);
+}
+
+// Runs the command in the background, feeding it the input if any.
+// The hooks run one at a time, and are dropped with a warning once too many are waiting.
+pub fn spawn_command(name: &'static str, mut hook: Command, input: Option<String>) {
+    hook.stdin(if input.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    });
+
+    signals::unblock_termination(&mut hook);
+    systemd::hide_socket(&mut hook);
+
+    let queue = QUEUE.get_or_init(|| {
+        let (sender, receiver) = mpsc::sync_channel(MAX_QUEUED);
+
+        thread::spawn(move || {
+            for hook in receiver {
+                run(hook);
+                RUNNING.fetch_sub(1, Ordering::SeqCst);
+            }
+        });
+
+        sender
+    });
+
+    RUNNING.fetch_add(1, Ordering::SeqCst);
+
+    if queue
+        .try_send(Hook {
+            name,
+            command: hook,
+            input,
+        })
+        .is_err()
+    {
+        RUNNING.fetch_sub(1, Ordering::SeqCst);
+
+        logger::warning(
+            &format!("Dropped the {name} hook, {MAX_QUEUED} hooks are already waiting"),
+            &[],
+        );
+    }
+}
+
+fn run(hook: Hook) {
+    let Hook {
+        name,
+        mut command,
+        input,
+    } = hook;
+
+    let status = command.spawn().and_then(|mut child| {
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            // the hook may exit without reading it
+            let _ = stdin.write_all(input.as_bytes());
+        }
+
+        child.wait()
+    });
+
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => logger::error(&format!("The {name} hook exited with {status}"), &[]),
+        Err(e) => logger::error(&format!("Failed to run the {name} hook: {e}"), &[]),
+    }
+}
+
+// Waits for the hooks still running to exit.
+pub fn wait() {
+    while RUNNING.load(Ordering::SeqCst) > 0 {
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process;
+
+    #[test]
+    fn test_spawn_drops_hooks_beyond_the_queue() {
+        let path = std::env::temp_dir().join(format!("every-hooks-{}", process::id()));
+        let _ = fs::remove_file(&path);
+
+        // holds up the queue, as would many skipped ticks firing --on-skip
+        spawn("on-skip", "sleep 0.2", vec![]);
+        thread::sleep(Duration::from_millis(50));
+
+        for _ in 0..MAX_QUEUED * 2 {
+            let mut hook = Command::new("sh");
+            hook.arg("-c").arg(format!("echo >> {}", path.display()));
+            spawn_command("on-skip", hook, None);
+        }
+
+        wait();
+
+        let runs = fs::read_to_string(&path).unwrap().lines().count();
+        assert_eq!(runs, MAX_QUEUED);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
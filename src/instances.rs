@@ -0,0 +1,445 @@
+use crate::control;
+use crate::exit::ExitReason;
+use crate::logger;
+use crate::scheduler::Control;
+use nix::errno::Errno;
+use nix::fcntl::{Flock, FlockArg};
+use nix::libc;
+use nix::sys::signal::kill;
+use nix::unistd::{Pid, Uid};
+use std::env;
+use std::fs::{self, DirBuilder, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc::Sender;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+// The socket of this instance in the registry, once registered.
+static SOCKET: OnceLock<PathBuf> = OnceLock::new();
+
+// The PID file of the name of this instance in the registry, once named.
+static NAME_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+// The lock held on the PID file of the name, with --single-instance, released on exit.
+static NAME_LOCK: OnceLock<Flock<File>> = OnceLock::new();
+
+// Registers this instance, by listening for control commands on a socket named after our PID,
+// in the registry directory: $XDG_RUNTIME_DIR/every, or /tmp/every-<uid> if not set.
+pub fn register<F>(controls: Sender<Control>, status: F) -> io::Result<()>
+where
+    F: Fn() -> Vec<String> + Send + Sync + 'static,
+{
+    let dir = create_registry_dir()?;
+    let socket = dir.join(format!("{}.sock", process::id()));
+
+    // left behind by a killed process that had our PID
+    let _ = fs::remove_file(&socket);
+
+    control::listen(&socket, controls, status)?;
+    let _ = SOCKET.set(socket);
+
+    Ok(())
+}
+
+// Registers the name of this instance, in a PID file named after it.
+// If exclusive, fails if an instance with this name is already running.
+pub fn register_name(name: &str, exclusive: bool) -> Result<(), String> {
+    let error = |e: io::Error| format!("Failed to register name '{name}': {e}");
+    let path = create_registry_dir()
+        .map_err(error)?
+        .join(format!("{name}.pid"));
+
+    if exclusive {
+        let lock = lock_name(name, &path)?;
+        write_pid(&lock).map_err(error)?;
+        let _ = NAME_LOCK.set(lock);
+    } else {
+        // written aside and renamed into place, so that a symlink left there is replaced, not followed
+        let temp = path.with_extension(format!("{}.tmp", process::id()));
+        let _ = fs::remove_file(&temp);
+
+        let result = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)
+            .and_then(|file| write_pid(&file))
+            .and_then(|()| fs::rename(&temp, &path));
+
+        if let Err(e) = result {
+            let _ = fs::remove_file(&temp);
+            return Err(error(e));
+        }
+    }
+
+    let _ = NAME_FILE.set(path);
+
+    Ok(())
+}
+
+// Locks the PID file of the name, until this instance exits, or fails if another instance holds it.
+fn lock_name(name: &str, path: &Path) -> Result<Flock<File>, String> {
+    let error = |e: io::Error| format!("Failed to register name '{name}': {e}");
+
+    loop {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .custom_flags(libc::O_NOFOLLOW)
+            .open(path)
+            .map_err(error)?;
+
+        let lock = match Flock::lock(file, FlockArg::LockExclusiveNonblock) {
+            Ok(lock) => lock,
+            Err((_, Errno::EWOULDBLOCK)) => {
+                return Err(match read_name(name) {
+                    Some(pid) => {
+                        format!("An instance named '{name}' is already running, with PID {pid}!")
+                    }
+                    None => format!("An instance named '{name}' is already running!"),
+                })
+            }
+            Err((_, e)) => return Err(error(e.into())),
+        };
+
+        // unless the instance that held the lock removed the file as it exited, then try again
+        let locked = lock.metadata().map_err(error)?;
+
+        if fs::symlink_metadata(path)
+            .is_ok_and(|current| (current.dev(), current.ino()) == (locked.dev(), locked.ino()))
+        {
+            return Ok(lock);
+        }
+    }
+}
+
+fn write_pid(mut file: &File) -> io::Result<()> {
+    file.set_len(0)?;
+    writeln!(file, "{}", process::id())
+}
+
+// Fails if an instance with this name is already running.
+pub fn check_name(name: &str) -> Result<(), String> {
+    match read_name(name) {
+        Some(pid) if is_running(pid) => Err(format!(
+            "An instance named '{name}' is already running, with PID {pid}!"
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Removes this instance from the registry.
+pub fn unregister() {
+    if let Some(socket) = SOCKET.get() {
+        let _ = fs::remove_file(socket);
+    }
+
+    if let Some(path) = NAME_FILE.get() {
+        // unless the name has been taken over by another instance
+        if fs::read_to_string(path).is_ok_and(|pid| pid.trim() == process::id().to_string()) {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Checks that the name of an instance is made of letters, digits, `.`, `_` and `-`,
+/// does not look like an option, and is not a number, that would be taken for a PID.
+pub(crate) fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && !name.starts_with(['.', '-'])
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        && name.parse::<u32>().is_err();
+
+    match valid {
+        true => Ok(()),
+        false => Err(format!(
+            "Invalid name: '{name}', expected letters, digits, '.', '_' or '-'"
+        )),
+    }
+}
+
+/// Prints the status of the instance with the given name or PID, or of all the running instances.
+pub(crate) fn status(instance: Option<String>) -> ExitReason {
+    let result = match instance {
+        Some(instance) => check_registry()
+            .and_then(|()| find(&instance))
+            .and_then(|pid| query(pid, "status")),
+        None => check_registry().and_then(|()| status_all()),
+    };
+
+    finish(result)
+}
+
+/// Stops the instance with the given name or PID, once its running commands have completed.
+pub(crate) fn stop(instance: String) -> ExitReason {
+    let result = check_registry()
+        .and_then(|()| find(&instance))
+        .and_then(|pid| {
+            query(pid, "stop")?;
+            wait_for_exit(pid);
+
+            Ok(vec![])
+        });
+
+    finish(result)
+}
+
+// Prints the lines of the result, or its error, and returns the reason to exit with.
+fn finish(result: Result<Vec<String>, String>) -> ExitReason {
+    match result {
+        Ok(lines) => {
+            for line in lines {
+                println!("{line}");
+            }
+
+            ExitReason::Success
+        }
+        Err(e) => {
+            logger::error(&e, &[]);
+            ExitReason::Error
+        }
+    }
+}
+
+fn status_all() -> Result<Vec<String>, String> {
+    let mut lines = vec![];
+
+    let names = names();
+
+    for pid in list() {
+        // the instance may have exited since listed
+        let Ok(status) = query(pid, "status") else {
+            continue;
+        };
+
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+
+        match names.iter().find(|(_, name_pid)| *name_pid == pid) {
+            Some((name, _)) => lines.push(format!("{name} (PID {pid}):")),
+            None => lines.push(format!("PID {pid}:")),
+        }
+
+        lines.extend(status.iter().map(|line| format!("  {line}")));
+    }
+
+    if lines.is_empty() {
+        return Err(String::from("No running instances!"));
+    }
+
+    Ok(lines)
+}
+
+// Returns the PID of the instance, given as a name or a PID.
+fn find(instance: &str) -> Result<u32, String> {
+    if let Ok(pid) = instance.parse::<u32>() {
+        if !socket_path(pid).exists() {
+            return Err(format!("No running instance with PID {pid}!"));
+        }
+
+        return Ok(pid);
+    }
+
+    validate_name(instance)?;
+
+    match read_name(instance) {
+        Some(pid) if socket_path(pid).exists() => Ok(pid),
+        _ => Err(format!("No running instance named '{instance}'!")),
+    }
+}
+
+// Returns the PIDs of the registered instances, in ascending order.
+fn list() -> Vec<u32> {
+    let Ok(entries) = fs::read_dir(registry_dir()) else {
+        return vec![];
+    };
+
+    let mut pids: Vec<u32> = entries
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name();
+            name.to_str()?.strip_suffix(".sock")?.parse().ok()
+        })
+        .collect();
+
+    pids.sort_unstable();
+
+    pids
+}
+
+// Returns the names of the registered instances, with their PIDs.
+fn names() -> Vec<(String, u32)> {
+    let Ok(entries) = fs::read_dir(registry_dir()) else {
+        return vec![];
+    };
+
+    entries
+        .filter_map(|entry| {
+            let file_name = entry.ok()?.file_name();
+            let name = file_name.to_str()?.strip_suffix(".pid")?;
+
+            Some((String::from(name), read_name(name)?))
+        })
+        .collect()
+}
+
+// Returns the PID of the instance with this name, if any.
+fn read_name(name: &str) -> Option<u32> {
+    fs::read_to_string(name_path(name))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn is_running(pid: u32) -> bool {
+    UnixStream::connect(socket_path(pid)).is_ok()
+}
+
+// Sends the control command to the instance, and returns the lines of the response.
+fn query(pid: u32, command: &str) -> Result<Vec<String>, String> {
+    let socket = socket_path(pid);
+
+    let stream = match UnixStream::connect(&socket) {
+        Ok(stream) => stream,
+        Err(e) if e.kind() == ErrorKind::ConnectionRefused => {
+            // the instance was killed, and could not unregister
+            let _ = fs::remove_file(&socket);
+            return Err(format!("No running instance with PID {pid}!"));
+        }
+        Err(e) => return Err(format!("Failed to connect to PID {pid}: {e}")),
+    };
+
+    send(stream, command).map_err(|e| format!("Failed to query PID {pid}: {e}"))?
+}
+
+// Waits until the instance has unregistered, or has exited without unregistering.
+fn wait_for_exit(pid: u32) {
+    let socket = socket_path(pid);
+
+    while socket.exists() && kill(Pid::from_raw(pid as i32), None).is_ok() {
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn send(mut stream: UnixStream, command: &str) -> io::Result<Result<Vec<String>, String>> {
+    writeln!(stream, "{command}")?;
+
+    let mut lines = vec![];
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+
+        if line == "ok" {
+            return Ok(Ok(lines));
+        }
+
+        if let Some(error) = line.strip_prefix("error: ") {
+            return Ok(Err(String::from(error)));
+        }
+
+        lines.push(line);
+    }
+
+    Err(io::Error::from(ErrorKind::UnexpectedEof))
+}
+
+fn socket_path(pid: u32) -> PathBuf {
+    registry_dir().join(format!("{pid}.sock"))
+}
+
+fn name_path(name: &str) -> PathBuf {
+    registry_dir().join(format!("{name}.pid"))
+}
+
+// Creates the registry directory if needed, and checks that it is safe to use.
+fn create_registry_dir() -> io::Result<PathBuf> {
+    let dir = registry_dir();
+    DirBuilder::new().recursive(true).mode(0o700).create(&dir)?;
+    check_registry_dir(&dir)?;
+
+    Ok(dir)
+}
+
+// Fails if the registry directory exists but is not safe to use.
+fn check_registry() -> Result<(), String> {
+    match check_registry_dir(&registry_dir()) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(format!("Unsafe registry directory: {e}")),
+        _ => Ok(()),
+    }
+}
+
+// Fails unless the directory is a real directory, owned by us, that no other user has access to:
+// another user may have created /tmp/every-<uid> first, or replaced it with a symlink.
+fn check_registry_dir(dir: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(dir)?;
+
+    let safe = metadata.is_dir()
+        && metadata.uid() == Uid::current().as_raw()
+        && metadata.mode() & 0o777 == 0o700;
+
+    match safe {
+        true => Ok(()),
+        false => Err(io::Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "'{}' must be a directory owned by us, with mode 0700",
+                dir.display()
+            ),
+        )),
+    }
+}
+
+fn registry_dir() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("every"),
+        _ => env::temp_dir().join(format!("every-{}", Uid::current())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        assert_eq!(
+            find("4294967295"),
+            Err(String::from("No running instance with PID 4294967295!"))
+        );
+        assert_eq!(
+            find("no-such-instance"),
+            Err(String::from(
+                "No running instance named 'no-such-instance'!"
+            ))
+        );
+        assert_eq!(
+            find("../web"),
+            Err(String::from(
+                "Invalid name: '../web', expected letters, digits, '.', '_' or '-'"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_validate_name() {
+        for name in ["web", "backup.daily", "sync_2", "2fa"] {
+            assert_eq!(validate_name(name), Ok(()));
+        }
+
+        for name in ["", "123", ".hidden", "-x", "a/b", "a b", "é"] {
+            assert_eq!(
+                validate_name(name),
+                Err(format!(
+                    "Invalid name: '{name}', expected letters, digits, '.', '_' or '-'"
+                ))
+            );
+        }
+    }
+}
@@ -0,0 +1,792 @@
+use crate::suggest::suggest;
+use regex::{Match, Regex};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The time between two ticks, with microsecond precision.
+///
+/// Parsed from the command line format: `1s`, `0.75s`, `500ms`, `250us`, `1m30s`, `1h2m3s`, `1d`, `2w`, etc.,
+/// or from an ISO 8601 duration: `PT1H30M`, `P1DT6H`, `P2W`, `PT0.5S`, etc.
+/// A bare number is a number of seconds: `5`, `0.5`.
+/// A frequency is converted to the interval between two ticks: `2hz`, or `10/m` for ten ticks per minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Interval {
+    duration: Duration,
+}
+
+// The largest interval, kept from the times of millisecond precision.
+const MAX_MICROS: u128 = u64::MAX as u128 * 1_000;
+
+impl Interval {
+    /// Returns None if the interval is zero.
+    pub fn from_millis(ms: u64) -> Option<Interval> {
+        match ms {
+            0 => None,
+            ms => Some(Interval {
+                duration: Duration::from_millis(ms),
+            }),
+        }
+    }
+
+    /// Returns None if the interval is zero.
+    pub fn from_micros(us: u64) -> Option<Interval> {
+        match us {
+            0 => None,
+            us => Some(Interval {
+                duration: Duration::from_micros(us),
+            }),
+        }
+    }
+
+    /// The interval in milliseconds, truncated.
+    pub fn as_millis(&self) -> u64 {
+        // the interval never exceeds u64::MAX ms
+        self.duration.as_millis() as u64
+    }
+
+    pub fn as_duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+impl FromStr for Interval {
+    type Err = String;
+
+    fn from_str(interval: &str) -> Result<Self, Self::Err> {
+        parse_interval(interval).map(|duration| Interval { duration })
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&format_interval(self.duration))
+    }
+}
+
+impl From<Interval> for Duration {
+    fn from(interval: Interval) -> Self {
+        interval.as_duration()
+    }
+}
+
+fn parse_interval(interval: &str) -> Result<Duration, String> {
+    if interval.is_empty() {
+        return Err(String::from("Interval cannot be empty"));
+    }
+
+    if let Some(frequency) = parse_frequency(interval) {
+        return frequency;
+    }
+
+    let re = Regex::new(concat!(
+        "^",
+        "(?:(?P<w>[0-9]+)w)?",
+        "(?:(?P<d>[0-9]+)d)?",
+        "(?:(?P<h>[0-9]+)h)?",
+        "(?:(?P<m>[0-9]+)m)?",
+        "(?:(?P<s>[0-9]+)(?:\\.(?P<f>[0-9]+))?s)?",
+        "(?:(?P<ms>[0-9]+)ms)?",
+        "(?:(?P<us>[0-9]+)(?:us|µs))?",
+        "$"
+    ))
+    .unwrap();
+
+    // ISO 8601 duration, without years and months, that have no fixed length
+    let iso_re = Regex::new(concat!(
+        "^P",
+        "(?:(?P<w>[0-9]+)W)?",
+        "(?:(?P<d>[0-9]+)D)?",
+        "(?:T",
+        "(?:(?P<h>[0-9]+)H)?",
+        "(?:(?P<m>[0-9]+)M)?",
+        "(?:(?P<s>[0-9]+)(?:[.,](?P<f>[0-9]+))?S)?",
+        ")?",
+        "$"
+    ))
+    .unwrap();
+
+    // a bare number is a number of seconds, as with watch -n
+    let seconds_re = Regex::new("^(?P<s>[0-9]+)(?:\\.(?P<f>[0-9]+))?$").unwrap();
+
+    // P and T must be followed by at least one component
+    let caps = re
+        .captures(interval)
+        .or_else(|| {
+            iso_re
+                .captures(interval)
+                .filter(|_| !interval.ends_with(['P', 'T']))
+        })
+        .or_else(|| seconds_re.captures(interval));
+
+    if let Some(caps) = caps {
+        let w = caps.name("w");
+        let d = caps.name("d");
+        let h = caps.name("h");
+        let m = caps.name("m");
+        let s = caps.name("s");
+        let f = caps.name("f");
+        let ms = caps.name("ms");
+        let us = caps.name("us");
+
+        let w = convert_match_to_u64(w);
+        let d = convert_match_to_u64(d);
+
+        // weeks are counted as days, None if too large
+        let d = d.zip(w).and_then(|(d, w)| w.checked_mul(7)?.checked_add(d));
+        let h = convert_match_to_u64(h);
+        let m = convert_match_to_u64(m);
+        let s = convert_match_to_u64(s);
+        let ms = convert_match_to_u64(ms);
+        let us = convert_match_to_u64(us);
+
+        let f = convert_fraction_match_to_us_u64(f).ok_or_else(|| {
+            format!("Invalid interval '{interval}': maximum precision is microsecond")
+        })?;
+
+        let total_us = calculate_total_us(d, h, m, s, ms, us, f);
+
+        match total_us {
+            Some(0) => Err(format!(
+                "Invalid interval '{interval}': interval cannot be zero"
+            )),
+            Some(us) if us <= MAX_MICROS => Ok(Duration::new(
+                (us / 1_000_000) as u64,
+                (us % 1_000_000 * 1_000) as u32,
+            )),
+            _ => Err(format!(
+                "Invalid interval '{interval}': interval is too large"
+            )),
+        }
+    } else {
+        match suggest_interval(interval) {
+            Some(suggestion) => Err(format!(
+                "Invalid interval '{interval}': unrecognized format, did you mean '{suggestion}'?"
+            )),
+            None => Err(format!(
+                "Invalid interval '{interval}': unrecognized format"
+            )),
+        }
+    }
+}
+
+// The units of the intervals, and their spelled out forms, for the suggestions.
+const UNITS: [(&str, &str); 21] = [
+    ("w", "w"),
+    ("week", "w"),
+    ("d", "d"),
+    ("day", "d"),
+    ("h", "h"),
+    ("hr", "h"),
+    ("hour", "h"),
+    ("m", "m"),
+    ("min", "m"),
+    ("minute", "m"),
+    ("s", "s"),
+    ("sec", "s"),
+    ("second", "s"),
+    ("ms", "ms"),
+    ("msec", "ms"),
+    ("millisecond", "ms"),
+    ("us", "us"),
+    ("usec", "us"),
+    ("microsecond", "us"),
+    ("hz", "hz"),
+    ("hertz", "hz"),
+];
+
+// Suggests a valid interval for a number followed by a misspelled unit, such as 2sec or 10 mins.
+fn suggest_interval(interval: &str) -> Option<String> {
+    let re = Regex::new("^(?P<n>[0-9]+(?:\\.[0-9]+)?) *(?P<unit>[a-zA-Zµ]+)$").unwrap();
+    let caps = re.captures(interval)?;
+
+    let unit = suggest(&caps["unit"], UNITS)?;
+    let suggestion = format!("{}{unit}", &caps["n"]);
+
+    // e.g. not 1.5m for 1.5min
+    match suggestion != interval && parse_interval(&suggestion).is_ok() {
+        true => Some(suggestion),
+        false => None,
+    }
+}
+
+// A frequency in hertz, such as 2hz or 0.5Hz, or a number of ticks per unit, such as 10/m,
+// converted to the time between two ticks, rounded to the microsecond.
+// Returns None if the interval is not a frequency.
+fn parse_frequency(interval: &str) -> Option<Result<Duration, String>> {
+    let re = Regex::new(concat!(
+        "^(?:",
+        "(?P<s>[0-9]+)(?:\\.(?P<f>[0-9]+))?(?i:hz)",
+        "|",
+        "(?P<count>[0-9]+)/(?P<unit>[smhdw])",
+        ")$"
+    ))
+    .unwrap();
+
+    let caps = re.captures(interval)?;
+
+    // the number of ticks in millionths, and the period that they are counted over in microseconds
+    let (ticks, period_us) = match caps.name("count") {
+        Some(count) => {
+            let period_us: u128 = match &caps["unit"] {
+                "s" => 1_000_000,
+                "m" => 60_000_000,
+                "h" => 3_600_000_000,
+                "d" => 86_400_000_000,
+                _ => 604_800_000_000,
+            };
+
+            let count = convert_match_to_u64(Some(count));
+
+            (count.map(|count| u128::from(count) * 1_000_000), period_us)
+        }
+        None => {
+            let Some(f) = convert_fraction_match_to_us_u64(caps.name("f")) else {
+                return Some(Err(format!(
+                    "Invalid interval '{interval}': maximum precision is microhertz"
+                )));
+            };
+
+            let s = convert_match_to_u64(caps.name("s"));
+
+            (
+                s.map(|s| u128::from(s) * 1_000_000 + u128::from(f)),
+                1_000_000,
+            )
+        }
+    };
+
+    // rounded to the nearest microsecond
+    let total_us = ticks.map(|ticks| (period_us * 1_000_000 + ticks / 2) / ticks.max(1));
+
+    Some(match (ticks, total_us) {
+        (Some(0), _) => Err(format!(
+            "Invalid interval '{interval}': frequency cannot be zero"
+        )),
+        (_, Some(us)) if us > 0 => Ok(Duration::new(
+            (us / 1_000_000) as u64,
+            (us % 1_000_000 * 1_000) as u32,
+        )),
+        _ => Err(format!(
+            "Invalid interval '{interval}': frequency is too high"
+        )),
+    })
+}
+
+// Optional arguments are None iff the number does not fit in u64.
+fn calculate_total_us(
+    d: Option<u64>,
+    h: Option<u64>,
+    m: Option<u64>,
+    s: Option<u64>,
+    ms: Option<u64>,
+    us: Option<u64>,
+    fraction_us: u64,
+) -> Option<u128> {
+    // cannot overflow u128
+    Some(
+        u128::from(d?) * 86_400_000_000
+            + u128::from(h?) * 3_600_000_000
+            + u128::from(m?) * 60_000_000
+            + u128::from(s?) * 1_000_000
+            + u128::from(ms?) * 1_000
+            + u128::from(us?)
+            + u128::from(fraction_us),
+    )
+}
+
+// Used with [0-9]+ matches.
+// Returns 0 if there is no match, and None if the number does not fit in u64.
+fn convert_match_to_u64(m: Option<Match>) -> Option<u64> {
+    match m {
+        Some(m) => m.as_str().parse().ok(),
+        None => Some(0),
+    }
+}
+
+// Used with [0-9]+ matches.
+// Returns 0 if there is no match, and None if the fraction is > 6 digits.
+// Examples:
+//  - "1" => 100000,
+//  - "12" => 120000
+//  - "123456" => 123456
+//  - "1234567" => None
+fn convert_fraction_match_to_us_u64(f: Option<Match>) -> Option<u64> {
+    match f {
+        Some(f) => {
+            let f = f.as_str();
+
+            match f.len() {
+                len if len <= 6 => format!("{f:0<6}").parse().ok(),
+                _ => None,
+            }
+        }
+        None => Some(0),
+    }
+}
+
+// Formats a duration in the interval format, with millisecond precision.
+pub(crate) fn format_duration(duration: Duration) -> String {
+    format_micros(duration.as_millis() * 1_000)
+}
+
+// Formats an interval in the interval format, with microsecond precision.
+pub(crate) fn format_interval(duration: Duration) -> String {
+    format_micros(duration.as_micros())
+}
+
+// Formats an interval, or an interval range if a maximum is given.
+pub(crate) fn format_interval_range(interval: Duration, max_interval: Option<Duration>) -> String {
+    match max_interval {
+        Some(max_interval) => format!(
+            "{}-{}",
+            format_interval(interval),
+            format_interval(max_interval)
+        ),
+        None => format_interval(interval),
+    }
+}
+
+fn format_micros(us: u128) -> String {
+    let d = us / 86_400_000_000;
+    let h = us / 3_600_000_000 % 24;
+    let m = us / 60_000_000 % 60;
+    let s = us / 1_000_000 % 60;
+    let us = us % 1_000_000;
+
+    let mut result = String::new();
+
+    if d != 0 {
+        result.push_str(&format!("{d}d"));
+    }
+
+    if h != 0 {
+        result.push_str(&format!("{h}h"));
+    }
+
+    if m != 0 {
+        result.push_str(&format!("{m}m"));
+    }
+
+    if us != 0 {
+        let fraction = format!("{us:06}");
+        result.push_str(&format!("{s}.{}s", fraction.trim_end_matches('0')));
+    } else if s != 0 || result.is_empty() {
+        result.push_str(&format!("{s}s"));
+    }
+
+    result
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_interval() {
+        fn ms(ms: u64) -> Result<Duration, &'static str> {
+            Ok(Duration::from_millis(ms))
+        }
+
+        fn us(us: u64) -> Result<Duration, &'static str> {
+            Ok(Duration::from_micros(us))
+        }
+
+        let tests_cases = [
+            // frequencies
+            ("2hz", ms(500)),
+            ("2Hz", ms(500)),
+            ("0.5HZ", ms(2000)),
+            ("3hz", us(333333)),
+            ("1000000hz", us(1)),
+            ("1.000001hz", us(999999)),
+            ("10/s", ms(100)),
+            ("10/m", ms(6000)),
+            ("4/h", ms(900_000)),
+            ("3/d", ms(28_800_000)),
+            ("7/w", ms(86_400_000)),
+            ("0hz", Err("Invalid interval '0hz': frequency cannot be zero")),
+            ("0/m", Err("Invalid interval '0/m': frequency cannot be zero")),
+            ("2000001hz", Err("Invalid interval '2000001hz': frequency is too high")),
+            ("18446744073709551616/s", Err("Invalid interval '18446744073709551616/s': frequency is too high")),
+            ("0.0000001hz", Err("Invalid interval '0.0000001hz': maximum precision is microhertz")),
+            ("hz", Err("Invalid interval 'hz': unrecognized format")),
+            ("10/", Err("Invalid interval '10/': unrecognized format")),
+            ("10/1m", Err("Invalid interval '10/1m': unrecognized format")),
+            ("1.5/m", Err("Invalid interval '1.5/m': unrecognized format")),
+            // empty
+            ("", Err("Interval cannot be empty")),
+            // unparsable
+            (" ", Err("Invalid interval ' ': unrecognized format")),
+            (".", Err("Invalid interval '.': unrecognized format")),
+            ("1.", Err("Invalid interval '1.': unrecognized format")),
+            (".5", Err("Invalid interval '.5': unrecognized format")),
+            ("1,5", Err("Invalid interval '1,5': unrecognized format")),
+            ("d", Err("Invalid interval 'd': unrecognized format")),
+            ("h", Err("Invalid interval 'h': unrecognized format")),
+            ("m", Err("Invalid interval 'm': unrecognized format")),
+            ("s", Err("Invalid interval 's': unrecognized format")),
+            (".s", Err("Invalid interval '.s': unrecognized format")),
+            (".1s", Err("Invalid interval '.1s': unrecognized format")),
+            ("1m.0s", Err("Invalid interval '1m.0s': unrecognized format")),
+            ("0.1d", Err("Invalid interval '0.1d': unrecognized format")),
+            ("0.1h", Err("Invalid interval '0.1h': unrecognized format")),
+            ("0.1m", Err("Invalid interval '0.1m': unrecognized format")),
+            ("-1s", Err("Invalid interval '-1s': unrecognized format")),
+            (" 1s", Err("Invalid interval ' 1s': unrecognized format")),
+            ("1s-", Err("Invalid interval '1s-': unrecognized format")),
+            ("1s ", Err("Invalid interval '1s ': unrecognized format")),
+            ("1s 1s", Err("Invalid interval '1s 1s': unrecognized format")),
+            ("1.1.1s", Err("Invalid interval '1.1.1s': unrecognized format")),
+            // suggestions
+            ("2sec", Err("Invalid interval '2sec': unrecognized format, did you mean '2s'?")),
+            ("2 s", Err("Invalid interval '2 s': unrecognized format, did you mean '2s'?")),
+            ("1.5Secs", Err("Invalid interval '1.5Secs': unrecognized format, did you mean '1.5s'?")),
+            ("10mins", Err("Invalid interval '10mins': unrecognized format, did you mean '10m'?")),
+            ("3hours", Err("Invalid interval '3hours': unrecognized format, did you mean '3h'?")),
+            ("500msecs", Err("Invalid interval '500msecs': unrecognized format, did you mean '500ms'?")),
+            ("2weeks", Err("Invalid interval '2weeks': unrecognized format, did you mean '2w'?")),
+            ("5H", Err("Invalid interval '5H': unrecognized format, did you mean '5h'?")),
+            ("1.5min", Err("Invalid interval '1.5min': unrecognized format")),
+            ("2x", Err("Invalid interval '2x': unrecognized format")),
+            ("2parsecs", Err("Invalid interval '2parsecs': unrecognized format")),
+            // seconds
+            ("0s", Err("Invalid interval '0s': interval cannot be zero")),
+            ("0.0s", Err("Invalid interval '0.0s': interval cannot be zero")),
+            ("0.00s", Err("Invalid interval '0.00s': interval cannot be zero")),
+            ("0.000s", Err("Invalid interval '0.000s': interval cannot be zero")),
+            ("0.0000s", Err("Invalid interval '0.0000s': interval cannot be zero")),
+            ("1s", ms(1_000)),
+            ("23s", ms(23_000)),
+            ("0.1s", ms(100)),
+            ("0.01s", ms(10)),
+            ("0.12s", ms(120)),
+            ("0.001s", ms(1)),
+            ("0.012s", ms(12)),
+            ("0.123s", ms(123)),
+            ("0.1234s", us(123_400)),
+            ("1234.5s", ms(1_234_500)),
+            ("1234.56s", ms(1_234_560)),
+            ("1234.567s", ms(1_234_567)),
+            ("1234.5678s", us(1_234_567_800)),
+            // bare numbers, as seconds
+            ("0", Err("Invalid interval '0': interval cannot be zero")),
+            ("0.0", Err("Invalid interval '0.0': interval cannot be zero")),
+            ("1", ms(1_000)),
+            ("5", ms(5_000)),
+            ("0.5", ms(500)),
+            ("1.25", ms(1_250)),
+            ("0.0005", us(500)),
+            ("0.0000001", Err("Invalid interval '0.0000001': maximum precision is microsecond")),
+            ("18446744073709551", ms(18446744073709551000)),
+            ("18446744073709552", Err("Invalid interval '18446744073709552': interval is too large")),
+            // minutes
+            ("0m", Err("Invalid interval '0m': interval cannot be zero")),
+            ("1m", ms(60 * 1000)),
+            ("123m", ms(123 * 60 * 1000)),
+            // minutes + seconds
+            ("0m0s", Err("Invalid interval '0m0s': interval cannot be zero")),
+            ("0m0.0s", Err("Invalid interval '0m0.0s': interval cannot be zero")),
+            ("0m0.00s", Err("Invalid interval '0m0.00s': interval cannot be zero")),
+            ("0m0.000s", Err("Invalid interval '0m0.000s': interval cannot be zero")),
+            ("0m0.0000s", Err("Invalid interval '0m0.0000s': interval cannot be zero")),
+            ("1m0s", ms(60 * 1000)),
+            ("1m0.0s", ms(60 * 1000)),
+            ("1m0.01s", ms(60 * 1000 + 10)),
+            ("1m0.001s", ms(60 * 1000 + 1)),
+            ("1m0.0010s", ms(60 * 1000 + 1)),
+            ("1m0.12s", ms(60 * 1000 + 120)),
+            ("1m0.123s", ms(60 * 1000 + 123)),
+            ("1m0.1234s", us(60_123_400)),
+            ("2m3.4s", ms((2 * 60 + 3) * 1_000 + 400)),
+            ("34m56.78s", ms((34 * 60 + 56) * 1_000 + 780)),
+            ("456m7.890s", ms((456 * 60 + 7) * 1_000 + 890)),
+            ("456m7.891s", ms((456 * 60 + 7) * 1_000 + 891)),
+            // hours
+            ("0h", Err("Invalid interval '0h': interval cannot be zero")),
+            ("1h", ms(3600 * 1000)),
+            ("123h", ms(123 * 3600 * 1000)),
+            // hours + seconds
+            ("0h0s", Err("Invalid interval '0h0s': interval cannot be zero")),
+            ("0h1s", ms(1000)),
+            ("1h0s", ms(3600 * 1000)),
+            ("1h1s", ms((3600 + 1) * 1000)),
+            ("1h2s", ms((3600 + 2) * 1000)),
+            ("1h2.3s", ms((3600 + 2) * 1000 + 300)),
+            ("1h2.34s", ms((3600 + 2) * 1000 + 340)),
+            ("1h2.345s", ms((3600 + 2) * 1000 + 345)),
+            ("1h2.3456s", us(3_602_345_600)),
+            // hours + minutes
+            ("0h0m", Err("Invalid interval '0h0m': interval cannot be zero")),
+            ("0h1m", ms(60 * 1000)),
+            ("1h0m", ms(3600 * 1000)),
+            ("1h1m", ms((3600 + 60) * 1000)),
+            ("1h2m", ms((3600 + 2 * 60) * 1000)),
+            // hours + minutes + seconds
+            ("1h2m3s", ms((3600 + 2 * 60 + 3) * 1000)),
+            ("1h2m3.4s", ms((3600 + 2 * 60 + 3) * 1000 + 400)),
+            ("1h2m3.45s", ms((3600 + 2 * 60 + 3) * 1000 + 450)),
+            ("1h2m3.456s", ms((3600 + 2 * 60 + 3) * 1000 + 456)),
+            ("1h2m3.4567s", us(3_723_456_700)),
+            // days
+            ("0d", Err("Invalid interval '0d': interval cannot be zero")),
+            ("1d", ms(86400 * 1000)),
+            ("123d", ms(123 * 86400 * 1000)),
+            // weeks
+            ("0w", Err("Invalid interval '0w': interval cannot be zero")),
+            ("1w", ms(7 * 86400 * 1000)),
+            ("52w", ms(52 * 7 * 86400 * 1000)),
+            ("0w0d", Err("Invalid interval '0w0d': interval cannot be zero")),
+            ("2w3d", ms(17 * 86400 * 1000)),
+            ("1w12h", ms((7 * 86400 + 12 * 3600) * 1000)),
+            ("1w1d1h1m1.5s", ms((8 * 86400 + 3600 + 60 + 1) * 1000 + 500)),
+            ("w", Err("Invalid interval 'w': unrecognized format")),
+            ("0.5w", Err("Invalid interval '0.5w': unrecognized format")),
+            // days + seconds
+            ("0d0s", Err("Invalid interval '0d0s': interval cannot be zero")),
+            ("0d1s", ms(1000)),
+            ("1d0s", ms(86400 * 1000)),
+            ("1d1s", ms((86400 + 1) * 1000)),
+            ("2d3s", ms((2 * 86400 + 3) * 1000)),
+            ("2d3.4s", ms((2 * 86400 + 3) * 1000 + 400)),
+            ("2d3.45s", ms((2 * 86400 + 3) * 1000 + 450)),
+            ("2d3.456s", ms((2 * 86400 + 3) * 1000 + 456)),
+            ("2d3.4567s", us(172_803_456_700)),
+            // days + minutes
+            ("0d0m", Err("Invalid interval '0d0m': interval cannot be zero")),
+            ("0d1m", ms(60 * 1000)),
+            ("1d0m", ms(86400 * 1000)),
+            ("1d1m", ms((86400 + 60) * 1000)),
+            ("2d3m", ms((2 * 86400 + 3 * 60) * 1000)),
+            // days + minutes + seconds
+            ("0d0m0s", Err("Invalid interval '0d0m0s': interval cannot be zero")),
+            ("0d0m0.0s", Err("Invalid interval '0d0m0.0s': interval cannot be zero")),
+            ("0d0m1s", ms(1000)),
+            ("0d1m0s", ms(60 * 1000)),
+            ("1d0m0s", ms(86400 * 1000)),
+            ("1d1m0s", ms((86400 + 60) * 1000)),
+            ("2d3m4s", ms((2 * 86400 + 3 * 60 + 4) * 1000)),
+            ("2d3m4.5s", ms((2 * 86400 + 3 * 60 + 4) * 1000 + 500)),
+            ("2d3m4.56s", ms((2 * 86400 + 3 * 60 + 4) * 1000 + 560)),
+            ("2d3m4.567s", ms((2 * 86400 + 3 * 60 + 4) * 1000 + 567)),
+            ("2d3m4.5678s", us(172_984_567_800)),
+            // days + hours
+            ("0d0h", Err("Invalid interval '0d0h': interval cannot be zero")),
+            ("0d1h", ms(3600 * 1000)),
+            ("1d0h", ms(86400 * 1000)),
+            ("1d1h", ms((86400 + 3600) * 1000)),
+            ("2d3h", ms((2 * 86400 + 3 * 3600) * 1000)),
+            // days + hours + seconds
+            ("0d0h0s", Err("Invalid interval '0d0h0s': interval cannot be zero")),
+            ("0d0h0.0s", Err("Invalid interval '0d0h0.0s': interval cannot be zero")),
+            ("0d0h1s", ms(1000)),
+            ("0d1h0s", ms(3600 * 1000)),
+            ("1d0h0s", ms(86400 * 1000)),
+            ("1d1h0s", ms((86400 + 3600) * 1000)),
+            ("2d3h4s", ms((2 * 86400 + 3 * 3600 + 4) * 1000)),
+            ("2d3h4.5s", ms((2 * 86400 + 3 * 3600 + 4) * 1000 + 500)),
+            ("2d3h4.56s", ms((2 * 86400 + 3 * 3600 + 4) * 1000 + 560)),
+            ("2d3h4.567s", ms((2 * 86400 + 3 * 3600 + 4) * 1000 + 567)),
+            ("2d3h4.5678s", us(183_604_567_800)),
+            // days + hours + minutes
+            ("0d0h0m", Err("Invalid interval '0d0h0m': interval cannot be zero")),
+            ("0d0h1m", ms(60 * 1000)),
+            ("0d1h0m", ms(3600 * 1000)),
+            ("1d0h0m", ms(86400 * 1000)),
+            ("2d3h4m", ms((2 * 86400 + 3 * 3600 + 4 * 60) * 1000)),
+            // days + hours + minutes + seconds
+            ("0d0h0m0s", Err("Invalid interval '0d0h0m0s': interval cannot be zero")),
+            ("0d0h0m0.0s", Err("Invalid interval '0d0h0m0.0s': interval cannot be zero")),
+            ("0d0h0m0.00s", Err("Invalid interval '0d0h0m0.00s': interval cannot be zero")),
+            ("0d0h0m0.000s", Err("Invalid interval '0d0h0m0.000s': interval cannot be zero")),
+            ("0d0h0m0.0000s", Err("Invalid interval '0d0h0m0.0000s': interval cannot be zero")),
+            ("0d0h0m1s", ms(1000)),
+            ("0d0h1m0s", ms(60 * 1000)),
+            ("0d1h0m0s", ms(3600 * 1000)),
+            ("1d0h0m0s", ms(86400 * 1000)),
+            ("1d2h3m4s", ms((86400 + 2 * 3600 + 3 * 60 + 4) * 1000)),
+            ("1d2h3m4.5s", ms((86400 + 2 * 3600 + 3 * 60 + 4) * 1000 + 500)),
+            ("1d2h3m4.56s", ms((86400 + 2 * 3600 + 3 * 60 + 4) * 1000 + 560)),
+            ("1d2h3m4.567s", ms((86400 + 2 * 3600 + 3 * 60 + 4) * 1000 + 567)),
+            ("1d2h3m4.5678s", us(93_784_567_800)),
+            ("2d3s", ms((2 * 86400 + 3) * 1000)),
+            ("2d3.4s", ms((2 * 86400 + 3) * 1000 + 400)),
+            ("2d3h4.56s", ms((2 * 86400 + 3 * 3600 + 4) * 1000 + 560)),
+            ("1d1.123s", ms((86400 + 1) * 1000 + 123)),
+            ("1d1.1234s", us(86_401_123_400)),
+            // out of order units
+            ("1h1d", Err("Invalid interval '1h1d': unrecognized format")),
+            ("1m1d", Err("Invalid interval '1m1d': unrecognized format")),
+            ("1m1h", Err("Invalid interval '1m1h': unrecognized format")),
+            ("1s1d", Err("Invalid interval '1s1d': unrecognized format")),
+            ("1s1h", Err("Invalid interval '1s1h': unrecognized format")),
+            ("1s1m", Err("Invalid interval '1s1m': unrecognized format")),
+            ("1d1w", Err("Invalid interval '1d1w': unrecognized format")),
+            ("1s1w", Err("Invalid interval '1s1w': unrecognized format")),
+            // milliseconds
+            ("0ms", Err("Invalid interval '0ms': interval cannot be zero")),
+            ("1ms", ms(1)),
+            ("500ms", ms(500)),
+            ("1500ms", ms(1_500)),
+            ("1s250ms", ms(1_250)),
+            ("1.5s250ms", ms(1_750)),
+            ("1m5ms", ms(60 * 1000 + 5)),
+            ("1h2m3s4ms", ms((3600 + 2 * 60 + 3) * 1000 + 4)),
+            ("1d1ms", ms(86400 * 1000 + 1)),
+            ("0s0ms", Err("Invalid interval '0s0ms': interval cannot be zero")),
+            ("ms", Err("Invalid interval 'ms': unrecognized format")),
+            ("0.5ms", Err("Invalid interval '0.5ms': unrecognized format")),
+            ("1ms1s", Err("Invalid interval '1ms1s': unrecognized format")),
+            ("1ms1ms", Err("Invalid interval '1ms1ms': unrecognized format")),
+            ("18446744073709551615ms", ms(u64::MAX)),
+            ("18446744073709551616ms", Err("Invalid interval '18446744073709551616ms': interval is too large")),
+            ("18446744073709551s616ms", Err("Invalid interval '18446744073709551s616ms': interval is too large")),
+            // microseconds
+            ("0.000001s", us(1)),
+            ("0.0005s", us(500)),
+            ("0.00025s", us(250)),
+            ("0.123456s", us(123_456)),
+            ("0.0000001s", Err("Invalid interval '0.0000001s': maximum precision is microsecond")),
+            ("1.1234567s", Err("Invalid interval '1.1234567s': maximum precision is microsecond")),
+            ("0us", Err("Invalid interval '0us': interval cannot be zero")),
+            ("1us", us(1)),
+            ("250us", us(250)),
+            ("250µs", us(250)),
+            ("1500us", us(1_500)),
+            ("1ms500us", us(1_500)),
+            ("1s250ms500us", us(1_250_500)),
+            ("1.5s1us", us(1_500_001)),
+            ("us", Err("Invalid interval 'us': unrecognized format")),
+            ("1us1ms", Err("Invalid interval '1us1ms': unrecognized format")),
+            ("18446744073709551615ms1us", Err("Invalid interval '18446744073709551615ms1us': interval is too large")),
+            // ISO 8601
+            ("PT1S", ms(1_000)),
+            ("PT0.5S", ms(500)),
+            ("PT0,5S", ms(500)),
+            ("PT0.001S", ms(1)),
+            ("PT0.0001S", us(100)),
+            ("PT1M", ms(60 * 1000)),
+            ("PT1H", ms(3600 * 1000)),
+            ("PT1H30M", ms((3600 + 30 * 60) * 1000)),
+            ("PT1H2M3.4S", ms((3600 + 2 * 60 + 3) * 1000 + 400)),
+            ("P1D", ms(86400 * 1000)),
+            ("P1DT6H", ms((86400 + 6 * 3600) * 1000)),
+            ("P2DT3H4M5S", ms((2 * 86400 + 3 * 3600 + 4 * 60 + 5) * 1000)),
+            ("PT0S", Err("Invalid interval 'PT0S': interval cannot be zero")),
+            ("P0D", Err("Invalid interval 'P0D': interval cannot be zero")),
+            ("P", Err("Invalid interval 'P': unrecognized format")),
+            ("PT", Err("Invalid interval 'PT': unrecognized format")),
+            ("P1DT", Err("Invalid interval 'P1DT': unrecognized format")),
+            ("P1H", Err("Invalid interval 'P1H': unrecognized format")),
+            ("PT1D", Err("Invalid interval 'PT1D': unrecognized format")),
+            ("PT1M1H", Err("Invalid interval 'PT1M1H': unrecognized format")),
+            ("P1M", Err("Invalid interval 'P1M': unrecognized format")),
+            ("P1Y", Err("Invalid interval 'P1Y': unrecognized format")),
+            ("P1W", ms(7 * 86400 * 1000)),
+            ("P2W3D", ms(17 * 86400 * 1000)),
+            ("P1WT12H", ms((7 * 86400 + 12 * 3600) * 1000)),
+            ("P0W", Err("Invalid interval 'P0W': interval cannot be zero")),
+            ("P1WT", Err("Invalid interval 'P1WT': unrecognized format")),
+            ("P1D1W", Err("Invalid interval 'P1D1W': unrecognized format")),
+            ("PT1W", Err("Invalid interval 'PT1W': unrecognized format")),
+            ("pt1h", Err("Invalid interval 'pt1h': unrecognized format")),
+            ("PT1.5M", Err("Invalid interval 'PT1.5M': unrecognized format")),
+            ("PT1h", Err("Invalid interval 'PT1h': unrecognized format")),
+            // integer overflow
+            ("18446744073709551s", ms(18446744073709551000)),
+            ("18446744073709551.999s", Err("Invalid interval '18446744073709551.999s': interval is too large")),
+            ("18446744073709552s", Err("Invalid interval '18446744073709552s': interval is too large")),
+            ("307445734561825m51s", ms(18446744073709551000)),
+            ("307445734561825m52s", Err("Invalid interval '307445734561825m52s': interval is too large")),
+            ("307445734561825m", ms(18446744073709500000)),
+            ("307445734561826m", Err("Invalid interval '307445734561826m': interval is too large")),
+            ("5124095576030h25m", ms(18446744073709500000)),
+            ("5124095576030h26m", Err("Invalid interval '5124095576030h26m': interval is too large")),
+            ("5124095576030h", ms(18446744073708000000)),
+            ("5124095576031h", Err("Invalid interval '5124095576031h': interval is too large")),
+            ("213503982334d14h", ms(18446744073708000000)),
+            ("213503982334d15h", Err("Invalid interval '213503982334d15h': interval is too large")),
+            ("213503982334d", ms(18446744073657600000)),
+            ("213503982335d", Err("Invalid interval '213503982335d': interval is too large")),
+            ("30500568904w", ms(18446744073139200000)),
+            ("30500568905w", Err("Invalid interval '30500568905w': interval is too large")),
+            ("30500568904w6d14h", ms(18446744073708000000)),
+            ("30500568904w6d15h", Err("Invalid interval '30500568904w6d15h': interval is too large")),
+            ("18446744073709551616w", Err("Invalid interval '18446744073709551616w': interval is too large")),
+        ];
+
+        for (input, expected) in tests_cases {
+            let actual = parse_interval(input);
+            let expected = expected.map_err(|e| e.to_string());
+
+            assert_eq!(actual, expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_format_duration() {
+        let test_cases = [
+            (0, "0s"),
+            (1, "0.001s"),
+            (10, "0.01s"),
+            (120, "0.12s"),
+            (999, "0.999s"),
+            (1_000, "1s"),
+            (1_500, "1.5s"),
+            (60_000, "1m"),
+            (65_500, "1m5.5s"),
+            (3_600_000, "1h"),
+            (3_600_001, "1h0.001s"),
+            (86_400_000, "1d"),
+            (90_061_001, "1d1h1m1.001s"),
+            (u64::MAX, "213503982334d14h25m51.615s"),
+        ];
+
+        for (ms, expected) in test_cases {
+            let actual = format_duration(Duration::from_millis(ms));
+
+            assert_eq!(actual, expected, "ms: {ms}");
+
+            if ms != 0 {
+                assert_eq!(parse_interval(&actual), Ok(Duration::from_millis(ms)), "ms: {ms}");
+            }
+        }
+
+        // sub-millisecond precision is truncated
+        assert_eq!(format_duration(Duration::from_micros(1_999)), "0.001s");
+    }
+
+    #[test]
+    fn test_format_interval() {
+        let test_cases = [
+            (1, "0.000001s"),
+            (250, "0.00025s"),
+            (1_000, "0.001s"),
+            (1_500_001, "1.500001s"),
+            (90_061_000_001, "1d1h1m1.000001s"),
+        ];
+
+        for (us, expected) in test_cases {
+            let actual = format_interval(Duration::from_micros(us));
+
+            assert_eq!(actual, expected, "us: {us}");
+            assert_eq!(parse_interval(&actual), Ok(Duration::from_micros(us)), "us: {us}");
+        }
+
+        // sub-microsecond precision is truncated
+        assert_eq!(format_interval(Duration::from_nanos(1_999)), "0.000001s");
+    }
+
+    #[test]
+    fn test_interval() {
+        let interval: Interval = "1m30.5s".parse().unwrap();
+
+        assert_eq!(interval.as_millis(), 90_500);
+        assert_eq!(interval.as_duration(), Duration::from_millis(90_500));
+        assert_eq!(interval.to_string(), "1m30.5s");
+        assert_eq!(Interval::from_millis(90_500), Some(interval));
+        assert_eq!(Interval::from_millis(0), None);
+        assert_eq!("0s".parse::<Interval>(), Err(String::from("Invalid interval '0s': interval cannot be zero")));
+    }
+}
@@ -0,0 +1,152 @@
+use crate::interval::Interval;
+use crate::runner::Job;
+use std::fs;
+use std::io;
+use std::num::IntErrorKind;
+use std::path::Path;
+
+/// The maximum number of runs at once, and of commands started per tick.
+pub(crate) const MAX_CONCURRENCY: u16 = 1000;
+
+/// Reads the command lines of a jobs file, or of stdin if the path is `-`,
+/// skipping blank lines and `#` comments.
+pub(crate) fn read_jobs_file(path: &Path) -> Result<Vec<Job>, String> {
+    let contents = if path == Path::new("-") {
+        io::read_to_string(io::stdin())
+            .map_err(|e| format!("Failed to read jobs from stdin: {e}"))?
+    } else {
+        fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read jobs file '{}': {e}", path.display()))?
+    };
+
+    let jobs = parse_jobs(&contents)?;
+
+    if jobs.is_empty() {
+        return Err(String::from("No commands in jobs file!"));
+    }
+
+    Ok(jobs)
+}
+
+/// Parses a concurrency level, from 1 to [`MAX_CONCURRENCY`].
+pub(crate) fn parse_concurrency(concurrency: &str) -> Result<u16, String> {
+    let invalid_range = || {
+        format!("Invalid concurrency: value {concurrency} is not in the range 1–{MAX_CONCURRENCY}")
+    };
+
+    match concurrency.parse() {
+        Ok(concurrency) if (1..=MAX_CONCURRENCY).contains(&concurrency) => Ok(concurrency),
+        Ok(_) => Err(invalid_range()),
+        Err(err) if *err.kind() == IntErrorKind::PosOverflow => Err(invalid_range()),
+        Err(_) => Err(format!("Invalid concurrency value: '{concurrency}'")),
+    }
+}
+
+fn parse_jobs(contents: &str) -> Result<Vec<Job>, String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(parse_job)
+        .collect()
+}
+
+// Parses a line of a jobs file: [@<interval>|@rotate] [-c <n>] <command>.
+fn parse_job(line: &str) -> Result<Job, String> {
+    let mut job = Job {
+        command: String::new(),
+        interval: None,
+        concurrency: None,
+        rotate: false,
+    };
+
+    let mut rest = line;
+
+    if let Some(every) = rest.strip_prefix('@') {
+        let (every, command) = split_word(every);
+
+        match every {
+            "rotate" => job.rotate = true,
+            every => job.interval = Some(every.parse::<Interval>()?.as_duration()),
+        }
+
+        rest = command;
+    }
+
+    if let Some(concurrency) = rest.strip_prefix("-c ") {
+        let (concurrency, command) = split_word(concurrency.trim_start());
+        job.concurrency = Some(parse_concurrency(concurrency)?);
+        rest = command;
+    }
+
+    if rest.is_empty() {
+        return Err(format!("Missing command in job: '{line}'"));
+    }
+
+    job.command = String::from(rest);
+
+    Ok(job)
+}
+
+// Splits the first word off the text.
+fn split_word(text: &str) -> (&str, &str) {
+    text.split_once(char::is_whitespace)
+        .map_or((text, ""), |(word, rest)| (word, rest.trim_start()))
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_jobs() {
+        let job = |command: &str, secs: Option<u64>, concurrency| Job { command: String::from(command), interval: secs.map(Duration::from_secs), concurrency, rotate: false };
+
+        let contents = "# every 10s\ncurl -s https://example.com\n\n  @1m -c 2 ./sync.sh  \n@15s  date\n";
+
+        assert_eq!(parse_jobs(contents), Ok(vec![
+            job("curl -s https://example.com", None, None),
+            job("./sync.sh", Some(60), Some(2)),
+            job("date", Some(15), None),
+        ]));
+
+        assert_eq!(parse_jobs("\n# nothing\n"), Ok(vec![]));
+        assert_eq!(parse_jobs("-c 3 date"), Ok(vec![job("date", None, Some(3))]));
+        assert_eq!(parse_jobs("@5x date"), Err(String::from("Invalid interval '5x': unrecognized format")));
+        assert_eq!(parse_jobs("-c 0 date"), Err(String::from("Invalid concurrency: value 0 is not in the range 1–1000")));
+        assert_eq!(parse_jobs("@1m"), Err(String::from("Missing command in job: '@1m'")));
+        assert_eq!(parse_jobs("@rotate -c 1 dig example.com"), Ok(vec![Job { rotate: true, ..job("dig example.com", None, Some(1)) }]));
+    }
+
+    #[test]
+    fn test_parse_concurrency() {
+        let test_cases = [
+            ("", Err("Invalid concurrency value: ''")),
+            ("-1", Err("Invalid concurrency value: '-1'")),
+            (" 1", Err("Invalid concurrency value: ' 1'")),
+            ("1 ", Err("Invalid concurrency value: '1 '")),
+            ("1 1", Err("Invalid concurrency value: '1 1'")),
+            ("1.", Err("Invalid concurrency value: '1.'")),
+            ("1.0", Err("Invalid concurrency value: '1.0'")),
+            ("0", Err("Invalid concurrency: value 0 is not in the range 1–1000")),
+            ("1", Ok(1)),
+            ("2", Ok(2)),
+            ("3", Ok(3)),
+            ("10", Ok(10)),
+            ("100", Ok(100)),
+            ("1000", Ok(1000)),
+            ("1001", Err("Invalid concurrency: value 1001 is not in the range 1–1000")),
+            ("9999999999", Err("Invalid concurrency: value 9999999999 is not in the range 1–1000")),
+            ("abc", Err("Invalid concurrency value: 'abc'")),
+        ];
+
+        for (input, expected) in test_cases {
+            let actual = parse_concurrency(input);
+            let expected = expected.map_err(|e| e.to_string());
+
+            assert_eq!(actual, expected, "input: {input}");
+        }
+    }
+}
@@ -0,0 +1,660 @@
+use crate::usage::{self, Usage};
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::process::{Child, ExitStatus};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+// The registry of the runs in progress, used to enforce the concurrency limit.
+pub struct Jobs {
+    registry: Mutex<Registry>,
+    // notified when a slot is released, or the queue changes
+    changed: Condvar,
+}
+
+struct Registry {
+    next_id: u64,
+    // run ID => PID, None while the command is being started
+    pids: HashMap<u64, Option<u32>>,
+    // the run IDs waiting for a slot, oldest first
+    queue: VecDeque<u64>,
+    // run ID => PID of the commands that were sent a kill signal, until they are reaped
+    killed: HashMap<u64, u32>,
+    // the signal and the delay before SIGKILL once stop() is called, for the commands still starting
+    stopping: Option<(Signal, Option<Duration>)>,
+}
+
+// A slot reserved in the registry for a run, released when dropped, even on panic.
+// If the slot owns a child process that has not been reaped, it is killed and reaped on drop,
+// so that a released slot never leaves a process running.
+pub struct JobSlot {
+    jobs: Arc<Jobs>,
+    id: u64,
+    child: Option<Child>,
+    // whether the command was killed by kill_oldest() or kill(), once reaped
+    killed: bool,
+    // whether it was killed by stop()
+    stopped: bool,
+}
+
+// A place in the queue, waiting for a slot to be released.
+// Queued runs get their slot in order, before any new reservation.
+pub struct QueuedSlot {
+    jobs: Arc<Jobs>,
+    id: u64,
+    limit: usize,
+}
+
+// Kills the command of a run, if still running. Obtained from JobSlot::killer().
+pub struct Killer {
+    jobs: Arc<Jobs>,
+    id: u64,
+}
+
+pub enum Reservation {
+    Slot(JobSlot),
+    Queued(QueuedSlot),
+}
+
+impl Jobs {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            registry: Mutex::new(Registry {
+                next_id: 1,
+                pids: HashMap::new(),
+                queue: VecDeque::new(),
+                killed: HashMap::new(),
+                stopping: None,
+            }),
+            changed: Condvar::new(),
+        })
+    }
+
+    // Reserves a slot if fewer than `limit` runs are in progress and none is queued,
+    // or returns the number of runs.
+    pub fn try_reserve(self: &Arc<Self>, limit: usize) -> Result<JobSlot, usize> {
+        let mut registry = self.lock();
+
+        if registry.pids.len() >= limit || !registry.queue.is_empty() || registry.stopping.is_some()
+        {
+            return Err(registry.pids.len());
+        }
+
+        let id = registry.next_id;
+        registry.next_id += 1;
+        registry.pids.insert(id, None);
+
+        Ok(self.slot(id))
+    }
+
+    // Reserves a slot, or a place in the queue if fewer than `max_queued` runs are queued.
+    // Returns the number of queued runs if the queue is full.
+    pub fn reserve_or_queue(
+        self: &Arc<Self>,
+        limit: usize,
+        max_queued: usize,
+    ) -> Result<Reservation, usize> {
+        if let Ok(slot) = self.try_reserve(limit) {
+            return Ok(Reservation::Slot(slot));
+        }
+
+        let mut registry = self.lock();
+
+        if registry.queue.len() >= max_queued || registry.stopping.is_some() {
+            return Err(registry.queue.len());
+        }
+
+        let id = registry.next_id;
+        registry.next_id += 1;
+        registry.queue.push_back(id);
+
+        Ok(Reservation::Queued(QueuedSlot {
+            jobs: Arc::clone(self),
+            id,
+            limit,
+        }))
+    }
+
+    // Sends the signal to the command of the oldest run, and returns its PID.
+    // If `kill_after` is set, and the command is still running after this delay, it is sent SIGKILL.
+    // The run keeps its slot until the command is reaped.
+    pub fn kill_oldest(
+        self: &Arc<Self>,
+        signal: Signal,
+        kill_after: Option<Duration>,
+    ) -> Option<u32> {
+        let mut registry = self.lock();
+
+        let (id, pid) = registry
+            .pids
+            .iter()
+            .filter_map(|(id, pid)| pid.map(|pid| (*id, pid)))
+            .min_by_key(|(id, _)| *id)?;
+
+        self.kill_run(&mut registry, id, pid, signal, kill_after);
+
+        Some(pid)
+    }
+
+    // Sends the signal to the commands of all the runs, then SIGKILL after `kill_after` if set,
+    // and returns once they are all reaped. No slot is given from now on, and the commands
+    // that are still being started are sent the signal too, once attached.
+    pub fn stop(self: &Arc<Self>, signal: Signal, kill_after: Option<Duration>) {
+        let mut registry = self.lock();
+        registry.stopping = Some((signal, kill_after));
+
+        let running: Vec<(u64, u32)> = registry
+            .pids
+            .iter()
+            .filter_map(|(id, pid)| pid.map(|pid| (*id, pid)))
+            .collect();
+
+        for (id, pid) in running {
+            self.kill_run(&mut registry, id, pid, signal, kill_after);
+        }
+
+        while !registry.pids.is_empty() {
+            registry = self
+                .changed
+                .wait(registry)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    fn kill_run(
+        self: &Arc<Self>,
+        registry: &mut Registry,
+        id: u64,
+        pid: u32,
+        signal: Signal,
+        kill_after: Option<Duration>,
+    ) {
+        signal_group(pid, signal);
+
+        // the PID is no longer reported, nor killed again
+        registry.pids.insert(id, None);
+        registry.killed.insert(id, pid);
+
+        if let Some(kill_after) = kill_after {
+            let jobs = Arc::clone(self);
+
+            thread::spawn(move || {
+                thread::sleep(kill_after);
+
+                // only if not reaped yet, as the PID could otherwise be reused
+                if let Some(pid) = jobs.lock().killed.get(&id) {
+                    signal_group(*pid, Signal::SIGKILL);
+                }
+            });
+        }
+    }
+
+    // Whether no run is in progress or queued.
+    pub fn is_idle(&self) -> bool {
+        let registry = self.lock();
+
+        registry.pids.is_empty() && registry.queue.is_empty()
+    }
+
+    // Returns the PIDs of the running commands, in ascending order.
+    pub fn pids(&self) -> Vec<u32> {
+        let mut pids: Vec<u32> = self.lock().pids.values().flatten().copied().collect();
+        pids.sort_unstable();
+
+        pids
+    }
+
+    // The registry is always left consistent, so a panic while holding the lock is harmless.
+    fn lock(&self) -> MutexGuard<'_, Registry> {
+        self.registry.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn slot(self: &Arc<Self>, id: u64) -> JobSlot {
+        JobSlot {
+            jobs: Arc::clone(self),
+            id,
+            child: None,
+            killed: false,
+            stopped: false,
+        }
+    }
+}
+
+impl QueuedSlot {
+    // Blocks until all the runs queued before this one got a slot, and a slot is free.
+    pub fn wait(self) -> JobSlot {
+        let mut registry = self.jobs.lock();
+
+        // never once stopping, as every is about to exit
+        while registry.queue.front() != Some(&self.id)
+            || registry.pids.len() >= self.limit
+            || registry.stopping.is_some()
+        {
+            registry = self
+                .jobs
+                .changed
+                .wait(registry)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+
+        registry.queue.pop_front();
+        registry.pids.insert(self.id, None);
+        drop(registry);
+
+        // the next queued run may get a slot too
+        self.jobs.changed.notify_all();
+
+        self.jobs.slot(self.id)
+    }
+}
+
+impl Drop for QueuedSlot {
+    fn drop(&mut self) {
+        let mut registry = self.jobs.lock();
+
+        if let Some(index) = registry.queue.iter().position(|id| *id == self.id) {
+            registry.queue.remove(index);
+            drop(registry);
+            self.jobs.changed.notify_all();
+        }
+    }
+}
+
+impl Reservation {
+    // Returns the slot, waiting for it if queued.
+    pub fn into_slot(self) -> JobSlot {
+        match self {
+            Reservation::Slot(slot) => slot,
+            Reservation::Queued(queued) => queued.wait(),
+        }
+    }
+}
+
+impl JobSlot {
+    // Attaches the child process to this slot, and returns its PID.
+    pub fn attach(&mut self, child: Child) -> u32 {
+        let pid = child.id();
+        let mut registry = self.jobs.lock();
+
+        match registry.stopping {
+            Some((signal, kill_after)) => {
+                self.jobs
+                    .kill_run(&mut registry, self.id, pid, signal, kill_after)
+            }
+            None => {
+                registry.pids.insert(self.id, Some(pid));
+            }
+        }
+
+        drop(registry);
+        self.child = Some(child);
+
+        pid
+    }
+
+    // Waits for the child process to exit, and returns its status and resource usage.
+    pub fn wait(&mut self) -> io::Result<(ExitStatus, Usage)> {
+        let child = self
+            .child
+            .as_mut()
+            .expect("No child process attached to the job slot");
+
+        // as Child::wait() does, so that the command does not wait for more input
+        drop(child.stdin.take());
+
+        let result = usage::wait(child.id())?;
+        self.child = None;
+
+        // the PID may be reused from now on
+        let mut registry = self.jobs.lock();
+        registry.pids.insert(self.id, None);
+        self.killed = registry.killed.remove(&self.id).is_some();
+        self.stopped = self.killed && registry.stopping.is_some();
+
+        Ok(result)
+    }
+
+    // Sends the signal to the command of this run, as kill_oldest() does, and returns its PID.
+    // Returns None if the command is not running, or was already killed.
+    pub fn kill(&self, signal: Signal, kill_after: Option<Duration>) -> Option<u32> {
+        self.killer().kill(signal, kill_after)
+    }
+
+    // A handle to kill the command of this run, from a thread that cannot borrow the slot.
+    pub fn killer(&self) -> Killer {
+        Killer {
+            jobs: Arc::clone(&self.jobs),
+            id: self.id,
+        }
+    }
+
+    // Whether the command was killed by kill_oldest() or kill(), once reaped by wait().
+    pub fn was_killed(&self) -> bool {
+        self.killed
+    }
+
+    // Whether the command was killed by stop(), as every is exiting, once reaped by wait().
+    pub fn was_stopped(&self) -> bool {
+        self.stopped
+    }
+}
+
+impl Killer {
+    // As JobSlot::kill().
+    pub fn kill(&self, signal: Signal, kill_after: Option<Duration>) -> Option<u32> {
+        let mut registry = self.jobs.lock();
+        let pid = registry.pids.get(&self.id).copied().flatten()?;
+
+        self.jobs
+            .kill_run(&mut registry, self.id, pid, signal, kill_after);
+
+        Some(pid)
+    }
+}
+
+impl Drop for JobSlot {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            signal_group(child.id(), Signal::SIGKILL);
+            let _ = child.wait();
+        }
+
+        let mut registry = self.jobs.lock();
+        registry.pids.remove(&self.id);
+        registry.killed.remove(&self.id);
+        drop(registry);
+
+        self.jobs.changed.notify_all();
+    }
+}
+
+// Sends the signal to the process group led by the command, so that the processes it started get it too,
+// or to the command alone if it does not lead a process group.
+fn signal_group(pid: u32, signal: Signal) {
+    let pid = Pid::from_raw(pid as i32);
+
+    if signal::killpg(pid, signal).is_err() {
+        let _ = signal::kill(pid, signal);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nix::sys::signal::kill;
+    use std::io::{BufRead, BufReader, Read};
+    use std::os::unix::process::{CommandExt, ExitStatusExt};
+    use std::process::{Command, Stdio};
+    use std::time::Instant;
+
+    #[test]
+    fn test_reserve_up_to_limit() {
+        let jobs = Jobs::new();
+
+        let first = jobs.try_reserve(2).unwrap();
+        let _second = jobs.try_reserve(2).unwrap();
+
+        assert_eq!(jobs.lock().pids.len(), 2);
+        assert_eq!(jobs.try_reserve(2).err(), Some(2));
+
+        drop(first);
+
+        assert_eq!(jobs.lock().pids.len(), 1);
+        assert!(jobs.try_reserve(2).is_ok());
+        assert_eq!(jobs.lock().pids.len(), 1);
+    }
+
+    #[test]
+    fn test_slot_is_released_on_panic() {
+        let jobs = Jobs::new();
+        let slot = jobs.try_reserve(1).unwrap();
+
+        let result = thread::spawn(move || {
+            let _slot = slot;
+            panic!("run handling failed");
+        })
+        .join();
+
+        assert!(result.is_err());
+        assert_eq!(jobs.lock().pids.len(), 0);
+    }
+
+    #[test]
+    fn test_wait() {
+        let jobs = Jobs::new();
+        let mut slot = jobs.try_reserve(1).unwrap();
+
+        let pid = slot.attach(Command::new("true").spawn().unwrap());
+
+        assert_eq!(jobs.pids(), [pid]);
+        assert!(slot.wait().unwrap().0.success());
+
+        drop(slot);
+
+        assert!(jobs.pids().is_empty());
+    }
+
+    #[test]
+    fn test_unreaped_child_is_killed_on_drop() {
+        let jobs = Jobs::new();
+        let mut slot = jobs.try_reserve(1).unwrap();
+
+        let pid = slot.attach(Command::new("sleep").arg("10").spawn().unwrap());
+        let pid = Pid::from_raw(pid.try_into().unwrap());
+
+        assert!(kill(pid, None).is_ok());
+
+        drop(slot);
+
+        // the process no longer exists, not even as a zombie
+        assert!(kill(pid, None).is_err());
+        assert_eq!(jobs.lock().pids.len(), 0);
+    }
+
+    #[test]
+    fn test_queue_is_bounded() {
+        let jobs = Jobs::new();
+
+        let _running = jobs.try_reserve(1).unwrap();
+
+        assert!(matches!(
+            jobs.reserve_or_queue(1, 2),
+            Ok(Reservation::Queued(_))
+        ));
+
+        let _first = jobs.reserve_or_queue(1, 2).unwrap();
+        let _second = jobs.reserve_or_queue(1, 2).unwrap();
+
+        assert_eq!(jobs.reserve_or_queue(1, 2).err(), Some(2));
+
+        // new runs cannot get ahead of the queue
+        assert_eq!(jobs.try_reserve(2).err(), Some(1));
+        assert!(!jobs.is_idle());
+    }
+
+    #[test]
+    fn test_queued_runs_get_a_slot_in_order() {
+        let jobs = Jobs::new();
+        let running = jobs.try_reserve(1).unwrap();
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        let mut threads = vec![];
+
+        for number in 1..=3 {
+            let Ok(Reservation::Queued(queued)) = jobs.reserve_or_queue(1, 3) else {
+                panic!("Expected a queued slot");
+            };
+
+            let sender = sender.clone();
+
+            threads.push(thread::spawn(move || {
+                let slot = queued.wait();
+                sender.send(number).unwrap();
+                drop(slot);
+            }));
+        }
+
+        drop(running);
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        drop(sender);
+
+        assert_eq!(receiver.iter().collect::<Vec<_>>(), [1, 2, 3]);
+        assert!(jobs.is_idle());
+    }
+
+    #[test]
+    fn test_dropped_queued_slot_leaves_the_queue() {
+        let jobs = Jobs::new();
+        let running = jobs.try_reserve(1).unwrap();
+
+        let first = jobs.reserve_or_queue(1, 2).unwrap();
+        let second = jobs.reserve_or_queue(1, 2).unwrap();
+
+        drop(first);
+        drop(running);
+
+        let slot = second.into_slot();
+
+        assert_eq!(jobs.lock().pids.len(), 1);
+        drop(slot);
+        assert!(jobs.is_idle());
+    }
+
+    #[test]
+    fn test_kill_oldest() {
+        let jobs = Jobs::new();
+
+        let mut first = jobs.try_reserve(2).unwrap();
+        let mut second = jobs.try_reserve(2).unwrap();
+
+        let first_pid = first.attach(Command::new("sleep").arg("10").spawn().unwrap());
+        let second_pid = second.attach(Command::new("sleep").arg("10").spawn().unwrap());
+
+        assert_eq!(jobs.kill_oldest(Signal::SIGKILL, None), Some(first_pid));
+        assert_eq!(jobs.pids(), [second_pid]);
+        assert!(!first.wait().unwrap().0.success());
+
+        assert_eq!(jobs.kill_oldest(Signal::SIGKILL, None), Some(second_pid));
+        assert_eq!(jobs.kill_oldest(Signal::SIGKILL, None), None);
+        assert!(!second.wait().unwrap().0.success());
+    }
+
+    #[test]
+    fn test_stop() {
+        let jobs = Jobs::new();
+
+        let mut running = jobs.try_reserve(2).unwrap();
+        let starting = jobs.try_reserve(2).unwrap();
+        running.attach(Command::new("sleep").arg("10").spawn().unwrap());
+
+        let running = thread::spawn(move || {
+            let status = running.wait().unwrap().0;
+            status.signal() == Some(Signal::SIGTERM as i32) && running.was_stopped()
+        });
+
+        // the command started once stopping is killed as soon as it is attached
+        let starting = thread::spawn(move || {
+            let mut slot = starting;
+            thread::sleep(Duration::from_millis(50));
+            slot.attach(Command::new("sleep").arg("10").spawn().unwrap());
+            let status = slot.wait().unwrap().0;
+            status.signal() == Some(Signal::SIGTERM as i32) && slot.was_stopped()
+        });
+
+        let start = Instant::now();
+        jobs.stop(Signal::SIGTERM, None);
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(jobs.is_idle());
+        // killed with the signal, and known as stopped
+        assert!(running.join().unwrap());
+        assert!(starting.join().unwrap());
+        assert!(jobs.try_reserve(2).is_err());
+    }
+
+    #[test]
+    fn test_kill_slot() {
+        let jobs = Jobs::new();
+
+        let mut first = jobs.try_reserve(2).unwrap();
+        let mut second = jobs.try_reserve(2).unwrap();
+
+        first.attach(Command::new("sleep").arg("10").spawn().unwrap());
+        let second_pid = second.attach(Command::new("sleep").arg("10").spawn().unwrap());
+
+        assert_eq!(second.kill(Signal::SIGKILL, None), Some(second_pid));
+        assert_eq!(second.kill(Signal::SIGKILL, None), None);
+        assert!(!second.wait().unwrap().0.success());
+        assert!(second.was_killed());
+
+        assert!(first.kill(Signal::SIGKILL, None).is_some());
+        assert!(first.wait().unwrap().0.signal().is_some());
+    }
+
+    #[test]
+    fn test_kill_oldest_kills_the_process_group() {
+        let jobs = Jobs::new();
+        let mut slot = jobs.try_reserve(1).unwrap();
+
+        // the background sleep keeps stdout open until it exits
+        let mut command = Command::new("sh");
+        command
+            .args(["-c", "sleep 10 & exec sleep 10"])
+            .stdout(Stdio::piped())
+            .process_group(0);
+
+        let mut child = command.spawn().unwrap();
+        let mut stdout = child.stdout.take().unwrap();
+
+        let pid = slot.attach(child);
+        let start = Instant::now();
+
+        assert_eq!(jobs.kill_oldest(Signal::SIGTERM, None), Some(pid));
+        assert_eq!(
+            slot.wait().unwrap().0.signal(),
+            Some(Signal::SIGTERM as i32)
+        );
+
+        stdout.read_to_end(&mut vec![]).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_kill_oldest_after_delay() {
+        let jobs = Jobs::new();
+        let mut slot = jobs.try_reserve(1).unwrap();
+
+        // the whole process group ignores SIGTERM
+        let mut command = Command::new("sh");
+        command
+            .args(["-c", "trap '' TERM; echo ready; sleep 10"])
+            .stdout(Stdio::piped())
+            .process_group(0);
+
+        let mut child = command.spawn().unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+        // wait for the trap to be set
+        stdout.read_line(&mut String::new()).unwrap();
+
+        let pid = slot.attach(child);
+        let start = Instant::now();
+
+        assert_eq!(
+            jobs.kill_oldest(Signal::SIGTERM, Some(Duration::from_millis(100))),
+            Some(pid)
+        );
+        assert_eq!(
+            slot.wait().unwrap().0.signal(),
+            Some(Signal::SIGKILL as i32)
+        );
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,123 @@
+use crate::interval::format_duration;
+use crate::logger;
+use crate::scheduler::{Control, Status};
+use nix::sys::termios::{self, LocalFlags, SetArg, SpecialCharacterIndices, Termios};
+use std::io::{self, Read};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use std::thread;
+
+// The terminal in raw mode since start(), None once restored.
+static RAW_MODE: Mutex<Option<RawMode>> = Mutex::new(None);
+
+// The terminal on stdin reading the keys as soon as they are typed, without echoing them,
+// until dropped. It stays in line mode for Ctrl-C.
+pub(crate) struct RawMode {
+    // the settings of the terminal before
+    termios: Termios,
+}
+
+impl RawMode {
+    pub(crate) fn enable() -> io::Result<Self> {
+        let termios = termios::tcgetattr(io::stdin())?;
+
+        let mut raw = termios.clone();
+        raw.local_flags
+            .remove(LocalFlags::ICANON | LocalFlags::ECHO);
+        raw.control_chars[SpecialCharacterIndices::VMIN as usize] = 1;
+        raw.control_chars[SpecialCharacterIndices::VTIME as usize] = 0;
+        termios::tcsetattr(io::stdin(), SetArg::TCSANOW, &raw)?;
+
+        Ok(Self { termios })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = termios::tcsetattr(io::stdin(), SetArg::TCSANOW, &self.termios);
+    }
+}
+
+// Reads single-key commands from the terminal on stdin, in the background: space to run now,
+// p to pause/resume, + and - to double or halve the interval, s to print the stats, q to quit.
+pub fn start<F>(controls: Sender<Control>, report: F) -> io::Result<()>
+where
+    F: Fn() -> Vec<String> + Send + 'static,
+{
+    *RAW_MODE.lock().unwrap() = Some(RawMode::enable()?);
+
+    thread::spawn(move || read_keys(&controls, report));
+
+    Ok(())
+}
+
+// Gives the terminal its settings back, if the keys have been started.
+pub fn restore() {
+    RAW_MODE.lock().unwrap().take();
+}
+
+fn read_keys<F>(controls: &Sender<Control>, report: F)
+where
+    F: Fn() -> Vec<String>,
+{
+    let mut key = [0];
+
+    while let Ok(1) = io::stdin().read(&mut key) {
+        let control = match key[0] {
+            b' ' => Control::RunNow,
+            b'p' => match get_status(controls) {
+                Some(status) if status.paused => {
+                    logger::notice("Resumed", &[]);
+                    Control::Resume
+                }
+                Some(_) => {
+                    logger::notice("Paused", &[]);
+                    Control::Pause
+                }
+                None => return,
+            },
+            b'+' | b'-' => {
+                let Some(status) = get_status(controls) else {
+                    return;
+                };
+
+                let interval = match key[0] {
+                    b'+' => status.interval.saturating_mul(2),
+                    _ => status.interval / 2,
+                };
+
+                if interval.is_zero() {
+                    continue;
+                }
+
+                logger::notice(
+                    &format!("Interval set to {}", format_duration(interval)),
+                    &[],
+                );
+
+                Control::SetInterval(interval)
+            }
+            b's' => {
+                for line in report() {
+                    logger::notice(&line, &[]);
+                }
+
+                continue;
+            }
+            b'q' => Control::Stop,
+            _ => continue,
+        };
+
+        if controls.send(control).is_err() {
+            return;
+        }
+    }
+}
+
+// Asks the scheduler for its status, None once it is stopped.
+pub(crate) fn get_status(controls: &Sender<Control>) -> Option<Status> {
+    let (reply, response) = mpsc::channel();
+
+    controls.send(Control::Status(reply)).ok()?;
+    response.recv().ok()
+}
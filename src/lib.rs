@@ -0,0 +1,79 @@
+//! Run a command every N seconds.
+//!
+//! This crate powers the `every` command line tool, and exposes its building blocks:
+//! the [`Interval`] format, the drift-free [`Scheduler`], and the command runner.
+//!
+//! ```no_run
+//! use every::{Interval, Scheduler};
+//!
+//! let interval: Interval = "1m30s".parse().unwrap();
+//!
+//! Scheduler::new(interval.into()).run(|tick| {
+//!     println!("Tick #{}", tick.number);
+//! });
+//! ```
+
+mod archive;
+mod args;
+mod bell;
+mod calendar;
+mod capped;
+mod cli;
+mod clock;
+mod control;
+mod countdown;
+mod daemon;
+mod diff;
+mod disk;
+mod exit;
+mod health;
+mod help;
+mod history;
+mod hooks;
+mod instances;
+mod interval;
+mod jobfile;
+mod jobs;
+mod keys;
+mod limits;
+mod load;
+mod logger;
+mod network;
+mod notify;
+#[cfg(feature = "otel")]
+mod otel;
+mod output;
+mod pool;
+mod power;
+mod pty;
+mod random;
+mod rate;
+mod report;
+mod runner;
+mod scheduler;
+mod signals;
+mod stall;
+mod stats;
+mod style;
+mod suggest;
+mod syslog;
+mod systemd;
+mod template;
+mod title;
+#[cfg(feature = "tui")]
+mod ui;
+mod usage;
+
+pub use cli::main;
+pub use clock::{Date, LocalTime, TimeOfDay, TimeWindow, Weekdays};
+pub use exit::ExitReason;
+pub use interval::Interval;
+pub use limits::Limits;
+pub use logger::LogFormat;
+pub use rate::RateLimit;
+pub use runner::{
+    preview, run, ArgsExhausted, Config, Cooldown, ErrorOutput, FreeSpace, Input, IntervalWindow,
+    Job, NetworkCheck, OnBusy, OnSpawnError, OutputMode, Report, ReportFormat, UntilMatch,
+};
+pub use scheduler::{Clock, Control, OnResume, Scheduler, Status, Tick};
+pub use style::{ColorChoice, Style};
@@ -0,0 +1,66 @@
+use nix::sys::resource::{setrlimit, Resource};
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use std::time::Duration;
+
+/// Limits on the resources of each command, enforced by the kernel.
+#[derive(Debug, Default, PartialEq)]
+pub struct Limits {
+    /// The maximum size of the address space, in bytes.
+    pub memory: Option<u64>,
+    /// The maximum CPU time, rounded up to the second.
+    pub cpu: Option<Duration>,
+}
+
+impl Limits {
+    // Sets the limits on the command, before it is executed.
+    pub fn apply(&self, command: &mut Command) {
+        if self.memory.is_none() && self.cpu.is_none() {
+            return;
+        }
+
+        let memory = self.memory;
+        let cpu = self
+            .cpu
+            .map(|cpu| cpu.as_secs() + u64::from(cpu.subsec_nanos() > 0));
+
+        // SAFETY: setrlimit() is async-signal-safe, and touches no memory of the parent
+        unsafe {
+            command.pre_exec(move || {
+                if let Some(memory) = memory {
+                    setrlimit(Resource::RLIMIT_AS, memory, memory)?;
+                }
+
+                // the command gets SIGXCPU when reaching the limit, and SIGKILL one second later
+                if let Some(cpu) = cpu {
+                    setrlimit(Resource::RLIMIT_CPU, cpu, cpu + 1)?;
+                }
+
+                Ok::<(), io::Error>(())
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let limits = Limits {
+            memory: Some(1 << 30),
+            cpu: Some(Duration::from_millis(9_500)),
+        };
+
+        let mut command = Command::new("sh");
+        command.args(["-c", "ulimit -v; ulimit -t"]);
+        limits.apply(&mut command);
+
+        let output = command.output().unwrap();
+
+        // ulimit reports the address space in KiB
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "1048576\n10\n");
+    }
+}
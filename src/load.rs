@@ -0,0 +1,23 @@
+use nix::libc;
+use std::io;
+
+// The load average of the system over the last minute: the number of processes running or waiting to run.
+pub fn one_minute() -> io::Result<f64> {
+    let mut load = [0.0];
+
+    // SAFETY: the pointer is valid for writing one value
+    match unsafe { libc::getloadavg(load.as_mut_ptr(), 1) } {
+        1 => Ok(load[0]),
+        _ => Err(io::Error::other("the load average is not available")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_minute() {
+        assert!(one_minute().unwrap() >= 0.0);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::countdown;
+use crate::history::timestamp;
+use crate::notify::json_string;
+use crate::style::Style;
+use std::fmt::Write;
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+// The settings of the log, set by init().
+static SETTINGS: OnceLock<(Level, LogFormat, Style)> = OnceLock::new();
+
+/// The format of the messages of `every` on standard error.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LogFormat {
+    /// Plain text, one message per line.
+    #[default]
+    Text,
+    /// One JSON object per line, with the time, level and fields of the message.
+    Json,
+}
+
+// The severity of a message, from the most to the least severe.
+// Notices are the output asked for, such as the summary or the stats, and are always printed.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Level {
+    Notice,
+    Error,
+    Warning,
+    Info,
+    Debug,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Notice => "notice",
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        }
+    }
+}
+
+// Sets the level of the least severe messages printed, their format, and whether the text is colored.
+// Must be called once, at startup.
+pub fn init(level: Level, format: LogFormat, colored: bool) {
+    let _ = SETTINGS.set((level, format, Style::new(colored)));
+}
+
+// The output asked for, such as the summary or the stats, always printed.
+pub fn notice(message: &str, fields: &[(&str, i64)]) {
+    log(Level::Notice, message, fields);
+}
+
+// Errors are always printed.
+pub fn error(message: &str, fields: &[(&str, i64)]) {
+    log(Level::Error, message, fields);
+}
+
+// Warnings are printed unless quiet.
+pub fn warning(message: &str, fields: &[(&str, i64)]) {
+    log(Level::Warning, message, fields);
+}
+
+// Scheduler events, printed when verbose.
+pub fn info(message: &str, fields: &[(&str, i64)]) {
+    log(Level::Info, message, fields);
+}
+
+// Details of the runs, such as their command line and the size of their output, printed when very verbose.
+pub fn debug(message: &str, fields: &[(&str, i64)]) {
+    log(Level::Debug, message, fields);
+}
+
+// Prints the message on standard error, if enabled.
+// The fields, such as the run ID or the PID, are only printed in the JSON format.
+fn log(level: Level, message: &str, fields: &[(&str, i64)]) {
+    let (max_level, format, style) = settings();
+
+    if level > max_level {
+        return;
+    }
+
+    let _line = countdown::hide();
+
+    match format {
+        LogFormat::Text => {
+            let color = match level {
+                Level::Notice => style.bold,
+                Level::Error => style.red,
+                Level::Warning => style.yellow,
+                Level::Info | Level::Debug => "",
+            };
+
+            match color {
+                "" => eprintln!("{message}"),
+                color => eprintln!("{color}{message}{}", style.reset),
+            }
+        }
+        LogFormat::Json => eprintln!("{}", to_json(SystemTime::now(), level, message, fields)),
+    }
+}
+
+fn settings() -> (Level, LogFormat, Style) {
+    SETTINGS
+        .get()
+        .copied()
+        .unwrap_or((Level::Warning, LogFormat::Text, Style::new(false)))
+}
+
+fn to_json(time: SystemTime, level: Level, message: &str, fields: &[(&str, i64)]) -> String {
+    let mut json = format!(
+        r#"{{"time":{},"level":"{}","message":{}"#,
+        timestamp(time),
+        level.name(),
+        json_string(message)
+    );
+
+    for (name, value) in fields {
+        write!(json, r#",{}:{value}"#, json_string(name)).unwrap();
+    }
+
+    json.push('}');
+
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_to_json() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+
+        assert_eq!(
+            to_json(time, Level::Info, "Tick #3 fired", &[]),
+            r#"{"time":1700000000.123,"level":"info","message":"Tick #3 fired"}"#
+        );
+
+        assert_eq!(
+            to_json(time, Level::Notice, "Paused", &[]),
+            r#"{"time":1700000000.123,"level":"notice","message":"Paused"}"#
+        );
+
+        assert_eq!(
+            to_json(
+                time,
+                Level::Error,
+                "Command \"false\" failed",
+                &[("run_id", 3), ("exit_code", -1)]
+            ),
+            r#"{"time":1700000000.123,"level":"error","message":"Command \"false\" failed","run_id":3,"exit_code":-1}"#
+        );
+    }
+
+    #[test]
+    fn test_level() {
+        assert!(Level::Notice < Level::Error);
+        assert!(Level::Error < Level::Warning);
+        assert!(Level::Warning < Level::Info);
+        assert!(Level::Info < Level::Debug);
+    }
+}
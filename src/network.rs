@@ -0,0 +1,119 @@
+use std::io;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+// How long to wait for a TCP connection to the address of --require-network.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+// The flags of a route usable for traffic, from the kernel's route.h.
+#[cfg(any(target_os = "linux", test))]
+const RTF_UP: u32 = 0x0001;
+#[cfg(any(target_os = "linux", test))]
+const RTF_REJECT: u32 = 0x0200;
+
+// Whether the machine has a default route, IPv4 or IPv6.
+#[cfg(target_os = "linux")]
+pub fn has_default_route() -> io::Result<bool> {
+    let ipv4 = std::fs::read_to_string("/proc/net/route")?;
+    // IPv6 may be disabled
+    let ipv6 = std::fs::read_to_string("/proc/net/ipv6_route").unwrap_or_default();
+
+    Ok(has_ipv4_default_route(&ipv4) || has_ipv6_default_route(&ipv6))
+}
+
+// Whether the machine has a default route, as reported by `route`.
+#[cfg(not(target_os = "linux"))]
+pub fn has_default_route() -> io::Result<bool> {
+    let status = std::process::Command::new("route")
+        .args(["-n", "get", "default"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+
+    Ok(status.success())
+}
+
+// Opens a TCP connection to the address, and closes it right away.
+pub fn connect(address: &str) -> io::Result<()> {
+    let mut last_error = None;
+
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(_) => return Ok(()),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| io::Error::other("no address found")))
+}
+
+// /proc/net/route: Iface, Destination, Gateway, Flags... in hexadecimal, after a header line.
+#[cfg(any(target_os = "linux", test))]
+fn has_ipv4_default_route(routes: &str) -> bool {
+    routes.lines().skip(1).any(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+
+        fields.len() > 3 && fields[1] == "00000000" && is_usable(fields[3])
+    })
+}
+
+// /proc/net/ipv6_route: Destination, prefix length, Source, prefix length, Next hop, Metric, RefCnt, Use,
+// Flags and Iface, in hexadecimal.
+#[cfg(any(target_os = "linux", test))]
+fn has_ipv6_default_route(routes: &str) -> bool {
+    routes.lines().any(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+
+        fields.len() > 9
+            && fields[1] == "00"
+            && fields[0].bytes().all(|b| b == b'0')
+            && is_usable(fields[8])
+            && fields[9] != "lo"
+    })
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn is_usable(flags: &str) -> bool {
+    u32::from_str_radix(flags, 16).is_ok_and(|flags| flags & RTF_UP != 0 && flags & RTF_REJECT == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_has_ipv4_default_route() {
+        let header =
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n";
+        let local = "eth0\t000200C0\t00000000\t0001\t0\t0\t0\t00FFFFFF\t0\t0\t0\n";
+        let default = "eth0\t00000000\t010200C0\t0003\t0\t0\t0\t00000000\t0\t0\t0\n";
+
+        assert!(has_ipv4_default_route(&format!("{header}{default}{local}")));
+        assert!(!has_ipv4_default_route(&format!("{header}{local}")));
+        assert!(!has_ipv4_default_route(header));
+    }
+
+    #[test]
+    fn test_has_ipv6_default_route() {
+        let local = "fe800000000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000002 00000000 00000001     eth0\n";
+        let default = "00000000000000000000000000000000 00 00000000000000000000000000000000 00 fd000000000000000000000000000001 00000400 00000001 00000000 00000003     eth0\n";
+        let unreachable = "00000000000000000000000000000000 00 00000000000000000000000000000000 00 00000000000000000000000000000000 ffffffff 00000001 00000000 00200200       lo\n";
+
+        assert!(has_ipv6_default_route(&format!(
+            "{local}{default}{unreachable}"
+        )));
+        assert!(!has_ipv6_default_route(&format!("{local}{unreachable}")));
+    }
+
+    #[test]
+    fn test_connect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        assert!(connect(&address).is_ok());
+
+        drop(listener);
+        assert!(connect(&address).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use crate::hooks;
+use std::fmt::Write;
+use std::process::Command;
+#[cfg(feature = "webhook")]
+use std::time::Duration;
+
+// The maximum number of characters of output sent, from the end of the output.
+const MAX_OUTPUT_CHARS: usize = 1000;
+
+// A run reported to the webhook.
+#[cfg(feature = "webhook")]
+pub struct Notification<'a> {
+    // "failure" or "recovery"
+    pub event: &'a str,
+    pub command: &'a str,
+    pub run_id: u64,
+    // None if the command was killed by a signal, or could not be started
+    pub exit_code: Option<i32>,
+    pub duration: Option<Duration>,
+    pub output: Option<&'a str>,
+}
+
+#[cfg(feature = "webhook")]
+impl Notification<'_> {
+    fn to_json(&self) -> String {
+        let mut json = format!(
+            r#"{{"event":{},"command":{},"run_id":{}"#,
+            json_string(self.event),
+            json_string(self.command),
+            self.run_id
+        );
+
+        match self.exit_code {
+            Some(code) => write!(json, r#","exit_code":{code}"#).unwrap(),
+            None => json.push_str(r#","exit_code":null"#),
+        }
+
+        match self.duration {
+            Some(duration) => write!(json, r#","duration":{:.3}"#, duration.as_secs_f64()).unwrap(),
+            None => json.push_str(r#","duration":null"#),
+        }
+
+        match self.output {
+            Some(output) => write!(json, r#","output":{}"#, json_string(tail(output))).unwrap(),
+            None => json.push_str(r#","output":null"#),
+        }
+
+        json.push('}');
+
+        json
+    }
+}
+
+// POSTs the notification as JSON to the URL in the background, with curl.
+#[cfg(feature = "webhook")]
+pub fn send(url: &str, notification: &Notification) {
+    let mut curl = Command::new("curl");
+
+    curl.args(["--silent", "--show-error", "--fail", "--max-time", "10"])
+        .args(["--header", "Content-Type: application/json"])
+        .args(["--data-binary", "@-", url]);
+
+    hooks::spawn_command("notify-url", curl, Some(notification.to_json()));
+}
+
+// Raises a desktop notification in the background, with notify-send or osascript on macOS.
+pub fn desktop(title: &str, body: &str) {
+    hooks::spawn_command("notify-desktop", desktop_command(title, body), None);
+}
+
+#[cfg(target_os = "macos")]
+fn desktop_command(title: &str, body: &str) -> Command {
+    let script = format!(
+        "display notification {} with title {}",
+        applescript_string(body),
+        applescript_string(title)
+    );
+
+    let mut command = Command::new("osascript");
+    command.arg("-e").arg(script);
+
+    command
+}
+
+#[cfg(not(target_os = "macos"))]
+fn desktop_command(title: &str, body: &str) -> Command {
+    let mut command = Command::new("notify-send");
+    command.args(["--app-name", "every", "--", title, body]);
+
+    command
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn applescript_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// The end of the output, that usually holds the error.
+pub(crate) fn tail(output: &str) -> &str {
+    match output.char_indices().rev().nth(MAX_OUTPUT_CHARS - 1) {
+        Some((index, _)) => &output[index..],
+        None => output,
+    }
+}
+
+pub(crate) fn json_string(value: &str) -> String {
+    let mut json = String::from('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => write!(json, "\\u{:04x}", u32::from(c)).unwrap(),
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "webhook")]
+    #[test]
+    fn test_to_json() {
+        let notification = Notification {
+            event: "failure",
+            command: "curl \"https://example.com\"",
+            run_id: 3,
+            exit_code: Some(22),
+            duration: Some(Duration::from_millis(1500)),
+            output: Some("error:\tnot found\n\x1b[0m"),
+        };
+
+        assert_eq!(
+            notification.to_json(),
+            r#"{"event":"failure","command":"curl \"https://example.com\"","run_id":3,"exit_code":22,"duration":1.500,"output":"error:\tnot found\n\u001b[0m"}"#
+        );
+
+        let notification = Notification {
+            event: "recovery",
+            command: "date",
+            run_id: 4,
+            exit_code: None,
+            duration: None,
+            output: None,
+        };
+
+        assert_eq!(
+            notification.to_json(),
+            r#"{"event":"recovery","command":"date","run_id":4,"exit_code":null,"duration":null,"output":null}"#
+        );
+    }
+
+    #[test]
+    fn test_applescript_string() {
+        assert_eq!(
+            applescript_string(r#"say "hi" \ bye"#),
+            r#""say \"hi\" \\ bye""#
+        );
+    }
+
+    #[test]
+    fn test_tail() {
+        let output = format!("{}é{}", "a".repeat(10), "b".repeat(MAX_OUTPUT_CHARS - 1));
+
+        assert_eq!(tail(&output), &output[10..]);
+        assert_eq!(tail("short"), "short");
+    }
+}
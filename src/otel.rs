@@ -0,0 +1,355 @@
+use crate::hooks;
+use crate::logger;
+use crate::notify::json_string;
+use crate::random::Random;
+use crate::signals;
+use std::fmt::Write;
+use std::io;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// The number of spans of skipped ticks that are held back, before they are exported on their own.
+const MAX_PENDING_SPANS: usize = 100;
+
+// The trace of this instance, once started.
+static TRACE: OnceLock<Trace> = OnceLock::new();
+
+// The generator of the span IDs.
+static RANDOM: Mutex<Option<Random>> = Mutex::new(None);
+
+// The root span, covering the whole lifetime of the scheduler.
+struct Trace {
+    url: String,
+    trace_id: String,
+    span_id: String,
+    command: String,
+    started_at: SystemTime,
+    // the spans of the skipped ticks, exported along with the next span of a run, or once there are
+    // enough of them, rather than one curl process each
+    pending: Mutex<Vec<String>>,
+    // set once the root span has been exported
+    finished: Mutex<bool>,
+}
+
+// A span of a run, started before the command so that its traceparent can be passed to it.
+pub struct Span {
+    span_id: String,
+    started_at: SystemTime,
+}
+
+// The attribute values of the spans.
+enum Value<'a> {
+    Str(&'a str),
+    Int(i64),
+    Bool(bool),
+}
+
+// Starts the trace of the runs, exported as OTLP/HTTP JSON to the collector at the endpoint.
+pub fn start(endpoint: &str, command: String) {
+    let url = match endpoint.trim_end_matches('/') {
+        url if url.ends_with("/v1/traces") => String::from(url),
+        url => format!("{url}/v1/traces"),
+    };
+
+    let trace_id = format!("{}{}", span_id(), span_id());
+
+    let _ = TRACE.set(Trace {
+        url,
+        trace_id,
+        span_id: span_id(),
+        command,
+        started_at: SystemTime::now(),
+        pending: Mutex::new(Vec::new()),
+        finished: Mutex::new(false),
+    });
+}
+
+impl Span {
+    pub fn start() -> Self {
+        Self {
+            span_id: span_id(),
+            started_at: SystemTime::now(),
+        }
+    }
+
+    // The environment variables propagating the span to the command, in the W3C Trace Context format.
+    pub fn env(&self) -> Vec<(&'static str, String)> {
+        match TRACE.get() {
+            Some(trace) => vec![(
+                "TRACEPARENT",
+                format!("00-{}-{}-01", trace.trace_id, self.span_id),
+            )],
+            None => vec![],
+        }
+    }
+
+    // Exports the span of the run, in the background, with its exit status and whether it succeeded.
+    // The run is None if the command could not be started.
+    pub fn end(self, tick_number: u64, command: &str, run: Option<(ExitStatus, bool)>) {
+        let Some(trace) = TRACE.get() else {
+            return;
+        };
+
+        let mut attributes = vec![
+            ("every.command", Value::Str(command)),
+            ("every.iteration", Value::Int(tick_number as i64)),
+            ("every.skipped", Value::Bool(false)),
+        ];
+
+        if let Some(code) = run.and_then(|(status, _)| status.code()) {
+            attributes.push(("every.exit_code", Value::Int(i64::from(code))));
+        }
+
+        let failed = !run.is_some_and(|(_, succeeded)| succeeded);
+
+        let span = trace.span(
+            "run",
+            &self.span_id,
+            Some(&trace.span_id),
+            self.started_at,
+            SystemTime::now(),
+            &attributes,
+            failed,
+        );
+
+        let mut spans = std::mem::take(&mut *trace.pending.lock().unwrap());
+        spans.push(span);
+
+        hooks::spawn_command("otel", trace.export(), Some(trace.payload(&spans)));
+    }
+}
+
+// Records an empty span for the skipped tick, exported later in a batch.
+pub fn skip(tick_number: u64, reason: &str) {
+    let Some(trace) = TRACE.get() else {
+        return;
+    };
+
+    let now = SystemTime::now();
+
+    let attributes = [
+        ("every.command", Value::Str(&trace.command)),
+        ("every.iteration", Value::Int(tick_number as i64)),
+        ("every.skipped", Value::Bool(true)),
+        ("every.skip_reason", Value::Str(reason)),
+    ];
+
+    let span = trace.span(
+        "skip",
+        &span_id(),
+        Some(&trace.span_id),
+        now,
+        now,
+        &attributes,
+        false,
+    );
+
+    let mut pending = trace.pending.lock().unwrap();
+    pending.push(span);
+
+    if pending.len() >= MAX_PENDING_SPANS {
+        let spans = std::mem::take(&mut *pending);
+
+        hooks::spawn_command("otel", trace.export(), Some(trace.payload(&spans)));
+    }
+}
+
+// Exports the root span, and waits for the collector to receive it, as we are about to exit.
+pub fn finish() {
+    let Some(trace) = TRACE.get() else {
+        return;
+    };
+
+    let mut finished = trace.finished.lock().unwrap();
+
+    if *finished {
+        return;
+    }
+
+    *finished = true;
+
+    let attributes = [("every.command", Value::Str(&trace.command))];
+
+    let span = trace.span(
+        "every",
+        &trace.span_id,
+        None,
+        trace.started_at,
+        SystemTime::now(),
+        &attributes,
+        false,
+    );
+
+    let mut spans = std::mem::take(&mut *trace.pending.lock().unwrap());
+    spans.push(span);
+
+    let mut curl = trace.export();
+    curl.stdin(Stdio::piped());
+    signals::unblock_termination(&mut curl);
+
+    let result = curl.spawn().and_then(|mut child| {
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = io::Write::write_all(&mut stdin, trace.payload(&spans).as_bytes());
+        }
+
+        child.wait()
+    });
+
+    match result {
+        Ok(status) if status.success() => {}
+        Ok(status) => logger::error(&format!("The otel hook exited with {status}"), &[]),
+        Err(e) => logger::error(&format!("Failed to run the otel hook: {e}"), &[]),
+    }
+}
+
+impl Trace {
+    #[allow(clippy::too_many_arguments)]
+    fn span(
+        &self,
+        name: &str,
+        span_id: &str,
+        parent_span_id: Option<&str>,
+        started_at: SystemTime,
+        ended_at: SystemTime,
+        attributes: &[(&str, Value)],
+        failed: bool,
+    ) -> String {
+        let mut json = format!(r#"{{"traceId":"{}","spanId":"{span_id}","#, self.trace_id);
+
+        if let Some(parent_span_id) = parent_span_id {
+            write!(json, r#""parentSpanId":"{parent_span_id}","#).unwrap();
+        }
+
+        write!(
+            json,
+            r#""name":{},"kind":1,"startTimeUnixNano":"{}","endTimeUnixNano":"{}","attributes":["#,
+            json_string(name),
+            nanos(started_at),
+            nanos(ended_at)
+        )
+        .unwrap();
+
+        for (i, (key, value)) in attributes.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+
+            let value = match value {
+                Value::Str(value) => format!(r#"{{"stringValue":{}}}"#, json_string(value)),
+                // 64-bit integers are strings in OTLP/JSON
+                Value::Int(value) => format!(r#"{{"intValue":"{value}"}}"#),
+                Value::Bool(value) => format!(r#"{{"boolValue":{value}}}"#),
+            };
+
+            write!(json, r#"{{"key":{},"value":{value}}}"#, json_string(key)).unwrap();
+        }
+
+        // the status codes are 0 for unset, and 2 for error
+        write!(
+            json,
+            r#"],"status":{{"code":{}}}}}"#,
+            if failed { 2 } else { 0 }
+        )
+        .unwrap();
+
+        json
+    }
+
+    fn payload(&self, spans: &[String]) -> String {
+        format!(
+            r#"{{"resourceSpans":[{{"resource":{{"attributes":[{{"key":"service.name","value":{{"stringValue":"every"}}}}]}},"scopeSpans":[{{"scope":{{"name":"every"}},"spans":[{}]}}]}}]}}"#,
+            spans.join(",")
+        )
+    }
+
+    // POSTs the payload on stdin to the collector, with curl.
+    fn export(&self) -> Command {
+        let mut curl = Command::new("curl");
+
+        curl.args(["--silent", "--show-error", "--fail", "--max-time", "10"])
+            .args(["--header", "Content-Type: application/json"])
+            .args(["--data-binary", "@-", &self.url]);
+
+        curl
+    }
+}
+
+// A random span ID, 8 bytes in hexadecimal.
+fn span_id() -> String {
+    let mut random = RANDOM.lock().unwrap();
+    let random = random.get_or_insert_with(Random::new);
+
+    // all zeros is an invalid ID
+    format!("{:016x}", random.next_u64().max(1))
+}
+
+fn nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_span() {
+        let trace = Trace {
+            url: String::from("http://localhost:4318/v1/traces"),
+            trace_id: String::from("0123456789abcdef0123456789abcdef"),
+            span_id: String::from("00000000000000aa"),
+            command: String::from("date"),
+            started_at: UNIX_EPOCH,
+            pending: Mutex::new(Vec::new()),
+            finished: Mutex::new(false),
+        };
+
+        let started_at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_000);
+
+        let attributes = [
+            ("every.command", Value::Str(r#"echo "hi""#)),
+            ("every.iteration", Value::Int(3)),
+            ("every.skipped", Value::Bool(false)),
+        ];
+
+        assert_eq!(
+            trace.span(
+                "run",
+                "00000000000000bb",
+                Some("00000000000000aa"),
+                started_at,
+                started_at + Duration::from_millis(1500),
+                &attributes,
+                true
+            ),
+            concat!(
+                r#"{"traceId":"0123456789abcdef0123456789abcdef","spanId":"00000000000000bb","#,
+                r#""parentSpanId":"00000000000000aa","name":"run","kind":1,"#,
+                r#""startTimeUnixNano":"1700000000000000000","endTimeUnixNano":"1700000001500000000","#,
+                r#""attributes":[{"key":"every.command","value":{"stringValue":"echo \"hi\""}},"#,
+                r#"{"key":"every.iteration","value":{"intValue":"3"}},"#,
+                r#"{"key":"every.skipped","value":{"boolValue":false}}],"status":{"code":2}}"#
+            )
+        );
+
+        assert!(trace
+            .span("every", "00000000000000aa", None, started_at, started_at, &[], false)
+            .starts_with(r#"{"traceId":"0123456789abcdef0123456789abcdef","spanId":"00000000000000aa","name":"every""#));
+
+        assert!(trace
+            .payload(&[String::from("{}"), String::from("{}")])
+            .ends_with(r#""scopeSpans":[{"scope":{"name":"every"},"spans":[{},{}]}]}]}"#));
+    }
+
+    #[test]
+    fn test_span_id() {
+        let id = span_id();
+
+        assert_eq!(id.len(), 16);
+        assert!(id.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(id, span_id());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::countdown;
+use crate::diff;
+use std::io::{self, Read, Write};
+use std::thread;
+
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[2J";
+
+// The output of a run, captured in memory.
+#[derive(PartialEq)]
+pub struct Output {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl Output {
+    // Reads the piped stdout & stderr of a child process, until both are closed.
+    pub fn capture<O: Read + Send, E: Read + Send>(
+        stdout: Option<O>,
+        stderr: Option<E>,
+    ) -> io::Result<Self> {
+        thread::scope(|scope| {
+            // both pipes must be drained at the same time, or the child may block writing to one
+            let stderr = scope.spawn(|| read_to_end(stderr));
+            let stdout = read_to_end(stdout)?;
+            let stderr = stderr.join().expect("stderr reader thread panicked")?;
+
+            Ok(Self { stdout, stderr })
+        })
+    }
+
+    // Clears the terminal, and writes the header followed by the captured output.
+    pub fn print_screen(&self, header: &str, previous: Option<&Output>) -> io::Result<()> {
+        let _line = countdown::hide();
+        writeln!(io::stdout().lock(), "{CLEAR_SCREEN}{header}")?;
+
+        self.write(previous)
+    }
+
+    // Writes the captured output to our own stdout & stderr, without interleaving with other runs.
+    // If a previous output is given, the characters that changed since are highlighted.
+    pub fn print(&self, previous: Option<&Output>) -> io::Result<()> {
+        let _line = countdown::hide();
+
+        self.write(previous)
+    }
+
+    // Called with the status line hidden.
+    fn write(&self, previous: Option<&Output>) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        write_output(&mut stdout, &self.stdout, previous.map(|p| &p.stdout[..]))?;
+        stdout.flush()?;
+
+        let mut stderr = io::stderr().lock();
+        write_output(&mut stderr, &self.stderr, previous.map(|p| &p.stderr[..]))
+    }
+}
+
+fn write_output<W: Write>(
+    writer: &mut W,
+    output: &[u8],
+    previous: Option<&[u8]>,
+) -> io::Result<()> {
+    match previous {
+        Some(previous) => {
+            let highlighted = diff::highlight_changes(
+                &String::from_utf8_lossy(previous),
+                &String::from_utf8_lossy(output),
+            );
+
+            writer.write_all(highlighted.as_bytes())
+        }
+        None => writer.write_all(output),
+    }
+}
+
+fn read_to_end<R: Read>(reader: Option<R>) -> io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+
+    if let Some(mut reader) = reader {
+        reader.read_to_end(&mut buffer)?;
+    }
+
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{ChildStderr, ChildStdout, Command, Stdio};
+
+    #[test]
+    fn test_capture() {
+        let mut child = Command::new("bash")
+            .args([
+                "-c",
+                "echo out && echo err >&2 && head -c 100000 /dev/zero >&2",
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let output = Output::capture(child.stdout.take(), child.stderr.take()).unwrap();
+
+        assert!(child.wait().unwrap().success());
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr.len(), 4 + 100_000);
+        assert!(output.stderr.starts_with(b"err\n"));
+    }
+
+    #[test]
+    fn test_capture_without_pipes() {
+        let output = Output::capture(None::<ChildStdout>, None::<ChildStderr>).unwrap();
+
+        assert!(output.stdout.is_empty());
+        assert!(output.stderr.is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+// A pool of reusable worker threads, up to a maximum number.
+// A new worker is only spawned when no worker is idle, so the number of threads never exceeds
+// the maximum number of jobs running at the same time; once all the workers are busy, the jobs are
+// queued until one of them is done.
+pub struct WorkerPool {
+    sender: Sender<Job>,
+    receiver: Arc<Mutex<Receiver<Job>>>,
+    idle_workers: Arc<AtomicUsize>,
+    // the jobs sent to the channel while all the workers were busy
+    queued_jobs: Arc<AtomicUsize>,
+    workers: usize,
+    max_workers: usize,
+}
+
+impl WorkerPool {
+    pub fn new(max_workers: usize) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            sender,
+            receiver: Arc::new(Mutex::new(receiver)),
+            idle_workers: Arc::new(AtomicUsize::new(0)),
+            queued_jobs: Arc::new(AtomicUsize::new(0)),
+            workers: 0,
+            max_workers: max_workers.max(1),
+        }
+    }
+
+    pub fn execute<F>(&mut self, job: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        // reserve an idle worker, that is guaranteed to pick up the job from the channel
+        let reserved = self
+            .idle_workers
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |idle| {
+                idle.checked_sub(1)
+            })
+            .is_ok();
+
+        if reserved {
+            self.sender
+                .send(Box::new(job))
+                .expect("Worker pool channel is disconnected");
+
+            return;
+        }
+
+        if self.workers == self.max_workers {
+            // picked up by the next worker done with its job
+            self.queued_jobs.fetch_add(1, Ordering::SeqCst);
+            self.sender
+                .send(Box::new(job))
+                .expect("Worker pool channel is disconnected");
+
+            return;
+        }
+
+        let receiver = Arc::clone(&self.receiver);
+        let idle_workers = Arc::clone(&self.idle_workers);
+        let queued_jobs = Arc::clone(&self.queued_jobs);
+
+        self.workers += 1;
+        thread::spawn(move || work(Box::new(job), &receiver, &idle_workers, &queued_jobs));
+    }
+}
+
+fn work(
+    first_job: Job,
+    receiver: &Mutex<Receiver<Job>>,
+    idle_workers: &AtomicUsize,
+    queued_jobs: &AtomicUsize,
+) {
+    let mut job = first_job;
+
+    loop {
+        job();
+
+        // take a queued job if any, rather than becoming idle
+        let dequeued = queued_jobs
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |queued| {
+                queued.checked_sub(1)
+            })
+            .is_ok();
+
+        if !dequeued {
+            idle_workers.fetch_add(1, Ordering::SeqCst);
+        }
+
+        job = match receiver.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Barrier;
+    use std::time::Duration;
+
+    #[test]
+    fn test_workers_are_reused() {
+        let mut pool = WorkerPool::new(10);
+        let (sender, receiver) = mpsc::channel();
+        let mut thread_ids = HashSet::new();
+
+        for _ in 0..3 {
+            let sender = sender.clone();
+            pool.execute(move || sender.send(thread::current().id()).unwrap());
+
+            thread_ids.insert(receiver.recv().unwrap());
+
+            // wait for the worker to be back to idle
+            while pool.idle_workers.load(Ordering::SeqCst) == 0 {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        assert_eq!(thread_ids.len(), 1);
+    }
+
+    #[test]
+    fn test_concurrent_jobs_get_their_own_worker() {
+        let mut pool = WorkerPool::new(10);
+        let barrier = Arc::new(Barrier::new(4));
+        let (sender, receiver) = mpsc::channel();
+
+        for _ in 0..3 {
+            let barrier = Arc::clone(&barrier);
+            let sender = sender.clone();
+
+            pool.execute(move || {
+                // all jobs must be running at the same time to get past the barrier
+                barrier.wait();
+                sender.send(thread::current().id()).unwrap();
+            });
+        }
+
+        barrier.wait();
+        drop(sender);
+
+        let thread_ids: HashSet<_> = receiver.iter().collect();
+
+        assert_eq!(thread_ids.len(), 3);
+    }
+
+    #[test]
+    fn test_jobs_beyond_max_workers_are_queued() {
+        let mut pool = WorkerPool::new(2);
+        let (sender, receiver) = mpsc::channel();
+
+        for _ in 0..6 {
+            let sender = sender.clone();
+
+            pool.execute(move || {
+                thread::sleep(Duration::from_millis(20));
+                sender.send(thread::current().id()).unwrap();
+            });
+        }
+
+        drop(sender);
+
+        let thread_ids: Vec<_> = receiver.iter().collect();
+
+        assert_eq!(thread_ids.len(), 6);
+        assert_eq!(thread_ids.iter().collect::<HashSet<_>>().len(), 2);
+        assert_eq!(pool.workers, 2);
+    }
+}
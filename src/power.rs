@@ -0,0 +1,154 @@
+use std::io;
+#[cfg(not(target_os = "macos"))]
+use std::{fs, path::Path};
+
+// The power source of the machine.
+#[derive(Debug, PartialEq)]
+pub struct Power {
+    pub on_battery: bool,
+    // the charge of the batteries, in percent, if any
+    pub battery_level: Option<u8>,
+}
+
+// Reads the power source from the kernel.
+#[cfg(not(target_os = "macos"))]
+pub fn read() -> io::Result<Power> {
+    read_power_supplies(Path::new("/sys/class/power_supply"))
+}
+
+// Reads the power source from `pmset`.
+#[cfg(target_os = "macos")]
+pub fn read() -> io::Result<Power> {
+    let output = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()?;
+
+    Ok(parse_pmset(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// On battery if any battery is discharging, so that a machine without batteries is always on AC power.
+#[cfg(not(target_os = "macos"))]
+fn read_power_supplies(dir: &Path) -> io::Result<Power> {
+    let mut on_battery = false;
+    let mut levels = vec![];
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let read = |name| fs::read_to_string(path.join(name)).unwrap_or_default();
+
+        if read("type").trim() != "Battery" {
+            continue;
+        }
+
+        on_battery |= read("status").trim() == "Discharging";
+
+        if let Ok(level) = read("capacity").trim().parse::<u8>() {
+            levels.push(u32::from(level.min(100)));
+        }
+    }
+
+    let battery_level = match levels.len() {
+        0 => None,
+        count => Some((levels.iter().sum::<u32>() / count as u32) as u8),
+    };
+
+    Ok(Power {
+        on_battery,
+        battery_level,
+    })
+}
+
+// Parses the output of `pmset -g batt`, such as:
+// Now drawing from 'Battery Power'
+//  -InternalBattery-0 (id=1234567)	85%; discharging; 5:12 remaining present: true
+#[cfg(any(target_os = "macos", test))]
+fn parse_pmset(output: &str) -> Power {
+    let battery_level = output
+        .split_whitespace()
+        .find_map(|word| word.strip_suffix("%;")?.parse::<u8>().ok());
+
+    Power {
+        on_battery: output.contains("'Battery Power'"),
+        battery_level,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pmset() {
+        assert_eq!(
+            parse_pmset("Now drawing from 'Battery Power'\n -InternalBattery-0 (id=4653155)\t85%; discharging; 5:12 remaining present: true\n"),
+            Power { on_battery: true, battery_level: Some(85) }
+        );
+        assert_eq!(
+            parse_pmset("Now drawing from 'AC Power'\n -InternalBattery-0 (id=4653155)\t100%; charged; 0:00 remaining present: true\n"),
+            Power { on_battery: false, battery_level: Some(100) }
+        );
+        assert_eq!(
+            parse_pmset("Now drawing from 'AC Power'\n"),
+            Power {
+                on_battery: false,
+                battery_level: None
+            }
+        );
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn test_read_power_supplies() {
+        let dir = std::env::temp_dir().join(format!("every-power-{}", std::process::id()));
+
+        let supply = |name: &str, files: &[(&str, &str)]| {
+            fs::create_dir_all(dir.join(name)).unwrap();
+
+            for (file, contents) in files {
+                fs::write(dir.join(name).join(file), contents).unwrap();
+            }
+        };
+
+        supply("AC", &[("type", "Mains\n"), ("online", "0\n")]);
+        supply(
+            "BAT0",
+            &[
+                ("type", "Battery\n"),
+                ("status", "Discharging\n"),
+                ("capacity", "40\n"),
+            ],
+        );
+        supply(
+            "BAT1",
+            &[
+                ("type", "Battery\n"),
+                ("status", "Unknown\n"),
+                ("capacity", "60\n"),
+            ],
+        );
+
+        assert_eq!(
+            read_power_supplies(&dir).unwrap(),
+            Power {
+                on_battery: true,
+                battery_level: Some(50)
+            }
+        );
+
+        fs::write(dir.join("BAT0/status"), "Charging\n").unwrap();
+        assert!(!read_power_supplies(&dir).unwrap().on_battery);
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        fs::create_dir_all(&dir).unwrap();
+        assert_eq!(
+            read_power_supplies(&dir).unwrap(),
+            Power {
+                on_battery: false,
+                battery_level: None
+            }
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,109 @@
+use nix::fcntl::OFlag;
+use nix::libc;
+use nix::pty::{self, PtyMaster};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read};
+use std::mem::MaybeUninit;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+
+// The size of the terminal when ours is unknown, e.g. when the output is redirected.
+const DEFAULT_ROWS: u16 = 24;
+const DEFAULT_COLUMNS: u16 = 80;
+
+// The side of a pseudo-terminal read by every, on which the command's output arrives.
+pub struct Master(PtyMaster);
+
+// Opens a pseudo-terminal for a command to write its stdout & stderr to, with the size of our own terminal.
+// The slave side is given to the command, and must be closed once it is started, for the master to get EOF.
+pub fn open() -> io::Result<(Master, File)> {
+    // both sides are close-on-exec, so that they do not leak into the commands started concurrently
+    let master = pty::posix_openpt(OFlag::O_RDWR | OFlag::O_NOCTTY | OFlag::O_CLOEXEC)?;
+    pty::grantpt(&master)?;
+    pty::unlockpt(&master)?;
+
+    let slave = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY)
+        .open(slave_name(&master)?)?;
+
+    let size = window_size().unwrap_or(libc::winsize {
+        ws_row: DEFAULT_ROWS,
+        ws_col: DEFAULT_COLUMNS,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    });
+
+    // SAFETY: the pointer is valid for reads
+    if unsafe { libc::ioctl(slave.as_raw_fd(), libc::TIOCSWINSZ, &size) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok((Master(master), slave))
+}
+
+impl Read for Master {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match self.0.read(buffer) {
+            // returned by Linux once the slave side is closed by all the processes of the command
+            Err(e) if e.raw_os_error() == Some(libc::EIO) => Ok(0),
+            result => result,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn slave_name(master: &PtyMaster) -> io::Result<String> {
+    Ok(pty::ptsname_r(master)?)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn slave_name(master: &PtyMaster) -> io::Result<String> {
+    // the name is held in a static buffer, overwritten by concurrent calls
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    let _lock = LOCK.lock().unwrap();
+
+    // SAFETY: only called from one thread at a time
+    Ok(unsafe { pty::ptsname(master) }?)
+}
+
+// The size of the terminal on our stdout, or else on our stderr.
+fn window_size() -> Option<libc::winsize> {
+    [io::stdout().as_raw_fd(), io::stderr().as_raw_fd()]
+        .into_iter()
+        .find_map(|fd| {
+            let mut size = MaybeUninit::<libc::winsize>::zeroed();
+
+            // SAFETY: the pointer is valid for writes
+            match unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, size.as_mut_ptr()) } {
+                // SAFETY: filled by ioctl() on success
+                0 => Some(unsafe { size.assume_init() }).filter(|size| size.ws_col > 0),
+                _ => None,
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_open() {
+        let (mut master, slave) = open().unwrap();
+
+        let mut child = Command::new("sh")
+            .args(["-c", "[ -t 1 ] && [ -t 2 ] && echo tty"])
+            .stdout(slave.try_clone().unwrap())
+            .stderr(slave)
+            .spawn()
+            .unwrap();
+
+        let mut output = String::new();
+        master.read_to_string(&mut output).unwrap();
+
+        assert!(child.wait().unwrap().success());
+        assert_eq!(output, "tty\r\n");
+    }
+}
@@ -0,0 +1,64 @@
+use std::process;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// A xorshift64* generator: not cryptographically secure, but good enough to spread ticks.
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    // Seeds the generator from the current time and PID.
+    pub fn new() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+
+        Self::from_seed(nanos ^ (u64::from(process::id()) << 32))
+    }
+
+    pub fn from_seed(seed: u64) -> Self {
+        // the state must never be zero
+        Self { state: seed.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    // Returns a duration drawn uniformly between zero and max, both included, with microsecond precision.
+    pub fn duration_up_to(&mut self, max: Duration) -> Duration {
+        let micros = u128::from(self.next_u64()) % (max.as_micros() + 1);
+
+        // cannot be larger than the u64 it was drawn from
+        Duration::from_micros(micros as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_up_to() {
+        let mut random = Random::from_seed(42);
+        let max = Duration::from_micros(10);
+
+        let mut drawn = [false; 11];
+
+        for _ in 0..1_000 {
+            let duration = random.duration_up_to(max);
+
+            assert!(duration <= max, "duration: {duration:?}");
+            drawn[duration.as_micros() as usize] = true;
+        }
+
+        // all the values in the range are drawn, bounds included
+        assert_eq!(drawn, [true; 11]);
+        assert_eq!(random.duration_up_to(Duration::ZERO), Duration::ZERO);
+    }
+}
@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+/// A limit on the number of runs started per period of time.
+#[derive(Debug, PartialEq)]
+pub struct RateLimit {
+    pub runs: u32,
+    pub per: Duration,
+}
+
+// A token bucket holding up to `runs` tokens, refilled continuously at `runs` tokens per period.
+// Each run takes a token, so that bursts never exceed the limit.
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    tokens_per_second: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(limit: &RateLimit, now: Instant) -> Self {
+        let capacity = f64::from(limit.runs);
+
+        Self {
+            capacity,
+            tokens: capacity,
+            tokens_per_second: capacity / limit.per.as_secs_f64(),
+            last_refill: now,
+        }
+    }
+
+    // Takes a token if one is available.
+    pub fn try_take(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * self.tokens_per_second).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens < 1.0 {
+            return false;
+        }
+
+        self.tokens -= 1.0;
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        let limit = RateLimit {
+            runs: 3,
+            per: Duration::from_secs(3),
+        };
+
+        let mut bucket = TokenBucket::new(&limit, start);
+
+        // the bucket starts full
+        assert!(bucket.try_take(at(0)));
+        assert!(bucket.try_take(at(0)));
+        assert!(bucket.try_take(at(0)));
+        assert!(!bucket.try_take(at(0)));
+
+        // one token per second
+        assert!(!bucket.try_take(at(500)));
+        assert!(bucket.try_take(at(1_000)));
+        assert!(!bucket.try_take(at(1_500)));
+
+        // the bucket never holds more than its capacity
+        assert!(bucket.try_take(at(60_000)));
+        assert!(bucket.try_take(at(60_000)));
+        assert!(bucket.try_take(at(60_000)));
+        assert!(!bucket.try_take(at(60_000)));
+    }
+}
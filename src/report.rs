@@ -0,0 +1,152 @@
+use crate::history::{timestamp, Record};
+use crate::logger;
+use crate::notify::json_string;
+use crate::runner::ReportFormat;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+const CSV_HEADER: &str =
+    "run_id,command,scheduled_at,started_at,ended_at,duration,exit_code,exit_signal,user_time,system_time,max_rss\n";
+
+// Held while appending, so that the CSV header is only written once.
+static LOCK: Mutex<()> = Mutex::new(());
+
+// Appends the completed run to the report file, created if needed.
+pub fn append(path: &Path, format: ReportFormat, record: &Record) {
+    let _lock = LOCK.lock().unwrap();
+
+    if let Err(e) = try_append(path, format, record) {
+        logger::error(
+            &format!("Failed to write report file {}: {e}", path.display()),
+            &[],
+        );
+    }
+}
+
+fn try_append(path: &Path, format: ReportFormat, record: &Record) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    let line = match format {
+        ReportFormat::Csv if file.metadata()?.len() == 0 => {
+            format!("{CSV_HEADER}{}", to_csv(record))
+        }
+        ReportFormat::Csv => to_csv(record),
+        ReportFormat::Json => to_json(record),
+    };
+
+    // a single write, so that other processes appending to the file do not interleave
+    file.write_all(line.as_bytes())
+}
+
+fn to_csv(record: &Record) -> String {
+    let fields = [
+        record.run_id.to_string(),
+        csv_field(record.command),
+        timestamp(record.scheduled_at),
+        timestamp(record.started_at),
+        timestamp(record.started_at + record.duration),
+        format!("{:.3}", record.duration.as_secs_f64()),
+        optional(record.exit_code, String::new()),
+        optional(record.exit_signal, String::new()),
+        format!("{:.3}", record.usage.user_time.as_secs_f64()),
+        format!("{:.3}", record.usage.system_time.as_secs_f64()),
+        record.usage.max_rss.to_string(),
+    ];
+
+    format!("{}\n", fields.join(","))
+}
+
+// One object per line, in the JSON Lines format.
+fn to_json(record: &Record) -> String {
+    let mut json = format!(
+        r#"{{"run_id":{},"command":{},"scheduled_at":{},"started_at":{},"ended_at":{},"duration":{:.3},"exit_code":{},"exit_signal":{},"user_time":{:.3},"system_time":{:.3},"max_rss":{}}}"#,
+        record.run_id,
+        json_string(record.command),
+        timestamp(record.scheduled_at),
+        timestamp(record.started_at),
+        timestamp(record.started_at + record.duration),
+        record.duration.as_secs_f64(),
+        optional(record.exit_code, String::from("null")),
+        optional(record.exit_signal, String::from("null")),
+        record.usage.user_time.as_secs_f64(),
+        record.usage.system_time.as_secs_f64(),
+        record.usage.max_rss
+    );
+
+    json.push('\n');
+
+    json
+}
+
+fn optional(value: Option<i32>, none: String) -> String {
+    value.map_or(none, |value| value.to_string())
+}
+
+// Quotes the field if needed, doubling its quotes.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usage::Usage;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn record(command: &str) -> Record<'_> {
+        let scheduled_at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_000);
+
+        Record {
+            run_id: 3,
+            command,
+            scheduled_at,
+            started_at: scheduled_at + Duration::from_millis(2),
+            duration: Duration::from_millis(1500),
+            exit_code: Some(1),
+            exit_signal: None,
+            usage: Usage {
+                user_time: Duration::from_millis(1200),
+                system_time: Duration::from_millis(30),
+                max_rss: 4096,
+            },
+            output_bytes: None,
+            output: None,
+        }
+    }
+
+    #[test]
+    fn test_to_csv() {
+        assert_eq!(
+            to_csv(&record("date")),
+            "3,date,1700000000.000,1700000000.002,1700000001.502,1.500,1,,1.200,0.030,4096\n"
+        );
+
+        assert_eq!(
+            to_csv(&Record {
+                exit_code: None,
+                exit_signal: Some(9),
+                ..record(r#"echo "a, b""#)
+            }),
+            "3,\"echo \"\"a, b\"\"\",1700000000.000,1700000000.002,1700000001.502,1.500,,9,1.200,0.030,4096\n"
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            to_json(&record(r#"echo "hi""#)),
+            concat!(
+                r#"{"run_id":3,"command":"echo \"hi\"","scheduled_at":1700000000.000,"#,
+                r#""started_at":1700000000.002,"ended_at":1700000001.502,"duration":1.500,"#,
+                r#""exit_code":1,"exit_signal":null,"user_time":1.200,"system_time":0.030,"max_rss":4096}"#,
+                "\n"
+            )
+        );
+    }
+}
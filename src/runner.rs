@@ -0,0 +1,3059 @@
+use crate::archive;
+use crate::bell;
+use crate::capped::OutputCap;
+use crate::clock::{self, Date, LocalTime, TimeOfDay, TimeWindow, Weekdays};
+use crate::control;
+use crate::countdown;
+use crate::daemon;
+use crate::disk;
+use crate::exit::ExitReason;
+use crate::health;
+use crate::history::{self, Record};
+use crate::hooks;
+use crate::instances;
+use crate::interval::{format_duration, format_interval_range};
+use crate::jobfile;
+use crate::jobs::{JobSlot, Jobs, Reservation};
+use crate::keys;
+use crate::limits::Limits;
+use crate::load;
+use crate::logger::{self, Level, LogFormat};
+use crate::network;
+use crate::notify;
+#[cfg(feature = "webhook")]
+use crate::notify::Notification;
+#[cfg(feature = "otel")]
+use crate::otel;
+use crate::output::Output;
+use crate::pool::WorkerPool;
+use crate::power::{self, Power};
+use crate::pty;
+use crate::rate::{RateLimit, TokenBucket};
+use crate::report;
+use crate::scheduler::{Clock, Control, OnResume, Scheduler};
+use crate::signals;
+use crate::stall::Activity;
+use crate::stats::Stats;
+use crate::style::ColorChoice;
+use crate::syslog::{self, Priority};
+use crate::systemd;
+use crate::template::Placeholders;
+use crate::title;
+#[cfg(feature = "tui")]
+use crate::ui;
+use crate::usage::{format_size, Usage};
+use nix::errno::Errno;
+use nix::fcntl::{Flock, FlockArg};
+use nix::libc;
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+use regex::Regex;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, IsTerminal, PipeReader, Read, Write};
+use std::os::fd::AsFd;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::{self, Child, ChildStdin, Command, ExitStatus, Stdio};
+use std::sync::atomic::{self, AtomicBool, AtomicU16, AtomicU64};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// How far ahead to look for the next runs, as the time window and days may leave none.
+const PREVIEW_HORIZON: Duration = Duration::from_secs(366 * 24 * 3600);
+
+// The maximum number of threads running the commands, whatever the concurrency level:
+// the runs beyond it wait for one of them to complete.
+const MAX_WORKERS: usize = 256;
+
+// How often the detached commands are checked for exit, while any is running.
+const REAP_INTERVAL: Duration = Duration::from_millis(50);
+
+// The minimum time to wait for the --if command, whatever the interval: after half the interval, or this,
+// it is killed and the condition is not met.
+const MIN_CONDITION_WAIT: Duration = Duration::from_secs(1);
+
+// The detached commands, sent to the thread reaping all of them, started with the first one.
+static REAPER: OnceLock<Sender<Detached>> = OnceLock::new();
+
+#[derive(Debug, PartialEq)]
+pub struct Config {
+    pub interval: Duration,
+    /// If set, the time between two ticks is drawn uniformly between the interval and this maximum.
+    pub max_interval: Option<Duration>,
+    /// The intervals during daily windows of local time, the first matching one applying.
+    /// The interval applies outside of them.
+    pub interval_windows: Vec<IntervalWindow>,
+    pub concurrency: u16,
+    pub on_busy: OnBusy,
+    pub max_per: Option<RateLimit>,
+    /// The signal sent to the oldest command with `OnBusy::Kill`, and to the stalled commands.
+    pub kill_signal: Signal,
+    /// Send SIGKILL to a command still running this long after the kill signal.
+    pub kill_after: Option<Duration>,
+    /// Kill a command that has not written to its stdout or stderr for this long.
+    pub stall_timeout: Option<Duration>,
+    /// Truncate the output of a run beyond this number of bytes, stdout and stderr together.
+    pub max_output: Option<u64>,
+    /// Kill a command once its output goes beyond this number of bytes.
+    pub max_output_kill: Option<u64>,
+    /// Keep one instance of the command running, restarting it whenever it exits,
+    /// but no sooner than the interval after its previous start.
+    pub supervise: bool,
+    /// The lines of `--args-from`, one per run, substituted for `{arg}` in the command.
+    pub arg_lines: Option<Vec<String>>,
+    pub args_exhausted: ArgsExhausted,
+    /// Start the commands without waiting for them, nor counting them against the concurrency level.
+    pub detach: bool,
+    /// Start this number of instances of the command on each tick, with `EVERY_BATCH_INDEX` from 0.
+    pub batch: Option<u16>,
+    /// The maximum number of missed ticks to run back-to-back when behind schedule.
+    pub catch_up: u64,
+    pub clock: Clock,
+    pub on_resume: OnResume,
+    /// Wait until this local time before the first run.
+    pub at: Option<LocalTime>,
+    /// Stop once this local time is reached.
+    pub until: Option<LocalTime>,
+    /// Stop after this number of runs.
+    pub max_runs: Option<u64>,
+    /// The exit codes counted as successes, 0 by default. Other exit codes and signals are failures.
+    pub ok_codes: Vec<i32>,
+    /// Stop and exit with an error once this number of runs in a row have failed.
+    pub max_failures: Option<u64>,
+    /// Pause the scheduler after a number of runs in a row have failed.
+    pub cooldown: Option<Cooldown>,
+    /// What to do when a command cannot be started, e.g. because it does not exist.
+    pub on_spawn_error: OnSpawnError,
+    /// Stop once a run succeeds, and exit with an error if none did.
+    pub until_success: bool,
+    /// Stop once the output of a run matches, or no longer matches, a pattern,
+    /// and exit with an error if none did.
+    pub until_match: Option<UntilMatch>,
+    /// Skip the ticks while this file exists.
+    pub pause_file: Option<PathBuf>,
+    /// Skip the ticks while the 1-minute load average of the system is above this value.
+    pub max_load: Option<f64>,
+    /// Skip the ticks while the machine is on battery power.
+    pub only_on_ac: bool,
+    /// Skip the ticks while on battery power, and the battery is below this percentage.
+    pub min_battery: Option<u8>,
+    /// Skip the ticks while the filesystem containing a path has less free space than this.
+    pub min_free_space: Option<FreeSpace>,
+    /// Skip the ticks while the network is unreachable.
+    pub require_network: Option<NetworkCheck>,
+    /// Only run on the ticks where a GET of this URL returns a 2xx response, skipping the others.
+    pub if_healthy: Option<String>,
+    /// Only run on the ticks where this shell command exits with 0, skipping the others.
+    pub condition: Option<String>,
+    /// Skip the ticks outside of this daily window of local time.
+    pub between: Option<TimeWindow>,
+    /// Skip the ticks on other days of the week.
+    pub days: Option<Weekdays>,
+    /// Skip the ticks on these local dates, sorted.
+    pub skip_dates: Vec<Date>,
+    /// The IANA time zone of the local times, instead of the system one.
+    pub time_zone: Option<String>,
+    /// 0: inherit child output, 1: discard stdout, 2: discard stdout & stderr.
+    pub quiet: u8,
+    /// 0: warnings and errors, 1: scheduler events, 2: details of the runs.
+    pub verbose: u8,
+    /// The format of the messages of every on standard error.
+    pub log_format: LogFormat,
+    /// When to color the messages of every, and whether to force or disable the colors of the commands.
+    pub color: ColorChoice,
+    pub summary: bool,
+    pub heartbeat_file: Option<PathBuf>,
+    /// Run this shell command whenever a run fails, with variables describing the run.
+    pub on_failure: Option<String>,
+    /// Run this shell command whenever a run succeeds, with variables describing the run.
+    pub on_success: Option<String>,
+    /// Run this shell command whenever a tick is skipped, with variables describing the tick.
+    pub on_skip: Option<String>,
+    /// POST a JSON description of each failed run to this URL.
+    #[cfg(feature = "webhook")]
+    pub notify_url: Option<String>,
+    /// Also POST to the notification URL when a run succeeds after a failure.
+    #[cfg(feature = "webhook")]
+    pub notify_recovery: bool,
+    /// Raise a desktop notification when a run fails, or when the output condition is met.
+    pub notify_desktop: bool,
+    /// Ring the terminal bell when a run fails.
+    pub bell: bool,
+    /// Also invert the screen briefly when ringing the bell, with --watch or --tui.
+    pub flash: bool,
+    /// Export a span per run to the OpenTelemetry collector at this URL, over OTLP/HTTP.
+    #[cfg(feature = "otel")]
+    pub otel_endpoint: Option<String>,
+    /// Take an exclusive lock on this file for each run, skipping the tick if it is already locked.
+    pub lock_file: Option<PathBuf>,
+    /// Record each completed run into this SQLite database.
+    pub history: Option<PathBuf>,
+    /// Append a record of each completed run to this CSV or JSON Lines file.
+    pub report: Option<Report>,
+    /// Register the instance under this name, for `every status` and `every stop`.
+    pub name: Option<String>,
+    /// Refuse to start if an instance with the same name is already running.
+    pub single_instance: bool,
+    pub control_socket: Option<PathBuf>,
+    /// Print a line of statistics at this interval.
+    pub stats_every: Option<Duration>,
+    /// Write the PID of every to this file, removed on exit.
+    pub pid_file: Option<PathBuf>,
+    /// Append the output of every and of the commands to this file.
+    pub log: Option<PathBuf>,
+    /// Fork into the background, detached from the terminal.
+    pub daemon: bool,
+    /// Notify systemd of the startup, of each successful run, and of the shutdown.
+    pub systemd: bool,
+    /// Record the runs and the skipped ticks in the system log.
+    pub syslog: bool,
+    /// Read single-key commands from the terminal, when stdin and stdout are one.
+    pub keys: bool,
+    /// Show the time until the next run on a status line, when stderr is a terminal.
+    pub countdown: bool,
+    /// Show the command, the outcome of the last run and the time until the next one in the terminal title.
+    pub set_title: bool,
+    /// Show a live dashboard of the runs instead of their output.
+    #[cfg(feature = "tui")]
+    pub tui: bool,
+    /// The environment variables set on the command, on top of the inherited ones.
+    pub env: BTreeMap<String, String>,
+    /// Start the command with an empty environment, except for the variables of `env`.
+    pub clean_env: bool,
+    /// The niceness of the command, from -20 (highest priority) to 19 (lowest).
+    pub nice: Option<i32>,
+    pub limits: Limits,
+    /// The working directory of the command, instead of the current one.
+    pub cwd: Option<PathBuf>,
+    pub stdin: Input,
+    pub output: OutputMode,
+    pub stderr: ErrorOutput,
+    /// Write the output of each run to its own file in this directory, instead of our own output.
+    pub output_dir: Option<PathBuf>,
+    /// Only keep this number of run files in the output directory.
+    pub keep_last: Option<usize>,
+    /// Highlight the changes in the output, compared to the last successful run.
+    pub diff: bool,
+    /// Run the command on a pseudo-terminal, that its stdout & stderr are both written to.
+    pub pty: bool,
+    pub command: String,
+    pub args: Vec<String>,
+    /// The commands of a jobs file, run instead of the command.
+    pub jobs: Vec<Job>,
+    /// The jobs file to read the jobs from again on SIGHUP, if they all come from it.
+    pub jobs_file: Option<PathBuf>,
+}
+
+/// A command of a jobs file.
+#[derive(Debug, PartialEq)]
+pub struct Job {
+    /// The command line, run through `sh -c`.
+    pub command: String,
+    /// Run on its own interval, independently of the global one, instead of on every tick.
+    pub interval: Option<Duration>,
+    /// The maximum number of runs of this command at once, within the global concurrency level.
+    pub concurrency: Option<u16>,
+    /// Take turns with the other rotating jobs, one per tick, instead of running on every tick.
+    pub rotate: bool,
+}
+
+/// What to do once all the lines of `--args-from` have been taken.
+#[derive(Debug, PartialEq)]
+pub enum ArgsExhausted {
+    /// Start over from the first line.
+    Wrap,
+    Stop,
+}
+
+/// What to do on a tick when the concurrency limit is reached.
+#[derive(Debug, PartialEq)]
+pub enum OnBusy {
+    Skip,
+    /// Run the tick as soon as a command completes, unless that many ticks are already queued.
+    Queue(usize),
+    /// Kill the oldest running command, and run the tick as soon as it exits.
+    Kill,
+}
+
+/// What to do when a command cannot be started.
+#[derive(Debug, PartialEq)]
+pub enum OnSpawnError {
+    /// Stop after the first run that cannot be started, and exit with an error.
+    Exit,
+    /// Keep trying on the next ticks, each run that cannot be started being a failed run.
+    Retry,
+    /// Keep trying on the next ticks, without counting the runs that cannot be started as failed runs.
+    Ignore,
+}
+
+/// A circuit breaker: once `after` runs in a row have failed, the scheduler is paused
+/// for the duration, then resumed for a single try until a run succeeds.
+#[derive(Debug, PartialEq)]
+pub struct Cooldown {
+    pub duration: Duration,
+    pub after: u64,
+}
+
+/// A condition on the lines of the output of a run, stdout and stderr alike.
+#[derive(Debug)]
+pub struct UntilMatch {
+    pub pattern: Regex,
+    /// Whether the condition is met when a line matches the pattern, or when none does.
+    pub matches: bool,
+}
+
+impl UntilMatch {
+    fn is_met(&self, output: &Output) -> bool {
+        let found = [&output.stdout, &output.stderr].iter().any(|output| {
+            String::from_utf8_lossy(output)
+                .lines()
+                .any(|line| self.pattern.is_match(line))
+        });
+
+        found == self.matches
+    }
+}
+
+impl PartialEq for UntilMatch {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern.as_str() == other.pattern.as_str() && self.matches == other.matches
+    }
+}
+
+/// Where the standard input of each command comes from.
+#[derive(Debug, PartialEq)]
+pub enum Input {
+    Null,
+    Inherit,
+    Data(String),
+    /// The file is opened again for each run.
+    File(PathBuf),
+}
+
+/// Where the standard error of each command goes.
+#[derive(Debug, PartialEq)]
+pub enum ErrorOutput {
+    /// Our own standard error, captured along with the standard output when needed.
+    Separate,
+    /// The standard output, in the order in which they were written.
+    Merge,
+    Discard,
+    /// The file is opened again for each run, and appended to.
+    File(PathBuf),
+}
+
+/// An interval that applies during a daily window of local time, parsed from `09:00-18:00=30s`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IntervalWindow {
+    pub window: TimeWindow,
+    pub interval: Duration,
+}
+
+/// A minimum of free space on the filesystem containing a path.
+#[derive(Debug, PartialEq)]
+pub struct FreeSpace {
+    pub bytes: u64,
+    pub path: PathBuf,
+}
+
+/// How `--require-network` tells that the network is reachable.
+#[derive(Debug, PartialEq)]
+pub enum NetworkCheck {
+    /// The machine has a default route.
+    DefaultRoute,
+    /// A TCP connection to this `host:port` succeeds.
+    Connect(String),
+}
+
+/// A file with a record of each completed run.
+#[derive(Debug, PartialEq)]
+pub struct Report {
+    pub path: PathBuf,
+    pub format: ReportFormat,
+}
+
+/// The format of the report file, from its extension.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ReportFormat {
+    /// A CSV file, with a header line.
+    Csv,
+    /// A JSON Lines file, with one object per line.
+    Json,
+}
+
+/// What to do with the output of each command.
+#[derive(Debug, PartialEq)]
+pub enum OutputMode {
+    /// The command writes directly to the standard output and error.
+    Inherit,
+    /// The output is captured, and only printed if the run fails.
+    OnFailure,
+    /// The output is captured, and only printed if it differs from the last successful run.
+    Changes,
+    /// The output is captured, and printed on a cleared screen below a header.
+    Watch,
+}
+
+/// The defaults match the command line defaults.
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            max_interval: None,
+            interval_windows: vec![],
+            concurrency: 1,
+            on_busy: OnBusy::Skip,
+            max_per: None,
+            kill_signal: Signal::SIGKILL,
+            kill_after: None,
+            stall_timeout: None,
+            max_output: None,
+            max_output_kill: None,
+            arg_lines: None,
+            args_exhausted: ArgsExhausted::Stop,
+            supervise: false,
+            detach: false,
+            batch: None,
+            catch_up: 0,
+            clock: Clock::Monotonic,
+            on_resume: OnResume::Warn,
+            at: None,
+            until: None,
+            max_runs: None,
+            ok_codes: vec![0],
+            max_failures: None,
+            cooldown: None,
+            on_spawn_error: OnSpawnError::Exit,
+            until_success: false,
+            until_match: None,
+            pause_file: None,
+            max_load: None,
+            only_on_ac: false,
+            min_battery: None,
+            min_free_space: None,
+            require_network: None,
+            if_healthy: None,
+            condition: None,
+            between: None,
+            days: None,
+            skip_dates: vec![],
+            time_zone: None,
+            quiet: 0,
+            verbose: 0,
+            log_format: LogFormat::Text,
+            color: ColorChoice::Auto,
+            summary: false,
+            heartbeat_file: None,
+            on_failure: None,
+            on_success: None,
+            on_skip: None,
+            #[cfg(feature = "webhook")]
+            notify_url: None,
+            #[cfg(feature = "webhook")]
+            notify_recovery: false,
+            notify_desktop: false,
+            bell: false,
+            flash: false,
+            #[cfg(feature = "otel")]
+            otel_endpoint: None,
+            lock_file: None,
+            history: None,
+            report: None,
+            name: None,
+            single_instance: false,
+            control_socket: None,
+            stats_every: None,
+            pid_file: None,
+            log: None,
+            daemon: false,
+            systemd: false,
+            syslog: false,
+            keys: true,
+            countdown: false,
+            set_title: false,
+            #[cfg(feature = "tui")]
+            tui: false,
+            env: BTreeMap::new(),
+            clean_env: false,
+            nice: None,
+            limits: Limits::default(),
+            cwd: None,
+            stdin: Input::Null,
+            stderr: ErrorOutput::Separate,
+            output: OutputMode::Inherit,
+            output_dir: None,
+            keep_last: None,
+            diff: false,
+            pty: false,
+            command: String::new(),
+            args: vec![],
+            jobs: vec![],
+            jobs_file: None,
+        }
+    }
+}
+
+/// Prints the local times of the next runs.
+pub fn preview(config: Config, count: usize) {
+    if let Some(time_zone) = &config.time_zone {
+        clock::set_time_zone(time_zone);
+    }
+
+    let subsecond = std::iter::once(config.interval)
+        .chain(config.interval_windows.iter().map(|window| window.interval))
+        .any(|interval| interval.subsec_nanos() != 0);
+
+    for time in config.next_runs(SystemTime::now()).take(count) {
+        let mut line = clock::format_local(time, "%a %Y-%m-%d %H:%M:%S");
+
+        if subsecond {
+            let millis = time
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .subsec_millis();
+            line.push_str(&format!(".{millis:03}"));
+        }
+
+        println!("{line}");
+    }
+}
+
+/// Runs the command on every tick of the interval, until stopped or terminated by a signal,
+/// and returns why it stopped, to exit with. An error that keeps it from starting is logged first.
+pub fn run(config: Config) -> Result<ExitReason, String> {
+    let level = match (config.verbose, config.quiet) {
+        (2.., _) => Level::Debug,
+        (1, _) => Level::Info,
+        (0, 0) => Level::Warning,
+        (0, 1..) => Level::Error,
+    };
+
+    // our standard error is redirected to the log file later on
+    let colored = config
+        .color
+        .enabled(io::stderr().is_terminal() && config.log.is_none());
+
+    logger::init(level, config.log_format, colored);
+
+    // checked before forking into the background, so that the error is reported on the terminal
+    if let (Some(name), true) = (&config.name, config.single_instance) {
+        if let Err(e) = instances::check_name(name) {
+            return fail_to_start(e);
+        }
+    }
+
+    if let Some(log) = &config.log {
+        if let Err(e) = start_logging(log, config.daemon) {
+            return fail_to_start(e);
+        }
+    }
+
+    // before any thread is started
+    if let Some(time_zone) = &config.time_zone {
+        clock::set_time_zone(time_zone);
+    }
+
+    let interval = config.interval;
+
+    let jobs = Jobs::new();
+    let stats = Arc::new(Mutex::new(Stats::new()));
+    let last_output = Arc::new(Mutex::new(None));
+    let config = Arc::new(config);
+    // set on SIGHUP, for the jobs file to be read again on the next tick
+    let reload = Arc::new(AtomicBool::new(false));
+
+    // blocked first, so that the thread of the termination signals does not get it either
+    if config.jobs_file.is_some() {
+        let reload = Arc::clone(&reload);
+
+        signals::on_hangup(move || reload.store(true, atomic::Ordering::SeqCst));
+    }
+
+    {
+        let config = Arc::clone(&config);
+        let stats = Arc::clone(&stats);
+        let jobs = Arc::clone(&jobs);
+
+        signals::on_termination(move || shutdown(&config, &stats, &jobs));
+    }
+
+    let mut scheduler = Scheduler::new(interval)
+        .catch_up(config.catch_up)
+        .clock(config.clock)
+        .on_resume(config.on_resume);
+
+    if let Some(max_interval) = config.max_interval {
+        scheduler = scheduler.max_interval(max_interval);
+    }
+
+    // in the order of the schedules of the tasks
+    let schedules: Vec<Duration> = config.jobs.iter().filter_map(|job| job.interval).collect();
+
+    for &interval in &schedules {
+        scheduler = scheduler.schedule(interval);
+    }
+
+    if let Some(at) = config.at {
+        logger::info(&format!("Waiting until {at} for the first run"), &[]);
+
+        scheduler = scheduler.start_at(at.resolve(SystemTime::now()));
+    }
+
+    if let Some(until) = config.until {
+        scheduler = scheduler.until(until.resolve(SystemTime::now()));
+    }
+
+    if let Some(pid_file) = &config.pid_file {
+        if let Err(e) = fs::write(pid_file, format!("{}\n", process::id())) {
+            return fail_to_start(format!(
+                "Failed to write PID file {}: {e}",
+                pid_file.display()
+            ));
+        }
+    }
+
+    let mut pool = WorkerPool::new(usize::from(config.concurrency).min(MAX_WORKERS));
+
+    if let Some(control_socket) = &config.control_socket {
+        let controls = scheduler.controller();
+        let stats = Arc::clone(&stats);
+        let jobs = Arc::clone(&jobs);
+
+        let result = control::listen(control_socket, controls, move || report(&stats, &jobs));
+
+        if let Err(e) = result {
+            return fail_to_start(format!(
+                "Failed to create control socket {}: {e}",
+                control_socket.display()
+            ));
+        }
+    }
+
+    {
+        let controls = scheduler.controller();
+        let stats = Arc::clone(&stats);
+        let jobs = Arc::clone(&jobs);
+        let command_line = config.command_line();
+
+        let result = instances::register(controls, move || {
+            let mut lines = report(&stats, &jobs);
+            lines.push(format!("Command:       {command_line}"));
+            lines
+        });
+
+        if let Err(e) = result {
+            logger::warning(&format!("Failed to register the instance: {e}"), &[]);
+        }
+    }
+
+    if let Some(name) = &config.name {
+        if let Err(e) = instances::register_name(name, config.single_instance) {
+            instances::unregister();
+            return fail_to_start(e);
+        }
+    }
+
+    // the root span, covering the whole lifetime of the scheduler
+    #[cfg(feature = "otel")]
+    if let Some(endpoint) = &config.otel_endpoint {
+        otel::start(endpoint, config.command_line());
+    }
+
+    #[cfg(feature = "tui")]
+    let events = if config.tui {
+        let title = format!(
+            "Every {}: {}",
+            format_interval_range(interval, config.max_interval),
+            config.command_line()
+        );
+
+        match ui::start(title, scheduler.controller()) {
+            Ok(events) => Some(events),
+            Err(e) => return fail_to_start(format!("Failed to start the dashboard: {e}")),
+        }
+    } else {
+        None
+    };
+
+    let mut bucket = config
+        .max_per
+        .as_ref()
+        .map(|limit| TokenBucket::new(limit, Instant::now()));
+
+    if config.syslog {
+        if let Err(e) = syslog::connect() {
+            return fail_to_start(format!("Failed to connect to the system log: {e}"));
+        }
+    }
+
+    if config.systemd {
+        if let Err(e) = systemd::connect() {
+            return fail_to_start(format!("Failed to connect to systemd: {e}"));
+        }
+
+        systemd::notify("READY=1");
+    }
+
+    if config.keys && reads_keys(&config) {
+        let controls = scheduler.controller();
+        let stats = Arc::clone(&stats);
+        let jobs = Arc::clone(&jobs);
+
+        if let Err(e) = keys::start(controls, move || report(&stats, &jobs)) {
+            logger::warning(&format!("Failed to read keys from the terminal: {e}"), &[]);
+        }
+    }
+
+    if let Some(stats_every) = config.stats_every {
+        let stats = Arc::clone(&stats);
+
+        thread::spawn(move || loop {
+            thread::sleep(stats_every);
+
+            logger::notice(
+                &stats.lock().unwrap_or_else(PoisonError::into_inner).line(),
+                &[],
+            );
+        });
+    }
+
+    if shows_countdown(&config) {
+        countdown::start(scheduler.controller(), Arc::clone(&jobs));
+    }
+
+    if config.set_title && io::stderr().is_terminal() {
+        title::start(scheduler.controller(), config.command_line());
+    }
+
+    let mut tasks = Arc::new(config.tasks());
+    // the indexes of the tasks that take turns, one per tick
+    let mut rotation = rotation_of(&tasks);
+    // the number of ticks of the interval so far, skipped ones included, to take turns
+    let mut turn = 0;
+    let controls = scheduler.controller();
+    let progress = Arc::new(Progress::default());
+    let mut runs = 0;
+    // the number of lines of --args-from taken so far
+    let mut arg_lines_taken = 0;
+    // the interval of the window of --interval-between of the last tick, and the time until the next one
+    let mut current_interval = interval;
+    let mut current_gap = interval;
+
+    scheduler.run(|tick| {
+        if let Some(jobs_file) = &config.jobs_file {
+            if reload.swap(false, atomic::Ordering::SeqCst) {
+                // the runs in progress keep the tasks they were started with
+                if let Some(reloaded) = reload_tasks(jobs_file, &tasks, &schedules) {
+                    tasks = Arc::new(reloaded);
+                    rotation = rotation_of(&tasks);
+                }
+            }
+        }
+
+        // the interval may only drive the jobs with their own interval
+        if !tasks.iter().any(|task| task.schedule == tick.schedule) {
+            return;
+        }
+
+        if tick.schedule == 0 {
+            turn += tick.skipped + 1;
+        }
+
+        if !tick.suspended.is_zero() {
+            let message = format!(
+                "Resumed from a suspend of {}",
+                format_duration(tick.suspended)
+            );
+
+            match config.on_resume {
+                OnResume::Warn => logger::warning(&message, &[]),
+                _ => logger::info(&message, &[]),
+            }
+        }
+
+        if tick.skipped > 0 {
+            stats
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .record_skipped_ticks(tick.skipped);
+
+            let message = format!(
+                "Behind schedule by {}, skipped {}",
+                format_duration(tick.behind),
+                plural(tick.skipped, "tick")
+            );
+
+            logger::info(&message, &[("run_id", tick.number as i64)]);
+
+            syslog::log(Priority::Notice, &message, &[]);
+        }
+
+        if !config.interval_windows.is_empty() && tick.schedule == 0 {
+            let window_interval = config.interval_at(tick.scheduled_at);
+
+            if window_interval != current_interval {
+                logger::info(
+                    &format!("Interval set to {}", format_duration(window_interval)),
+                    &[("run_id", tick.number as i64)],
+                );
+
+                current_interval = window_interval;
+            }
+
+            // only sent when it changes, so that the interval may still be changed by hand
+            let gap = config.gap_after(tick.scheduled_at);
+
+            if gap != current_gap {
+                let _ = controls.send(Control::SetInterval(gap));
+                current_gap = gap;
+            }
+        }
+
+        // the command is restarted once it exits, and the ticks in the meantime only throttle the restarts
+        if config.supervise && !jobs.is_idle() {
+            return;
+        }
+
+        logger::info(
+            &format!("Tick #{} fired", tick.number),
+            &[("run_id", tick.number as i64)],
+        );
+
+        if config.skips_date_of(SystemTime::now()) {
+            skip_tick(&config, &stats, tick.number, "on a skipped date");
+            return;
+        }
+
+        if !config.allows_run_at(SystemTime::now()) {
+            skip_tick(
+                &config,
+                &stats,
+                tick.number,
+                "outside the allowed time window",
+            );
+            return;
+        }
+
+        if let Some(reason) = unmet_precondition(&config) {
+            skip_tick(&config, &stats, tick.number, &reason);
+            return;
+        }
+
+        let limit = config.concurrency.into();
+
+        // the concurrency and rate limits are shared by all the commands
+        'tasks: for index in 0..tasks.len() {
+            let task = &tasks[index];
+
+            if task.schedule != tick.schedule {
+                continue;
+            }
+
+            if task.rotate && rotation[((turn - 1) % rotation.len() as u64) as usize] != index {
+                continue;
+            }
+
+            // once an instance of the batch cannot start, the rest of the batch is skipped too
+            for batch_index in 0..config.batch.unwrap_or(1) {
+                // the scheduler may fire caught-up ticks before handling the stop
+                if config.max_runs.is_some_and(|max_runs| runs >= max_runs) {
+                    return;
+                }
+
+                if args_exhausted(&config, arg_lines_taken) {
+                    return;
+                }
+
+                let stats = Arc::clone(&stats);
+
+                if config.detach {
+                    if let Some(bucket) = &mut bucket {
+                        if !bucket.try_take(Instant::now()) {
+                            skip_tick(&config, &stats, tick.number, "rate limit reached");
+                            continue 'tasks;
+                        }
+                    }
+
+                    runs += 1;
+
+                    if config.max_runs == Some(runs) {
+                        logger::info(&format!("Started {}, stopping", plural(runs, "run")), &[]);
+
+                        let _ = controls.send(Control::Stop);
+                    }
+
+                    let env: Vec<_> = config
+                        .batch
+                        .map(|_| ("EVERY_BATCH_INDEX", batch_index.to_string()))
+                        .into_iter()
+                        .collect();
+
+                    let placeholders = Placeholders::new(tick.number, tick.scheduled_at)
+                        .with_arg(take_arg_line(&config, &mut arg_lines_taken, &controls))
+                        .quoted(task.shell);
+
+                    stats
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .record_start();
+
+                    if !detach(&config, &task.argv, &env, tick.number, placeholders, &stats) {
+                        on_spawn_error(&config, &progress, &controls, tick.number);
+                    }
+
+                    continue;
+                }
+
+                if let Some(concurrency) = task.concurrency {
+                    let running = task.running.load(atomic::Ordering::SeqCst);
+
+                    if running >= concurrency {
+                        let reason =
+                            format!("{} already running", plural(running.into(), "command"));
+                        skip_tick(&config, &stats, tick.number, &reason);
+                        continue 'tasks;
+                    }
+                }
+
+                let reservation = match config.on_busy {
+                    OnBusy::Skip => jobs.try_reserve(limit).map(Reservation::Slot),
+                    OnBusy::Queue(max_queued) => jobs.reserve_or_queue(limit, max_queued),
+                    OnBusy::Kill => jobs.try_reserve(limit).map(Reservation::Slot).or_else(|_| {
+                        if let Some(pid) = jobs.kill_oldest(config.kill_signal, config.kill_after) {
+                            logger::info(
+                                &format!("Tick #{}: killed command with PID {pid}", tick.number),
+                                &[("run_id", tick.number as i64), ("pid", i64::from(pid))],
+                            );
+                        }
+
+                        // wait for the slot of the killed command
+                        jobs.reserve_or_queue(limit, limit)
+                    }),
+                };
+
+                let reservation = match reservation {
+                    Ok(reservation) => reservation,
+                    Err(count) => {
+                        let reason = match config.on_busy {
+                            OnBusy::Skip => {
+                                format!("{} already running", plural(count as u64, "command"))
+                            }
+                            OnBusy::Queue(_) | OnBusy::Kill => {
+                                format!("{} already queued", plural(count as u64, "tick"))
+                            }
+                        };
+
+                        skip_tick(&config, &stats, tick.number, &reason);
+                        continue 'tasks;
+                    }
+                };
+
+                let lock = match &config.lock_file {
+                    Some(path) => match lock(path) {
+                        Ok(Some(lock)) => Some(lock),
+                        Ok(None) => {
+                            skip_tick(&config, &stats, tick.number, "lock file already locked");
+                            continue 'tasks;
+                        }
+                        Err(e) => {
+                            let reason = format!("failed to lock {}: {e}", path.display());
+                            skip_tick(&config, &stats, tick.number, &reason);
+                            continue 'tasks;
+                        }
+                    },
+                    None => None,
+                };
+
+                if let Some(bucket) = &mut bucket {
+                    if !bucket.try_take(Instant::now()) {
+                        skip_tick(&config, &stats, tick.number, "rate limit reached");
+                        continue 'tasks;
+                    }
+                }
+
+                if matches!(reservation, Reservation::Queued(_)) {
+                    logger::info(
+                        &format!("Tick #{} queued", tick.number),
+                        &[("run_id", tick.number as i64)],
+                    );
+                }
+
+                runs += 1;
+
+                if config.max_runs == Some(runs) {
+                    logger::info(&format!("Started {}, stopping", plural(runs, "run")), &[]);
+
+                    let _ = controls.send(Control::Stop);
+                }
+
+                let arg = take_arg_line(&config, &mut arg_lines_taken, &controls);
+
+                let config = Arc::clone(&config);
+                let last_output = Arc::clone(&last_output);
+                #[cfg(feature = "tui")]
+                let events = events.clone();
+
+                // counted as running by its task from now on, for the concurrency of the next ticks
+                let mut accounting = Accounting::new(&task.running, &stats);
+
+                let tasks = Arc::clone(&tasks);
+                let controls = controls.clone();
+                let progress = Arc::clone(&progress);
+                let tick_number = tick.number;
+                let scheduled_at = tick.scheduled_at;
+
+                let handle_run = move || {
+                    let mut slot = reservation.into_slot();
+
+                    let skipped_before = accounting.start();
+
+                    #[cfg(feature = "tui")]
+                    if let Some(events) = &events {
+                        let _ = events.send(ui::Event::Started {
+                            tick: tick_number,
+                            started_at: SystemTime::now(),
+                        });
+                    }
+
+                    let task = &tasks[index];
+
+                    #[cfg(feature = "otel")]
+                    let span = otel::Span::start();
+                    #[cfg(feature = "otel")]
+                    let mut env = span.env();
+                    #[cfg(not(feature = "otel"))]
+                    let mut env = vec![];
+
+                    if config.batch.is_some() {
+                        env.push(("EVERY_BATCH_INDEX", batch_index.to_string()));
+                    }
+
+                    let run = execute(
+                        &config,
+                        &task.argv,
+                        &env,
+                        tick_number,
+                        Placeholders::new(tick_number, scheduled_at)
+                            .with_arg(arg)
+                            .quoted(task.shell),
+                        &mut slot,
+                        &last_output,
+                    );
+
+                    if slot.was_stopped() {
+                        return;
+                    }
+
+                    #[cfg(feature = "otel")]
+                    span.end(
+                        tick_number,
+                        &task.command_line,
+                        run.as_ref().map(|run| (run.status, run.succeeded)),
+                    );
+
+                    // for the duration of the run only
+                    drop(lock);
+
+                    accounting.end(run.as_ref());
+                    title::record(run.as_ref().map(|run| run.status));
+
+                    #[cfg(feature = "tui")]
+                    if let Some(events) = &events {
+                        let _ = events.send(ui::Event::Finished {
+                            tick: tick_number,
+                            run: run.as_ref().map(|run| (run.status, run.duration)),
+                        });
+                    }
+
+                    if run.is_none() && !on_spawn_error(&config, &progress, &controls, tick_number)
+                    {
+                        return;
+                    }
+
+                    if let Some(run) = &run {
+                        let skipped = stats
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .skipped_ticks()
+                            - skipped_before;
+                        report_overrun(&config, task, tick_number, run, skipped);
+                    }
+
+                    if config.until_success && run.as_ref().is_some_and(|run| run.succeeded) {
+                        logger::info(
+                            &format!("Run #{tick_number} succeeded, stopping"),
+                            &[("run_id", tick_number as i64)],
+                        );
+
+                        progress.condition_met.store(true, atomic::Ordering::SeqCst);
+                        let _ = controls.send(Control::Stop);
+                    }
+
+                    if run.as_ref().is_some_and(|run| run.output_matched) {
+                        logger::info(
+                            &format!("Run #{tick_number} met the output condition, stopping"),
+                            &[("run_id", tick_number as i64)],
+                        );
+
+                        if config.notify_desktop {
+                            notify::desktop("every: output condition met", &task.command_line);
+                        }
+
+                        progress.condition_met.store(true, atomic::Ordering::SeqCst);
+                        let _ = controls.send(Control::Stop);
+                    }
+
+                    let (name, hook) = match &run {
+                        Some(run) if run.succeeded => ("on-success", &config.on_success),
+                        _ => ("on-failure", &config.on_failure),
+                    };
+
+                    if let Some(hook) = hook {
+                        hooks::spawn(name, hook, hook_env(tick_number, run.as_ref()));
+                    }
+
+                    let failure = failure_reason(run.as_ref());
+                    let succeeded = failure.is_none();
+                    *progress.last_failure.lock().unwrap() = failure;
+                    #[cfg(feature = "webhook")]
+                    let was_failing = task.failing.swap(!succeeded, atomic::Ordering::SeqCst);
+
+                    count_failures(&config, &progress, &controls, failure);
+
+                    if config.bell && !succeeded {
+                        ring_bell(&config);
+                    }
+
+                    if config.notify_desktop && !succeeded {
+                        let outcome = match &run {
+                            Some(run) => format!("exited with {}", run.status),
+                            None => String::from("could not be started"),
+                        };
+
+                        notify::desktop(
+                            &format!("every: run #{tick_number} failed"),
+                            &format!("{} {outcome}", task.command_line),
+                        );
+                    }
+
+                    if let Some(run) = &run {
+                        let record = Record {
+                            run_id: tick_number,
+                            command: &task.command_line,
+                            scheduled_at,
+                            started_at: run.started_at,
+                            duration: run.duration,
+                            exit_code: run.status.code(),
+                            exit_signal: run.status.signal(),
+                            usage: run.usage,
+                            output_bytes: run.output_bytes,
+                            output: run.output_text.as_deref(),
+                        };
+
+                        if let Some(path) = &config.history {
+                            history::record(path, &record);
+                        }
+
+                        if let Some(report) = &config.report {
+                            report::append(&report.path, report.format, &record);
+                        }
+                    }
+
+                    #[cfg(feature = "webhook")]
+                    if let Some(url) = &config.notify_url {
+                        let event = match (succeeded, was_failing) {
+                            (false, _) => Some("failure"),
+                            (true, true) if config.notify_recovery => Some("recovery"),
+                            (true, _) => None,
+                        };
+
+                        if let Some(event) = event {
+                            let notification = Notification {
+                                event,
+                                command: &task.command_line,
+                                run_id: tick_number,
+                                exit_code: run.as_ref().and_then(|run| run.status.code()),
+                                duration: run.as_ref().map(|run| run.duration),
+                                output: run.as_ref().and_then(|run| run.output_text.as_deref()),
+                            };
+
+                            notify::send(url, &notification);
+                        }
+                    }
+
+                    let restarts_now = config.supervise
+                        && run
+                            .as_ref()
+                            .is_some_and(|run| run.duration >= config.interval);
+
+                    // or else on the next tick, no sooner than the interval after the start of the run
+                    if restarts_now {
+                        drop(slot);
+                        let _ = controls.send(Control::RunNow);
+                    }
+                };
+
+                // the slot and the accounting of the run are released while unwinding
+                pool.execute(move || {
+                    if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(handle_run)) {
+                        logger::error(
+                            &format!(
+                                "Run #{tick_number} failed, every panicked: {}",
+                                panic_message(&*panic)
+                            ),
+                            &[("run_id", tick_number as i64)],
+                        );
+                    }
+                });
+            }
+        }
+    });
+
+    // stopped: wait for the running commands to complete, then for their hooks
+    while !jobs.is_idle() {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    hooks::wait();
+
+    shutdown(&config, &stats, &jobs);
+
+    if let Some(failure) = *progress.stopped_by_failure.lock().unwrap() {
+        return Ok(failure);
+    }
+
+    let waits_for_condition = config.until_success || config.until_match.is_some();
+    let last_failure = *progress.last_failure.lock().unwrap();
+
+    if waits_for_condition && !progress.condition_met.load(atomic::Ordering::SeqCst) {
+        return Ok(last_failure.unwrap_or(ExitReason::ConditionNotMet));
+    }
+
+    // as run by hand, the last of the -n runs tells how it went
+    if let (Some(_), Some(failure)) = (config.max_runs, last_failure) {
+        return Ok(failure);
+    }
+
+    Ok(ExitReason::Success)
+}
+
+// Logs the error that keeps every from starting, and returns it.
+fn fail_to_start(error: String) -> Result<ExitReason, String> {
+    logger::error(&error, &[]);
+    Err(error)
+}
+
+// Why the run failed, to exit with: None if it succeeded. The run is None if the command could not be started.
+fn failure_reason(run: Option<&Run>) -> Option<ExitReason> {
+    let Some(run) = run else {
+        return Some(ExitReason::SpawnFailed);
+    };
+
+    if run.succeeded {
+        return None;
+    }
+
+    if run.killed {
+        return Some(ExitReason::Killed);
+    }
+
+    match (run.status.code(), run.status.signal()) {
+        // not in --ok-codes
+        (Some(0), _) => Some(ExitReason::Error),
+        (Some(code), _) => Some(ExitReason::Command(code)),
+        // as reported by shells
+        (None, Some(signal)) => Some(ExitReason::Command(128 + signal)),
+        (None, None) => Some(ExitReason::Error),
+    }
+}
+
+// Applies --on-spawn-error to a run that could not be started,
+// and returns whether it is handled as a failed run.
+fn on_spawn_error(
+    config: &Config,
+    progress: &Progress,
+    controls: &Sender<Control>,
+    tick_number: u64,
+) -> bool {
+    match config.on_spawn_error {
+        OnSpawnError::Exit => {
+            logger::error(
+                &format!("Run #{tick_number} could not be started, stopping"),
+                &[("run_id", tick_number as i64)],
+            );
+
+            progress
+                .stopped_by_failure
+                .lock()
+                .unwrap()
+                .get_or_insert(ExitReason::SpawnFailed);
+            let _ = controls.send(Control::Stop);
+
+            true
+        }
+        OnSpawnError::Retry => true,
+        OnSpawnError::Ignore => false,
+    }
+}
+
+// Counts the failed runs in a row, to stop with --max-failures, or pause with --cooldown.
+fn count_failures(
+    config: &Arc<Config>,
+    progress: &Arc<Progress>,
+    controls: &Sender<Control>,
+    failure: Option<ExitReason>,
+) {
+    let Some(failure) = failure else {
+        progress
+            .consecutive_failures
+            .store(0, atomic::Ordering::SeqCst);
+        return;
+    };
+
+    let failures = progress
+        .consecutive_failures
+        .fetch_add(1, atomic::Ordering::SeqCst)
+        + 1;
+
+    if config.max_failures == Some(failures) {
+        logger::error(
+            &format!("Stopping after {} in a row", plural(failures, "failure")),
+            &[],
+        );
+
+        *progress.stopped_by_failure.lock().unwrap() = Some(failure);
+        let _ = controls.send(Control::Stop);
+
+        return;
+    }
+
+    let Some(cooldown) = &config.cooldown else {
+        return;
+    };
+
+    // once open, the circuit opens again on every failure, until a run succeeds
+    if failures < cooldown.after || progress.cooling_down.swap(true, atomic::Ordering::SeqCst) {
+        return;
+    }
+
+    logger::warning(
+        &format!(
+            "{} in a row, pausing for {}",
+            plural(failures, "failure"),
+            format_duration(cooldown.duration)
+        ),
+        &[],
+    );
+
+    let _ = controls.send(Control::Pause);
+
+    let progress = Arc::clone(progress);
+    let controls = controls.clone();
+    let duration = cooldown.duration;
+
+    thread::spawn(move || {
+        thread::sleep(duration);
+
+        logger::info("Cooldown over, resuming", &[]);
+
+        progress.cooling_down.store(false, atomic::Ordering::SeqCst);
+        let _ = controls.send(Control::Resume);
+    });
+}
+
+// Whether the keys can be read from stdin: when it is a terminal that we do not share
+// with the commands or the dashboard, and that our output is shown on.
+fn reads_keys(config: &Config) -> bool {
+    #[cfg(feature = "tui")]
+    if config.tui {
+        return false;
+    }
+
+    config.stdin != Input::Inherit && io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+// Rings the bell on the terminal, that the dashboard draws on if shown.
+fn ring_bell(config: &Config) {
+    #[cfg(feature = "tui")]
+    if config.tui {
+        ui::ring(config.flash);
+        return;
+    }
+
+    bell::ring(config.flash);
+}
+
+// Whether the countdown is shown: when requested, and stderr is a terminal that the dashboard does not take over.
+fn shows_countdown(config: &Config) -> bool {
+    #[cfg(feature = "tui")]
+    if config.tui {
+        return false;
+    }
+
+    config.countdown && io::stderr().is_terminal()
+}
+
+// The lines of the status report: stats of the runs, and PIDs of the running commands.
+fn report(stats: &Mutex<Stats>, jobs: &Jobs) -> Vec<String> {
+    let mut lines = stats
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .report_lines();
+    let pids = jobs.pids();
+
+    if !pids.is_empty() {
+        let pids: Vec<String> = pids.iter().map(u32::to_string).collect();
+        lines.push(format!("PIDs:          {}", pids.join(", ")));
+    }
+
+    lines
+}
+
+// The times of the ticks drift by a few milliseconds, while the windows of local time start on a second.
+fn nearest_second(time: SystemTime) -> SystemTime {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+    UNIX_EPOCH + Duration::from_secs((since_epoch + Duration::from_millis(500)).as_secs())
+}
+
+// Records a tick skipped for the given reason.
+fn skip_tick(config: &Config, stats: &Mutex<Stats>, tick_number: u64, reason: &str) {
+    stats
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .record_skipped_ticks(1);
+
+    let message = format!("Tick #{tick_number} skipped: {reason}");
+
+    logger::info(&message, &[("run_id", tick_number as i64)]);
+
+    syslog::log(
+        Priority::Notice,
+        &message,
+        &[("RUN_ID", tick_number.to_string())],
+    );
+
+    #[cfg(feature = "otel")]
+    otel::skip(tick_number, reason);
+
+    if let Some(on_skip) = &config.on_skip {
+        let env = vec![
+            ("EVERY_RUN_ID", tick_number.to_string()),
+            ("EVERY_SKIP_REASON", String::from(reason)),
+        ];
+
+        hooks::spawn("on-skip", on_skip, env);
+    }
+}
+
+// Why the tick must be skipped, from the state of the machine, or None if it can run.
+// The --if command is run last, as the most expensive check.
+fn unmet_precondition(config: &Config) -> Option<String> {
+    if let Some(pause_file) = &config.pause_file {
+        if pause_file.exists() {
+            return Some(format!("paused by {}", pause_file.display()));
+        }
+    }
+
+    if let Some(max_load) = config.max_load {
+        match load::one_minute() {
+            Ok(load) if load > max_load => {
+                return Some(format!("load average of {load:.2} above {max_load}"));
+            }
+            Ok(_) => {}
+            Err(e) => logger::warning(&format!("Failed to read the load average: {e}"), &[]),
+        }
+    }
+
+    if config.only_on_ac || config.min_battery.is_some() {
+        match power::read() {
+            Ok(power) => {
+                if let Some(reason) = config.power_skip_reason(&power) {
+                    return Some(reason);
+                }
+            }
+            Err(e) => logger::warning(&format!("Failed to read the power source: {e}"), &[]),
+        }
+    }
+
+    if let Some(FreeSpace { bytes, path }) = &config.min_free_space {
+        match disk::free_space(path) {
+            Ok(free) if free < *bytes => {
+                return Some(format!(
+                    "{} free on {}, below {}",
+                    format_size(free),
+                    path.display(),
+                    format_size(*bytes)
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => logger::warning(
+                &format!("Failed to read the free space on {}: {e}", path.display()),
+                &[],
+            ),
+        }
+    }
+
+    match &config.require_network {
+        Some(NetworkCheck::DefaultRoute) => match network::has_default_route() {
+            Ok(true) => {}
+            Ok(false) => return Some(String::from("no network route")),
+            Err(e) => logger::warning(&format!("Failed to read the network routes: {e}"), &[]),
+        },
+        Some(NetworkCheck::Connect(address)) => {
+            if let Err(e) = network::connect(address) {
+                return Some(format!("cannot connect to {address}: {e}"));
+            }
+        }
+        None => {}
+    }
+
+    // waited for half the interval at most, after which the result of the previous check is used
+    if let Some(url) = &config.if_healthy {
+        match health::check(url, config.interval / 2) {
+            Some(Ok(200..=299)) => {}
+            Some(Ok(code)) => return Some(format!("{url} returned HTTP {code}")),
+            Some(Err(e)) => return Some(format!("failed to check {url}: {e}")),
+            None => return Some(format!("waiting for the first check of {url}")),
+        }
+    }
+
+    if let Some(condition) = &config.condition {
+        return check_condition(config, condition).err();
+    }
+
+    None
+}
+
+// Runs the --if command through `sh -c`, and returns the reason to skip the tick if it did not exit with 0
+// in time.
+fn check_condition(config: &Config, condition: &str) -> Result<(), String> {
+    let mut command = Command::new("sh");
+
+    command
+        .arg("-c")
+        .arg(condition)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null());
+
+    // in the environment of the commands
+    if config.clean_env {
+        command.env_clear();
+    }
+
+    command.envs(&config.env);
+
+    if let Some(cwd) = &config.cwd {
+        command.current_dir(cwd);
+    }
+
+    signals::unblock_termination(&mut command);
+    systemd::hide_socket(&mut command);
+
+    // in a process group of its own, so that all of its processes are killed once timed out
+    let mut child = command
+        .process_group(0)
+        .spawn()
+        .map_err(|e| format!("failed to check the condition: {e}"))?;
+
+    let pid = Pid::from_raw(child.id() as i32);
+    let (sender, receiver) = mpsc::channel();
+
+    // waited for on its own thread, that reaps it even once the tick has given up on it
+    thread::spawn(move || {
+        let _ = sender.send(child.wait());
+    });
+
+    match receiver.recv_timeout((config.interval / 2).max(MIN_CONDITION_WAIT)) {
+        Ok(Ok(status)) if status.success() => Ok(()),
+        Ok(Ok(_)) => Err(String::from("condition not met")),
+        Ok(Err(e)) => Err(format!("failed to check the condition: {e}")),
+        Err(_) => {
+            let _ = signal::killpg(pid, Signal::SIGKILL);
+            Err(String::from("condition not met, timed out"))
+        }
+    }
+}
+
+// Takes an exclusive lock on the file, created if needed, or returns None if it is already locked.
+// The lock is released when the returned value is dropped.
+fn lock(path: &Path) -> io::Result<Option<Flock<File>>> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)?;
+
+    match Flock::lock(file, FlockArg::LockExclusiveNonblock) {
+        Ok(lock) => Ok(Some(lock)),
+        Err((_, Errno::EWOULDBLOCK)) => Ok(None),
+        Err((_, errno)) => Err(errno.into()),
+    }
+}
+
+// The output of a child process, read from pipes or from a pseudo-terminal.
+type Reader = Box<dyn Read + Send>;
+
+// Runs the command once for the tick, and returns the completed run, or None if it could not be run.
+fn execute(
+    config: &Config,
+    argv: &[String],
+    // set on the command for this run only, on top of --env
+    env: &[(&'static str, String)],
+    tick_number: u64,
+    mut placeholders: Placeholders,
+    slot: &mut JobSlot,
+    last_output: &Mutex<Option<Output>>,
+) -> Option<Run> {
+    let stdin = match input(&config.stdin) {
+        Ok(stdin) => stdin,
+        Err(e) => {
+            logger::error(
+                &format!("Failed to open stdin file: {e}"),
+                &[("run_id", tick_number as i64)],
+            );
+            return None;
+        }
+    };
+
+    let mut command = Command::new(&argv[0]);
+
+    // so that signals reach the processes started by the command too,
+    // unless it may read from the terminal, that only the foreground process group can do
+    if config.stdin != Input::Inherit {
+        command.process_group(0);
+    }
+
+    command
+        .args(argv[1..].iter().map(|arg| placeholders.expand(arg)))
+        .stdin(stdin);
+
+    let mut output_file = None;
+    // the pseudo-terminal or the pipe that stdout & stderr are both written to, if any
+    let mut merged: Option<Reader> = None;
+
+    if let Some(output_dir) = &config.output_dir {
+        let files =
+            archive::create_run_file(output_dir, tick_number, SystemTime::now(), config.keep_last)
+                .and_then(|(path, file)| Ok((path, file.try_clone()?, file)));
+
+        match files {
+            Ok((path, stdout, stderr)) => {
+                output_file = Some(path);
+                command.stdout(stdout).stderr(stderr)
+            }
+            Err(e) => {
+                logger::error(
+                    &format!("Failed to create output file: {e}"),
+                    &[("run_id", tick_number as i64)],
+                );
+                return None;
+            }
+        };
+    } else if config.pty {
+        match pty::open().and_then(|(master, slave)| Ok((master, slave.try_clone()?, slave))) {
+            Ok((master, stdout, stderr)) => {
+                merged = Some(Box::new(master));
+                command.stdout(stdout).stderr(stderr)
+            }
+            Err(e) => {
+                logger::error(
+                    &format!("Failed to open a pseudo-terminal: {e}"),
+                    &[("run_id", tick_number as i64)],
+                );
+                return None;
+            }
+        };
+    } else {
+        // relayed through the countdown, so that the status line is cleared first,
+        // to watch for --stall-timeout and --max-output, or to count the bytes for --history
+        let pipes = config.captures_output()
+            || countdown::is_started()
+            || config.stall_timeout.is_some()
+            || config.max_output.is_some()
+            || config.max_output_kill.is_some()
+            || config.history.is_some();
+
+        match redirect_output(&mut command, config, pipes) {
+            Ok(pipe) => merged = pipe.map(|pipe| Box::new(pipe) as _),
+            Err(e) => {
+                logger::error(
+                    &format!("Failed to redirect stderr: {e}"),
+                    &[("run_id", tick_number as i64)],
+                );
+                return None;
+            }
+        }
+    }
+
+    prepare(&mut command, config, env);
+
+    logger::debug(
+        &format!("Running {}", argv.join(" ")),
+        &[("run_id", tick_number as i64)],
+    );
+
+    let child = command.spawn();
+
+    // closes our copies of the pseudo-terminal slave or of the merged pipe, so that reading them ends with the command
+    drop(command);
+
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            logger::error(
+                &format!("Failed to start command: {e}"),
+                &[("run_id", tick_number as i64)],
+            );
+
+            syslog::log(
+                Priority::Error,
+                &format!("Run #{tick_number} failed to start: {e}"),
+                &[("RUN_ID", tick_number.to_string())],
+            );
+
+            return None;
+        }
+    };
+
+    let start_time = Instant::now();
+    let started_at = SystemTime::now();
+    let child_stdin = child.stdin.take();
+    // when merged, both are read as its stdout
+    let (child_stdout, child_stderr): (Option<Reader>, Option<Reader>) = match merged {
+        Some(merged) => (Some(merged), None),
+        None => (
+            child.stdout.take().map(|stdout| Box::new(stdout) as _),
+            child.stderr.take().map(|stderr| Box::new(stderr) as _),
+        ),
+    };
+
+    // watched for --stall-timeout
+    let activity = config.stall_timeout.map(|_| Activity::new());
+
+    let (child_stdout, child_stderr) = match &activity {
+        Some(activity) => (
+            child_stdout.map(|stdout| Box::new(activity.watch(stdout)) as _),
+            child_stderr.map(|stderr| Box::new(activity.watch(stderr)) as _),
+        ),
+        None => (child_stdout, child_stderr),
+    };
+
+    let (child_stdout, child_stderr) = match output_cap(config, slot, tick_number) {
+        Some(cap) => (
+            child_stdout.map(|stdout| Box::new(cap.wrap(stdout)) as _),
+            child_stderr.map(|stderr| Box::new(cap.wrap(stderr)) as _),
+        ),
+        None => (child_stdout, child_stderr),
+    };
+    let pid = slot.attach(child);
+
+    logger::info(
+        &format!("Started command with PID {pid}"),
+        &[("run_id", tick_number as i64), ("pid", i64::from(pid))],
+    );
+
+    syslog::log(
+        Priority::Info,
+        &format!("Run #{tick_number} started with PID {pid}"),
+        &[
+            ("RUN_ID", tick_number.to_string()),
+            ("COMMAND_PID", pid.to_string()),
+        ],
+    );
+
+    let (output, output_bytes) = thread::scope(|scope| {
+        if let (Input::Data(data), Some(child_stdin)) = (&config.stdin, child_stdin) {
+            // written while reading the output, as the command may be doing both
+            scope.spawn(move || write_stdin(child_stdin, data));
+        }
+
+        if let (Some(activity), Some(timeout)) = (&activity, config.stall_timeout) {
+            let slot = &*slot;
+
+            scope.spawn(move || {
+                if !activity.wait_for_stall(timeout) {
+                    return;
+                }
+
+                if let Some(pid) = slot.kill(config.kill_signal, config.kill_after) {
+                    logger::error(
+                        &format!(
+                            "Command with PID {pid} produced no output for {}, killing it",
+                            format_duration(timeout)
+                        ),
+                        &[("run_id", tick_number as i64), ("pid", i64::from(pid))],
+                    );
+                }
+            });
+        }
+
+        let (output, output_bytes) = if config.captures_output() {
+            match Output::capture(child_stdout, child_stderr) {
+                Ok(output) => {
+                    let bytes = output.stdout.len() + output.stderr.len();
+                    (Some(output), Some(bytes as u64))
+                }
+                Err(e) => {
+                    logger::error(
+                        &format!("Failed to read the command's output: {e}"),
+                        &[("run_id", tick_number as i64), ("pid", i64::from(pid))],
+                    );
+                    (None, None)
+                }
+            }
+        } else {
+            match countdown::relay(child_stdout, child_stderr) {
+                Ok(bytes) => (None, Some(bytes)),
+                Err(e) => {
+                    logger::error(
+                        &format!("Failed to relay the command's output: {e}"),
+                        &[("run_id", tick_number as i64), ("pid", i64::from(pid))],
+                    );
+                    (None, None)
+                }
+            }
+        };
+
+        if let Some(activity) = &activity {
+            activity.close();
+        }
+
+        (output, output_bytes)
+    });
+
+    let (status, usage) = match slot.wait() {
+        Ok(result) => result,
+        Err(e) => {
+            // the child process is killed when the slot is released
+            logger::error(
+                &format!("Error checking child process status: {e}"),
+                &[("run_id", tick_number as i64), ("pid", i64::from(pid))],
+            );
+            return None;
+        }
+    };
+
+    // interrupted, every is exiting and does not report the run
+    if slot.was_stopped() {
+        logger::info(
+            &format!("Command with PID {pid} killed on exit"),
+            &[("run_id", tick_number as i64), ("pid", i64::from(pid))],
+        );
+        return None;
+    }
+
+    let output_matched = config
+        .until_match
+        .as_ref()
+        .zip(output.as_ref())
+        .is_some_and(|(until_match, output)| until_match.is_met(output));
+
+    #[cfg(feature = "webhook")]
+    let notifies = config.notify_url.is_some();
+    #[cfg(not(feature = "webhook"))]
+    let notifies = false;
+
+    let output_text = output
+        .as_ref()
+        .filter(|_| notifies || config.history.is_some())
+        .map(|output| {
+            format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            )
+        });
+
+    let run = Run {
+        status,
+        succeeded: config.is_success(status),
+        started_at,
+        duration: start_time.elapsed(),
+        output_matched,
+        output_file,
+        output_text,
+        output_bytes,
+        usage,
+        killed: slot.was_killed(),
+    };
+
+    let mut fields = vec![("run_id", tick_number as i64), ("pid", i64::from(pid))];
+
+    if let Some(code) = status.code() {
+        fields.push(("exit_code", i64::from(code)));
+    }
+
+    if let Some(signal) = status.signal() {
+        fields.push(("exit_signal", i64::from(signal)));
+    }
+
+    logger::info(
+        &format!(
+            "Command with PID {pid} exited with {status} after {}, CPU time {}, max RSS {}",
+            format_duration(run.duration),
+            format_duration(usage.user_time + usage.system_time),
+            format_size(usage.max_rss)
+        ),
+        &fields,
+    );
+
+    if let Some(bytes) = run.output_bytes {
+        logger::debug(
+            &format!("Command with PID {pid} printed {}", format_size(bytes)),
+            &fields,
+        );
+    }
+
+    if let Some(output) = output {
+        print_output(config, output, &run, last_output);
+    }
+
+    log_run_end(tick_number, &run);
+
+    if !run.succeeded {
+        logger::error(&format!("Command exited with {status}"), &fields);
+    } else {
+        if let Some(heartbeat_file) = &config.heartbeat_file {
+            write_heartbeat(heartbeat_file);
+        }
+
+        systemd::notify("WATCHDOG=1");
+    }
+
+    Some(run)
+}
+
+// The environment variables describing a run to a hook.
+// The run is None if the command could not be started.
+fn hook_env(tick_number: u64, run: Option<&Run>) -> Vec<(&'static str, String)> {
+    let mut env = vec![("EVERY_RUN_ID", tick_number.to_string())];
+
+    let Some(run) = run else {
+        return env;
+    };
+
+    if let Some(code) = run.status.code() {
+        env.push(("EVERY_EXIT_CODE", code.to_string()));
+    }
+
+    if let Some(signal) = run.status.signal() {
+        env.push(("EVERY_EXIT_SIGNAL", signal.to_string()));
+    }
+
+    env.push((
+        "EVERY_DURATION",
+        format!("{:.3}", run.duration.as_secs_f64()),
+    ));
+
+    if let Some(output_file) = &run.output_file {
+        env.push(("EVERY_OUTPUT_FILE", output_file.display().to_string()));
+    }
+
+    env
+}
+
+// Warns that the run took longer than the interval, if ticks were skipped while it was running.
+fn report_overrun(config: &Config, task: &Task, tick_number: u64, run: &Run, skipped: u64) {
+    let interval = config
+        .max_interval
+        .unwrap_or_else(|| config.interval_at(SystemTime::now()));
+    let interval = task.interval.unwrap_or(interval);
+
+    if run.duration <= interval || skipped == 0 {
+        return;
+    }
+
+    let message = format!(
+        "Run #{tick_number} took {}, longer than the interval of {}: skipped {}",
+        format_duration(run.duration),
+        format_duration(interval),
+        plural(skipped, "tick")
+    );
+
+    logger::warning(&message, &[("run_id", tick_number as i64)]);
+
+    syslog::log(
+        Priority::Warning,
+        &message,
+        &[("RUN_ID", tick_number.to_string())],
+    );
+}
+
+// Records the end of the run in the system log, with its exit code or signal.
+fn log_run_end(tick_number: u64, run: &Run) {
+    let mut fields = vec![("RUN_ID", tick_number.to_string())];
+
+    if let Some(code) = run.status.code() {
+        fields.push(("EXIT_CODE", code.to_string()));
+    }
+
+    if let Some(signal) = run.status.signal() {
+        fields.push(("EXIT_SIGNAL", signal.to_string()));
+    }
+
+    let priority = if run.succeeded {
+        Priority::Info
+    } else {
+        Priority::Warning
+    };
+
+    let message = format!(
+        "Run #{tick_number} exited with {} after {}",
+        run.status,
+        format_duration(run.duration)
+    );
+
+    syslog::log(priority, &message, &fields);
+}
+
+impl Config {
+    // Why the tick must be skipped on this power source, with --only-on-ac or --min-battery, if it must.
+    fn power_skip_reason(&self, power: &Power) -> Option<String> {
+        if !power.on_battery {
+            return None;
+        }
+
+        if self.only_on_ac {
+            return Some(String::from("on battery power"));
+        }
+
+        match (power.battery_level, self.min_battery) {
+            (Some(level), Some(min_level)) if level < min_level => {
+                Some(format!("battery at {level}%, below {min_level}%"))
+            }
+            _ => None,
+        }
+    }
+
+    // Whether the command exited with one of the exit codes counted as successes.
+    fn is_success(&self, status: ExitStatus) -> bool {
+        status
+            .code()
+            .is_some_and(|code| self.ok_codes.contains(&code))
+    }
+
+    /// The times of the next runs on the wall clock, from now or from `at`, up to `until`.
+    /// The ticks outside of `between` and `days`, or on `skip_dates`, are left out, and the search ends after a year
+    /// of schedule. With an interval range, the ticks are shown at the minimum interval.
+    pub fn next_runs(&self, now: SystemTime) -> impl Iterator<Item = SystemTime> + '_ {
+        let start = self.at.map_or(now, |at| at.resolve(now));
+        let until = self.until.map(|until| until.resolve(now));
+        let horizon = start + PREVIEW_HORIZON;
+
+        std::iter::successors(Some(start), |time| time.checked_add(self.gap_after(*time)))
+            .take_while(move |time| *time < horizon && until.is_none_or(|until| *time < until))
+            .filter(|time| self.allows_run_at(*time) && !self.skips_date_of(*time))
+    }
+
+    // The interval from the tick at this time: the one of the first window of --interval-between
+    // that contains it, if any.
+    fn interval_at(&self, time: SystemTime) -> Duration {
+        if self.interval_windows.is_empty() {
+            return self.interval;
+        }
+
+        let (time_of_day, _) = TimeOfDay::local(nearest_second(time));
+
+        self.interval_windows
+            .iter()
+            .find(|window| window.window.contains(time_of_day))
+            .map_or(self.interval, |window| window.interval)
+    }
+
+    // The time from the tick at this time to the next one: the interval, shortened so that
+    // the next tick falls on the start or end of a window of --interval-between.
+    fn gap_after(&self, time: SystemTime) -> Duration {
+        let time = nearest_second(time);
+
+        self.interval_windows
+            .iter()
+            .flat_map(|window| [window.window.start, window.window.end])
+            .filter_map(|boundary| {
+                let boundary = LocalTime::TimeOfDay(boundary).resolve(time);
+                boundary.duration_since(time).ok()
+            })
+            .fold(self.interval_at(time), Duration::min)
+    }
+
+    // Whether the time is within the allowed window and days, if any.
+    fn allows_run_at(&self, time: SystemTime) -> bool {
+        if self.between.is_none() && self.days.is_none() {
+            return true;
+        }
+
+        let (time_of_day, weekday) = TimeOfDay::local(time);
+
+        self.between
+            .is_none_or(|window| window.contains(time_of_day))
+            && self.days.is_none_or(|days| days.contains(weekday))
+    }
+
+    // Whether the local date of the time is one of the skipped dates.
+    fn skips_date_of(&self, time: SystemTime) -> bool {
+        !self.skip_dates.is_empty() && self.skip_dates.binary_search(&Date::local(time)).is_ok()
+    }
+
+    fn captures_output(&self) -> bool {
+        self.output != OutputMode::Inherit || self.diff || self.until_match.is_some()
+    }
+
+    // The commands to run on the ticks: the jobs if any, or the command.
+    fn tasks(&self) -> Vec<Task> {
+        if self.jobs.is_empty() {
+            let mut argv = vec![self.command.clone()];
+            argv.extend(self.args.iter().cloned());
+
+            return vec![Task::new(argv, self.command_line(), None)];
+        }
+
+        job_tasks(&self.jobs)
+    }
+
+    fn command_line(&self) -> String {
+        if !self.jobs.is_empty() {
+            let commands: Vec<&str> = self.jobs.iter().map(|job| job.command.as_str()).collect();
+
+            return commands.join("; ");
+        }
+
+        let mut command_line = self.command.clone();
+
+        for arg in &self.args {
+            command_line.push(' ');
+            command_line.push_str(arg);
+        }
+
+        command_line
+    }
+}
+
+// A command run on the ticks of its schedule, with its own concurrency limit if any.
+struct Task {
+    argv: Vec<String>,
+    // the command as written by the user, to report it
+    command_line: String,
+    // the interval of its own schedule, if not on the ticks of the interval
+    interval: Option<Duration>,
+    // the index of its schedule in the scheduler, 0 for the interval
+    schedule: usize,
+    concurrency: Option<u16>,
+    // whether it takes turns with the other rotating tasks
+    rotate: bool,
+    // whether its command line is run through sh -c, and the placeholders must be quoted
+    shell: bool,
+    // shared with the task of the same command once the jobs are reloaded, as its runs go on
+    running: Arc<AtomicU16>,
+    // whether the last completed run failed, to report the recovery
+    #[cfg(feature = "webhook")]
+    failing: Arc<AtomicBool>,
+}
+
+impl Task {
+    fn new(argv: Vec<String>, command_line: String, concurrency: Option<u16>) -> Self {
+        Self {
+            argv,
+            command_line,
+            interval: None,
+            schedule: 0,
+            concurrency,
+            rotate: false,
+            shell: false,
+            running: Arc::new(AtomicU16::new(0)),
+            #[cfg(feature = "webhook")]
+            failing: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+// The tasks of the jobs, those with their own interval getting their own schedule, in order.
+fn job_tasks(jobs: &[Job]) -> Vec<Task> {
+    let mut schedules = 0;
+
+    jobs.iter()
+        .map(|job| {
+            let argv = vec![String::from("sh"), String::from("-c"), job.command.clone()];
+
+            let mut task = Task::new(argv, job.command.clone(), job.concurrency);
+            task.rotate = job.rotate;
+            task.shell = true;
+
+            if job.interval.is_some() {
+                schedules += 1;
+                task.interval = job.interval;
+                task.schedule = schedules;
+            }
+
+            task
+        })
+        .collect()
+}
+
+// The indexes of the tasks that take turns, one per tick.
+fn rotation_of(tasks: &[Task]) -> Vec<usize> {
+    (0..tasks.len()).filter(|&i| tasks[i].rotate).collect()
+}
+
+// Reads the jobs file again, and returns its tasks to replace the current ones, or None if it cannot be used.
+// A job of the same command as before keeps counting its runs in progress. The schedules are set up once,
+// so a new interval is only applied if a job had it at startup.
+fn reload_tasks(jobs_file: &Path, tasks: &[Task], schedules: &[Duration]) -> Option<Vec<Task>> {
+    let jobs = match jobfile::read_jobs_file(jobs_file) {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            logger::error(&format!("Failed to reload the jobs: {e}"), &[]);
+            return None;
+        }
+    };
+
+    let mut reloaded = vec![];
+
+    for mut task in job_tasks(&jobs) {
+        let schedule = match task.interval {
+            Some(interval) => schedules.iter().position(|&s| s == interval).map(|i| i + 1),
+            None => Some(0),
+        };
+
+        let previous = tasks
+            .iter()
+            .find(|previous| previous.command_line == task.command_line);
+
+        match (schedule, previous) {
+            (Some(schedule), _) => task.schedule = schedule,
+            (None, Some(previous)) => {
+                logger::warning(
+                    &format!(
+                        "The interval of job '{}' was not reloaded, restart every to apply it",
+                        task.command_line
+                    ),
+                    &[],
+                );
+
+                task.interval = previous.interval;
+                task.schedule = previous.schedule;
+            }
+            (None, None) => {
+                logger::warning(
+                    &format!(
+                        "Job '{}' was not reloaded, restart every to apply its interval",
+                        task.command_line
+                    ),
+                    &[],
+                );
+                continue;
+            }
+        }
+
+        if let Some(previous) = previous {
+            task.running = Arc::clone(&previous.running);
+            #[cfg(feature = "webhook")]
+            {
+                task.failing = Arc::clone(&previous.failing);
+            }
+        }
+
+        reloaded.push(task);
+    }
+
+    if reloaded.is_empty() {
+        logger::error("Failed to reload the jobs: no job left to run", &[]);
+        return None;
+    }
+
+    // the other options are only read at startup
+    logger::notice(
+        &format!(
+            "Reloaded {} from {}, the options such as --env and the hooks were kept",
+            plural(reloaded.len() as u64, "job"),
+            jobs_file.display()
+        ),
+        &[],
+    );
+
+    Some(reloaded)
+}
+
+// What the completed runs tell about when to stop, shared by the runs.
+#[derive(Default)]
+struct Progress {
+    // whether the run that --until-success or --until-match waits for happened
+    condition_met: AtomicBool,
+    consecutive_failures: AtomicU64,
+    // the failure that stopped every, with --max-failures or --on-spawn-error exit
+    stopped_by_failure: Mutex<Option<ExitReason>>,
+    // whether the scheduler is paused by --cooldown
+    cooling_down: AtomicBool,
+    // why the last completed run failed, None if it succeeded
+    last_failure: Mutex<Option<ExitReason>>,
+}
+
+// The accounting of a run being handled, that is completed when dropped, even if the handling panics:
+// the run is no longer counted as running by its task, and is recorded as a failure unless ended.
+struct Accounting {
+    // decremented once the run completes
+    running: Arc<AtomicU16>,
+    stats: Arc<Mutex<Stats>>,
+    started: bool,
+    ended: bool,
+}
+
+impl Accounting {
+    // Counts the run as running by its task, as soon as the tick hands it over.
+    fn new(running: &Arc<AtomicU16>, stats: &Arc<Mutex<Stats>>) -> Self {
+        running.fetch_add(1, atomic::Ordering::SeqCst);
+
+        Self {
+            running: Arc::clone(running),
+            stats: Arc::clone(stats),
+            started: false,
+            ended: false,
+        }
+    }
+
+    // Records the start of the run, and returns the number of ticks skipped so far.
+    fn start(&mut self) -> u64 {
+        let mut stats = self.stats.lock().unwrap_or_else(PoisonError::into_inner);
+        stats.record_start();
+        self.started = true;
+
+        stats.skipped_ticks()
+    }
+
+    // Records the end of the run, None if the command could not be started.
+    fn end(mut self, run: Option<&Run>) {
+        let mut stats = self.stats.lock().unwrap_or_else(PoisonError::into_inner);
+
+        match run {
+            Some(run) => {
+                stats.record_end(run.succeeded, Some(run.duration));
+                stats.record_usage(run.usage);
+            }
+            None => stats.record_end(false, None),
+        }
+
+        self.ended = true;
+    }
+}
+
+impl Drop for Accounting {
+    fn drop(&mut self) {
+        self.running.fetch_sub(1, atomic::Ordering::SeqCst);
+
+        if self.started && !self.ended {
+            // poisoned if the panic happened while recording the run
+            let mut stats = self.stats.lock().unwrap_or_else(PoisonError::into_inner);
+            stats.record_end(false, None);
+        }
+    }
+}
+
+// The message of a panic, as given to panic!().
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    match panic.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => panic
+            .downcast_ref::<String>()
+            .map_or("unknown error", String::as_str),
+    }
+}
+
+// A completed run.
+struct Run {
+    status: ExitStatus,
+    // whether the exit code is one of --ok-codes
+    succeeded: bool,
+    started_at: SystemTime,
+    duration: Duration,
+    // whether the output met the --until-match condition
+    output_matched: bool,
+    // the file holding the output, with --output-dir
+    output_file: Option<PathBuf>,
+    // the captured output, to send it with --notify-url or record it with --history
+    output_text: Option<String>,
+    // the number of bytes of output read from the command, whether captured or relayed,
+    // but not of a stream discarded or redirected to a file, None if it could not be read
+    output_bytes: Option<u64>,
+    usage: Usage,
+    // whether the command was killed by every, e.g. with --on-busy kill
+    killed: bool,
+}
+
+// Prints the captured output of a run, according to the output mode.
+// The output of a successful run is kept, to compare the next runs against.
+fn print_output(config: &Config, output: Output, run: &Run, last_output: &Mutex<Option<Output>>) {
+    // the lock is held while printing, so that concurrent runs are compared in order
+    let mut last_output = last_output.lock().unwrap();
+    let previous = last_output.as_ref().filter(|_| config.diff);
+
+    match config.output {
+        OutputMode::Inherit => {
+            let _ = output.print(previous);
+        }
+        OutputMode::OnFailure => {
+            if !run.succeeded {
+                let _ = output.print(previous);
+            }
+        }
+        OutputMode::Changes => {
+            if last_output.as_ref() != Some(&output) {
+                let _ = output.print(previous);
+            }
+        }
+        OutputMode::Watch => {
+            let header = format!(
+                "Every {}: {}\nLast run: {}, took {}, {}\n",
+                format_interval_range(config.interval, config.max_interval),
+                config.command_line(),
+                clock::format_local(run.started_at, "%Y-%m-%d %H:%M:%S %Z"),
+                format_duration(run.duration),
+                run.status
+            );
+
+            let _ = output.print_screen(&header, previous);
+        }
+    }
+
+    if run.succeeded {
+        *last_output = Some(output);
+    }
+}
+
+fn shutdown(config: &Config, stats: &Mutex<Stats>, jobs: &Arc<Jobs>) {
+    systemd::notify("STOPPING=1");
+
+    #[cfg(feature = "tui")]
+    ui::restore();
+
+    keys::restore();
+    bell::restore();
+    countdown::stop();
+    title::restore();
+
+    if config.summary {
+        logger::notice(
+            &stats
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .summary(),
+            &[],
+        );
+    }
+
+    // the commands in a process group of their own do not get the signal of the terminal
+    jobs.stop(config.kill_signal, config.kill_after);
+
+    #[cfg(feature = "otel")]
+    otel::finish();
+
+    if let Some(control_socket) = &config.control_socket {
+        let _ = fs::remove_file(control_socket);
+    }
+
+    instances::unregister();
+
+    if let Some(pid_file) = &config.pid_file {
+        let _ = fs::remove_file(pid_file);
+    }
+}
+
+// Redirects our output to the log file, after forking into the background if requested.
+// Must be called before any thread is started.
+fn start_logging(path: &Path, daemon: bool) -> Result<(), String> {
+    // opened first, so that errors are reported on the terminal
+    let log = daemon::open_log(path)
+        .map_err(|e| format!("Failed to open log file {}: {e}", path.display()))?;
+
+    if daemon {
+        daemon::daemonize().map_err(|e| format!("Failed to run in the background: {e}"))?;
+    }
+
+    daemon::redirect_output(&log)
+        .map_err(|e| format!("Failed to redirect output to the log file: {e}"))
+}
+
+// Writes the current Unix timestamp to the heartbeat file, updating its modification time.
+fn write_heartbeat(path: &Path) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    if let Err(e) = fs::write(path, format!("{timestamp}\n")) {
+        logger::error(&format!("Failed to write heartbeat file: {e}"), &[]);
+    }
+}
+
+// Formats a time as UTC, the same whatever the local time zone, for the names of the archived files
+// and the runs of --tui.
+pub(crate) fn format_utc(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let (days, seconds) = (seconds / 86_400, seconds % 86_400);
+
+    // civil date from the number of days since the epoch:
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        seconds / 3_600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn input(stdin: &Input) -> io::Result<Stdio> {
+    match stdin {
+        Input::Null => Ok(Stdio::null()),
+        Input::Inherit => Ok(Stdio::inherit()),
+        Input::Data(_) => Ok(Stdio::piped()),
+        Input::File(path) => File::open(path).map(Stdio::from),
+    }
+}
+
+fn write_stdin(mut child_stdin: ChildStdin, data: &str) {
+    // the command may exit without reading its input
+    if let Err(e) = child_stdin.write_all(data.as_bytes()) {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            logger::error(&format!("Failed to write to the command's stdin: {e}"), &[]);
+        }
+    }
+}
+
+// The limits of --max-output and --max-output-kill on the output of the run, if any.
+fn output_cap(config: &Config, slot: &JobSlot, tick_number: u64) -> Option<Arc<OutputCap>> {
+    if config.max_output.is_none() && config.max_output_kill.is_none() {
+        return None;
+    }
+
+    let killer = slot.killer();
+    let (signal, kill_after) = (config.kill_signal, config.kill_after);
+    let kill_at = config.max_output_kill.unwrap_or_default();
+
+    Some(OutputCap::new(
+        config.max_output,
+        config.max_output_kill,
+        move || {
+            if let Some(pid) = killer.kill(signal, kill_after) {
+                logger::error(
+                    &format!(
+                        "Command with PID {pid} wrote more than {}, killing it",
+                        format_size(kill_at)
+                    ),
+                    &[("run_id", tick_number as i64), ("pid", i64::from(pid))],
+                );
+            }
+        },
+    ))
+}
+
+// Takes the line of --args-from for the next run, if any,
+// and stops once the last line is taken, unless the lines wrap around.
+fn take_arg_line(config: &Config, taken: &mut usize, controls: &Sender<Control>) -> Option<String> {
+    let lines = config.arg_lines.as_ref()?;
+    let line = lines[*taken % lines.len()].clone();
+
+    *taken += 1;
+
+    if config.args_exhausted == ArgsExhausted::Stop && *taken == lines.len() {
+        logger::info(
+            &format!(
+                "Took the last of {}, stopping",
+                plural(*taken as u64, "argument line")
+            ),
+            &[],
+        );
+
+        let _ = controls.send(Control::Stop);
+    }
+
+    Some(line)
+}
+
+// Whether all the lines of --args-from have been taken, and no more runs must be started.
+fn args_exhausted(config: &Config, taken: usize) -> bool {
+    config.args_exhausted == ArgsExhausted::Stop
+        && config
+            .arg_lines
+            .as_ref()
+            .is_some_and(|lines| taken >= lines.len())
+}
+
+// Sets up the environment of a run, shared by the commands waited for and the detached ones.
+fn prepare(command: &mut Command, config: &Config, env: &[(&'static str, String)]) {
+    if let Some(cwd) = &config.cwd {
+        command.current_dir(cwd);
+    }
+
+    signals::unblock_termination(command);
+
+    if config.systemd {
+        systemd::hide_socket(command);
+    }
+
+    if config.clean_env {
+        command.env_clear();
+    } else {
+        clock::inherit_time_zone(command);
+    }
+
+    // the commands usually disable their colors when their output is not a terminal
+    match config.color {
+        ColorChoice::Auto => {}
+        ColorChoice::Always => {
+            command
+                .env("CLICOLOR_FORCE", "1")
+                .env("FORCE_COLOR", "1")
+                .env_remove("NO_COLOR");
+        }
+        ColorChoice::Never => {
+            command
+                .env("NO_COLOR", "1")
+                .env_remove("CLICOLOR_FORCE")
+                .env_remove("FORCE_COLOR");
+        }
+    }
+
+    command.envs(&config.env);
+    command.envs(env.iter().map(|(name, value)| (name, value)));
+
+    config.limits.apply(command);
+
+    if let Some(nice) = config.nice {
+        // SAFETY: setpriority() is async-signal-safe, and touches no memory of the parent
+        unsafe {
+            command.pre_exec(move || {
+                if libc::setpriority(libc::PRIO_PROCESS, 0, nice) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+
+                Ok(())
+            });
+        }
+    }
+}
+
+// Starts a run with --detach, that is neither waited for nor counted against the concurrency level.
+// Its exit is only logged, and recorded in the statistics, by a background thread that reaps it.
+// Returns whether the command was started.
+fn detach(
+    config: &Arc<Config>,
+    argv: &[String],
+    env: &[(&'static str, String)],
+    tick_number: u64,
+    mut placeholders: Placeholders,
+    stats: &Arc<Mutex<Stats>>,
+) -> bool {
+    let stdin = match input(&config.stdin) {
+        Ok(stdin) => stdin,
+        Err(e) => {
+            logger::error(
+                &format!("Failed to open stdin file: {e}"),
+                &[("run_id", tick_number as i64)],
+            );
+            stats
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .record_end(false, None);
+            return false;
+        }
+    };
+
+    let mut command = Command::new(&argv[0]);
+
+    // so that the commands do not receive the signals of the terminal, and keep running once every stops
+    if config.stdin != Input::Inherit {
+        command.process_group(0);
+    }
+
+    command
+        .args(argv[1..].iter().map(|arg| placeholders.expand(arg)))
+        .stdin(stdin);
+
+    if let Err(e) = redirect_output(&mut command, config, false) {
+        logger::error(
+            &format!("Failed to redirect stderr: {e}"),
+            &[("run_id", tick_number as i64)],
+        );
+        stats
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .record_end(false, None);
+        return false;
+    }
+
+    prepare(&mut command, config, env);
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            logger::error(
+                &format!("Failed to start command: {e}"),
+                &[("run_id", tick_number as i64)],
+            );
+            stats
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .record_end(false, None);
+            return false;
+        }
+    };
+
+    let start_time = Instant::now();
+    let pid = child.id();
+
+    logger::info(
+        &format!("Started detached command with PID {pid}"),
+        &[("run_id", tick_number as i64), ("pid", i64::from(pid))],
+    );
+
+    // written on its own thread, as the command may read it slowly, and closed once written
+    if let (Input::Data(data), Some(child_stdin)) = (&config.stdin, child.stdin.take()) {
+        let data = data.clone();
+
+        thread::spawn(move || write_stdin(child_stdin, &data));
+    }
+
+    let reaper = REAPER.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || reap_detached(&receiver));
+
+        sender
+    });
+
+    let _ = reaper.send(Detached {
+        child,
+        config: Arc::clone(config),
+        stats: Arc::clone(stats),
+        tick_number,
+        start_time,
+    });
+
+    true
+}
+
+// A detached command, until reaped.
+struct Detached {
+    child: Child,
+    config: Arc<Config>,
+    stats: Arc<Mutex<Stats>>,
+    tick_number: u64,
+    start_time: Instant,
+}
+
+impl Detached {
+    // Records the end of the command if it has exited, and returns whether it was reaped.
+    fn try_reap(&mut self) -> bool {
+        let pid = self.child.id();
+        let fields = [("run_id", self.tick_number as i64), ("pid", i64::from(pid))];
+
+        match self.child.try_wait() {
+            Ok(None) => false,
+            Ok(Some(status)) => {
+                let duration = self.start_time.elapsed();
+
+                logger::info(
+                    &format!(
+                        "Detached command with PID {pid} exited with {status} after {}",
+                        format_duration(duration)
+                    ),
+                    &fields,
+                );
+
+                self.stats
+                    .lock()
+                    .unwrap()
+                    .record_end(self.config.is_success(status), Some(duration));
+
+                true
+            }
+            Err(e) => {
+                logger::error(
+                    &format!("Failed to wait for detached command with PID {pid}: {e}"),
+                    &fields,
+                );
+
+                true
+            }
+        }
+    }
+}
+
+// Reaps the detached commands received, checking them every REAP_INTERVAL while any is running.
+fn reap_detached(receiver: &Receiver<Detached>) {
+    let mut running: Vec<Detached> = Vec::new();
+
+    loop {
+        // blocks until the next command while none is running
+        let detached = if running.is_empty() {
+            receiver.recv().ok()
+        } else {
+            receiver.recv_timeout(REAP_INTERVAL).ok()
+        };
+
+        running.extend(detached);
+        running.retain_mut(|detached| !detached.try_reap());
+    }
+}
+
+// Sets the stdout & stderr of the command, and returns the pipe they are both written to, if merged and read by us.
+fn redirect_output(
+    command: &mut Command,
+    config: &Config,
+    pipes: bool,
+) -> io::Result<Option<PipeReader>> {
+    command.stdout(output(config.quiet >= 1, pipes));
+
+    let stderr = match &config.stderr {
+        _ if config.quiet >= 2 => Stdio::null(),
+        ErrorOutput::Separate => output(false, pipes),
+        ErrorOutput::Discard => Stdio::null(),
+        // goes wherever stdout goes
+        ErrorOutput::Merge if config.quiet >= 1 => Stdio::null(),
+        ErrorOutput::Merge if pipes => {
+            let (reader, writer) = io::pipe()?;
+            command.stdout(writer.try_clone()?).stderr(writer);
+
+            return Ok(Some(reader));
+        }
+        ErrorOutput::Merge => Stdio::from(io::stdout().as_fd().try_clone_to_owned()?),
+        ErrorOutput::File(path) => {
+            Stdio::from(OpenOptions::new().create(true).append(true).open(path)?)
+        }
+    };
+
+    command.stderr(stderr);
+
+    Ok(None)
+}
+
+fn output(discard: bool, capture: bool) -> Stdio {
+    if discard {
+        Stdio::null()
+    } else if capture {
+        Stdio::piped()
+    } else {
+        Stdio::inherit()
+    }
+}
+
+fn plural(count: u64, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_utc() {
+        let test_cases = [
+            (0, "1970-01-01 00:00:00 UTC"),
+            (951_782_400, "2000-02-29 00:00:00 UTC"),
+            (1_700_000_000, "2023-11-14 22:13:20 UTC"),
+            (4_107_542_399, "2100-02-28 23:59:59 UTC"),
+        ];
+
+        for (timestamp, expected) in test_cases {
+            let time = UNIX_EPOCH + Duration::from_secs(timestamp);
+
+            assert_eq!(format_utc(time), expected, "timestamp: {timestamp}");
+        }
+    }
+
+    #[test]
+    fn test_until_match() {
+        let output = Output {
+            stdout: b"starting\nstatus: ready\n".to_vec(),
+            stderr: b"warning: slow disk\n".to_vec(),
+        };
+
+        let until = |pattern, matches| UntilMatch {
+            pattern: Regex::new(pattern).unwrap(),
+            matches,
+        };
+
+        assert!(until("^status: ready$", true).is_met(&output));
+        assert!(until("^warning:", true).is_met(&output));
+        assert!(!until("^ready", true).is_met(&output));
+        assert!(!until("starting", false).is_met(&output));
+        assert!(until("error", false).is_met(&output));
+    }
+
+    #[test]
+    fn test_next_runs() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        let config = Config {
+            interval: Duration::from_secs(3600),
+            ..Config::default()
+        };
+
+        assert_eq!(
+            config.next_runs(now).take(3).collect::<Vec<_>>(),
+            [0, 1, 2].map(|hours| now + Duration::from_secs(hours * 3600))
+        );
+
+        let config = Config {
+            interval: Duration::from_secs(3600),
+            between: Some(TimeWindow {
+                start: TimeOfDay::local(now).0,
+                end: TimeOfDay::local(now + Duration::from_secs(1800)).0,
+            }),
+            ..Config::default()
+        };
+
+        // once a day
+        assert_eq!(
+            config.next_runs(now).take(2).collect::<Vec<_>>(),
+            [0, 24].map(|hours| now + Duration::from_secs(hours * 3600))
+        );
+
+        let config = Config {
+            interval: Duration::from_secs(3600),
+            interval_windows: vec![IntervalWindow {
+                window: TimeWindow {
+                    start: TimeOfDay::local(now).0,
+                    end: TimeOfDay::local(now + Duration::from_secs(100)).0,
+                },
+                interval: Duration::from_secs(60),
+            }],
+            ..Config::default()
+        };
+
+        // every minute within the window, then every hour from its end
+        assert_eq!(
+            config.next_runs(now).take(4).collect::<Vec<_>>(),
+            [0, 60, 100, 3700].map(|seconds| now + Duration::from_secs(seconds))
+        );
+    }
+
+    #[test]
+    fn test_power_skip_reason() {
+        let power = |on_battery, battery_level| Power {
+            on_battery,
+            battery_level,
+        };
+
+        let only_on_ac = Config {
+            only_on_ac: true,
+            ..Config::default()
+        };
+
+        assert_eq!(only_on_ac.power_skip_reason(&power(false, Some(10))), None);
+        assert_eq!(
+            only_on_ac
+                .power_skip_reason(&power(true, Some(90)))
+                .as_deref(),
+            Some("on battery power")
+        );
+
+        let min_battery = Config {
+            min_battery: Some(20),
+            ..Config::default()
+        };
+
+        assert_eq!(min_battery.power_skip_reason(&power(false, Some(10))), None);
+        assert_eq!(min_battery.power_skip_reason(&power(true, Some(20))), None);
+        assert_eq!(min_battery.power_skip_reason(&power(true, None)), None);
+        assert_eq!(
+            min_battery
+                .power_skip_reason(&power(true, Some(19)))
+                .as_deref(),
+            Some("battery at 19%, below 20%")
+        );
+    }
+
+    #[test]
+    fn test_accounting_on_panic() {
+        let running = Arc::new(AtomicU16::new(0));
+        let stats = Arc::new(Mutex::new(Stats::new()));
+
+        let panic = panic::catch_unwind(|| {
+            let mut accounting = Accounting::new(&running, &stats);
+            assert_eq!(running.load(atomic::Ordering::SeqCst), 1);
+            accounting.start();
+
+            panic!("run handling failed");
+        })
+        .unwrap_err();
+
+        assert_eq!(panic_message(&*panic), "run handling failed");
+        assert_eq!(running.load(atomic::Ordering::SeqCst), 0);
+        assert_eq!(
+            stats.lock().unwrap_or_else(PoisonError::into_inner).line(),
+            "Runs: 1, failed: 1, skipped ticks: 0, running: 0"
+        );
+
+        let panic = panic::catch_unwind(|| panic!("run #{} failed", 3)).unwrap_err();
+        assert_eq!(panic_message(&*panic), "run #3 failed");
+    }
+    #[test]
+    fn test_reload_tasks() {
+        let jobs_file = std::env::temp_dir().join(format!("every-reload-{}", process::id()));
+        let schedules = [Duration::from_secs(60)];
+
+        fs::write(&jobs_file, "-c 1 ./sync.sh\n@1m ./poll.sh\n").unwrap();
+        let tasks = reload_tasks(&jobs_file, &[], &schedules).unwrap();
+        tasks[0].running.store(1, atomic::Ordering::SeqCst);
+
+        // the runs in progress are still counted, and a new interval needs a schedule of its own
+        fs::write(
+            &jobs_file,
+            "-c 2 ./sync.sh\n@1m ./report.sh\n@5m ./poll.sh\n@5m ./new.sh\n",
+        )
+        .unwrap();
+        let reloaded = reload_tasks(&jobs_file, &tasks, &schedules).unwrap();
+
+        let summary: Vec<_> = reloaded
+            .iter()
+            .map(|task| {
+                (
+                    task.command_line.as_str(),
+                    task.interval,
+                    task.schedule,
+                    task.concurrency,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            [
+                ("./sync.sh", None, 0, Some(2)),
+                ("./report.sh", Some(Duration::from_secs(60)), 1, None),
+                ("./poll.sh", Some(Duration::from_secs(60)), 1, None),
+            ]
+        );
+        assert!(Arc::ptr_eq(&reloaded[0].running, &tasks[0].running));
+        assert_eq!(reloaded[0].running.load(atomic::Ordering::SeqCst), 1);
+
+        fs::write(&jobs_file, "@5m ./new.sh\n").unwrap();
+        assert!(reload_tasks(&jobs_file, &reloaded, &schedules).is_none());
+
+        fs::remove_file(&jobs_file).unwrap();
+        assert!(reload_tasks(&jobs_file, &reloaded, &schedules).is_none());
+    }
+}
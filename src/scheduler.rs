@@ -0,0 +1,546 @@
+use crate::random::Random;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::hint;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+// How long before a tick we stop sleeping, and spin instead: sleeping is not precise enough
+// for sub-millisecond intervals.
+const SPIN_THRESHOLD: Duration = Duration::from_micros(200);
+
+// How often the system time is checked, to notice suspends and clock changes.
+const WALL_CLOCK_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// How far the system time must jump ahead of the monotonic clock to be considered a suspend.
+const SUSPEND_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// A drift-free scheduler: ticks are fired at fixed points in time, regardless of how long
+/// the tick function takes. If the scheduler falls behind schedule, missed ticks are skipped,
+/// unless catch-up is enabled.
+pub struct Scheduler {
+    interval: Duration,
+    max_interval: Option<Duration>,
+    catch_up: u64,
+    clock: Clock,
+    on_resume: OnResume,
+    start_at: Option<SystemTime>,
+    until: Option<SystemTime>,
+    // the intervals of the other schedules
+    schedules: Vec<Duration>,
+    controls: Receiver<Control>,
+    sender: Sender<Control>,
+}
+
+/// The clock that the schedule follows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Clock {
+    /// Immune to system time changes, but stops while the machine is suspended.
+    #[default]
+    Monotonic,
+    /// Follows the system time, across suspends and clock changes.
+    Wall,
+}
+
+/// What to do when the machine resumes from a suspend.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnResume {
+    /// Report the suspend in the next tick, and keep the schedule.
+    #[default]
+    Warn,
+    /// Fire a tick immediately, and restart the schedule from there.
+    RunOnce,
+    /// Fire the ticks missed during the suspend back-to-back.
+    CatchUp,
+}
+
+#[derive(Debug)]
+pub struct Tick {
+    /// 1-based index of the tick in the schedule, skipped ticks included.
+    pub number: u64,
+    /// Number of ticks skipped since the previous tick, because we were behind schedule.
+    pub skipped: u64,
+    /// How far behind schedule we were when these ticks were skipped.
+    pub behind: Duration,
+    /// How long the machine was suspended since the previous tick, detected as a jump
+    /// of the system time ahead of the monotonic clock.
+    pub suspended: Duration,
+    /// When the tick was due, that is earlier than now when behind schedule.
+    pub scheduled_at: SystemTime,
+    /// The schedule of the tick: 0 for the interval, then the schedules added, from 1.
+    pub schedule: usize,
+}
+
+/// Commands that can be sent to the scheduler while it is running.
+#[derive(Debug)]
+pub enum Control {
+    Pause,
+    Resume,
+    /// Fires a tick immediately, and restarts the schedule from there.
+    RunNow,
+    /// The new interval applies from the previous tick, and replaces the interval range if any.
+    SetInterval(Duration),
+    Stop,
+    Status(Sender<Status>),
+}
+
+#[derive(Debug)]
+pub struct Status {
+    pub paused: bool,
+    pub interval: Duration,
+    /// The maximum interval, if the interval is a range.
+    pub max_interval: Option<Duration>,
+    pub next_tick_in: Duration,
+}
+
+impl Scheduler {
+    pub fn new(interval: Duration) -> Self {
+        let (sender, controls) = mpsc::channel();
+
+        Self {
+            interval,
+            max_interval: None,
+            catch_up: 0,
+            clock: Clock::Monotonic,
+            on_resume: OnResume::Warn,
+            start_at: None,
+            until: None,
+            schedules: Vec::new(),
+            controls,
+            sender,
+        }
+    }
+
+    /// Draws the time between two ticks uniformly between the interval and `max`,
+    /// instead of a fixed interval.
+    pub fn max_interval(mut self, max: Duration) -> Self {
+        self.max_interval = Some(max).filter(|max| *max > self.interval);
+        self
+    }
+
+    /// Fires up to `max` missed ticks back-to-back when behind schedule, instead of skipping them.
+    /// Older missed ticks are still skipped.
+    pub fn catch_up(mut self, max: u64) -> Self {
+        self.catch_up = max;
+        self
+    }
+
+    /// Sets the clock that the schedule follows, monotonic by default.
+    pub fn clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Sets what to do when the machine resumes from a suspend.
+    pub fn on_resume(mut self, on_resume: OnResume) -> Self {
+        self.on_resume = on_resume;
+        self
+    }
+
+    /// Fires the first tick at the given system time, instead of immediately.
+    pub fn start_at(mut self, start: SystemTime) -> Self {
+        self.start_at = Some(start);
+        self
+    }
+
+    /// Stops the scheduler once the system time reaches the deadline.
+    pub fn until(mut self, deadline: SystemTime) -> Self {
+        self.until = Some(deadline);
+        self
+    }
+
+    /// Adds a schedule with its own interval, that fires its own ticks from the start, independently
+    /// of the interval. Its missed ticks are skipped, and it is not affected by the controls but pausing.
+    pub fn schedule(mut self, interval: Duration) -> Self {
+        self.schedules.push(interval);
+        self
+    }
+
+    /// Returns a sender to control the scheduler, from any thread.
+    pub fn controller(&self) -> Sender<Control> {
+        self.sender.clone()
+    }
+
+    /// Calls the function on every tick, starting immediately unless a start time is set,
+    /// until a Stop command is received or the deadline is reached.
+    pub fn run<F>(self, function: F)
+    where
+        F: FnMut(&Tick),
+    {
+        // if all controllers are dropped, the schedule runs uninterrupted
+        tick(self, function);
+    }
+}
+
+fn tick<F>(scheduler: Scheduler, mut function: F)
+where
+    F: FnMut(&Tick),
+{
+    let Scheduler {
+        mut interval,
+        max_interval,
+        catch_up,
+        clock,
+        on_resume,
+        start_at,
+        until,
+        schedules,
+        controls,
+        ..
+    } = scheduler;
+
+    let mut clock_offset = ClockOffset::new();
+
+    let start_in = start_at.map_or(Duration::ZERO, |start_at| {
+        start_at
+            .duration_since(SystemTime::now())
+            .unwrap_or_default()
+    });
+
+    let mut random = Random::new();
+    // the random part of the gap between two ticks, above the interval
+    let mut spread = max_interval.map_or(Duration::ZERO, |max| max - interval);
+    let mut gap = interval;
+    let mut next_tick = Instant::now() + start_in;
+    let mut paused = false;
+    let mut run_now = false;
+    // the number of missed ticks to catch up on after a suspend
+    let mut resume_catch_up = 0;
+    let mut tick = Tick {
+        number: 1,
+        skipped: 0,
+        behind: Duration::ZERO,
+        suspended: Duration::ZERO,
+        scheduled_at: SystemTime::now(),
+        schedule: 0,
+    };
+    // the next ticks of the other schedules, earliest first, with the index of their schedule
+    let mut deadlines: BinaryHeap<Reverse<(Instant, usize)>> = (1..=schedules.len())
+        .map(|schedule| Reverse((next_tick, schedule)))
+        .collect();
+    // the ticks of the other schedules skipped since their last tick, and how far behind they were
+    let mut skipped = vec![(0, Duration::ZERO); schedules.len()];
+
+    loop {
+        // wait for the next tick, handling control commands in the meantime
+        loop {
+            let now = Instant::now();
+
+            let (direction, change) = clock_offset.update(ClockOffset::new());
+
+            if clock == Clock::Wall && !change.is_zero() {
+                // the next ticks move with the system time, relative to the monotonic clock
+                next_tick = shift(next_tick, direction, change, now);
+                update_deadlines(&mut deadlines, |next_tick| {
+                    shift(next_tick, direction, change, now)
+                });
+            }
+
+            if let Some(change) = suspend(direction, change) {
+                tick.suspended += change;
+
+                // the other schedules do not catch up
+                if on_resume != OnResume::Warn {
+                    update_deadlines(&mut deadlines, |next_tick| next_tick.min(now));
+                }
+
+                match on_resume {
+                    OnResume::Warn => {}
+                    OnResume::RunOnce => next_tick = next_tick.min(now),
+                    OnResume::CatchUp => {
+                        // the wall clock already moved the next tick
+                        if clock == Clock::Monotonic {
+                            next_tick = next_tick.checked_sub(change).unwrap_or(now);
+                        }
+
+                        let missed = change.as_nanos() / interval.as_nanos();
+                        resume_catch_up += u64::try_from(missed).unwrap_or(u64::MAX);
+                    }
+                }
+            }
+
+            // the ticks of the other schedules that are due before the next tick of the interval
+            if let Some(&Reverse((deadline, schedule))) = deadlines.peek() {
+                if deadline <= now && deadline < next_tick {
+                    if deadline_reached(until) {
+                        return;
+                    }
+
+                    deadlines.pop();
+
+                    let (skipped, behind) = &mut skipped[schedule - 1];
+
+                    if !paused {
+                        function(&Tick {
+                            number: tick.number,
+                            skipped: *skipped,
+                            behind: *behind,
+                            suspended: Duration::ZERO,
+                            scheduled_at: SystemTime::now()
+                                - Instant::now().saturating_duration_since(deadline),
+                            schedule,
+                        });
+                    }
+
+                    tick.number += 1;
+                    *skipped = 0;
+                    *behind = Duration::ZERO;
+
+                    let interval = schedules[schedule - 1];
+                    let now = Instant::now();
+                    let mut deadline = deadline + interval;
+
+                    if deadline <= now {
+                        let missed = (now - deadline).as_nanos() / interval.as_nanos() + 1;
+                        let missed = u32::try_from(missed).unwrap_or(u32::MAX);
+
+                        *behind = now - deadline;
+                        *skipped = missed.into();
+                        deadline += interval.saturating_mul(missed);
+                        tick.number += u64::from(missed);
+                    }
+
+                    deadlines.push(Reverse((deadline, schedule)));
+
+                    continue;
+                }
+            }
+
+            if next_tick <= now {
+                break;
+            }
+
+            if deadline_reached(until) {
+                return;
+            }
+
+            let mut timeout = (next_tick - now).min(WALL_CLOCK_CHECK_INTERVAL);
+
+            if let Some(Reverse((deadline, _))) = deadlines.peek() {
+                timeout = timeout.min(deadline.saturating_duration_since(now));
+            }
+
+            if let Some(until) = until {
+                let until = until.duration_since(SystemTime::now()).unwrap_or_default();
+
+                timeout = timeout.min(until);
+            }
+
+            match receive(&controls, timeout) {
+                Ok(Control::Pause) => paused = true,
+                Ok(Control::Resume) => paused = false,
+                Ok(Control::RunNow) => {
+                    next_tick = now;
+                    run_now = true;
+                }
+                Ok(Control::SetInterval(new_interval)) => {
+                    let previous_tick = next_tick.checked_sub(gap).unwrap_or(now);
+                    next_tick = (previous_tick + new_interval).max(now);
+                    interval = new_interval;
+                    spread = Duration::ZERO;
+                    gap = new_interval;
+                }
+                Ok(Control::Stop) => return,
+                Ok(Control::Status(reply)) => {
+                    let _ = reply.send(Status {
+                        paused,
+                        interval,
+                        max_interval: Some(interval + spread).filter(|_| !spread.is_zero()),
+                        next_tick_in: next_tick - now,
+                    });
+                }
+                // the next tick is checked again
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    thread::sleep(timeout.saturating_sub(SPIN_THRESHOLD));
+                    hint::spin_loop();
+                }
+            }
+        }
+
+        if deadline_reached(until) {
+            return;
+        }
+
+        if !paused || run_now {
+            tick.scheduled_at =
+                SystemTime::now() - Instant::now().saturating_duration_since(next_tick);
+            function(&tick);
+        }
+
+        run_now = false;
+
+        let now = Instant::now();
+
+        gap = interval + random.duration_up_to(spread);
+        next_tick += gap;
+        tick.number += 1;
+        tick.skipped = 0;
+        tick.behind = Duration::ZERO;
+        tick.suspended = Duration::ZERO;
+
+        if next_tick > now {
+            resume_catch_up = 0;
+        }
+
+        while next_tick <= now {
+            // behind schedule: the due ticks that can be caught up are fired without waiting,
+            // counted with the minimum interval if the interval is a range
+            let due = (now - next_tick).as_nanos() / interval.as_nanos() + 1;
+
+            if due <= u128::from(catch_up.max(resume_catch_up)) {
+                break;
+            }
+
+            // skip ticks to catch up
+            if tick.skipped == 0 {
+                tick.behind = now.duration_since(next_tick);
+            }
+
+            gap = interval + random.duration_up_to(spread);
+            next_tick += gap;
+            tick.number += 1;
+            tick.skipped += 1;
+        }
+    }
+}
+
+// The offset of the system time from the monotonic clock, that changes with suspends,
+// clock changes and clock adjustments.
+struct ClockOffset {
+    instant: Instant,
+    system_time: SystemTime,
+}
+
+impl ClockOffset {
+    fn new() -> Self {
+        Self {
+            instant: Instant::now(),
+            system_time: SystemTime::now(),
+        }
+    }
+
+    // Returns how much the system time moved, relative to the monotonic clock, since the last call.
+    fn update(&mut self, now: Self) -> (Ordering, Duration) {
+        let monotonic = now.instant - self.instant;
+
+        let change = match now.system_time.duration_since(self.system_time) {
+            Ok(elapsed) if elapsed >= monotonic => (Ordering::Greater, elapsed - monotonic),
+            Ok(elapsed) => (Ordering::Less, monotonic - elapsed),
+            // the system time went backwards
+            Err(e) => (Ordering::Less, monotonic + e.duration()),
+        };
+
+        *self = now;
+
+        change
+    }
+}
+
+// Moves the time along with the system time, relative to the monotonic clock.
+fn shift(instant: Instant, direction: Ordering, change: Duration, now: Instant) -> Instant {
+    match direction {
+        Ordering::Greater => instant.checked_sub(change).unwrap_or(now),
+        _ => instant + change,
+    }
+}
+
+fn update_deadlines(
+    deadlines: &mut BinaryHeap<Reverse<(Instant, usize)>>,
+    update: impl Fn(Instant) -> Instant,
+) {
+    *deadlines = deadlines
+        .drain()
+        .map(|Reverse((deadline, schedule))| Reverse((update(deadline), schedule)))
+        .collect();
+}
+
+// The duration of the suspend, if the system time jumped far enough ahead of the monotonic clock.
+fn suspend(direction: Ordering, change: Duration) -> Option<Duration> {
+    Some(change).filter(|change| direction == Ordering::Greater && *change >= SUSPEND_THRESHOLD)
+}
+
+fn deadline_reached(until: Option<SystemTime>) -> bool {
+    until.is_some_and(|until| SystemTime::now() >= until)
+}
+
+// Waits for a control command, sleeping until shortly before the timeout, then spinning.
+// May return a Timeout before the timeout has elapsed.
+fn receive(controls: &Receiver<Control>, timeout: Duration) -> Result<Control, RecvTimeoutError> {
+    if timeout > SPIN_THRESHOLD {
+        return controls.recv_timeout(timeout - SPIN_THRESHOLD);
+    }
+
+    hint::spin_loop();
+
+    controls.try_recv().map_err(|e| match e {
+        TryRecvError::Empty => RecvTimeoutError::Timeout,
+        TryRecvError::Disconnected => RecvTimeoutError::Disconnected,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_offset() {
+        let start = ClockOffset::new();
+
+        // the times on both clocks, in milliseconds from the start
+        let at = |monotonic: u64, system_time: u64| ClockOffset {
+            instant: start.instant + Duration::from_millis(monotonic),
+            system_time: start.system_time + Duration::from_millis(system_time),
+        };
+
+        let mut offset = at(0, 0);
+
+        // both clocks moved together
+        assert_eq!(
+            offset.update(at(1000, 1000)),
+            (Ordering::Greater, Duration::ZERO)
+        );
+
+        // suspended for 30s: the monotonic clock stopped, the system time did not
+        assert_eq!(
+            offset.update(at(2000, 32_000)),
+            (Ordering::Greater, Duration::from_secs(30))
+        );
+
+        // the system time was set back by 5s
+        assert_eq!(
+            offset.update(at(3000, 28_000)),
+            (Ordering::Less, Duration::from_secs(5))
+        );
+
+        // the offset is relative to the last call
+        assert_eq!(
+            offset.update(at(4000, 29_000)),
+            (Ordering::Greater, Duration::ZERO)
+        );
+
+        // the system time went back before the last call
+        let mut offset = at(1000, 1000);
+        assert_eq!(
+            offset.update(at(2000, 500)),
+            (Ordering::Less, Duration::from_millis(1500))
+        );
+    }
+
+    #[test]
+    fn test_suspend() {
+        let ms = Duration::from_millis;
+
+        assert_eq!(suspend(Ordering::Greater, ms(30_000)), Some(ms(30_000)));
+        assert_eq!(
+            suspend(Ordering::Greater, SUSPEND_THRESHOLD),
+            Some(SUSPEND_THRESHOLD)
+        );
+        assert_eq!(suspend(Ordering::Greater, SUSPEND_THRESHOLD - ms(1)), None);
+        assert_eq!(suspend(Ordering::Greater, Duration::ZERO), None);
+
+        // the system time set back is not a suspend
+        assert_eq!(suspend(Ordering::Less, ms(30_000)), None);
+    }
+}
@@ -0,0 +1,81 @@
+use nix::sys::signal::{self, SigHandler, SigSet, SigmaskHow, Signal};
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use std::thread;
+
+const TERMINATION_SIGNALS: [Signal; 2] = [Signal::SIGINT, Signal::SIGTERM];
+
+// Runs the given function in a dedicated thread when a termination signal is received,
+// then terminates the process with this signal.
+// Must be called before spawning any other thread, so that they inherit the signal mask.
+pub fn on_termination<F>(function: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    let signals = termination_signals();
+
+    signals
+        .thread_block()
+        .expect("Failed to block termination signals");
+
+    thread::spawn(move || {
+        let signal = signals
+            .wait()
+            .expect("Failed to wait for termination signals");
+
+        function();
+
+        // restore the default action, and re-raise the signal to terminate the process,
+        // so that our parent knows that we were terminated by this signal
+        unsafe { signal::signal(signal, SigHandler::SigDfl) }
+            .expect("Failed to restore the default signal handler");
+
+        signal::pthread_sigmask(SigmaskHow::SIG_UNBLOCK, Some(&signals), None)
+            .expect("Failed to unblock termination signals");
+
+        signal::raise(signal).expect("Failed to raise signal");
+    });
+}
+
+// Runs the given function in a dedicated thread each time SIGHUP is received, instead of terminating.
+// Must be called before spawning any other thread, so that they inherit the signal mask.
+pub fn on_hangup<F>(function: F)
+where
+    F: Fn() + Send + 'static,
+{
+    let mut signals = SigSet::empty();
+    signals.add(Signal::SIGHUP);
+
+    signals.thread_block().expect("Failed to block SIGHUP");
+
+    thread::spawn(move || {
+        while signals.wait().is_ok() {
+            function();
+        }
+    });
+}
+
+// Unblocks the termination signals and SIGHUP in the command, that would otherwise inherit our signal mask.
+pub fn unblock_termination(command: &mut Command) {
+    let mut signals = termination_signals();
+    signals.add(Signal::SIGHUP);
+
+    // SAFETY: pthread_sigmask() is async-signal-safe, and touches no memory of the parent
+    unsafe {
+        command.pre_exec(move || {
+            signal::pthread_sigmask(SigmaskHow::SIG_UNBLOCK, Some(&signals), None)
+                .map_err(io::Error::from)
+        });
+    }
+}
+
+fn termination_signals() -> SigSet {
+    let mut signals = SigSet::empty();
+
+    for signal in TERMINATION_SIGNALS {
+        signals.add(signal);
+    }
+
+    signals
+}
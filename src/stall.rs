@@ -0,0 +1,109 @@
+use std::io::{self, Read};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+// The output activity of a command, to detect when it stops producing any.
+pub struct Activity {
+    state: Mutex<State>,
+    // notified when the output is closed
+    closed: Condvar,
+}
+
+struct State {
+    last_output_at: Instant,
+    closed: bool,
+}
+
+// A reader of the output of a command, recording its activity.
+pub struct Watched<R> {
+    reader: R,
+    activity: Arc<Activity>,
+}
+
+impl Activity {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(State {
+                last_output_at: Instant::now(),
+                closed: false,
+            }),
+            closed: Condvar::new(),
+        })
+    }
+
+    pub fn watch<R: Read>(self: &Arc<Self>, reader: R) -> Watched<R> {
+        Watched {
+            reader,
+            activity: Arc::clone(self),
+        }
+    }
+
+    // Called once the output has been read to the end, to stop wait_for_stall().
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.closed.notify_all();
+    }
+
+    // Blocks until either the output is closed, and returns false,
+    // or no output was produced for the timeout, and returns true.
+    pub fn wait_for_stall(&self, timeout: Duration) -> bool {
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            if state.closed {
+                return false;
+            }
+
+            let idle = state.last_output_at.elapsed();
+
+            if idle >= timeout {
+                return true;
+            }
+
+            state = self.closed.wait_timeout(state, timeout - idle).unwrap().0;
+        }
+    }
+}
+
+impl<R: Read> Read for Watched<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let length = self.reader.read(buffer)?;
+
+        if length > 0 {
+            self.activity.state.lock().unwrap().last_output_at = Instant::now();
+        }
+
+        Ok(length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_wait_for_stall() {
+        let activity = Activity::new();
+        let start = Instant::now();
+
+        assert!(activity.wait_for_stall(Duration::from_millis(50)));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        // output resets the timer
+        let mut reader = activity.watch(&b"output"[..]);
+        reader.read_exact(&mut [0; 6]).unwrap();
+        assert!(activity.state.lock().unwrap().last_output_at > start);
+
+        let closer = {
+            let activity = Arc::clone(&activity);
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                activity.close();
+            })
+        };
+
+        assert!(!activity.wait_for_stall(Duration::from_secs(10)));
+        closer.join().unwrap();
+    }
+}
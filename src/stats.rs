@@ -0,0 +1,267 @@
+use crate::interval::format_duration;
+use crate::usage::{format_size, Usage};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// The number of most recent run durations kept for the percentiles.
+const MAX_RECENT_DURATIONS: usize = 1000;
+
+pub struct Stats {
+    start_time: Instant,
+    runs: u64,
+    successes: u64,
+    failures: u64,
+    skipped_ticks: u64,
+    // number of completed runs that have a duration
+    timed_runs: u32,
+    min_duration: Duration,
+    max_duration: Duration,
+    total_duration: Duration,
+    recent_durations: VecDeque<Duration>,
+    // number of completed runs that have a resource usage
+    measured_runs: u64,
+    user_time: Duration,
+    system_time: Duration,
+    max_rss: u64,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self {
+            start_time: Instant::now(),
+            runs: 0,
+            successes: 0,
+            failures: 0,
+            skipped_ticks: 0,
+            timed_runs: 0,
+            min_duration: Duration::MAX,
+            max_duration: Duration::ZERO,
+            total_duration: Duration::ZERO,
+            recent_durations: VecDeque::with_capacity(MAX_RECENT_DURATIONS),
+            measured_runs: 0,
+            user_time: Duration::ZERO,
+            system_time: Duration::ZERO,
+            max_rss: 0,
+        }
+    }
+
+    pub fn record_start(&mut self) {
+        self.runs += 1;
+    }
+
+    pub fn record_skipped_ticks(&mut self, count: u64) {
+        self.skipped_ticks += count;
+    }
+
+    pub fn skipped_ticks(&self) -> u64 {
+        self.skipped_ticks
+    }
+
+    // The duration is None if the command could not be started, or waited for.
+    pub fn record_end(&mut self, success: bool, duration: Option<Duration>) {
+        if success {
+            self.successes += 1;
+        } else {
+            self.failures += 1;
+        }
+
+        if let Some(duration) = duration {
+            self.timed_runs = self.timed_runs.saturating_add(1);
+            self.min_duration = self.min_duration.min(duration);
+            self.max_duration = self.max_duration.max(duration);
+            self.total_duration = self.total_duration.saturating_add(duration);
+
+            if self.recent_durations.len() == MAX_RECENT_DURATIONS {
+                self.recent_durations.pop_front();
+            }
+
+            self.recent_durations.push_back(duration);
+        }
+    }
+
+    pub fn record_usage(&mut self, usage: Usage) {
+        self.measured_runs += 1;
+        self.user_time = self.user_time.saturating_add(usage.user_time);
+        self.system_time = self.system_time.saturating_add(usage.system_time);
+        self.max_rss = self.max_rss.max(usage.max_rss);
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = String::from("Summary:");
+
+        for line in self.report_lines() {
+            summary.push_str(&format!("\n  {line}"));
+        }
+
+        summary
+    }
+
+    // A single line with the counters, and the percentiles of the most recent run durations.
+    pub fn line(&self) -> String {
+        let running = self.runs - self.successes - self.failures;
+
+        let mut line = format!(
+            "Runs: {}, failed: {}, skipped ticks: {}, running: {running}",
+            self.runs, self.failures, self.skipped_ticks
+        );
+
+        if !self.recent_durations.is_empty() {
+            let mut durations: Vec<Duration> = self.recent_durations.iter().copied().collect();
+            durations.sort_unstable();
+
+            line.push_str(&format!(
+                ", p50: {}, p95: {}",
+                format_duration(percentile(&durations, 50)),
+                format_duration(percentile(&durations, 95))
+            ));
+        }
+
+        line
+    }
+
+    pub fn report_lines(&self) -> Vec<String> {
+        self.report(self.start_time.elapsed())
+    }
+
+    fn report(&self, elapsed: Duration) -> Vec<String> {
+        let running = self.runs - self.successes - self.failures;
+
+        let mut lines = vec![
+            format!(
+                "Runs:          {} ({} succeeded, {} failed, {running} running)",
+                self.runs, self.successes, self.failures
+            ),
+            format!("Skipped ticks: {}", self.skipped_ticks),
+        ];
+
+        if self.timed_runs != 0 {
+            lines.push(format!(
+                "Run duration:  min {}, avg {}, max {}",
+                format_duration(self.min_duration),
+                format_duration(self.total_duration / self.timed_runs),
+                format_duration(self.max_duration)
+            ));
+        }
+
+        if self.measured_runs != 0 {
+            lines.push(format!(
+                "CPU time:      {} user, {} system",
+                format_duration(self.user_time),
+                format_duration(self.system_time)
+            ));
+            lines.push(format!("Max RSS:       {}", format_size(self.max_rss)));
+        }
+
+        lines.push(format!("Elapsed time:  {}", format_duration(elapsed)));
+
+        lines
+    }
+}
+
+// The nearest-rank percentile of the sorted, non-empty durations.
+fn percentile(durations: &[Duration], percent: usize) -> Duration {
+    let rank = (durations.len() * percent).div_ceil(100);
+
+    durations[rank.max(1) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut stats = Stats::new();
+
+        stats.record_start();
+        stats.record_end(true, Some(Duration::from_millis(100)));
+        stats.record_start();
+        stats.record_end(false, Some(Duration::from_millis(400)));
+        stats.record_usage(Usage {
+            user_time: Duration::from_millis(250),
+            system_time: Duration::from_millis(50),
+            max_rss: 3 << 20,
+        });
+        stats.record_usage(Usage {
+            user_time: Duration::from_millis(100),
+            system_time: Duration::from_millis(20),
+            max_rss: 1 << 20,
+        });
+        stats.record_start();
+        stats.record_end(false, None);
+        stats.record_start();
+        stats.record_skipped_ticks(2);
+        stats.record_skipped_ticks(1);
+
+        assert_eq!(
+            stats.report(Duration::from_millis(61_500)),
+            [
+                "Runs:          4 (1 succeeded, 2 failed, 1 running)",
+                "Skipped ticks: 3",
+                "Run duration:  min 0.1s, avg 0.25s, max 0.4s",
+                "CPU time:      0.35s user, 0.07s system",
+                "Max RSS:       3M",
+                "Elapsed time:  1m1.5s",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line() {
+        let mut stats = Stats::new();
+
+        stats.record_start();
+        assert_eq!(
+            stats.line(),
+            "Runs: 1, failed: 0, skipped ticks: 0, running: 1"
+        );
+
+        for millis in 1..=20 {
+            stats.record_end(millis % 10 != 0, Some(Duration::from_millis(millis * 10)));
+            stats.record_start();
+        }
+
+        stats.record_skipped_ticks(3);
+
+        assert_eq!(
+            stats.line(),
+            "Runs: 21, failed: 2, skipped ticks: 3, running: 1, p50: 0.1s, p95: 0.19s"
+        );
+    }
+
+    #[test]
+    fn test_line_keeps_recent_durations() {
+        let mut stats = Stats::new();
+
+        for _ in 0..MAX_RECENT_DURATIONS {
+            stats.record_start();
+            stats.record_end(true, Some(Duration::from_secs(10)));
+        }
+
+        for _ in 0..MAX_RECENT_DURATIONS {
+            stats.record_start();
+            stats.record_end(true, Some(Duration::from_secs(1)));
+        }
+
+        assert_eq!(
+            stats.line(),
+            "Runs: 2000, failed: 0, skipped ticks: 0, running: 0, p50: 1s, p95: 1s"
+        );
+    }
+
+    #[test]
+    fn test_report_without_completed_runs() {
+        let mut stats = Stats::new();
+
+        stats.record_start();
+
+        assert_eq!(
+            stats.report(Duration::from_millis(500)),
+            [
+                "Runs:          1 (0 succeeded, 0 failed, 1 running)",
+                "Skipped ticks: 0",
+                "Elapsed time:  0.5s",
+            ]
+        );
+    }
+}
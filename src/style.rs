@@ -0,0 +1,143 @@
+use std::env;
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const UNDERLINE: &str = "\x1b[4m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// When to color the output of `every`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorChoice {
+    /// When the output is a terminal, unless disabled by `NO_COLOR` or `TERM=dumb`.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// The escape codes of the styles of an output, empty when it is not colored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Style {
+    pub bold: &'static str,
+    pub dim: &'static str,
+    pub underline: &'static str,
+    pub red: &'static str,
+    pub yellow: &'static str,
+    pub reset: &'static str,
+}
+
+impl ColorChoice {
+    /// Whether to color an output, given whether it is a terminal.
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Auto => Environment::get_current(is_terminal).supports_color(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+impl Style {
+    pub fn new(colored: bool) -> Self {
+        match colored {
+            true => Self {
+                bold: BOLD,
+                dim: DIM,
+                underline: UNDERLINE,
+                red: RED,
+                yellow: YELLOW,
+                reset: RESET,
+            },
+            false => Self {
+                bold: "",
+                dim: "",
+                underline: "",
+                red: "",
+                yellow: "",
+                reset: "",
+            },
+        }
+    }
+}
+
+struct Environment {
+    is_terminal: bool,
+    env_no_color: Option<String>,
+    env_term: Option<String>,
+}
+
+impl Environment {
+    fn get_current(is_terminal: bool) -> Self {
+        Self {
+            is_terminal,
+            env_no_color: env::var("NO_COLOR").ok(),
+            env_term: env::var("TERM").ok(),
+        }
+    }
+
+    #[cfg(test)]
+    fn mock(is_terminal: bool, env_vars: &Vec<(&str, &str)>) -> Self {
+        let get_env_var = |key| {
+            env_vars
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.to_string())
+        };
+
+        Self {
+            is_terminal,
+            env_no_color: get_env_var("NO_COLOR"),
+            env_term: get_env_var("TERM"),
+        }
+    }
+
+    fn supports_color(&self) -> bool {
+        self.is_terminal
+            // any non-empty value for NO_COLOR should disable colors
+            // https://no-color.org/
+            && self.env_no_color.as_ref().is_none_or(|v| v.is_empty())
+            // TERM=dumb should also disable colors
+            && self.env_term.as_ref().is_none_or(|v| v != "dumb")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_environment_supports_color() {
+        let test_cases = [
+            (false, vec![], false),
+            (true, vec![], true),
+            (true, vec![("NO_COLOR", "")], true),
+            (true, vec![("NO_COLOR", "0")], false),
+            (true, vec![("NO_COLOR", "1")], false),
+            (true, vec![("TERM", "xterm")], true),
+            (true, vec![("TERM", "dumb")], false),
+            (true, vec![("NO_COLOR", ""), ("TERM", "dumb")], false),
+            (true, vec![("NO_COLOR", "1"), ("TERM", "xterm")], false),
+            (true, vec![("NO_COLOR", ""), ("TERM", "xterm")], true),
+        ];
+
+        for (is_terminal, env_vars, expected_supports_color) in test_cases {
+            let environment = Environment::mock(is_terminal, &env_vars);
+
+            assert_eq!(
+                environment.supports_color(),
+                expected_supports_color,
+                "{:?}",
+                (is_terminal, &env_vars, expected_supports_color)
+            );
+        }
+    }
+
+    #[test]
+    fn test_color_choice() {
+        assert!(ColorChoice::Always.enabled(false));
+        assert!(!ColorChoice::Never.enabled(true));
+        assert!(!ColorChoice::Auto.enabled(false));
+    }
+}
@@ -0,0 +1,73 @@
+/// Returns the value of the candidate closest to the input, for a "did you mean" hint,
+/// if it is within an edit distance of a third of the length of the input.
+///
+/// The candidates are pairs of the name compared with the input, ignoring case, and of the value to return,
+/// the first one winning a tie.
+pub(crate) fn suggest<'a, T>(
+    input: &str,
+    candidates: impl IntoIterator<Item = (&'a str, T)>,
+) -> Option<T> {
+    let input = input.to_lowercase();
+    let max_distance = input.chars().count() / 3;
+
+    candidates
+        .into_iter()
+        .map(|(name, value)| (edit_distance(&input, &name.to_lowercase()), value))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, value)| value)
+}
+
+// The Levenshtein distance: the number of characters to insert, delete or substitute to turn a into b.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+
+    // the distances from the first characters of a to each prefix of b
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != *b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("verbose", "verbose"), 0);
+        assert_eq!(edit_distance("verbos", "verbose"), 1);
+        assert_eq!(edit_distance("vrebose", "verbose"), 2);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("µs", "us"), 1);
+    }
+
+    #[test]
+    fn test_suggest() {
+        let options = || ["verbose", "version", "quiet"].map(|name| (name, name));
+
+        assert_eq!(suggest("verbos", options()), Some("verbose"));
+        assert_eq!(suggest("VERSION", options()), Some("version"));
+        assert_eq!(suggest("quet", options()), Some("quiet"));
+        assert_eq!(suggest("v", options()), None);
+        assert_eq!(suggest("loud", options()), None);
+        assert_eq!(suggest("", options()), None);
+
+        // the first of the closest candidates
+        assert_eq!(suggest("ab", [("a", 1), ("b", 2), ("ab", 3)]), Some(3));
+        assert_eq!(suggest("abcx", [("abcy", 1), ("abcz", 2)]), Some(1));
+    }
+}
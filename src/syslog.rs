@@ -0,0 +1,139 @@
+use std::io;
+use std::os::unix::net::UnixDatagram;
+use std::process;
+use std::sync::OnceLock;
+
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+const SYSLOG_SOCKET: &str = "/dev/log";
+const IDENTIFIER: &str = "every";
+// the "user-level messages" facility
+const FACILITY: u8 = 1;
+
+// The system log, set by connect().
+static SYSTEM_LOG: OnceLock<SystemLog> = OnceLock::new();
+
+// The severity of a message, as defined by syslog.
+#[derive(Clone, Copy)]
+pub enum Priority {
+    Error = 3,
+    Warning = 4,
+    Notice = 5,
+    Info = 6,
+}
+
+struct SystemLog {
+    socket: UnixDatagram,
+    // whether the socket speaks the native protocol of the journal, that has structured fields
+    journal: bool,
+}
+
+// Connects to the journal if available, or to the syslog daemon.
+pub fn connect() -> io::Result<()> {
+    let socket = UnixDatagram::unbound()?;
+
+    let journal = match socket.connect(JOURNAL_SOCKET) {
+        Ok(()) => true,
+        Err(_) => {
+            socket.connect(SYSLOG_SOCKET)?;
+            false
+        }
+    };
+
+    let _ = SYSTEM_LOG.set(SystemLog { socket, journal });
+
+    Ok(())
+}
+
+// Records the message in the system log, if connected.
+// The fields are only recorded by the journal, and must be named in uppercase.
+pub fn log(priority: Priority, message: &str, fields: &[(&str, String)]) {
+    if let Some(system_log) = SYSTEM_LOG.get() {
+        let entry = if system_log.journal {
+            journal_entry(priority, message, fields)
+        } else {
+            syslog_message(priority, message).into_bytes()
+        };
+
+        let _ = system_log.socket.send(&entry);
+    }
+}
+
+// Formats an entry in the native protocol of the journal, with one KEY=value field per line.
+fn journal_entry(priority: Priority, message: &str, fields: &[(&str, String)]) -> Vec<u8> {
+    let mut entry = vec![];
+
+    push_field(&mut entry, "MESSAGE", message);
+    push_field(&mut entry, "PRIORITY", &(priority as u8).to_string());
+    push_field(&mut entry, "SYSLOG_IDENTIFIER", IDENTIFIER);
+
+    for (name, value) in fields {
+        push_field(&mut entry, name, value);
+    }
+
+    entry
+}
+
+// Appends a field to a journal entry: KEY=value on a line, or if the value spans several lines,
+// the name on a line, followed by the length of the value as 64-bit little-endian, and the value.
+fn push_field(entry: &mut Vec<u8>, name: &str, value: &str) {
+    entry.extend_from_slice(name.as_bytes());
+
+    if value.contains('\n') {
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+
+    entry.extend_from_slice(value.as_bytes());
+    entry.push(b'\n');
+}
+
+// Formats a message in the traditional syslog format, as accepted by /dev/log.
+fn syslog_message(priority: Priority, message: &str) -> String {
+    format!(
+        "<{}>{IDENTIFIER}[{}]: {message}",
+        FACILITY * 8 + priority as u8,
+        process::id()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_entry() {
+        let fields = [
+            ("RUN_ID", String::from("3")),
+            ("EXIT_CODE", String::from("1")),
+        ];
+
+        assert_eq!(
+            journal_entry(Priority::Error, "Run #3 failed", &fields),
+            b"MESSAGE=Run #3 failed\nPRIORITY=3\nSYSLOG_IDENTIFIER=every\nRUN_ID=3\nEXIT_CODE=1\n"
+        );
+    }
+
+    #[test]
+    fn test_journal_entry_with_newlines() {
+        let fields = [("OUTPUT", String::from("one\ntwo"))];
+
+        let mut expected = b"MESSAGE\n\x0d\0\0\0\0\0\0\0Run #3\nfailed\n".to_vec();
+        expected.extend_from_slice(b"PRIORITY=3\nSYSLOG_IDENTIFIER=every\n");
+        expected.extend_from_slice(b"OUTPUT\n\x07\0\0\0\0\0\0\0one\ntwo\n");
+
+        assert_eq!(
+            journal_entry(Priority::Error, "Run #3\nfailed", &fields),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_syslog_message() {
+        assert_eq!(
+            syslog_message(Priority::Notice, "Tick #2 skipped"),
+            format!("<13>every[{}]: Tick #2 skipped", process::id())
+        );
+    }
+}
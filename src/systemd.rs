@@ -0,0 +1,47 @@
+use std::env;
+use std::io;
+#[cfg(target_os = "linux")]
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::process::Command;
+use std::sync::OnceLock;
+
+const NOTIFY_SOCKET: &str = "NOTIFY_SOCKET";
+
+// The socket to notify systemd on, set by connect().
+static NOTIFIER: OnceLock<UnixDatagram> = OnceLock::new();
+
+// Connects to the notification socket of systemd.
+// Does nothing if NOTIFY_SOCKET is not set, as when not started by systemd.
+pub fn connect() -> io::Result<()> {
+    let Some(path) = env::var_os(NOTIFY_SOCKET) else {
+        return Ok(());
+    };
+
+    let path = path.into_encoded_bytes();
+
+    let address = match path.strip_prefix(b"@") {
+        #[cfg(target_os = "linux")]
+        Some(name) => SocketAddr::from_abstract_name(name)?,
+        _ => SocketAddr::from_pathname(String::from_utf8_lossy(&path).as_ref())?,
+    };
+
+    let socket = UnixDatagram::unbound()?;
+    socket.connect_addr(&address)?;
+
+    let _ = NOTIFIER.set(socket);
+
+    Ok(())
+}
+
+// Sends a state such as READY=1 to systemd, if connected.
+pub fn notify(state: &str) {
+    if let Some(socket) = NOTIFIER.get() {
+        let _ = socket.send(state.as_bytes());
+    }
+}
+
+// Hides the notification socket from the command, that is not the service.
+pub fn hide_socket(command: &mut Command) {
+    command.env_remove(NOTIFY_SOCKET);
+}
@@ -0,0 +1,209 @@
+use crate::clock;
+use crate::random::Random;
+use std::time::SystemTime;
+
+const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+// The values of the placeholders for a run.
+pub struct Placeholders {
+    tick_number: u64,
+    scheduled_at: SystemTime,
+    // the line of --args-from for the run, if any
+    arg: Option<String>,
+    // whether the values are shell-quoted, for the commands run through sh -c
+    quoted: bool,
+    random: Random,
+}
+
+impl Placeholders {
+    pub fn new(tick_number: u64, scheduled_at: SystemTime) -> Self {
+        Self {
+            tick_number,
+            scheduled_at,
+            arg: None,
+            quoted: false,
+            random: Random::new(),
+        }
+    }
+
+    pub fn with_arg(mut self, arg: Option<String>) -> Self {
+        self.arg = arg;
+        self
+    }
+
+    pub fn quoted(mut self, quoted: bool) -> Self {
+        self.quoted = quoted;
+        self
+    }
+
+    // Expands the placeholders in a command argument: {i} for the tick number, {ts} or {ts:<format>}
+    // for the scheduled local time, with a strftime() format, {uuid} for a random UUID, and {arg} for
+    // the line of --args-from.
+    // {{ and }} are literal braces, and unknown placeholders are kept as is, such as {} for find.
+    // Once quoted, the values are single-quoted when they contain other characters than a-z, digits
+    // and -_./:,=+@%, so that a line of --args-from cannot inject commands into sh -c.
+    pub fn expand(&mut self, arg: &str) -> String {
+        let mut expanded = String::with_capacity(arg.len());
+        let mut rest = arg;
+
+        while let Some(index) = rest.find(['{', '}']) {
+            expanded.push_str(&rest[..index]);
+            rest = &rest[index..];
+
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                expanded.push_str(&rest[..1]);
+                rest = &rest[2..];
+                continue;
+            }
+
+            let value = rest
+                .strip_prefix('{')
+                .and_then(|rest| rest.split_once('}'))
+                .and_then(|(name, after)| Some((self.value(name)?, after)));
+
+            match value {
+                Some((value, after)) if self.quoted => {
+                    expanded.push_str(&shell_quote(&value));
+                    rest = after;
+                }
+                Some((value, after)) => {
+                    expanded.push_str(&value);
+                    rest = after;
+                }
+                None => {
+                    expanded.push_str(&rest[..1]);
+                    rest = &rest[1..];
+                }
+            }
+        }
+
+        expanded.push_str(rest);
+
+        expanded
+    }
+
+    fn value(&mut self, name: &str) -> Option<String> {
+        match name {
+            "i" => Some(self.tick_number.to_string()),
+            "ts" => Some(clock::format_local(
+                self.scheduled_at,
+                DEFAULT_TIMESTAMP_FORMAT,
+            )),
+            "uuid" => Some(self.uuid()),
+            "arg" => self.arg.clone(),
+            _ => name
+                .strip_prefix("ts:")
+                .map(|format| clock::format_local(self.scheduled_at, format)),
+        }
+    }
+
+    // A random (version 4) UUID.
+    fn uuid(&mut self) -> String {
+        let high = (self.random.next_u64() & !0xf000) | 0x4000;
+        let low = (self.random.next_u64() >> 2) | (0b10 << 62);
+
+        format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            high >> 32,
+            (high >> 16) & 0xffff,
+            high & 0xffff,
+            low >> 48,
+            low & 0xffff_ffff_ffff
+        )
+    }
+}
+
+// Quotes a value for a shell, unless it is only made of characters that the shell reads as is.
+fn shell_quote(value: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./:,=+@%".contains(c);
+
+    if !value.is_empty() && value.chars().all(plain) {
+        return String::from(value);
+    }
+
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    #[test]
+    fn test_expand() {
+        let scheduled_at = SystemTime::now();
+        let mut placeholders = Placeholders::new(42, scheduled_at);
+        let ts = clock::format_local(scheduled_at, DEFAULT_TIMESTAMP_FORMAT);
+        let year = clock::format_local(scheduled_at, "%Y");
+
+        let test_cases = [
+            ("", String::new()),
+            ("hello", String::from("hello")),
+            ("{i}", String::from("42")),
+            ("run-{i}-of-{i}", String::from("run-42-of-42")),
+            ("backup-{ts}.sql", format!("backup-{ts}.sql")),
+            ("{ts:%Y}", year.clone()),
+            ("{ts:}", String::new()),
+            // escapes
+            ("{{i}}", String::from("{i}")),
+            ("{{{i}}}", String::from("{42}")),
+            ("}}", String::from("}")),
+            // unknown placeholders & lone braces are kept as is
+            ("{}", String::from("{}")),
+            ("{x}", String::from("{x}")),
+            ("{print $1}", String::from("{print $1}")),
+            ("{{x", String::from("{x")),
+            ("{", String::from("{")),
+            ("}", String::from("}")),
+            ("{i", String::from("{i")),
+            ("{{i}", String::from("{i}")),
+            ("{ {i}", String::from("{ 42")),
+        ];
+
+        for (arg, expected) in test_cases {
+            assert_eq!(placeholders.expand(arg), expected, "arg: {arg}");
+        }
+    }
+
+    #[test]
+    fn test_expand_arg() {
+        let mut placeholders = Placeholders::new(1, SystemTime::now());
+        assert_eq!(placeholders.expand("{arg}"), "{arg}");
+
+        let mut placeholders = placeholders.with_arg(Some(String::from("https://example.com")));
+        assert_eq!(placeholders.expand("{arg}"), "https://example.com");
+        assert_eq!(placeholders.expand("{{arg}}"), "{arg}");
+    }
+
+    #[test]
+    fn test_expand_quoted() {
+        let arg = Some(String::from("x; echo INJECTED"));
+        let mut placeholders = Placeholders::new(42, SystemTime::now())
+            .with_arg(arg)
+            .quoted(true);
+
+        assert_eq!(placeholders.expand("echo {arg}"), "echo 'x; echo INJECTED'");
+        assert_eq!(placeholders.expand("echo {i}"), "echo 42");
+        assert_eq!(placeholders.expand("{ts:%Y %m}").len(), 9);
+        assert_eq!(placeholders.expand("{ts:}"), "''");
+        assert_eq!(placeholders.expand("'{{arg}}'"), "'{arg}'");
+
+        let mut placeholders = placeholders.with_arg(Some(String::from("it's $HOME")));
+        assert_eq!(placeholders.expand("echo {arg}"), r"echo 'it'\''s $HOME'");
+    }
+
+    #[test]
+    fn test_uuid() {
+        let mut placeholders = Placeholders::new(1, SystemTime::now());
+        let uuid =
+            Regex::new("^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}$")
+                .unwrap();
+
+        let first = placeholders.expand("{uuid}");
+        let second = placeholders.expand("{uuid}");
+
+        assert!(uuid.is_match(&first), "uuid: {first}");
+        assert!(uuid.is_match(&second), "uuid: {second}");
+        assert_ne!(first, second);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::countdown::time_left;
+use crate::keys::get_status;
+use crate::scheduler::{Control, Status};
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+// How often the title is updated.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+// The xterm sequences saving and restoring the title, on a stack.
+const SAVE_TITLE: &str = "\x1b[22;0t";
+const RESTORE_TITLE: &str = "\x1b[23;0t";
+
+// Whether the title has been saved by start(), and must be restored.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+// The outcome of the last run, once completed.
+static LAST_RUN: Mutex<Option<String>> = Mutex::new(None);
+
+// Sets the title of the terminal on stderr to the command, the outcome of the last run,
+// and the time until the next one, updated in the background until the scheduler stops.
+pub fn start(controls: Sender<Control>, command: String) {
+    eprint!("{SAVE_TITLE}");
+    STARTED.store(true, Ordering::SeqCst);
+
+    thread::spawn(move || {
+        while let Some(status) = get_status(&controls) {
+            let last_run = LAST_RUN.lock().unwrap().clone();
+
+            if STARTED.load(Ordering::SeqCst) {
+                eprint!(
+                    "\x1b]0;{}\x07",
+                    text(&command, last_run.as_deref(), &status)
+                );
+            }
+
+            thread::sleep(REFRESH_INTERVAL);
+        }
+    });
+}
+
+// Records the outcome of a run, shown on the next update. The status is None if the command could not be started.
+pub fn record(status: Option<ExitStatus>) {
+    let outcome = match status {
+        Some(status) => match (status.code(), status.signal()) {
+            (Some(code), _) => format!("exit {code}"),
+            (None, Some(signal)) => format!("signal {signal}"),
+            (None, None) => status.to_string(),
+        },
+        None => String::from("failed to start"),
+    };
+
+    *LAST_RUN.lock().unwrap() = Some(outcome);
+}
+
+// Gives the terminal its title back, if it has been set.
+pub fn restore() {
+    if STARTED.swap(false, Ordering::SeqCst) {
+        eprint!("{RESTORE_TITLE}");
+    }
+}
+
+fn text(command: &str, last_run: Option<&str>, status: &Status) -> String {
+    let next_run = match status.paused {
+        true => String::from("paused"),
+        false => format!("next run in {}", time_left(status)),
+    };
+
+    // control characters would end the sequence
+    let command: String = command.chars().filter(|c| !c.is_control()).collect();
+
+    match last_run {
+        Some(last_run) => format!("{command}: {last_run}, {next_run}"),
+        None => format!("{command}: {next_run}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text() {
+        let status = Status {
+            paused: false,
+            interval: Duration::from_secs(60),
+            max_interval: None,
+            next_tick_in: Duration::from_millis(11_200),
+        };
+
+        assert_eq!(
+            text("./check.sh", None, &status),
+            "./check.sh: next run in 12s"
+        );
+        assert_eq!(
+            text("echo\x07 hi", Some("exit 1"), &status),
+            "echo hi: exit 1, next run in 12s"
+        );
+        assert_eq!(
+            text(
+                "./check.sh",
+                Some("signal 9"),
+                &Status {
+                    paused: true,
+                    ..status
+                }
+            ),
+            "./check.sh: signal 9, paused"
+        );
+    }
+}
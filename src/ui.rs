@@ -0,0 +1,403 @@
+use crate::bell;
+use crate::interval::format_duration;
+use crate::keys::{get_status, RawMode};
+use crate::runner::format_utc;
+use crate::scheduler::{Control, Status};
+use nix::unistd;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+const MAX_RUNS: usize = 20;
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+const SPARKLINE_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+const ENTER_SCREEN: &str = "\x1b[?1049h\x1b[?25l";
+const LEAVE_SCREEN: &str = "\x1b[?25h\x1b[?1049l";
+
+// The terminal taken over by the dashboard, None once restored.
+static TERMINAL: Mutex<Option<Terminal>> = Mutex::new(None);
+
+/// An event reported by the runner to the dashboard.
+pub enum Event {
+    Started {
+        tick: u64,
+        started_at: SystemTime,
+    },
+    /// The run is None if the command could not be run.
+    Finished {
+        tick: u64,
+        run: Option<(ExitStatus, Duration)>,
+    },
+}
+
+struct Terminal {
+    // restores the settings of the terminal once dropped
+    raw_mode: RawMode,
+    // the original stdout, where the dashboard is drawn
+    screen: File,
+    stderr: RawFd,
+}
+
+struct Dashboard {
+    title: String,
+    // the most recent runs, oldest first
+    runs: VecDeque<RunRow>,
+}
+
+struct RunRow {
+    tick: u64,
+    started_at: SystemTime,
+    state: RunState,
+}
+
+enum RunState {
+    Running,
+    Completed(ExitStatus, Duration),
+    Failed,
+}
+
+// Takes over the terminal to show a live dashboard of the runs, and returns the sender of events.
+// Our stdout & stderr, inherited by the commands, are redirected to /dev/null until restore().
+// Keys read from stdin control the scheduler: p to pause/resume, r to run now, q to quit.
+pub fn start(title: String, controls: Sender<Control>) -> io::Result<Sender<Event>> {
+    let raw_mode = RawMode::enable()?;
+
+    let null = OpenOptions::new().write(true).open("/dev/null")?;
+    let screen = duplicate(io::stdout().as_raw_fd())?;
+    let stderr = unistd::dup(io::stderr().as_raw_fd())?;
+    unistd::dup2(null.as_raw_fd(), io::stdout().as_raw_fd())?;
+    unistd::dup2(null.as_raw_fd(), io::stderr().as_raw_fd())?;
+
+    let mut terminal = Terminal {
+        raw_mode,
+        screen,
+        stderr,
+    };
+
+    write!(terminal.screen, "{ENTER_SCREEN}")?;
+    *TERMINAL.lock().unwrap() = Some(terminal);
+
+    let (events, receiver) = mpsc::channel();
+
+    {
+        let controls = controls.clone();
+        thread::spawn(move || read_keys(&controls));
+    }
+
+    thread::spawn(move || {
+        let dashboard = Dashboard {
+            title,
+            runs: VecDeque::new(),
+        };
+
+        draw_loop(dashboard, &receiver, &controls);
+    });
+
+    Ok(events)
+}
+
+// Rings the bell of the terminal the dashboard is drawn on, and with flash, briefly inverts the screen.
+pub fn ring(flash: bool) {
+    let screen = match TERMINAL.lock().unwrap().as_ref() {
+        Some(terminal) => terminal.screen.try_clone(),
+        None => return,
+    };
+
+    if let Ok(screen) = screen {
+        bell::ring_on(screen, flash);
+    }
+}
+
+// Gives the terminal back, if the dashboard has been started.
+pub fn restore() {
+    let Some(mut terminal) = TERMINAL.lock().unwrap().take() else {
+        return;
+    };
+
+    let _ = write!(terminal.screen, "{LEAVE_SCREEN}");
+    let _ = unistd::dup2(terminal.screen.as_raw_fd(), io::stdout().as_raw_fd());
+    let _ = unistd::dup2(terminal.stderr, io::stderr().as_raw_fd());
+    let _ = unistd::close(terminal.stderr);
+    drop(terminal.raw_mode);
+}
+
+fn duplicate(fd: RawFd) -> io::Result<File> {
+    let fd = unistd::dup(fd)?;
+
+    // SAFETY: the file descriptor has just been created, and is owned by nobody else
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+fn read_keys(controls: &Sender<Control>) {
+    let mut key = [0];
+
+    while let Ok(1) = io::stdin().read(&mut key) {
+        let control = match key[0] {
+            b'p' => match get_status(controls) {
+                Some(status) if status.paused => Control::Resume,
+                Some(_) => Control::Pause,
+                None => return,
+            },
+            b'r' => Control::RunNow,
+            b'q' => Control::Stop,
+            _ => continue,
+        };
+
+        if controls.send(control).is_err() {
+            return;
+        }
+    }
+}
+
+fn draw_loop(mut dashboard: Dashboard, events: &Receiver<Event>, controls: &Sender<Control>) {
+    loop {
+        let status = get_status(controls);
+        let lines = dashboard.render(status.as_ref());
+
+        {
+            let mut terminal = TERMINAL.lock().unwrap();
+
+            let Some(terminal) = terminal.as_mut() else {
+                return;
+            };
+
+            // redraw over the previous frame, to avoid flickering
+            let mut frame = String::from("\x1b[H");
+
+            for line in lines {
+                frame.push_str(&line);
+                frame.push_str("\x1b[K\n");
+            }
+
+            frame.push_str("\x1b[J");
+
+            let _ = terminal.screen.write_all(frame.as_bytes());
+        }
+
+        // handle the events received until the next refresh
+        let deadline = Instant::now() + REFRESH_INTERVAL;
+
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+
+            match events.recv_timeout(timeout) {
+                Ok(event) => dashboard.handle(event),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+}
+
+impl Dashboard {
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::Started { tick, started_at } => {
+                self.runs.push_back(RunRow {
+                    tick,
+                    started_at,
+                    state: RunState::Running,
+                });
+
+                if self.runs.len() > MAX_RUNS {
+                    self.runs.pop_front();
+                }
+            }
+            Event::Finished { tick, run } => {
+                if let Some(row) = self.runs.iter_mut().find(|row| row.tick == tick) {
+                    row.state = match run {
+                        Some((status, duration)) => RunState::Completed(status, duration),
+                        None => RunState::Failed,
+                    };
+                }
+            }
+        }
+    }
+
+    fn render(&self, status: Option<&Status>) -> Vec<String> {
+        let state = match status {
+            Some(status) if status.paused => String::from("paused"),
+            Some(status) => format!(
+                "running, next tick in {}",
+                format_duration(status.next_tick_in)
+            ),
+            None => String::from("stopping"),
+        };
+
+        let durations: Vec<Duration> = self
+            .runs
+            .iter()
+            .filter_map(|row| match row.state {
+                RunState::Completed(_, duration) => Some(duration),
+                _ => None,
+            })
+            .collect();
+
+        let mut lines = vec![self.title.clone(), format!("State: {state}")];
+
+        if let Some(max) = durations.iter().max() {
+            lines.push(format!(
+                "Run times: {} (max {})",
+                sparkline(&durations),
+                format_duration(*max)
+            ));
+        }
+
+        lines.push(String::new());
+        lines.push(format!(
+            "{:>6}  {:<23}  {:<8}  Exit",
+            "Tick", "Started", "Duration"
+        ));
+
+        for row in self.runs.iter().rev() {
+            let (duration, exit) = match &row.state {
+                RunState::Running => (String::new(), String::from("running")),
+                RunState::Completed(status, duration) => {
+                    (format_duration(*duration), describe_status(status))
+                }
+                RunState::Failed => (String::new(), String::from("error")),
+            };
+
+            lines.push(format!(
+                "{:>6}  {:<23}  {:<8}  {exit}",
+                row.tick,
+                format_utc(row.started_at),
+                duration
+            ));
+        }
+
+        lines.push(String::new());
+        lines.push(String::from("p: pause/resume  r: run now  q: quit"));
+
+        lines
+    }
+}
+
+fn describe_status(status: &ExitStatus) -> String {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => code.to_string(),
+        (None, Some(signal)) => format!("signal {signal}"),
+        (None, None) => status.to_string(),
+    }
+}
+
+// Draws the durations as bars, scaled to the longest one.
+fn sparkline(durations: &[Duration]) -> String {
+    let max = durations.iter().max().copied().unwrap_or_default();
+    let last = SPARKLINE_CHARS.len() - 1;
+
+    durations
+        .iter()
+        .map(|duration| {
+            let index = if max.is_zero() {
+                0
+            } else {
+                (duration.as_nanos() * last as u128 / max.as_nanos()) as usize
+            };
+
+            SPARKLINE_CHARS[index]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_sparkline() {
+        let durations = [0, 100, 350, 700].map(Duration::from_millis);
+
+        assert_eq!(sparkline(&durations), "▁▂▄█");
+        assert_eq!(sparkline(&[Duration::ZERO]), "▁");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn test_render() {
+        let mut dashboard = Dashboard {
+            title: String::from("Every 1s: date"),
+            runs: VecDeque::new(),
+        };
+
+        let started_at = |seconds| UNIX_EPOCH + Duration::from_secs(seconds);
+
+        for (tick, state) in [
+            (
+                1,
+                RunState::Completed(ExitStatus::from_raw(0), Duration::from_millis(500)),
+            ),
+            (
+                2,
+                RunState::Completed(ExitStatus::from_raw(1 << 8), Duration::from_secs(1)),
+            ),
+            (
+                3,
+                RunState::Completed(ExitStatus::from_raw(9), Duration::from_millis(250)),
+            ),
+            (4, RunState::Failed),
+            (5, RunState::Running),
+        ] {
+            dashboard.runs.push_back(RunRow {
+                tick,
+                started_at: started_at(tick),
+                state,
+            });
+        }
+
+        let status = Status {
+            paused: false,
+            interval: Duration::from_secs(1),
+            max_interval: None,
+            next_tick_in: Duration::from_millis(400),
+        };
+
+        assert_eq!(
+            dashboard.render(Some(&status)),
+            [
+                "Every 1s: date",
+                "State: running, next tick in 0.4s",
+                "Run times: ▄█▂ (max 1s)",
+                "",
+                "  Tick  Started                  Duration  Exit",
+                "     5  1970-01-01 00:00:05 UTC            running",
+                "     4  1970-01-01 00:00:04 UTC            error",
+                "     3  1970-01-01 00:00:03 UTC  0.25s     signal 9",
+                "     2  1970-01-01 00:00:02 UTC  1s        1",
+                "     1  1970-01-01 00:00:01 UTC  0.5s      0",
+                "",
+                "p: pause/resume  r: run now  q: quit",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_handle_keeps_recent_runs() {
+        let mut dashboard = Dashboard {
+            title: String::new(),
+            runs: VecDeque::new(),
+        };
+
+        for tick in 1..=(MAX_RUNS as u64 + 5) {
+            dashboard.handle(Event::Started {
+                tick,
+                started_at: SystemTime::now(),
+            });
+        }
+
+        dashboard.handle(Event::Finished { tick: 6, run: None });
+
+        assert_eq!(dashboard.runs.len(), MAX_RUNS);
+        assert_eq!(dashboard.runs[0].tick, 6);
+        assert!(matches!(dashboard.runs[0].state, RunState::Failed));
+    }
+}
@@ -0,0 +1,119 @@
+use nix::libc;
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+use std::time::Duration;
+
+// The resources used by a child process, and the processes it waited for.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Usage {
+    pub user_time: Duration,
+    pub system_time: Duration,
+    // the maximum resident set size, in bytes
+    pub max_rss: u64,
+}
+
+// Waits for the child process to exit, and returns its status and resource usage.
+pub fn wait(pid: u32) -> io::Result<(ExitStatus, Usage)> {
+    let mut status = 0;
+    let mut rusage = MaybeUninit::<libc::rusage>::zeroed();
+
+    loop {
+        // SAFETY: both pointers are valid for writes
+        let result =
+            unsafe { libc::wait4(pid as libc::pid_t, &mut status, 0, rusage.as_mut_ptr()) };
+
+        if result != -1 {
+            break;
+        }
+
+        let e = io::Error::last_os_error();
+
+        if e.kind() != io::ErrorKind::Interrupted {
+            return Err(e);
+        }
+    }
+
+    // SAFETY: filled by wait4() on success
+    let rusage = unsafe { rusage.assume_init() };
+
+    let usage = Usage {
+        user_time: duration(rusage.ru_utime),
+        system_time: duration(rusage.ru_stime),
+        max_rss: max_rss_bytes(rusage.ru_maxrss),
+    };
+
+    Ok((ExitStatus::from_raw(status), usage))
+}
+
+// Formats a number of bytes with a binary suffix, such as 12.5M.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    let size = format!("{size:.1}");
+
+    format!("{}{}", size.trim_end_matches(".0"), UNITS[unit])
+}
+
+fn duration(time: libc::timeval) -> Duration {
+    Duration::new(time.tv_sec as u64, time.tv_usec as u32 * 1000)
+}
+
+// ru_maxrss is in bytes on macOS, and in KiB elsewhere.
+fn max_rss_bytes(max_rss: libc::c_long) -> u64 {
+    let max_rss = max_rss.max(0) as u64;
+
+    if cfg!(target_os = "macos") {
+        max_rss
+    } else {
+        max_rss * 1024
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    // reaped by wait()
+    #[allow(clippy::zombie_processes)]
+    fn test_wait() {
+        let child = Command::new("sh")
+            .args([
+                "-c",
+                "i=0; while [ $i -lt 20000 ]; do i=$((i + 1)); done; exit 3",
+            ])
+            .spawn()
+            .unwrap();
+
+        let (status, usage) = wait(child.id()).unwrap();
+
+        assert_eq!(status.code(), Some(3));
+        assert!(!(usage.user_time + usage.system_time).is_zero());
+        assert!(usage.max_rss > 0);
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0B");
+        assert_eq!(format_size(1023), "1023B");
+        assert_eq!(format_size(1024), "1K");
+        assert_eq!(format_size(12_800_000), "12.2M");
+        assert_eq!(format_size(3 << 30), "3G");
+        assert_eq!(format_size(u64::MAX), "16777216T");
+    }
+}
@@ -1,7 +1,21 @@
 use assert_cmd::prelude::*;
-use helpers::{get_cmd, test_run, RunTestCase, TimestampedOutputLine};
+use helpers::{get_cmd, get_temp_path, test_run, RunTestCase, TimestampedOutputLine};
+use nix::fcntl::{Flock, FlockArg};
+use nix::sys::signal::{kill, Signal};
+use nix::unistd::Pid;
 use predicates::prelude::*;
-use std::process::Command;
+use regex::Regex;
+use std::env;
+use std::fs::{self, File, Permissions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixDatagram, UnixStream};
+use std::os::unix::process::ExitStatusExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 mod helpers;
 
@@ -45,11 +59,32 @@ fn test_invalid_option() {
     get_cmd()
         .arg("-x")
         .assert()
-        .failure()
+        .code(2)
         .stdout("")
         .stderr("Invalid option: -x\n");
 }
 
+#[test]
+fn test_options_before_interval() {
+    get_cmd()
+        .args(["-n", "2", "--verbose", "0.1s", "-q", "echo", "hello"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr(predicate::str::contains("Tick #2 fired"));
+}
+
+#[test]
+fn test_very_verbose() {
+    get_cmd()
+        .args(["-n", "1", "-vv", "0.1s", "-q", "echo", "hello"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr(predicate::str::contains("Tick #1 fired"))
+        .stderr(predicate::str::is_match(r"(?m)^Running .*echo hello$").unwrap());
+}
+
 #[test]
 fn test_invalid_interval() {
     get_cmd()
@@ -86,6 +121,17 @@ fn test_run_echo() {
     });
 }
 
+#[test]
+fn test_run_with_wall_clock() {
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--clock", "wall", "echo", "hello world"],
+        run_time_ms: 350,
+        grace_period_ms: 40,
+        expected_stdout: TimestampedOutputLine::repeat_at(&[0, 100, 200, 300], "hello world"),
+        expected_stderr: vec![],
+    });
+}
+
 #[test]
 fn test_run_with_long_running_command() {
     test_run(RunTestCase {
@@ -97,7 +143,18 @@ fn test_run_with_long_running_command() {
             &[0, 200, 400, 600],
             "hello world",
         ),
-        expected_stderr: vec![],
+        // the run at 600 ms is still running when stopped
+        expected_stderr: [(150, 1), (350, 3), (550, 5)]
+            .iter()
+            .map(|(timestamp_ms, run)| {
+                TimestampedOutputLine::matching_at(
+                    *timestamp_ms,
+                    &format!(
+                        r"Run #{run} took 0\.1[5-6]\d?s, longer than the interval of 0\.1s: skipped 1 tick"
+                    ),
+                )
+            })
+            .collect(),
     });
 }
 
@@ -117,8 +174,28 @@ fn test_run_with_non_zero_exit_code() {
 
 #[test]
 fn test_run_with_non_existing_command() {
+    get_cmd()
+        .args(["0.1s", "non-existing-command", "arg1", "arg2"])
+        .assert()
+        .code(3)
+        .stdout("")
+        .stderr(concat!(
+            "Failed to start command: No such file or directory (os error 2)\n",
+            "Run #1 could not be started, stopping\n",
+        ));
+}
+
+#[test]
+fn test_run_with_on_spawn_error_retry() {
     test_run(RunTestCase {
-        args: vec!["0.1s", "non-existing-command", "arg1", "arg2"],
+        args: vec![
+            "0.1s",
+            "--on-spawn-error",
+            "retry",
+            "non-existing-command",
+            "arg1",
+            "arg2",
+        ],
         run_time_ms: 350,
         grace_period_ms: 40,
         expected_stdout: vec![],
@@ -129,6 +206,24 @@ fn test_run_with_non_existing_command() {
     });
 }
 
+#[test]
+fn test_run_with_on_spawn_error_ignore() {
+    // not a failure, that would stop
+    get_cmd()
+        .args([
+            "10ms",
+            "-n",
+            "3",
+            "--max-failures",
+            "1",
+            "--on-spawn-error",
+            "ignore",
+            "/nonexistent",
+        ])
+        .assert()
+        .success();
+}
+
 #[test]
 fn test_run_with_concurrency() {
     test_run(RunTestCase {
@@ -162,6 +257,2416 @@ fn test_run_with_concurrency_and_long_running_command() {
             &[0, 100, 200, 500, 600, 700, 1000, 1100, 1200],
             "hello world",
         ),
+        expected_stderr: [(450, 1), (550, 2), (650, 3), (950, 6), (1050, 7), (1150, 8)]
+            .iter()
+            .map(|(timestamp_ms, run)| {
+                TimestampedOutputLine::matching_at(
+                    *timestamp_ms,
+                    &format!(
+                        r"Run #{run} took 0\.4[5-6]\d?s, longer than the interval of 0\.1s: skipped 2 ticks"
+                    ),
+                )
+            })
+            .collect(),
+    });
+}
+
+#[test]
+fn test_run_with_on_busy_queue() {
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "--on-busy",
+            "queue:1",
+            "bash",
+            "-c",
+            "echo hello world && sleep 0.27",
+        ],
+        run_time_ms: 350,
+        grace_period_ms: 40,
+        expected_stdout: TimestampedOutputLine::repeat_at(
+            // the tick at 100ms runs as soon as the first run completes,
+            // the tick at 200ms is skipped as the queue is full
+            &[0, 270],
+            "hello world",
+        ),
+        expected_stderr: vec![TimestampedOutputLine::matching_at(
+            270,
+            r"Run #1 took 0\.2[7-8]\d?s, longer than the interval of 0\.1s: skipped 1 tick",
+        )],
+    });
+}
+
+#[test]
+fn test_run_with_on_busy_kill() {
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "--on-busy",
+            "kill",
+            "bash",
+            "-c",
+            "echo hello world && exec sleep 1",
+        ],
+        run_time_ms: 250,
+        grace_period_ms: 40,
+        expected_stdout: TimestampedOutputLine::repeat_at(&[0, 100, 200], "hello world"),
+        expected_stderr: TimestampedOutputLine::repeat_at(
+            &[100, 200],
+            "Command exited with signal: 9 (SIGKILL)",
+        ),
+    });
+}
+
+#[test]
+fn test_run_with_max_per() {
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--max-per", "2/1s", "echo", "hello world"],
+        run_time_ms: 450,
+        grace_period_ms: 40,
+        // the next token is only available after 500ms
+        expected_stdout: TimestampedOutputLine::repeat_at(&[0, 100], "hello world"),
+        expected_stderr: vec![],
+    });
+}
+
+#[test]
+fn test_run_with_catch_up() {
+    // stopped from 50ms to 350ms, missing the ticks at 100, 200 and 300ms:
+    // the first missed tick is fired late on resume, the others are caught up or skipped
+    let test_cases = [
+        (vec!["0.1s", "echo", "hello"], 4),
+        (vec!["0.1s", "--catch-up=1", "-c", "5", "echo", "hello"], 5),
+        (vec!["0.1s", "--catch-up", "-c", "5", "echo", "hello"], 6),
+    ];
+
+    for (args, expected_runs) in test_cases {
+        let child = get_cmd()
+            .args(&args)
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let pid = Pid::from_raw(child.id().try_into().unwrap());
+
+        thread::sleep(Duration::from_millis(50));
+        kill(pid, Signal::SIGSTOP).unwrap();
+        thread::sleep(Duration::from_millis(300));
+        kill(pid, Signal::SIGCONT).unwrap();
+        thread::sleep(Duration::from_millis(200));
+        kill(pid, Signal::SIGINT).unwrap();
+
+        let output = child.wait_with_output().unwrap();
+        let runs = String::from_utf8(output.stdout).unwrap().lines().count();
+
+        assert_eq!(runs, expected_runs, "args: {args:?}");
+    }
+}
+
+#[test]
+fn test_run_at_future_time() {
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "--verbose",
+            "--at",
+            "2999-01-01T00:00",
+            "echo",
+            "hello",
+        ],
+        run_time_ms: 250,
+        grace_period_ms: 40,
+        expected_stdout: vec![],
+        expected_stderr: vec![TimestampedOutputLine::at(
+            0,
+            "Waiting until 2999-01-01T00:00 for the first run",
+        )],
+    });
+}
+
+#[test]
+fn test_run_on_other_days() {
+    let output = Command::new("date").arg("+%w").output().unwrap();
+    let today: usize = String::from_utf8(output.stdout)
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap();
+
+    // every day of the week but today
+    let days = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+    let other_days = format!("{}-{}", days[(today + 1) % 7], days[(today + 6) % 7]);
+
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--verbose", "--days", &other_days, "echo", "hello"],
+        run_time_ms: 250,
+        grace_period_ms: 40,
+        expected_stdout: vec![],
+        expected_stderr: vec![
+            TimestampedOutputLine::at(0, "Tick #1 fired"),
+            TimestampedOutputLine::at(0, "Tick #1 skipped: outside the allowed time window"),
+            TimestampedOutputLine::at(100, "Tick #2 fired"),
+            TimestampedOutputLine::at(100, "Tick #2 skipped: outside the allowed time window"),
+            TimestampedOutputLine::at(200, "Tick #3 fired"),
+            TimestampedOutputLine::at(200, "Tick #3 skipped: outside the allowed time window"),
+        ],
+    });
+}
+
+#[test]
+fn test_run_with_interval_windows() {
+    // the two windows cover the whole day
+    test_run(RunTestCase {
+        args: vec![
+            "10s",
+            "--interval-between",
+            "00:00-12:00=0.1s",
+            "--interval-between",
+            "12:00-00:00=0.1s",
+            "echo",
+            "hello",
+        ],
+        run_time_ms: 250,
+        grace_period_ms: 40,
+        expected_stdout: TimestampedOutputLine::repeat_at(&[0, 100, 200], "hello"),
+        expected_stderr: vec![],
+    });
+}
+
+#[test]
+fn test_run_on_skipped_dates() {
+    let output = Command::new("date").arg("+%Y-%m-%d").output().unwrap();
+    let today = String::from_utf8(output.stdout).unwrap();
+
+    let skip_dates = get_temp_path("skip-dates");
+    fs::write(&skip_dates, format!("# holidays\n2000-01-01\n{today}")).unwrap();
+
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "--verbose",
+            "--skip-dates",
+            skip_dates.to_str().unwrap(),
+            "echo",
+            "hello",
+        ],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        expected_stdout: vec![],
+        expected_stderr: vec![
+            TimestampedOutputLine::at(0, "Tick #1 fired"),
+            TimestampedOutputLine::at(0, "Tick #1 skipped: on a skipped date"),
+            TimestampedOutputLine::at(100, "Tick #2 fired"),
+            TimestampedOutputLine::at(100, "Tick #2 skipped: on a skipped date"),
+        ],
+    });
+
+    fs::remove_file(&skip_dates).unwrap();
+}
+
+#[test]
+fn test_run_with_time_zone() {
+    let hour = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        / 3600
+        % 24;
+
+    // the current hour in Tokyo, which is UTC+9 all year round
+    let window = format!("{:02}:00-{:02}:00", (hour + 9) % 24, (hour + 10) % 24);
+
+    let child = get_cmd()
+        .env("TZ", "UTC")
+        .args(["1s", "--tz", "Asia/Tokyo", "--between", &window])
+        .args(["bash", "-c", "echo $TZ"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    thread::sleep(Duration::from_millis(150));
+    kill(Pid::from_raw(child.id() as i32), Signal::SIGINT).unwrap();
+
+    // the command runs in the window, with the time zone of the environment
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "UTC\n");
+}
+
+#[test]
+fn test_run_until_past_deadline() {
+    get_cmd()
+        .args(["100ms", "--until", "2000-01-01T00:00", "echo", "hello"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+}
+
+#[test]
+fn test_run_with_max_runs() {
+    get_cmd()
+        .args(["10ms", "-n", "3", "echo", "hello"])
+        .assert()
+        .success()
+        .stdout("hello\n".repeat(3))
+        .stderr("");
+}
+
+#[test]
+fn test_run_until_success() {
+    get_cmd()
+        .args([
+            "10ms",
+            "--until-success",
+            "bash",
+            "-c",
+            "echo {i}; [ {i} -ge 3 ]",
+        ])
+        .assert()
+        .success()
+        .stdout("1\n2\n3\n");
+
+    // capped without a success
+    get_cmd()
+        .args(["10ms", "--until-success", "-n", "2", "false"])
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn test_run_with_stall_timeout() {
+    // killed once it stops writing, not after the first output
+    get_cmd()
+        .args([
+            "10ms",
+            "--max-failures",
+            "1",
+            "--stall-timeout",
+            "0.1s",
+            "sh",
+            "-c",
+            "echo a; sleep 0.06; echo b; sleep 10",
+        ])
+        .assert()
+        .code(4)
+        .stdout("a\nb\n")
+        .stderr(predicate::str::contains(
+            "produced no output for 0.1s, killing it",
+        ));
+}
+
+#[test]
+fn test_run_with_max_output() {
+    get_cmd()
+        .args([
+            "10ms",
+            "-n",
+            "1",
+            "--max-output",
+            "4",
+            "echo",
+            "hello world",
+        ])
+        .assert()
+        .success()
+        .stdout("hell\n[output truncated after 4B]\n");
+
+    get_cmd()
+        .args([
+            "10ms",
+            "--max-failures",
+            "1",
+            "--max-output-kill",
+            "1K",
+            "sh",
+            "-c",
+            "yes; sleep 10",
+        ])
+        .assert()
+        .code(4)
+        .stderr(predicate::str::contains("wrote more than 1K, killing it"));
+}
+
+#[test]
+fn test_man() {
+    get_cmd()
+        .arg("--man")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(".TH EVERY 1"))
+        .stdout(predicate::str::contains(
+            ".TP\n\\-c <n>\nSet the concurrency level (default: 1).\n",
+        ));
+}
+
+#[test]
+fn test_color() {
+    get_cmd()
+        .args(["0.1s", "-n", "1", "--color", "always", "false"])
+        .assert()
+        .stderr("\x1b[31mCommand exited with exit status: 1\x1b[0m\n");
+
+    // not a terminal
+    get_cmd()
+        .args(["0.1s", "-n", "1", "false"])
+        .assert()
+        .stderr("Command exited with exit status: 1\n");
+
+    let script = "echo ${CLICOLOR_FORCE:--} ${FORCE_COLOR:--} ${NO_COLOR:--}";
+
+    get_cmd()
+        .args(["0.1s", "-n", "1", "--color", "always", "sh", "-c", script])
+        .env("NO_COLOR", "1")
+        .assert()
+        .success()
+        .stdout("1 1 -\n");
+
+    get_cmd()
+        .args(["0.1s", "-n", "1", "--color", "never", "sh", "-c", script])
+        .env("FORCE_COLOR", "1")
+        .assert()
+        .success()
+        .stdout("- - 1\n");
+}
+
+#[test]
+fn test_preview() {
+    get_cmd()
+        .args([
+            "--preview",
+            "3",
+            "1d",
+            "--at",
+            "2099-12-31T09:00",
+            "--days",
+            "mon-fri",
+        ])
+        .assert()
+        .success()
+        .stdout("Thu 2099-12-31 09:00:00\nFri 2100-01-01 09:00:00\nMon 2100-01-04 09:00:00\n");
+}
+
+#[test]
+fn test_supervise() {
+    test_run(RunTestCase {
+        args: vec!["--supervise", "0.1s", "sh", "-c", "echo hello; sleep 0.15"],
+        run_time_ms: 350,
+        grace_period_ms: 40,
+        // restarted as soon as it exits, after running for longer than the delay
+        expected_stdout: TimestampedOutputLine::repeat_at(&[0, 150, 300], "hello"),
+        expected_stderr: vec![],
+    });
+}
+
+#[test]
+fn test_supervise_throttles_restarts() {
+    test_run(RunTestCase {
+        args: vec!["--supervise", "0.1s", "echo", "hello"],
+        run_time_ms: 250,
+        grace_period_ms: 40,
+        expected_stdout: TimestampedOutputLine::repeat_at(&[0, 100, 200], "hello"),
         expected_stderr: vec![],
     });
 }
+
+#[test]
+fn test_running_commands_are_killed_on_sigint() {
+    // the second command ignores SIGTERM, until killed
+    for args in [
+        vec![],
+        vec!["--kill-signal", "TERM", "--kill-after", "0.2s"],
+    ] {
+        let mut child = get_cmd()
+            .args(["10s"])
+            .args(&args)
+            .args(["sh", "-c", "trap '' TERM; echo $$; sleep 10"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let command_pid = Pid::from_raw(line.trim().parse().unwrap());
+
+        kill(
+            Pid::from_raw(child.id().try_into().unwrap()),
+            Signal::SIGINT,
+        )
+        .unwrap();
+        let status = child.wait().unwrap();
+
+        assert_eq!(
+            status.signal(),
+            Some(Signal::SIGINT as i32),
+            "args: {args:?}"
+        );
+        assert!(kill(command_pid, None).is_err(), "args: {args:?}");
+    }
+}
+
+#[test]
+fn test_run_with_detach() {
+    let start = std::time::Instant::now();
+
+    // not waited for, including on exit
+    get_cmd()
+        .args([
+            "0.1s",
+            "-n",
+            "3",
+            "--verbose",
+            "-qq",
+            "--detach",
+            "sleep",
+            "2",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_match(r"(?m)^Started detached command with PID \d+$").unwrap());
+
+    assert!(start.elapsed() < Duration::from_millis(1500));
+}
+
+#[test]
+fn test_run_with_detach_reaps_commands() {
+    // the first command exits before the second tick
+    get_cmd()
+        .args(["0.2s", "-n", "2", "--verbose", "--detach", "true"])
+        .assert()
+        .success()
+        .stderr(
+            predicate::str::is_match(
+                r"(?m)^Detached command with PID \d+ exited with exit status: 0 after .+$",
+            )
+            .unwrap(),
+        );
+}
+
+#[test]
+fn test_run_with_batch() {
+    get_cmd()
+        .args(["1s", "-n", "3", "--batch", "3", "-c", "3"])
+        .args([
+            "sh",
+            "-c",
+            "sleep 0.$EVERY_BATCH_INDEX; echo $EVERY_BATCH_INDEX",
+        ])
+        .assert()
+        .success()
+        .stdout("0\n1\n2\n");
+}
+
+#[test]
+fn test_run_with_batch_above_concurrency() {
+    get_cmd()
+        .args(["0.2s", "-n", "4", "--verbose", "--batch", "3", "-c", "2"])
+        .args(["sh", "-c", "sleep 0.1; echo $EVERY_BATCH_INDEX"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^([01]\n){4}$").unwrap())
+        .stderr(predicate::str::contains(
+            "Tick #1 skipped: 2 commands already running",
+        ));
+}
+
+#[test]
+fn test_run_with_pause_file() {
+    let pause_file = get_temp_path("pause");
+    let pause_file = pause_file.to_str().unwrap();
+
+    // paused by the first run
+    let script = format!("echo hello; touch {pause_file}");
+
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "--pause-file",
+            pause_file,
+            "--verbose",
+            "sh",
+            "-c",
+            &script,
+        ],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        expected_stdout: vec![TimestampedOutputLine::at(0, "hello")],
+        expected_stderr: vec![
+            TimestampedOutputLine::at(0, "Tick #1 fired"),
+            TimestampedOutputLine::matching_at(0, r"Started command with PID \d+"),
+            TimestampedOutputLine::matching_at(
+                0,
+                r"Command with PID \d+ exited with exit status: 0 .*",
+            ),
+            TimestampedOutputLine::at(100, "Tick #2 fired"),
+            TimestampedOutputLine::at(100, &format!("Tick #2 skipped: paused by {pause_file}")),
+        ],
+    });
+
+    fs::remove_file(pause_file).unwrap();
+}
+
+#[test]
+fn test_run_with_max_load() {
+    get_cmd()
+        .args(["10ms", "-n", "1", "--max-load", "1000000", "echo", "hello"])
+        .assert()
+        .success()
+        .stdout("hello\n");
+}
+
+#[test]
+fn test_run_with_min_free_space() {
+    get_cmd()
+        .args([
+            "10ms",
+            "-n",
+            "1",
+            "--min-free-space",
+            "1@/",
+            "echo",
+            "hello",
+        ])
+        .assert()
+        .success()
+        .stdout("hello\n");
+
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "--verbose",
+            "--min-free-space",
+            "1000000T@/",
+            "echo",
+            "hello",
+        ],
+        run_time_ms: 50,
+        grace_period_ms: 40,
+        expected_stdout: vec![],
+        expected_stderr: vec![
+            TimestampedOutputLine::at(0, "Tick #1 fired"),
+            TimestampedOutputLine::matching_at(0, r"Tick #1 skipped: .+ free on /, below 1000000T"),
+        ],
+    });
+}
+
+#[test]
+fn test_run_with_require_network() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let option = format!("--require-network={address}");
+
+    get_cmd()
+        .args(["10ms", "-n", "1", &option, "echo", "hello"])
+        .assert()
+        .success()
+        .stdout("hello\n");
+
+    drop(listener);
+
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--verbose", &option, "echo", "hello"],
+        run_time_ms: 50,
+        grace_period_ms: 40,
+        expected_stdout: vec![],
+        expected_stderr: vec![
+            TimestampedOutputLine::at(0, "Tick #1 fired"),
+            TimestampedOutputLine::matching_at(
+                0,
+                &format!("Tick #1 skipped: cannot connect to {address}: .*"),
+            ),
+        ],
+    });
+}
+
+#[test]
+fn test_run_with_if_healthy() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/health", listener.local_addr().unwrap());
+
+    // healthy on the first tick only
+    let server = thread::spawn(move || {
+        for status in ["200 OK", "503 Service Unavailable"] {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            )
+            .unwrap();
+        }
+    });
+
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--verbose", "--if-healthy", &url, "echo", "hello"],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        // after the health check
+        expected_stdout: vec![TimestampedOutputLine::at(10, "hello")],
+        expected_stderr: vec![
+            TimestampedOutputLine::at(0, "Tick #1 fired"),
+            TimestampedOutputLine::matching_at(10, r"Started command with PID \d+"),
+            TimestampedOutputLine::matching_at(
+                10,
+                r"Command with PID \d+ exited with exit status: 0 .*",
+            ),
+            TimestampedOutputLine::at(100, "Tick #2 fired"),
+            TimestampedOutputLine::at(100, &format!("Tick #2 skipped: {url} returned HTTP 503")),
+        ],
+    });
+
+    server.join().unwrap();
+}
+
+#[test]
+fn test_run_with_condition() {
+    let counter_file = get_temp_path("condition");
+    let counter_file = counter_file.to_str().unwrap();
+
+    // met on every other tick
+    let condition = format!(
+        "n=$(cat {counter_file} 2>/dev/null || echo 0); echo $((n + 1)) > {counter_file}; [ $((n % 2)) = 0 ]"
+    );
+
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--if", &condition, "echo", "hello"],
+        run_time_ms: 350,
+        grace_period_ms: 40,
+        expected_stdout: TimestampedOutputLine::repeat_at(&[0, 200], "hello"),
+        expected_stderr: vec![],
+    });
+
+    let _ = fs::remove_file(counter_file);
+}
+
+#[test]
+fn test_run_with_condition_timed_out() {
+    // killed after half the interval, or a second at least
+    test_run(RunTestCase {
+        args: vec!["1s", "--verbose", "--if", "sleep 10", "echo", "hello"],
+        run_time_ms: 1300,
+        grace_period_ms: 40,
+        expected_stdout: vec![],
+        expected_stderr: vec![
+            TimestampedOutputLine::at(0, "Tick #1 fired"),
+            TimestampedOutputLine::at(1000, "Tick #1 skipped: condition not met, timed out"),
+        ],
+    });
+}
+
+#[test]
+fn test_run_with_ok_codes() {
+    get_cmd()
+        .args([
+            "10ms",
+            "-n",
+            "2",
+            "--max-failures",
+            "1",
+            "--ok-codes",
+            "0,3",
+            "sh",
+            "-c",
+            "exit 3",
+        ])
+        .assert()
+        .success()
+        .stderr("");
+
+    // 0 is then a failure
+    get_cmd()
+        .args(["10ms", "--max-failures", "1", "--ok-codes", "3", "true"])
+        .assert()
+        .code(1)
+        .stderr("Command exited with exit status: 0\nStopping after 1 failure in a row\n");
+}
+
+#[test]
+fn test_exit_codes() {
+    // the exit code of the last run
+    get_cmd()
+        .args(["10ms", "--max-failures", "1", "sh", "-c", "exit 7"])
+        .assert()
+        .code(7);
+
+    // the signal that killed the last run
+    get_cmd()
+        .args(["10ms", "--max-failures", "1", "sh", "-c", "kill -TERM $$"])
+        .assert()
+        .code(143);
+
+    // the last run could not be started
+    get_cmd()
+        .args(["10ms", "--max-failures", "1", "/nonexistent"])
+        .assert()
+        .code(3);
+
+    // the last run was killed by every
+    get_cmd()
+        .args([
+            "50ms",
+            "--on-busy",
+            "kill",
+            "--max-failures",
+            "1",
+            "sleep",
+            "1",
+        ])
+        .assert()
+        .code(4);
+
+    // the last of the -n runs failed
+    get_cmd()
+        .args(["10ms", "-n", "1", "false"])
+        .assert()
+        .code(1);
+    get_cmd()
+        .args(["10ms", "-n", "2", "sh", "-c", "exit $(( {i} + 5 ))"])
+        .assert()
+        .code(7);
+
+    // or was killed by every
+    get_cmd()
+        .args(["10ms", "-n", "1", "--stall-timeout", "0.1s", "sleep", "1"])
+        .assert()
+        .code(4);
+}
+
+#[test]
+fn test_run_with_max_failures() {
+    // the second run succeeds, and resets the count of failures
+    get_cmd()
+        .args([
+            "10ms",
+            "--max-failures",
+            "2",
+            "bash",
+            "-c",
+            "echo {i}; [ {i} -eq 2 ]",
+        ])
+        .assert()
+        .code(1)
+        .stdout("1\n2\n3\n4\n")
+        .stderr(predicates::str::ends_with(
+            "Stopping after 2 failures in a row\n",
+        ));
+}
+
+#[test]
+fn test_run_with_cooldown() {
+    // which ticks fall in a pause depends on the load: only the order of the runs and their gaps are checked
+    let output = get_cmd()
+        .args([
+            "0.1s",
+            "-n",
+            "4",
+            "--cooldown",
+            "0.25s",
+            "--cooldown-after",
+            "2",
+        ])
+        .args(["bash", "-c", "echo {i} $EPOCHREALTIME; false"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+
+    let runs: Vec<(u64, f64)> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let (tick, time) = line.split_once(' ').unwrap();
+            (tick.parse().unwrap(), time.parse().unwrap())
+        })
+        .collect();
+
+    assert_eq!(runs.len(), 4, "runs: {runs:?}");
+    assert_eq!((runs[0].0, runs[1].0), (1, 2), "runs: {runs:?}");
+    assert!(
+        runs.windows(2).all(|pair| pair[0].0 < pair[1].0),
+        "runs: {runs:?}"
+    );
+
+    // paused after the second failure, then after each failure
+    assert!(runs[1].1 - runs[0].1 < 0.25, "runs: {runs:?}");
+    assert!(runs[2].1 - runs[1].1 >= 0.25, "runs: {runs:?}");
+    assert!(runs[3].1 - runs[2].1 >= 0.25, "runs: {runs:?}");
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let pauses: Vec<_> = stderr
+        .lines()
+        .filter(|line| line.contains("pausing"))
+        .collect();
+
+    assert_eq!(
+        pauses,
+        [
+            "2 failures in a row, pausing for 0.25s",
+            "3 failures in a row, pausing for 0.25s",
+            "4 failures in a row, pausing for 0.25s",
+        ]
+    );
+}
+
+#[test]
+fn test_run_until_match() {
+    get_cmd()
+        .args([
+            "10ms",
+            "--until-match",
+            "^ready$",
+            "bash",
+            "-c",
+            "[ {i} -ge 3 ] && echo ready || echo waiting",
+        ])
+        .assert()
+        .success()
+        .stdout("waiting\nwaiting\nready\n")
+        .stderr("");
+
+    get_cmd()
+        .args([
+            "10ms",
+            "--until-no-match",
+            "waiting",
+            "bash",
+            "-c",
+            "[ {i} -ge 2 ] && echo done || echo waiting",
+        ])
+        .assert()
+        .success()
+        .stdout("waiting\ndone\n")
+        .stderr("");
+
+    // capped without a match
+    get_cmd()
+        .args([
+            "10ms",
+            "--until-match",
+            "ready",
+            "-n",
+            "2",
+            "echo",
+            "waiting",
+        ])
+        .assert()
+        .code(5)
+        .stdout("waiting\nwaiting\n");
+}
+
+#[test]
+fn test_run_with_env() {
+    let env_file = get_temp_path("env-file");
+    fs::write(&env_file, "# comment\nFOO=from-file\nBAR=bar\n").unwrap();
+
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "--env-file",
+            env_file.to_str().unwrap(),
+            "-e",
+            "FOO=foo",
+            "bash",
+            "-c",
+            "echo $FOO $BAR",
+        ],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        expected_stdout: TimestampedOutputLine::repeat_at(&[0, 100], "foo bar"),
+        expected_stderr: vec![],
+    });
+
+    fs::remove_file(env_file).unwrap();
+}
+
+#[test]
+fn test_run_with_jobs_file() {
+    let jobs_file = get_temp_path("jobs-file");
+    fs::write(&jobs_file, "# comment\necho first\n\necho second\n").unwrap();
+
+    // the second command waits for the first one, as they share the concurrency limit
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "--on-busy",
+            "queue",
+            "--jobs-file",
+            jobs_file.to_str().unwrap(),
+        ],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        expected_stdout: vec![
+            TimestampedOutputLine::at(0, "first"),
+            TimestampedOutputLine::at(0, "second"),
+            TimestampedOutputLine::at(100, "first"),
+            TimestampedOutputLine::at(100, "second"),
+        ],
+        expected_stderr: vec![],
+    });
+
+    fs::remove_file(jobs_file).unwrap();
+}
+
+#[test]
+fn test_run_with_job_intervals() {
+    let jobs_file = get_temp_path("jobs-file-intervals");
+    fs::write(&jobs_file, "echo fast\n@0.2s echo slow\n").unwrap();
+
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "--on-busy",
+            "queue",
+            "--jobs-file",
+            jobs_file.to_str().unwrap(),
+        ],
+        run_time_ms: 250,
+        grace_period_ms: 40,
+        expected_stdout: vec![
+            TimestampedOutputLine::at(0, "fast"),
+            TimestampedOutputLine::at(0, "slow"),
+            TimestampedOutputLine::at(100, "fast"),
+            TimestampedOutputLine::at(200, "fast"),
+            TimestampedOutputLine::at(200, "slow"),
+        ],
+        expected_stderr: vec![],
+    });
+
+    fs::remove_file(jobs_file).unwrap();
+}
+
+#[test]
+fn test_run_with_independent_job_intervals() {
+    let jobs_file = get_temp_path("jobs-file-independent-intervals");
+    fs::write(&jobs_file, "@0.15s echo slow\n").unwrap();
+
+    // the global interval drives no job
+    test_run(RunTestCase {
+        args: vec!["--jobs-file", jobs_file.to_str().unwrap(), "0.1s"],
+        run_time_ms: 350,
+        grace_period_ms: 40,
+        expected_stdout: vec![
+            TimestampedOutputLine::at(0, "slow"),
+            TimestampedOutputLine::at(150, "slow"),
+            TimestampedOutputLine::at(300, "slow"),
+        ],
+        expected_stderr: vec![],
+    });
+
+    fs::remove_file(jobs_file).unwrap();
+}
+
+#[test]
+fn test_run_with_jobs_file_reloaded_on_sighup() {
+    let jobs_file = get_temp_path("jobs-file-reload");
+    fs::write(&jobs_file, "echo first\n").unwrap();
+
+    let child = get_cmd()
+        .args([
+            "0.1s",
+            "-c",
+            "2",
+            "--jobs-file",
+            jobs_file.to_str().unwrap(),
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let pid = Pid::from_raw(child.id().try_into().unwrap());
+
+    thread::sleep(Duration::from_millis(150));
+    fs::write(&jobs_file, "echo second\n").unwrap();
+    kill(pid, Signal::SIGHUP).unwrap();
+
+    // the interval of a job cannot move to a new schedule
+    thread::sleep(Duration::from_millis(200));
+    fs::write(&jobs_file, "@1s echo second\necho third\n").unwrap();
+    kill(pid, Signal::SIGHUP).unwrap();
+
+    thread::sleep(Duration::from_millis(200));
+    kill(pid, Signal::SIGINT).unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert_eq!(output.status.signal(), Some(Signal::SIGINT as i32));
+    assert_eq!(lines.first(), Some(&"first"));
+    assert!(lines.contains(&"third"), "stdout: {stdout}");
+
+    // once reloaded, the jobs replace the previous ones
+    let second = lines.iter().position(|&line| line == "second").unwrap();
+    let third = lines.iter().position(|&line| line == "third").unwrap();
+    assert!(
+        lines[..second].iter().all(|&line| line == "first"),
+        "stdout: {stdout}"
+    );
+    assert!(!lines[second..].contains(&"first"), "stdout: {stdout}");
+    assert!(lines[third..].contains(&"second"), "stdout: {stdout}");
+
+    let reloaded = format!(
+        "from {}, the options such as --env and the hooks were kept",
+        jobs_file.display()
+    );
+    assert!(
+        stderr.contains(&format!("Reloaded 1 job {reloaded}")),
+        "stderr: {stderr}"
+    );
+    assert!(
+        stderr.contains(&format!("Reloaded 2 jobs {reloaded}")),
+        "stderr: {stderr}"
+    );
+    assert!(
+        stderr.contains(
+            "The interval of job 'echo second' was not reloaded, restart every to apply it"
+        ),
+        "stderr: {stderr}"
+    );
+
+    fs::remove_file(jobs_file).unwrap();
+}
+
+#[test]
+fn test_run_with_args_from() {
+    let args_file = get_temp_path("args-from");
+    fs::write(&args_file, "first\n\nsecond\n").unwrap();
+    let args_file = args_file.to_str().unwrap();
+
+    // stops once the last line is taken
+    get_cmd()
+        .args(["10ms", "--args-from", args_file, "echo", "line: {arg}"])
+        .assert()
+        .success()
+        .stdout("line: first\nline: second\n");
+
+    get_cmd()
+        .args(["10ms", "-n", "5", "--args-from", args_file])
+        .args(["--args-exhausted", "wrap", "echo", "{arg}"])
+        .assert()
+        .success()
+        .stdout("first\nsecond\nfirst\nsecond\nfirst\n");
+
+    fs::remove_file(args_file).unwrap();
+}
+
+#[test]
+fn test_run_with_args_from_in_shell_commands() {
+    let args_file = get_temp_path("args-from-shell");
+    fs::write(&args_file, "x; echo INJECTED\nit's $HOME\n").unwrap();
+    let args_file = args_file.to_str().unwrap();
+
+    // the lines are quoted in the commands run through sh -c
+    get_cmd()
+        .args(["10ms", "--args-from", args_file, "--cmd", "echo {arg}"])
+        .assert()
+        .success()
+        .stdout("x; echo INJECTED\nit's $HOME\n");
+
+    fs::remove_file(args_file).unwrap();
+}
+
+#[test]
+fn test_run_with_rotating_commands() {
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "--cmd",
+            "echo dns",
+            "--cmd",
+            "echo http",
+            "--cmd",
+            "echo db",
+        ],
+        run_time_ms: 350,
+        grace_period_ms: 40,
+        expected_stdout: vec![
+            TimestampedOutputLine::at(0, "dns"),
+            TimestampedOutputLine::at(100, "http"),
+            TimestampedOutputLine::at(200, "db"),
+            TimestampedOutputLine::at(300, "dns"),
+        ],
+        expected_stderr: vec![],
+    });
+}
+
+#[test]
+fn test_run_with_rotating_jobs() {
+    let jobs_file = get_temp_path("jobs-file-rotate");
+    fs::write(
+        &jobs_file,
+        "echo always
+@rotate echo even
+@rotate echo odd
+",
+    )
+    .unwrap();
+
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "--on-busy",
+            "queue",
+            "--jobs-file",
+            jobs_file.to_str().unwrap(),
+        ],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        expected_stdout: vec![
+            TimestampedOutputLine::at(0, "always"),
+            TimestampedOutputLine::at(0, "even"),
+            TimestampedOutputLine::at(100, "always"),
+            TimestampedOutputLine::at(100, "odd"),
+        ],
+        expected_stderr: vec![],
+    });
+
+    fs::remove_file(jobs_file).unwrap();
+}
+
+#[test]
+fn test_run_with_clean_env() {
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--clean-env", "-e", "FOO=foo", "env"],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        expected_stdout: TimestampedOutputLine::repeat_at(&[0, 100], "FOO=foo"),
+        expected_stderr: vec![],
+    });
+}
+
+#[test]
+fn test_run_with_nice() {
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--nice", "19", "nice"],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        expected_stdout: TimestampedOutputLine::repeat_at(&[0, 100], "19"),
+        expected_stderr: vec![],
+    });
+}
+
+#[test]
+fn test_run_with_pid_file() {
+    let pid_file = get_temp_path("pid-file");
+
+    let mut child = get_cmd()
+        .args(["1s", "--pid-file", pid_file.to_str().unwrap(), "true"])
+        .spawn()
+        .unwrap();
+
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(
+        fs::read_to_string(&pid_file).unwrap(),
+        format!("{}\n", child.id())
+    );
+
+    kill(Pid::from_raw(child.id() as i32), Signal::SIGINT).unwrap();
+    child.wait().unwrap();
+
+    // the PID file is removed on exit
+    assert!(!pid_file.exists());
+}
+
+#[test]
+fn test_run_with_lock_file() {
+    let lock_file = get_temp_path("lock-file");
+    let lock = Flock::lock(File::create(&lock_file).unwrap(), FlockArg::LockExclusive).unwrap();
+
+    // ticks at 0, 200 & 400 ms are skipped while the lock is held
+    let unlock = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(500));
+        drop(lock);
+    });
+
+    get_cmd()
+        .args([
+            "0.2s",
+            "-n",
+            "2",
+            "--lock-file",
+            lock_file.to_str().unwrap(),
+        ])
+        .args(["echo", "{i}"])
+        .assert()
+        .success()
+        .stdout("4\n5\n")
+        .stderr("");
+
+    unlock.join().unwrap();
+    fs::remove_file(&lock_file).unwrap();
+}
+
+#[test]
+fn test_run_with_log() {
+    let log = get_temp_path("log");
+
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "--log",
+            log.to_str().unwrap(),
+            "bash",
+            "-c",
+            "echo out && echo err >&2 && false",
+        ],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        expected_stdout: vec![],
+        expected_stderr: vec![],
+    });
+
+    assert_eq!(
+        fs::read_to_string(&log).unwrap(),
+        "out\nerr\nCommand exited with exit status: 1\n".repeat(2)
+    );
+
+    fs::remove_file(log).unwrap();
+}
+
+#[test]
+fn test_run_as_daemon() {
+    let log = get_temp_path("daemon-log");
+    let pid_file = get_temp_path("daemon-pid-file");
+
+    // the original process exits at once
+    get_cmd()
+        .args(["0.1s", "--daemon", "--log", log.to_str().unwrap()])
+        .args(["--pid-file", pid_file.to_str().unwrap(), "echo", "hello"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    thread::sleep(Duration::from_millis(150));
+
+    let pid: i32 = fs::read_to_string(&pid_file)
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap();
+    kill(Pid::from_raw(pid), Signal::SIGTERM).unwrap();
+
+    thread::sleep(Duration::from_millis(50));
+
+    assert_eq!(fs::read_to_string(&log).unwrap(), "hello\nhello\n");
+    assert!(!pid_file.exists());
+
+    fs::remove_file(log).unwrap();
+}
+
+#[test]
+fn test_run_with_systemd() {
+    let socket_path = get_temp_path("notify-socket");
+    let socket = UnixDatagram::bind(&socket_path).unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+
+    let child = get_cmd()
+        .env("NOTIFY_SOCKET", &socket_path)
+        .args([
+            "0.1s",
+            "--systemd",
+            "bash",
+            "-c",
+            "echo ${NOTIFY_SOCKET:-hidden}",
+        ])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    thread::sleep(Duration::from_millis(150));
+    kill(Pid::from_raw(child.id() as i32), Signal::SIGINT).unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "hidden\nhidden\n"
+    );
+
+    let mut buffer = [0; 64];
+    let mut receive = || {
+        let length = socket.recv(&mut buffer).unwrap();
+        String::from_utf8(buffer[..length].to_vec()).unwrap()
+    };
+
+    assert_eq!(receive(), "READY=1");
+    assert_eq!(receive(), "WATCHDOG=1");
+    assert_eq!(receive(), "WATCHDOG=1");
+    assert_eq!(receive(), "STOPPING=1");
+
+    fs::remove_file(socket_path).unwrap();
+}
+
+#[test]
+fn test_run_with_placeholders() {
+    test_run(RunTestCase {
+        args: vec!["0.1s", "echo", "run-{i}", "{}", "{{i}}"],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        expected_stdout: vec![
+            TimestampedOutputLine::at(0, "run-1 {} {i}"),
+            TimestampedOutputLine::at(100, "run-2 {} {i}"),
+        ],
+        expected_stderr: vec![],
+    });
+}
+
+#[test]
+fn test_run_with_cwd() {
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--cwd", "/", "pwd"],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        expected_stdout: TimestampedOutputLine::repeat_at(&[0, 100], "/"),
+        expected_stderr: vec![],
+    });
+}
+
+#[test]
+fn test_run_quiet() {
+    test_run(RunTestCase {
+        args: vec!["0.1s", "-q", "bash", "-c", "echo out && echo err >&2"],
+        run_time_ms: 350,
+        grace_period_ms: 40,
+        expected_stdout: vec![],
+        expected_stderr: TimestampedOutputLine::repeat_at(&[0, 100, 200, 300], "err"),
+    });
+}
+
+#[test]
+fn test_run_very_quiet() {
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "-qq",
+            "bash",
+            "-c",
+            "echo out && echo err >&2 && false",
+        ],
+        run_time_ms: 350,
+        grace_period_ms: 40,
+        expected_stdout: vec![],
+        expected_stderr: TimestampedOutputLine::repeat_at(
+            &[0, 100, 200, 300],
+            "Command exited with exit status: 1",
+        ),
+    });
+}
+
+#[test]
+fn test_run_verbose() {
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--verbose", "sleep", "0.15"],
+        run_time_ms: 320,
+        grace_period_ms: 40,
+        expected_stdout: vec![],
+        expected_stderr: vec![
+            TimestampedOutputLine::at(0, "Tick #1 fired"),
+            TimestampedOutputLine::matching_at(0, r"Started command with PID \d+"),
+            TimestampedOutputLine::at(100, "Tick #2 fired"),
+            TimestampedOutputLine::at(100, "Tick #2 skipped: 1 command already running"),
+            TimestampedOutputLine::matching_at(
+                150,
+                r"Command with PID \d+ exited with exit status: 0 after 0\.1[5-6]\d?s, CPU time \d+(\.\d+)?s, max RSS \d+(\.\d)?[BKMG]",
+            ),
+            TimestampedOutputLine::matching_at(
+                150,
+                r"Run #1 took 0\.1[5-6]\d?s, longer than the interval of 0\.1s: skipped 1 tick",
+            ),
+            TimestampedOutputLine::at(200, "Tick #3 fired"),
+            TimestampedOutputLine::matching_at(200, r"Started command with PID \d+"),
+            TimestampedOutputLine::at(300, "Tick #4 fired"),
+            TimestampedOutputLine::at(300, "Tick #4 skipped: 1 command already running"),
+            // interrupted
+            TimestampedOutputLine::matching_at(320, r"Command with PID \d+ killed on exit"),
+        ],
+    });
+}
+
+#[test]
+fn test_run_with_log_format_json() {
+    let output = get_cmd()
+        .args([
+            "50ms",
+            "-n",
+            "1",
+            "--verbose",
+            "--log-format",
+            "json",
+            "false",
+        ])
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let lines: Vec<&str> = stderr.lines().collect();
+
+    let time = r#"\{"time":\d+\.\d{3},"#;
+
+    let expected = [
+        r#""level":"info","message":"Tick #1 fired","run_id":1\}"#,
+        r#""level":"info","message":"Started 1 run, stopping"\}"#,
+        r#""level":"info","message":"Started command with PID \d+","run_id":1,"pid":\d+\}"#,
+        r#""level":"info","message":"Command with PID \d+ exited with exit status: 1 after .+","run_id":1,"pid":\d+,"exit_code":1\}"#,
+        r#""level":"error","message":"Command exited with exit status: 1","run_id":1,"pid":\d+,"exit_code":1\}"#,
+    ];
+
+    assert_eq!(lines.len(), expected.len(), "stderr: {stderr}");
+
+    for (line, expected) in lines.iter().zip(expected) {
+        let pattern = Regex::new(&format!("^{time}{expected}$")).unwrap();
+        assert!(pattern.is_match(line), "line: {line}");
+    }
+}
+
+#[test]
+fn test_run_summary() {
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--summary", "bash", "-c", "sleep 0.15 && false"],
+        run_time_ms: 320,
+        grace_period_ms: 40,
+        expected_stdout: vec![],
+        expected_stderr: vec![
+            TimestampedOutputLine::at(150, "Command exited with exit status: 1"),
+            TimestampedOutputLine::matching_at(
+                150,
+                r"Run #1 took 0\.1[5-6]\d?s, longer than the interval of 0\.1s: skipped 1 tick",
+            ),
+            TimestampedOutputLine::at(320, "Summary:"),
+            TimestampedOutputLine::at(320, "  Runs:          2 (0 succeeded, 1 failed, 1 running)"),
+            TimestampedOutputLine::at(320, "  Skipped ticks: 2"),
+            TimestampedOutputLine::matching_at(
+                320,
+                r"  Run duration:  min 0\.1[5-6]\d?s, avg 0\.1[5-6]\d?s, max 0\.1[5-6]\d?s",
+            ),
+            TimestampedOutputLine::matching_at(
+                320,
+                r"  CPU time:      \d+(\.\d+)?s user, \d+(\.\d+)?s system",
+            ),
+            TimestampedOutputLine::matching_at(320, r"  Max RSS:       \d+(\.\d)?[BKMG]"),
+            TimestampedOutputLine::matching_at(320, r"  Elapsed time:  0\.3[0-3]\d?s"),
+        ],
+    });
+}
+
+#[test]
+fn test_run_with_stats_every() {
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--stats-every", "0.25s", "sleep", "0.02"],
+        run_time_ms: 350,
+        grace_period_ms: 40,
+        expected_stdout: vec![],
+        expected_stderr: vec![TimestampedOutputLine::matching_at(
+            250,
+            r"Runs: 3, failed: 0, skipped ticks: 0, running: 0, p50: 0\.02\d?s, p95: 0\.02\d?s",
+        )],
+    });
+}
+
+#[test]
+fn test_run_with_heartbeat_file() {
+    let heartbeat_file = get_temp_path("heartbeat");
+
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "--heartbeat-file",
+            heartbeat_file.to_str().unwrap(),
+            "true",
+        ],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        expected_stdout: vec![],
+        expected_stderr: vec![],
+    });
+
+    let timestamp: u64 = fs::read_to_string(&heartbeat_file)
+        .unwrap()
+        .trim_end()
+        .parse()
+        .unwrap();
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    assert!(
+        (now - 1..=now).contains(&timestamp),
+        "timestamp: {timestamp}"
+    );
+
+    fs::remove_file(&heartbeat_file).unwrap();
+}
+
+#[test]
+fn test_run_with_heartbeat_file_and_failing_command() {
+    let heartbeat_file = get_temp_path("heartbeat-failing");
+
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "--heartbeat-file",
+            heartbeat_file.to_str().unwrap(),
+            "false",
+        ],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        expected_stdout: vec![],
+        expected_stderr: TimestampedOutputLine::repeat_at(
+            &[0, 100],
+            "Command exited with exit status: 1",
+        ),
+    });
+
+    assert!(!heartbeat_file.exists());
+}
+
+#[test]
+fn test_run_with_on_failure_hook() {
+    get_cmd()
+        .args([
+            "50ms",
+            "-n",
+            "3",
+            "--on-failure",
+            "echo \"run $EVERY_RUN_ID failed with $EVERY_EXIT_CODE\"",
+            "bash",
+            "-c",
+            "exit $(({i} % 2))",
+        ])
+        .assert()
+        .code(1)
+        .stdout("run 1 failed with 1\nrun 3 failed with 1\n");
+
+    let output_dir = get_temp_path("on-failure-output-dir");
+    fs::create_dir(&output_dir).unwrap();
+
+    get_cmd()
+        .args([
+            "50ms",
+            "-n",
+            "1",
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+            "--on-failure",
+            "cat \"$EVERY_OUTPUT_FILE\"",
+            "bash",
+            "-c",
+            "echo oops; false",
+        ])
+        .assert()
+        .code(1)
+        .stdout("oops\n");
+
+    fs::remove_dir_all(output_dir).unwrap();
+}
+
+#[test]
+fn test_run_with_on_success_and_on_skip_hooks() {
+    // the first run is still running on the second tick, that is skipped
+    get_cmd()
+        .args([
+            "100ms",
+            "-n",
+            "2",
+            "--on-success",
+            "echo \"run $EVERY_RUN_ID succeeded\"",
+            "--on-skip",
+            "echo \"tick $EVERY_RUN_ID skipped: $EVERY_SKIP_REASON\"",
+            "sleep",
+            "0.15",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            "tick 2 skipped: 1 command already running\n\
+             run 1 succeeded\n\
+             run 3 succeeded\n",
+        );
+}
+
+#[cfg(feature = "webhook")]
+#[test]
+fn test_run_with_notify_url() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(&stream);
+        let mut content_length = 0;
+
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+
+            if let Some(length) = line.to_lowercase().strip_prefix("content-length: ") {
+                content_length = length.trim().parse().unwrap();
+            }
+
+            if line == "\r\n" {
+                break;
+            }
+        }
+
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        write!(
+            &stream,
+            "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n"
+        )
+        .unwrap();
+
+        String::from_utf8(body).unwrap()
+    });
+
+    get_cmd()
+        .args([
+            "50ms",
+            "-n",
+            "1",
+            "--on-failure-output",
+            "--notify-url",
+            &url,
+            "bash",
+            "-c",
+            "echo oops; exit 3",
+        ])
+        .assert()
+        .success()
+        .stdout("oops\n");
+
+    let body = server.join().unwrap();
+
+    assert!(
+        body.starts_with(r#"{"event":"failure","command":"bash -c echo oops; exit 3","run_id":1,"exit_code":3,"duration":"#),
+        "body: {body}"
+    );
+    assert!(body.ends_with(r#","output":"oops\n"}"#), "body: {body}");
+}
+
+#[cfg(not(target_os = "macos"))]
+#[test]
+fn test_run_with_notify_desktop() {
+    // a fake notify-send, that prints its arguments
+    let bin_dir = get_temp_path("notify-desktop-bin");
+    fs::create_dir(&bin_dir).unwrap();
+    let notify_send = bin_dir.join("notify-send");
+    fs::write(&notify_send, "#!/bin/sh\necho \"$@\"\n").unwrap();
+    fs::set_permissions(&notify_send, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = format!("{}:{}", bin_dir.display(), env::var("PATH").unwrap());
+
+    get_cmd()
+        .env("PATH", path)
+        .args(["50ms", "-n", "1", "--notify-desktop", "false"])
+        .assert()
+        .code(1)
+        .stdout("--app-name every -- every: run #1 failed false exited with exit status: 1\n");
+
+    fs::remove_dir_all(bin_dir).unwrap();
+}
+
+#[test]
+fn test_run_with_history() {
+    // a fake sqlite3, that prints its arguments and script
+    let bin_dir = get_temp_path("history-bin");
+    fs::create_dir(&bin_dir).unwrap();
+    let sqlite3 = bin_dir.join("sqlite3");
+    fs::write(&sqlite3, "#!/bin/sh\necho \"$@\"\ncat\n").unwrap();
+    fs::set_permissions(&sqlite3, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = format!("{}:{}", bin_dir.display(), env::var("PATH").unwrap());
+
+    let output = get_cmd()
+        .env("PATH", &path)
+        .args([
+            "50ms",
+            "-n",
+            "1",
+            "--history",
+            "runs.sqlite",
+            "--on-failure-output",
+        ])
+        .args(["sh", "-c", "echo oops; exit 3"])
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout
+        .starts_with("oops\n-bail runs.sqlite\n.timeout 5000\nCREATE TABLE IF NOT EXISTS runs ("));
+    assert!(stdout.contains("\nINSERT INTO runs ("));
+    assert!(stdout.contains(", 3, NULL, "));
+    assert!(stdout.ends_with(", 5, 'oops\n');\n"));
+
+    // the size of the output is recorded even if it is not captured
+    let output = get_cmd()
+        .env("PATH", &path)
+        .args(["50ms", "-n", "1", "--history", "runs.sqlite"])
+        .args(["sh", "-c", "echo oops; exit 3"])
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.starts_with("oops\n-bail runs.sqlite\n"));
+    assert!(stdout.ends_with(", 5, NULL);\n"));
+
+    fs::remove_dir_all(bin_dir).unwrap();
+}
+
+#[cfg(feature = "otel")]
+#[test]
+fn test_run_with_otel_endpoint() {
+    // a fake curl, that appends the spans it receives to a file
+    let bin_dir = get_temp_path("otel-bin");
+    fs::create_dir(&bin_dir).unwrap();
+    let spans = bin_dir.join("spans");
+    let curl = bin_dir.join("curl");
+    let script = format!(
+        "#!/bin/sh\necho \"$@\" >> {0}\ncat >> {0}\necho >> {0}\n",
+        spans.display()
+    );
+    fs::write(&curl, script).unwrap();
+    fs::set_permissions(&curl, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = format!("{}:{}", bin_dir.display(), env::var("PATH").unwrap());
+
+    let output = get_cmd()
+        .env("PATH", path)
+        .args([
+            "50ms",
+            "-n",
+            "1",
+            "--otel-endpoint",
+            "http://localhost:4318",
+        ])
+        .args(["sh", "-c", "echo $TRACEPARENT; exit 3"])
+        .output()
+        .unwrap();
+
+    // 00-<trace id>-<span id>-01
+    let traceparent = String::from_utf8(output.stdout).unwrap();
+    let (trace_id, span_id) = (&traceparent[3..35], &traceparent[36..52]);
+
+    let spans = fs::read_to_string(&spans).unwrap();
+    let lines: Vec<&str> = spans.lines().collect();
+
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].ends_with(" http://localhost:4318/v1/traces"));
+    assert!(lines[1].contains(&format!(r#""traceId":"{trace_id}","spanId":"{span_id}","#)));
+    assert!(lines[1].contains(r#""name":"run","#));
+    assert!(lines[1].contains(r#"{"key":"every.iteration","value":{"intValue":"1"}}"#));
+    assert!(lines[1].contains(r#"{"key":"every.exit_code","value":{"intValue":"3"}}"#));
+    assert!(lines[1].contains(r#""status":{"code":2}"#));
+
+    // the root span, exported last
+    assert!(lines[3].contains(r#""name":"every","#));
+    assert!(!lines[3].contains("parentSpanId"));
+
+    fs::remove_dir_all(bin_dir).unwrap();
+}
+
+#[test]
+fn test_run_with_report() {
+    let csv_report = get_temp_path("report.csv");
+    let json_report = get_temp_path("report.json");
+
+    // appended to, with a single CSV header
+    for report in [&csv_report, &csv_report, &json_report] {
+        get_cmd()
+            .args(["50ms", "-n", "2", "--report", report.to_str().unwrap()])
+            .args(["sh", "-c", "exit $(({i} - 1))"])
+            .assert()
+            .code(1);
+    }
+
+    let csv = fs::read_to_string(&csv_report).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+
+    assert_eq!(lines.len(), 5);
+    assert_eq!(
+        lines[0],
+        "run_id,command,scheduled_at,started_at,ended_at,duration,exit_code,exit_signal,user_time,system_time,max_rss"
+    );
+    assert!(lines[1].starts_with("1,sh -c exit $(({i} - 1)),") && lines[1].contains(",0,,"));
+    assert!(lines[2].starts_with("2,") && lines[2].contains(",1,,"));
+    assert!(lines[3].starts_with("1,") && lines[4].starts_with("2,"));
+
+    let json = fs::read_to_string(&json_report).unwrap();
+    let lines: Vec<&str> = json.lines().collect();
+
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with(r#"{"run_id":1,"command":"sh -c exit $(({i} - 1))","#));
+    assert!(lines[0].contains(r#","exit_code":0,"exit_signal":null,"user_time":"#));
+    assert!(lines[1].starts_with(r#"{"run_id":2,"#));
+    assert!(lines[1].contains(r#","exit_code":1,"exit_signal":null,"user_time":"#));
+
+    fs::remove_file(&csv_report).unwrap();
+    fs::remove_file(&json_report).unwrap();
+}
+
+#[test]
+fn test_history_with_missing_file() {
+    get_cmd()
+        .args(["history", "/non-existing/runs.sqlite"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("History file not found: /non-existing/runs.sqlite\n");
+}
+
+#[test]
+fn test_run_with_control_socket() {
+    let control_socket = get_temp_path("control.sock");
+
+    let child = get_cmd()
+        .args(["0.1s", "--control-socket", control_socket.to_str().unwrap()])
+        .args(["echo", "hello"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // ticks at 0 & 100 ms
+    thread::sleep(Duration::from_millis(150));
+    assert_eq!(send_control(&control_socket, "pause"), ["ok"]);
+
+    let status = send_control(&control_socket, "status");
+    assert_eq!(
+        status[..2],
+        ["State:         paused", "Interval:      0.1s"]
+    );
+    assert_eq!(
+        status[2],
+        "Runs:          2 (2 succeeded, 0 failed, 0 running)"
+    );
+    assert_eq!(status.last().unwrap(), "ok");
+
+    // ticks at 200, 300 & 400 ms are not fired
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(send_control(&control_socket, "resume"), ["ok"]);
+
+    // tick at 500 ms
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(send_control(&control_socket, "set-interval 1h"), ["ok"]);
+    assert_eq!(
+        send_control(&control_socket, "set-interval 1x"),
+        ["error: Invalid interval '1x': unrecognized format"]
+    );
+    assert_eq!(
+        send_control(&control_socket, "foo"),
+        ["error: Unknown command: 'foo'"]
+    );
+
+    // forced tick
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(send_control(&control_socket, "run-now"), ["ok"]);
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(send_control(&control_socket, "stop"), ["ok"]);
+
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "hello\n".repeat(4)
+    );
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+    assert!(!control_socket.exists());
+}
+
+// Sends a command to the control socket, and returns the response lines.
+fn send_control(control_socket: &Path, command: &str) -> Vec<String> {
+    let mut stream = UnixStream::connect(control_socket).unwrap();
+    writeln!(stream, "{command}").unwrap();
+
+    let mut lines = Vec::new();
+
+    for line in BufReader::new(stream).lines() {
+        let line = line.unwrap();
+        let is_last = line == "ok" || line.starts_with("error: ");
+
+        lines.push(line);
+
+        if is_last {
+            return lines;
+        }
+    }
+
+    panic!("Unexpected end of control response: {lines:?}");
+}
+
+#[test]
+fn test_status() {
+    let runtime_dir = get_temp_path("status");
+    let _ = fs::remove_dir_all(&runtime_dir);
+
+    let child = get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .args(["0.1s", "echo", "hello"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let pid = child.id().to_string();
+
+    // ticks at 0 & 100 ms
+    thread::sleep(Duration::from_millis(150));
+
+    let output = get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .args(["status", &pid])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert_eq!(
+        lines[..2],
+        ["State:         running", "Interval:      0.1s"]
+    );
+    assert_eq!(
+        lines[3],
+        "Runs:          2 (2 succeeded, 0 failed, 0 running)"
+    );
+    assert_eq!(lines.last().unwrap(), &"Command:       echo hello");
+
+    get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicates::str::starts_with(format!(
+            "PID {pid}:\n  State:         running\n"
+        )));
+
+    kill(Pid::from_raw(child.id() as i32), Signal::SIGTERM).unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+
+    get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .args(["status", &pid])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(format!("No running instance with PID {pid}!\n"));
+
+    get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .arg("status")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("No running instances!\n");
+
+    fs::remove_dir_all(&runtime_dir).unwrap();
+}
+
+#[test]
+fn test_stop() {
+    let runtime_dir = get_temp_path("stop");
+    let _ = fs::remove_dir_all(&runtime_dir);
+
+    let child = get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .args(["1s", "bash", "-c", "sleep 0.3; echo done"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let pid = child.id().to_string();
+
+    // stopped while the first run is still running
+    thread::sleep(Duration::from_millis(100));
+
+    get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .args(["stop", &pid])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    // the run has completed before the instance exited
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "done\n");
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+
+    get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .args(["stop", &pid])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(format!("No running instance with PID {pid}!\n"));
+
+    fs::remove_dir_all(&runtime_dir).unwrap();
+}
+
+#[test]
+fn test_run_with_name() {
+    let runtime_dir = get_temp_path("name");
+    let _ = fs::remove_dir_all(&runtime_dir);
+
+    let child = get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .args(["1s", "--name", "backup", "--single-instance", "true"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let pid = child.id();
+    thread::sleep(Duration::from_millis(100));
+
+    get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .args(["1s", "--name", "backup", "--single-instance", "true"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(format!(
+            "An instance named 'backup' is already running, with PID {pid}!\n"
+        ));
+
+    get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .args(["status", "backup"])
+        .assert()
+        .success()
+        .stdout(predicates::str::starts_with("State:         running\n"));
+
+    get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicates::str::starts_with(format!(
+            "backup (PID {pid}):\n"
+        )));
+
+    get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .args(["stop", "backup"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+
+    get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .args(["stop", "backup"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("No running instance named 'backup'!\n");
+
+    fs::remove_dir_all(&runtime_dir).unwrap();
+}
+
+#[test]
+fn test_run_with_name_does_not_follow_symlinks() {
+    let runtime_dir = get_temp_path("name-symlink");
+    let _ = fs::remove_dir_all(&runtime_dir);
+    fs::create_dir_all(runtime_dir.join("every")).unwrap();
+    fs::set_permissions(runtime_dir.join("every"), Permissions::from_mode(0o700)).unwrap();
+
+    let target = runtime_dir.join("target");
+    fs::write(&target, "untouched\n").unwrap();
+    std::os::unix::fs::symlink(&target, runtime_dir.join("every/backup.pid")).unwrap();
+
+    // refused with --single-instance
+    get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .args([
+            "1s",
+            "-n",
+            "1",
+            "--name",
+            "backup",
+            "--single-instance",
+            "true",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicates::str::starts_with(
+            "Failed to register name 'backup': ",
+        ));
+
+    // replaced otherwise
+    get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .args(["1s", "-n", "1", "--name", "backup", "true"])
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(&target).unwrap(), "untouched\n");
+    assert!(!runtime_dir.join("every/backup.pid").is_symlink());
+
+    fs::remove_dir_all(&runtime_dir).unwrap();
+}
+
+#[test]
+fn test_unsafe_registry_is_refused() {
+    let runtime_dir = get_temp_path("unsafe-registry");
+    let _ = fs::remove_dir_all(&runtime_dir);
+    fs::create_dir_all(runtime_dir.join("every")).unwrap();
+    fs::set_permissions(runtime_dir.join("every"), Permissions::from_mode(0o777)).unwrap();
+
+    let error = format!(
+        "'{}' must be a directory owned by us, with mode 0700",
+        runtime_dir.join("every").display()
+    );
+
+    get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .args(["1s", "-n", "1", "--name", "backup", "true"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(format!(
+            "Failed to register name 'backup': {error}\n"
+        )));
+
+    get_cmd()
+        .env("XDG_RUNTIME_DIR", &runtime_dir)
+        .arg("status")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(format!("Unsafe registry directory: {error}\n"));
+
+    fs::remove_dir_all(&runtime_dir).unwrap();
+}
+
+#[test]
+fn test_run_with_on_failure_output() {
+    let flag_file = get_temp_path("on-failure-output");
+    let flag_file = flag_file.to_str().unwrap();
+
+    // every other run fails
+    let script = format!(
+        "echo out; echo err >&2; if [ -f {flag_file} ]; then rm {flag_file}; exit 1; fi; touch {flag_file}"
+    );
+
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--on-failure-output", "bash", "-c", &script],
+        run_time_ms: 250,
+        grace_period_ms: 40,
+        expected_stdout: vec![TimestampedOutputLine::at(100, "out")],
+        expected_stderr: vec![
+            TimestampedOutputLine::at(100, "err"),
+            TimestampedOutputLine::at(100, "Command exited with exit status: 1"),
+        ],
+    });
+
+    let _ = fs::remove_file(flag_file);
+}
+
+#[test]
+fn test_run_with_changes() {
+    let counter_file = get_temp_path("changes");
+    let counter_file = counter_file.to_str().unwrap();
+
+    // the output changes every other run
+    let script = format!(
+        "n=$(cat {counter_file} 2>/dev/null || echo 0); echo $((n / 2)); echo $((n + 1)) > {counter_file}"
+    );
+
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--changes", "bash", "-c", &script],
+        run_time_ms: 350,
+        grace_period_ms: 40,
+        expected_stdout: vec![
+            TimestampedOutputLine::at(0, "0"),
+            TimestampedOutputLine::at(200, "1"),
+        ],
+        expected_stderr: vec![],
+    });
+
+    let _ = fs::remove_file(counter_file);
+}
+
+#[test]
+fn test_run_with_watch() {
+    let header = |timestamp_ms| {
+        [
+            TimestampedOutputLine::matching_at(
+                timestamp_ms,
+                r"\x1b\[H\x1b\[2JEvery 0\.1s: echo hello",
+            ),
+            TimestampedOutputLine::matching_at(
+                timestamp_ms,
+                r"Last run: \d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2} \S+, took \d+(\.\d+)?s, exit status: 0",
+            ),
+            TimestampedOutputLine::at(timestamp_ms, ""),
+            TimestampedOutputLine::at(timestamp_ms, "hello"),
+        ]
+    };
+
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--watch", "echo", "hello"],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        expected_stdout: [header(0), header(100)].into_iter().flatten().collect(),
+        expected_stderr: vec![],
+    });
+}
+
+#[test]
+fn test_run_with_diff() {
+    let counter_file = get_temp_path("diff");
+    let counter_file = counter_file.to_str().unwrap();
+
+    let script = format!(
+        "n=$(cat {counter_file} 2>/dev/null || echo 0); echo value $n; echo same; echo $((n + 1)) > {counter_file}"
+    );
+
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--diff", "bash", "-c", &script],
+        run_time_ms: 250,
+        grace_period_ms: 40,
+        expected_stdout: vec![
+            TimestampedOutputLine::at(0, "value 0"),
+            TimestampedOutputLine::at(0, "same"),
+            TimestampedOutputLine::at(100, "value \x1b[7m1\x1b[0m"),
+            TimestampedOutputLine::at(100, "same"),
+            TimestampedOutputLine::at(200, "value \x1b[7m2\x1b[0m"),
+            TimestampedOutputLine::at(200, "same"),
+        ],
+        expected_stderr: vec![],
+    });
+
+    let _ = fs::remove_file(counter_file);
+}
+
+#[test]
+fn test_run_with_pty() {
+    // both streams are the terminal, read back as stdout with its line endings
+    get_cmd()
+        .args([
+            "10ms",
+            "-n",
+            "1",
+            "--pty",
+            "sh",
+            "-c",
+            "[ -t 1 ] && [ -t 2 ] && echo tty >&2",
+        ])
+        .assert()
+        .success()
+        .stdout("tty\r\n")
+        .stderr("");
+}
+
+#[test]
+fn test_run_with_stderr() {
+    let script = "echo out; echo err >&2; echo done";
+
+    // in order, also when captured
+    for mode in ["--changes", "--summary"] {
+        get_cmd()
+            .args([
+                "10ms", "-n", "1", mode, "--stderr", "merge", "sh", "-c", script,
+            ])
+            .assert()
+            .success()
+            .stdout("out\nerr\ndone\n");
+    }
+
+    get_cmd()
+        .args(["10ms", "-n", "1", "--stderr", "discard", "sh", "-c", script])
+        .assert()
+        .success()
+        .stdout("out\ndone\n")
+        .stderr("");
+
+    let stderr_file = get_temp_path("stderr");
+    let mode = format!("file:{}", stderr_file.to_str().unwrap());
+
+    get_cmd()
+        .args(["10ms", "-n", "2", "--stderr", &mode, "sh", "-c", script])
+        .assert()
+        .success()
+        .stdout("out\ndone\nout\ndone\n")
+        .stderr("");
+
+    assert_eq!(fs::read_to_string(&stderr_file).unwrap(), "err\nerr\n");
+
+    fs::remove_file(&stderr_file).unwrap();
+}
+
+#[test]
+fn test_run_with_stdin_data() {
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--stdin-data", "hello\nworld\n", "cat"],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        expected_stdout: vec![
+            TimestampedOutputLine::at(0, "hello"),
+            TimestampedOutputLine::at(0, "world"),
+            TimestampedOutputLine::at(100, "hello"),
+            TimestampedOutputLine::at(100, "world"),
+        ],
+        expected_stderr: vec![],
+    });
+}
+
+#[test]
+fn test_run_with_stdin_file() {
+    let stdin_file = get_temp_path("stdin");
+    fs::write(&stdin_file, "hello from file\n").unwrap();
+
+    test_run(RunTestCase {
+        args: vec!["0.1s", "--stdin-file", stdin_file.to_str().unwrap(), "cat"],
+        run_time_ms: 250,
+        grace_period_ms: 40,
+        expected_stdout: TimestampedOutputLine::repeat_at(&[0, 100, 200], "hello from file"),
+        expected_stderr: vec![],
+    });
+
+    fs::remove_file(&stdin_file).unwrap();
+}
+
+#[test]
+fn test_run_with_missing_stdin_file() {
+    test_run(RunTestCase {
+        args: vec![
+            "0.1s",
+            "--stdin-file",
+            "/non-existing-file",
+            "--on-spawn-error",
+            "retry",
+            "cat",
+        ],
+        run_time_ms: 150,
+        grace_period_ms: 40,
+        expected_stdout: vec![],
+        expected_stderr: TimestampedOutputLine::repeat_at(
+            &[0, 100],
+            "Failed to open stdin file: No such file or directory (os error 2)",
+        ),
+    });
+}
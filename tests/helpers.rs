@@ -1,18 +1,29 @@
 use assert_cmd::prelude::*;
 use nix::sys::signal::{kill, Signal};
 use nix::unistd::Pid;
+use regex::Regex;
 use std::io::BufRead;
 use std::io::BufReader;
+use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::thread;
 use std::time::{Duration, Instant};
+use std::{env, fs, process};
 
 pub fn get_cmd() -> Command {
     Command::cargo_bin("every").unwrap()
 }
 
-pub struct RunTestCase {
-    pub args: Vec<&'static str>,
+// Returns a path in the temp directory that is unique to this test process, and does not exist.
+pub fn get_temp_path(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("every-test-{}-{name}", process::id()));
+    let _ = fs::remove_file(&path);
+
+    path
+}
+
+pub struct RunTestCase<'a> {
+    pub args: Vec<&'a str>,
     pub run_time_ms: u64,
     pub grace_period_ms: u64,
     pub expected_stdout: Vec<TimestampedOutputLine>,
@@ -22,16 +33,31 @@ pub struct RunTestCase {
 pub struct TimestampedOutputLine {
     pub timestamp_ms: u64,
     pub line: String,
+    // whether the line is a regex that the actual line must match
+    pub is_pattern: bool,
 }
 
 impl TimestampedOutputLine {
+    pub fn at(timestamp_ms: u64, line: &str) -> TimestampedOutputLine {
+        TimestampedOutputLine {
+            timestamp_ms,
+            line: String::from(line),
+            is_pattern: false,
+        }
+    }
+
+    pub fn matching_at(timestamp_ms: u64, pattern: &str) -> TimestampedOutputLine {
+        TimestampedOutputLine {
+            timestamp_ms,
+            line: format!("^{pattern}$"),
+            is_pattern: true,
+        }
+    }
+
     pub fn repeat_at(timestamps_ms: &[u64], line: &str) -> Vec<TimestampedOutputLine> {
         timestamps_ms
             .iter()
-            .map(|timestamp_ms| TimestampedOutputLine {
-                timestamp_ms: *timestamp_ms,
-                line: String::from(line),
-            })
+            .map(|timestamp_ms| TimestampedOutputLine::at(*timestamp_ms, line))
             .collect()
     }
 }
@@ -94,7 +120,7 @@ fn capture_output<R: std::io::Read>(reader: R, start_time: Instant) -> Vec<Times
     for line in reader.lines() {
         let line = line.unwrap();
         let timestamp_ms = start_time.elapsed().as_millis().try_into().unwrap();
-        output_lines.push(TimestampedOutputLine { timestamp_ms, line });
+        output_lines.push(TimestampedOutputLine::at(timestamp_ms, &line));
     }
 
     output_lines
@@ -135,7 +161,13 @@ fn assert_output_lines(
             ));
         }
 
-        if actual.line != expected.line {
+        let matches = if expected.is_pattern {
+            Regex::new(&expected.line).unwrap().is_match(&actual.line)
+        } else {
+            actual.line == expected.line
+        };
+
+        if !matches {
             fail(format!("line {line_number}: output does not match",));
         }
     }